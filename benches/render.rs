@@ -0,0 +1,88 @@
+use myeditor_core::{
+    backend::{Backend, MemoryBackend},
+    editor::Editor,
+    events::Events,
+    input::{Event, KeyCode, KeyEvent, KeyModifiers},
+    paths, script,
+    terminal::{Size, Terminal},
+};
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+const SIZE: Size = Size {
+    width: 80,
+    height: 24,
+};
+const TARGET: Duration = Duration::from_secs(2);
+
+fn main() {
+    let dir = std::env::temp_dir().join(format!("myeditor-bench-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    paths::set_data_dir(dir.join("data"));
+
+    let file = dir.join("large.txt");
+    let text = (0..100_000)
+        .map(|i| format!("line {i} with (some) [brackets] and text to draw\n"))
+        .collect::<String>();
+    std::fs::write(&file, text).unwrap();
+
+    bench("render/open", &mut || {
+        open(&file).draw().unwrap();
+    });
+
+    let setups = [
+        ("render/scroll", ""),
+        ("render/cursorline", ":set cursorline<CR>:set cc=40<CR>"),
+    ];
+
+    for (name, setup) in setups {
+        let mut editor = open(&file);
+        let mut step = 0;
+
+        for event in script::parse(setup).unwrap() {
+            editor.handle_input(event).unwrap();
+        }
+
+        bench(name, &mut || {
+            let key = if step % 400 < 200 { 'j' } else { 'k' };
+
+            step += 1;
+            editor
+                .handle_input(Event::Key(KeyEvent::new(
+                    KeyCode::Char(key),
+                    KeyModifiers::NONE,
+                )))
+                .unwrap();
+            editor.draw().unwrap();
+        });
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+fn open(file: &Path) -> Editor {
+    let mut backend = MemoryBackend::new(SIZE, Vec::new());
+    let events = Events::script(backend.events());
+    let terminal = Terminal::new(Box::new(backend)).unwrap();
+
+    Editor::new_with_file(terminal, events, file.to_path_buf(), false)
+}
+
+fn bench(name: &str, f: &mut dyn FnMut()) {
+    f();
+
+    let mut iterations = 0u32;
+    let start = Instant::now();
+
+    while start.elapsed() < TARGET {
+        f();
+        iterations += 1;
+    }
+
+    println!(
+        "{name:<32} {:>12} us/run ({iterations} runs)",
+        start.elapsed().as_micros() / iterations as u128
+    );
+}
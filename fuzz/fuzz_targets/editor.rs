@@ -0,0 +1,102 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use myeditor_core::{
+    backend::{Backend, MemoryBackend},
+    buffer::LINE_BREAKS,
+    editor::{Editor, EventResult},
+    events::Events,
+    input::{Event, KeyCode, KeyEvent, KeyModifiers},
+    terminal::{Size, Terminal},
+};
+
+const SIZE: Size = Size {
+    width: 40,
+    height: 10,
+};
+
+const KEYS: &[KeyCode] = &[
+    KeyCode::Char('h'),
+    KeyCode::Char('j'),
+    KeyCode::Char('k'),
+    KeyCode::Char('l'),
+    KeyCode::Char('i'),
+    KeyCode::Char('I'),
+    KeyCode::Char('a'),
+    KeyCode::Char('A'),
+    KeyCode::Char('0'),
+    KeyCode::Char('$'),
+    KeyCode::Char('%'),
+    KeyCode::Char('u'),
+    KeyCode::Char('x'),
+    KeyCode::Char(':'),
+    KeyCode::Char('d'),
+    KeyCode::Char('m'),
+    KeyCode::Char('s'),
+    KeyCode::Char('/'),
+    KeyCode::Char(','),
+    KeyCode::Char('1'),
+    KeyCode::Char('.'),
+    KeyCode::Char('('),
+    KeyCode::Char(')'),
+    KeyCode::Char(' '),
+    KeyCode::Char('\t'),
+    KeyCode::Char('é'),
+    KeyCode::Char('界'),
+    KeyCode::Tab,
+    KeyCode::Enter,
+    KeyCode::Backspace,
+    KeyCode::Esc,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Up,
+    KeyCode::Down,
+];
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&split, data)) = data.split_first() else {
+        return;
+    };
+
+    let (text, keys) = data.split_at(std::cmp::min(split as usize, data.len()));
+
+    let events = keys
+        .iter()
+        .map(|&byte| {
+            Event::Key(KeyEvent::new(
+                KEYS[byte as usize % KEYS.len()],
+                KeyModifiers::NONE,
+            ))
+        })
+        .collect::<Vec<_>>();
+    let count = events.len();
+
+    let mut backend = MemoryBackend::new(SIZE, events);
+    let events = Events::script(backend.events());
+    let Ok(terminal) = Terminal::new(Box::new(backend)) else {
+        return;
+    };
+    let mut editor = Editor::new(terminal, events);
+    let text = String::from_utf8_lossy(text)
+        .chars()
+        .filter(|&c| c == '\t' || LINE_BREAKS.contains(&c) || !c.is_control())
+        .collect::<String>();
+
+    editor.set_text(&text);
+
+    let _ = editor.draw();
+
+    for _ in 0..count {
+        match editor.handle_event() {
+            Ok(Some(EventResult::Quit)) => break,
+            Ok(None) => {}
+            Err(e) => editor.report(e),
+        }
+
+        editor.assert_invariants();
+
+        let _ = editor.draw();
+    }
+
+    editor.assert_history(&text);
+});
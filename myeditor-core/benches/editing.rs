@@ -0,0 +1,135 @@
+use myeditor_core::{buffer::Buffer, diff::DiffView, regex::Regex};
+use ropey::Rope;
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+const SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+const TARGET: Duration = Duration::from_millis(500);
+
+fn main() {
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with('-'));
+    let run = |name: &str, f: &mut dyn FnMut()| {
+        if filter
+            .as_ref()
+            .is_none_or(|filter| name.contains(filter.as_str()))
+        {
+            bench(name, f);
+        }
+    };
+
+    for lines in SIZES {
+        let mut buffer = buffer_with_lines(lines);
+        let middle = buffer.text.len_chars() / 2;
+
+        run(&format!("insert_char/{lines}"), &mut || {
+            buffer.insert_char(black_box(middle), 'x').unwrap();
+            buffer.remove(middle..middle + 1).unwrap();
+        });
+
+        let mut buffer = buffer_with_lines(lines);
+
+        run(&format!("insert_delete_line/{lines}"), &mut || {
+            buffer
+                .insert(black_box(middle), "a freshly typed line\n")
+                .unwrap();
+            buffer.remove(middle..middle + 21).unwrap();
+        });
+    }
+
+    for lines in &SIZES[..2] {
+        let buffer = buffer_with_lines(*lines);
+        let regex = Regex::new("needle[0-9]+", false).unwrap();
+
+        run(&format!("search/{lines}"), &mut || {
+            let found = buffer
+                .text
+                .lines()
+                .filter(|line| {
+                    let chars = line.chars().collect::<Vec<_>>();
+
+                    regex.find_at(&chars, 0).is_some()
+                })
+                .count();
+
+            black_box(found);
+        });
+    }
+
+    for lines in &SIZES[..2] {
+        let mut buffer = buffer_with_lines(*lines);
+        let step = buffer.text.len_chars() / 1000;
+
+        for edit in 0..1000 {
+            buffer.insert_char(edit * step, 'x').unwrap();
+            buffer.history.commit(&buffer.text);
+        }
+
+        run(&format!("undo_redo/{lines}"), &mut || {
+            while let Some((state, _)) = buffer.history.undo() {
+                buffer.text = black_box(state.text.clone());
+            }
+
+            while let Some((state, _)) = buffer.history.redo() {
+                buffer.text = black_box(state.text.clone());
+            }
+        });
+    }
+
+    let a = buffer_with_lines(10_000);
+    let mut b = buffer_with_lines(10_000);
+
+    for line in (0..10_000).step_by(97) {
+        let idx = b.text.line_to_char(line);
+        b.insert(idx, "changed ").unwrap();
+    }
+
+    let mut diff = DiffView::new(b);
+
+    run("diff/10000", &mut || diff.update(black_box(&a)));
+}
+
+fn buffer_with_lines(lines: usize) -> Buffer {
+    let mut text = String::new();
+
+    for i in 0..lines {
+        if i % 1000 == 999 {
+            text.push_str(&format!("let needle{i} = find(haystack);\n"));
+        } else {
+            text.push_str(&format!("line {i} of some ordinary text in a buffer\n"));
+        }
+    }
+
+    Buffer {
+        text: Rope::from_str(&text),
+        ..Buffer::new()
+    }
+}
+
+fn bench(name: &str, f: &mut dyn FnMut()) {
+    f();
+
+    let mut iterations = 1u64;
+
+    let elapsed = loop {
+        let start = Instant::now();
+
+        for _ in 0..iterations {
+            f();
+        }
+
+        let elapsed = start.elapsed();
+
+        if elapsed >= TARGET || iterations >= 1 << 30 {
+            break elapsed;
+        }
+
+        iterations *= 2;
+    };
+
+    println!(
+        "{name:<32} {:>12} ns/iter ({iterations} iterations)",
+        elapsed.as_nanos() / iterations as u128
+    );
+}
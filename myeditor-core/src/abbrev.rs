@@ -0,0 +1,56 @@
+use std::collections::BTreeMap;
+
+#[derive(Debug, Default)]
+pub struct Abbreviations {
+    entries: BTreeMap<String, String>,
+}
+
+impl Abbreviations {
+    pub fn define(&mut self, lhs: &str, rhs: &str) -> Result<(), String> {
+        if lhs.is_empty() || !lhs.chars().all(is_word_char) {
+            return Err(format!("Invalid abbreviation: {lhs}"));
+        }
+
+        self.entries.insert(lhs.to_string(), rhs.to_string());
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, lhs: &str) -> Result<(), String> {
+        self.entries
+            .remove(lhs)
+            .map(|_| ())
+            .ok_or_else(|| format!("No such abbreviation: {lhs}"))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        self.entries
+            .iter()
+            .map(|(lhs, rhs)| format!("i  {lhs:<10} {rhs}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn expansion(&self, before: &str) -> Option<(usize, &str)> {
+        let start = before
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_word_char(*c))
+            .last()
+            .map(|(i, _)| i)?;
+
+        let word = &before[start..];
+
+        self.entries
+            .get(word)
+            .map(|rhs| (word.chars().count(), rhs.as_str()))
+    }
+}
+
+pub fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
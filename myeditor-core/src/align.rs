@@ -0,0 +1,81 @@
+pub fn align(lines: &[&str], delimiter: &str) -> Vec<String> {
+    let rows = lines
+        .iter()
+        .map(|line| {
+            line.contains(delimiter).then(|| {
+                line.split(delimiter)
+                    .enumerate()
+                    .map(|(i, field)| {
+                        if i == 0 {
+                            field.trim_end()
+                        } else {
+                            field.trim()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut widths = Vec::new();
+
+    for fields in rows.iter().flatten() {
+        for (i, field) in fields.iter().enumerate() {
+            if widths.len() <= i {
+                widths.push(0);
+            }
+
+            widths[i] = widths[i].max(field.chars().count());
+        }
+    }
+
+    lines
+        .iter()
+        .zip(&rows)
+        .map(|(line, fields)| match fields {
+            Some(fields) => join(fields, &widths, delimiter),
+            None => line.to_string(),
+        })
+        .collect()
+}
+
+fn join(fields: &[&str], widths: &[usize], delimiter: &str) -> String {
+    let mut out = String::new();
+
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            if widths[i - 1] > 0 {
+                out.push(' ');
+            }
+
+            out.push_str(delimiter);
+
+            if !field.is_empty() || i + 1 < fields.len() {
+                out.push(' ');
+            }
+        }
+
+        if i + 1 < fields.len() {
+            out.push_str(&pad(field, widths[i]));
+        } else {
+            out.push_str(field);
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+fn pad(field: &str, width: usize) -> String {
+    let missing = width.saturating_sub(field.chars().count());
+    let is_rule =
+        !field.is_empty() && field.contains('-') && field.chars().all(|c| c == '-' || c == ':');
+
+    if !is_rule {
+        return format!("{field}{}", " ".repeat(missing));
+    }
+
+    match field.strip_suffix(':') {
+        Some(start) => format!("{start}{}:", "-".repeat(missing)),
+        None => format!("{field}{}", "-".repeat(missing)),
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{
+    colors::ColorDepth,
+    display::WIDE_TAIL,
+    events::{EventSource, Queue},
+    input::Event,
+    style::{ContentStyle, CursorStyle},
+    terminal::Size,
+};
+use std::{fmt::Debug, io};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub c: char,
+    pub style: ContentStyle,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            c: ' ',
+            style: ContentStyle::default(),
+        }
+    }
+}
+
+pub trait Backend: Debug {
+    fn size(&self) -> io::Result<Size>;
+
+    fn color_depth(&self) -> ColorDepth;
+
+    fn start(&mut self) -> io::Result<()>;
+
+    fn finish(&mut self) -> io::Result<()>;
+
+    fn draw(&mut self, x: u16, y: u16, cells: &[Cell]) -> io::Result<()>;
+
+    fn move_cursor(&mut self, x: u16, y: u16) -> io::Result<()>;
+
+    fn show_cursor(&mut self) -> io::Result<()>;
+
+    fn hide_cursor(&mut self) -> io::Result<()>;
+
+    fn flush(&mut self) -> io::Result<()>;
+
+    fn events(&mut self) -> Box<dyn EventSource>;
+
+    fn resize(&mut self, _size: Size) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn set_cursor_style(&mut self, _style: CursorStyle) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn set_title(&mut self, _title: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_raw(&mut self, _text: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn enhanced_keys(&self) -> bool {
+        false
+    }
+
+    fn is_headless(&self) -> bool {
+        false
+    }
+
+    fn snapshot(&self) -> Option<String> {
+        None
+    }
+}
+
+#[derive(Debug)]
+pub struct MemoryBackend {
+    size: Size,
+    cells: Vec<Vec<Cell>>,
+    events: Vec<Event>,
+}
+
+impl MemoryBackend {
+    pub fn new(size: Size, events: Vec<Event>) -> Self {
+        Self {
+            size,
+            cells: vec![vec![Cell::default(); size.width as usize]; size.height as usize],
+            events,
+        }
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn size(&self) -> io::Result<Size> {
+        Ok(self.size)
+    }
+
+    fn color_depth(&self) -> ColorDepth {
+        ColorDepth::TrueColor
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn draw(&mut self, x: u16, y: u16, cells: &[Cell]) -> io::Result<()> {
+        if let Some(row) = self.cells.get_mut(y as usize) {
+            for (cell, &new) in row.iter_mut().skip(x as usize).zip(cells) {
+                *cell = new;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn move_cursor(&mut self, _x: u16, _y: u16) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn events(&mut self) -> Box<dyn EventSource> {
+        Box::new(Queue::new(std::mem::take(&mut self.events)))
+    }
+
+    fn resize(&mut self, size: Size) -> io::Result<()> {
+        self.size = size;
+        self.cells = vec![vec![Cell::default(); size.width as usize]; size.height as usize];
+
+        Ok(())
+    }
+
+    fn is_headless(&self) -> bool {
+        true
+    }
+
+    fn snapshot(&self) -> Option<String> {
+        Some(
+            self.cells
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| cell.c)
+                        .filter(|&c| c != WIDE_TAIL)
+                        .collect::<String>()
+                        .trim_end()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+}
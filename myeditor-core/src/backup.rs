@@ -0,0 +1,91 @@
+use crate::paths;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const DIR_NAME: &str = "backups";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub time: SystemTime,
+}
+
+pub fn dir(path: &Path) -> Option<PathBuf> {
+    paths::data_file(DIR_NAME).map(|dir| dir.join(escape(path)))
+}
+
+fn escape(path: &Path) -> String {
+    let mut name = String::new();
+
+    for chunk in path.as_os_str().as_encoded_bytes().utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '%' | '/' => name.push_str(&format!("%{:02X}", c as u8)),
+                c => name.push(c),
+            }
+        }
+
+        for byte in chunk.invalid() {
+            name.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    name
+}
+
+pub fn list(path: &Path) -> Vec<Backup> {
+    let Some(entries) = dir(path).and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+
+    let mut backups = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let millis = entry.file_name().to_str()?.parse::<u64>().ok()?;
+
+            Some(Backup {
+                path: entry.path(),
+                time: UNIX_EPOCH + Duration::from_millis(millis),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.time));
+    backups
+}
+
+pub fn save(path: &Path, keep: usize) -> std::io::Result<()> {
+    if keep == 0 || !path.is_file() {
+        return Ok(());
+    }
+
+    let Some(dir) = dir(path) else {
+        return Ok(());
+    };
+
+    let contents = std::fs::read(path)?;
+    let backups = list(path);
+
+    let unchanged = backups
+        .first()
+        .and_then(|newest| std::fs::read(&newest.path).ok())
+        .is_some_and(|newest| newest == contents);
+
+    if !unchanged {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(millis.to_string()), contents)?;
+    }
+
+    for old in list(path).iter().skip(keep) {
+        std::fs::remove_file(&old.path)?;
+    }
+
+    Ok(())
+}
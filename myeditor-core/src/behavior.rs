@@ -0,0 +1,188 @@
+use crate::{align, statusline, tasks};
+use ropey::Rope;
+use std::{ops::Range, path::Path};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Newline {
+    Continue(String),
+    End,
+}
+
+pub trait Behavior {
+    fn newline(&self, _line: &str) -> Option<Newline> {
+        None
+    }
+
+    fn toggle(&self, _line: &str) -> Option<String> {
+        None
+    }
+
+    fn folds(&self, _text: &Rope) -> Vec<Range<usize>> {
+        Vec::new()
+    }
+
+    fn format(&self, _lines: &[&str]) -> Option<Vec<String>> {
+        None
+    }
+}
+
+pub fn for_path(path: Option<&Path>) -> Option<&'static dyn Behavior> {
+    match path.and_then(statusline::filetype)? {
+        "markdown" => Some(&Markdown),
+        "todo" => Some(&Todo),
+        _ => None,
+    }
+}
+
+pub struct Markdown;
+
+impl Behavior for Markdown {
+    fn newline(&self, line: &str) -> Option<Newline> {
+        let (marker, rest) = list_item(line)?;
+        let text = if checkbox(rest).is_some() {
+            &rest[3..]
+        } else {
+            rest
+        };
+
+        if text.trim().is_empty() {
+            return Some(Newline::End);
+        }
+
+        let indent = whitespace(line);
+        let bullet = marker.trim_end();
+        let bullet = match bullet.strip_suffix(['.', ')']) {
+            Some(digits) => match digits.parse::<usize>() {
+                Ok(number) => format!("{}{}", number + 1, &bullet[digits.len()..]),
+                Err(_) => bullet.to_string(),
+            },
+            None => bullet.to_string(),
+        };
+
+        let checkbox = if checkbox(rest).is_some() { "[ ] " } else { "" };
+
+        Some(Newline::Continue(format!("{indent}{bullet} {checkbox}")))
+    }
+
+    fn toggle(&self, line: &str) -> Option<String> {
+        let (_, rest) = list_item(line)?;
+        let start = line.len() - rest.len();
+
+        let (checked, text) = match checkbox(rest) {
+            Some(checked) => (!checked, &rest[3..]),
+            None => (false, rest),
+        };
+
+        let text = text.strip_prefix(' ').unwrap_or(text);
+        let mark = if checked { 'x' } else { ' ' };
+
+        Some(format!("{}[{mark}] {text}", &line[..start]))
+    }
+
+    fn folds(&self, text: &Rope) -> Vec<Range<usize>> {
+        let mut open: Vec<(usize, usize)> = Vec::new();
+        let mut folds = Vec::new();
+        let mut fenced = false;
+        let lines = text.len_lines();
+
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.to_string();
+
+            if is_fence(&line) {
+                fenced = !fenced;
+                continue;
+            }
+
+            let Some(level) = heading(&line).filter(|_| !fenced) else {
+                continue;
+            };
+
+            while let Some(&(start, _)) = open.last().filter(|&&(_, open)| open >= level) {
+                folds.push(start..idx);
+                open.pop();
+            }
+
+            open.push((idx, level));
+        }
+
+        let end = if text.len_chars() > 0 && text.char(text.len_chars() - 1) == '\n' {
+            lines - 1
+        } else {
+            lines
+        };
+
+        folds.extend(open.into_iter().map(|(start, _)| start..end));
+        folds.retain(|fold| fold.len() > 1);
+        folds.sort_by_key(|fold| fold.start);
+
+        folds
+    }
+
+    fn format(&self, lines: &[&str]) -> Option<Vec<String>> {
+        lines
+            .iter()
+            .all(|line| line.trim_start().starts_with('|'))
+            .then(|| align::align(lines, "|"))
+    }
+}
+
+pub struct Todo;
+
+impl Behavior for Todo {
+    fn newline(&self, line: &str) -> Option<Newline> {
+        let (_, text) = tasks::state(line)?;
+
+        if text.trim().is_empty() {
+            return Some(Newline::End);
+        }
+
+        let prefix = line[..line.len() - text.len()]
+            .replacen("DONE ", "TODO ", 1)
+            .replacen("[x] ", "[ ] ", 1)
+            .replacen("[X] ", "[ ] ", 1);
+
+        Some(Newline::Continue(prefix))
+    }
+
+    fn toggle(&self, line: &str) -> Option<String> {
+        Some(tasks::toggle(line))
+    }
+}
+
+fn whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+fn list_item(line: &str) -> Option<(&str, &str)> {
+    let after = line.trim_start();
+    let digits = after.chars().take_while(char::is_ascii_digit).count();
+    let marker = match after[digits..].chars().next() {
+        Some('.' | ')') if digits > 0 => digits + 1,
+        Some('-' | '*' | '+') if digits == 0 => 1,
+        _ => return None,
+    };
+
+    let rest = &after[marker..];
+
+    Some((&after[..marker], rest.strip_prefix(' ')?))
+}
+
+fn checkbox(text: &str) -> Option<bool> {
+    match text.get(..3)? {
+        "[ ]" => Some(false),
+        "[x]" | "[X]" => Some(true),
+        _ => None,
+    }
+}
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+fn heading(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+
+    ((1..=6).contains(&level) && line[level..].starts_with([' ', '\n', '\r'])).then_some(level)
+}
@@ -0,0 +1,106 @@
+use crate::paths;
+use std::path::{Path, PathBuf};
+
+const FILE_NAME: &str = "bookmarks";
+
+#[derive(Debug, Default)]
+pub struct Bookmarks {
+    entries: Vec<Bookmark>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub project: PathBuf,
+    pub path: PathBuf,
+    pub line: usize,
+    pub note: String,
+}
+
+impl Bookmarks {
+    pub fn load() -> Self {
+        let Some(text) = paths::data_file(FILE_NAME).and_then(|p| std::fs::read_to_string(p).ok())
+        else {
+            return Self::default();
+        };
+
+        let entries = text
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(4, '\t');
+
+                Some(Bookmark {
+                    project: PathBuf::from(parts.next()?),
+                    path: PathBuf::from(parts.next()?),
+                    line: parts.next()?.parse().ok()?,
+                    note: parts.next().unwrap_or_default().to_string(),
+                })
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = paths::data_file(FILE_NAME) else {
+            return Ok(());
+        };
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let text = self
+            .entries
+            .iter()
+            .map(|bookmark| {
+                format!(
+                    "{}\t{}\t{}\t{}\n",
+                    bookmark.project.display(),
+                    bookmark.path.display(),
+                    bookmark.line,
+                    bookmark.note
+                )
+            })
+            .collect::<String>();
+
+        std::fs::write(path, text)
+    }
+
+    pub fn get(&self, path: &Path, line: usize) -> Option<&Bookmark> {
+        self.entries
+            .iter()
+            .find(|bookmark| bookmark.path == path && bookmark.line == line)
+    }
+
+    pub fn set(&mut self, mut bookmark: Bookmark) {
+        bookmark.note = bookmark.note.replace(['\t', '\n'], " ");
+
+        match self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.path == bookmark.path && entry.line == bookmark.line)
+        {
+            Some(entry) => *entry = bookmark,
+            None => {
+                self.entries.push(bookmark);
+                self.entries
+                    .sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+            }
+        }
+    }
+
+    pub fn remove(&mut self, path: &Path, line: usize) -> bool {
+        let before = self.entries.len();
+
+        self.entries
+            .retain(|bookmark| bookmark.path != path || bookmark.line != line);
+
+        self.entries.len() < before
+    }
+
+    pub fn project<'a>(&'a self, project: &'a Path) -> impl Iterator<Item = &'a Bookmark> {
+        self.entries
+            .iter()
+            .filter(move |bookmark| bookmark.project == project)
+    }
+}
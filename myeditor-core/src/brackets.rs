@@ -0,0 +1,100 @@
+use ropey::Rope;
+use std::ops::Range;
+
+const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+pub fn is_bracket(c: char) -> bool {
+    PAIRS.iter().any(|&(open, close)| c == open || c == close)
+}
+
+pub fn bracket_near(text: &Rope, char_idx: usize) -> Option<usize> {
+    let at = |idx: usize| text.get_char(idx).filter(|&c| is_bracket(c)).map(|_| idx);
+
+    at(char_idx).or_else(|| char_idx.checked_sub(1).and_then(at))
+}
+
+pub fn next_bracket_on_line(text: &Rope, char_idx: usize) -> Option<usize> {
+    let line = text.char_to_line(char_idx);
+    let end = text.line_to_char(line) + text.line(line).len_chars();
+
+    (char_idx..end).find(|&idx| is_bracket(text.char(idx)))
+}
+
+pub fn matching_bracket(text: &Rope, char_idx: usize, within: Range<usize>) -> Option<usize> {
+    let c = text.get_char(char_idx)?;
+
+    let (open, close, forward) = PAIRS.iter().find_map(|&(open, close)| {
+        if c == open {
+            Some((open, close, true))
+        } else if c == close {
+            Some((open, close, false))
+        } else {
+            None
+        }
+    })?;
+
+    let mut depth = 0usize;
+    let mut idx = char_idx;
+
+    loop {
+        let c = text.get_char(idx)?;
+
+        if c == open {
+            if forward {
+                depth += 1;
+            } else {
+                depth -= 1;
+            }
+        } else if c == close {
+            if forward {
+                depth -= 1;
+            } else {
+                depth += 1;
+            }
+        }
+
+        if depth == 0 {
+            return Some(idx);
+        }
+
+        idx = if forward {
+            idx + 1
+        } else {
+            idx.checked_sub(1)?
+        };
+
+        if !within.contains(&idx) {
+            return None;
+        }
+    }
+}
+
+pub fn nesting(text: &Rope, within: Range<usize>) -> Vec<(usize, Option<usize>)> {
+    let mut open = Vec::new();
+    let mut found = Vec::new();
+    let end = std::cmp::min(within.end, text.len_chars());
+
+    for (idx, c) in text.slice(..end).chars().enumerate() {
+        let depth = if PAIRS.iter().any(|&(open, _)| c == open) {
+            open.push(c);
+
+            Some(open.len() - 1)
+        } else if let Some(&(pair, _)) = PAIRS.iter().find(|&&(_, close)| c == close) {
+            if open.last() == Some(&pair) {
+                open.pop();
+
+                Some(open.len())
+            } else {
+                None
+            }
+        } else {
+            continue;
+        };
+
+        if idx >= within.start {
+            found.push((idx, depth));
+        }
+    }
+
+    found
+}
@@ -0,0 +1,682 @@
+use crate::{
+    csv, hex,
+    indent::{self, Indent},
+    largefile::{self, LargeFile},
+    undo::History,
+    view::Viewport,
+};
+use ropey::Rope;
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufWriter, ErrorKind, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+pub const LINE_BREAKS: [char; 7] = [
+    '\n', '\r', '\u{b}', '\u{c}', '\u{85}', '\u{2028}', '\u{2029}',
+];
+
+#[derive(Debug)]
+pub struct Buffer {
+    pub text: Rope,
+    pub path: Option<PathBuf>,
+    pub cursor: (usize, usize),
+    pub view: Viewport,
+    pub cursor_x_remember: usize,
+    pub marks: HashMap<char, (usize, usize)>,
+    pub kind: BufferKind,
+    pub read_only: bool,
+    pub modified: bool,
+    pub mtime: Option<SystemTime>,
+    pub bytes: Vec<u8>,
+    pub lossy: bool,
+    pub history: History,
+    pub indent: Option<Indent>,
+    pub large: Option<LargeFile>,
+    pub table: Option<char>,
+    pub folds: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferKind {
+    File,
+    Directory,
+    Hex,
+    Help,
+    GitStatus,
+    Large,
+    Tutor,
+}
+
+impl Default for Buffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Buffer {
+    pub fn new() -> Self {
+        Self {
+            text: Rope::new(),
+            path: None,
+            cursor: (0, 0),
+            view: Viewport::default(),
+            cursor_x_remember: 0,
+            marks: HashMap::new(),
+            kind: BufferKind::File,
+            read_only: false,
+            modified: false,
+            mtime: None,
+            bytes: Vec::new(),
+            lossy: false,
+            history: History::default(),
+            indent: None,
+            large: None,
+            table: None,
+            folds: Vec::new(),
+        }
+    }
+
+    pub fn from_file(path: PathBuf) -> std::io::Result<Self> {
+        if path.is_dir() {
+            return Self::from_directory(path);
+        }
+
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        let (text, lossy) = match String::from_utf8(bytes) {
+            Ok(text) => (text, false),
+            Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), true),
+        };
+
+        let text = Rope::from(text);
+
+        Ok(Self {
+            indent: indent::detect(&text),
+            table: csv::detect(&path, &text),
+            text,
+            lossy,
+            read_only: is_read_only(&path),
+            mtime: modified_time(&path),
+            path: Some(path),
+            ..Self::new()
+        })
+    }
+
+    pub fn from_large_file(path: PathBuf) -> std::io::Result<Self> {
+        let file = LargeFile::open(&path)?;
+        let (text, lossy) = file.text();
+
+        Ok(Self {
+            text: text.into(),
+            kind: BufferKind::Large,
+            lossy,
+            read_only: true,
+            mtime: modified_time(&path),
+            path: Some(path),
+            large: Some(file),
+            ..Self::new()
+        })
+    }
+
+    pub fn from_file_hex(path: PathBuf) -> std::io::Result<Self> {
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            text: hex::dump(&bytes).into(),
+            bytes,
+            kind: BufferKind::Hex,
+            read_only: is_read_only(&path),
+            mtime: modified_time(&path),
+            path: Some(path),
+            ..Self::new()
+        })
+    }
+
+    pub fn from_help(text: &str) -> Self {
+        Self {
+            text: text.into(),
+            kind: BufferKind::Help,
+            read_only: true,
+            ..Self::new()
+        }
+    }
+
+    pub fn from_tutor(text: &str) -> Self {
+        Self {
+            text: text.into(),
+            kind: BufferKind::Tutor,
+            ..Self::new()
+        }
+    }
+
+    pub fn from_git_status(root: PathBuf, text: &str) -> Self {
+        Self {
+            text: text.into(),
+            path: Some(root),
+            kind: BufferKind::GitStatus,
+            read_only: true,
+            ..Self::new()
+        }
+    }
+
+    pub fn from_directory(path: PathBuf) -> std::io::Result<Self> {
+        let text = directory_listing(&path)?;
+
+        Ok(Self {
+            text: text.into(),
+            path: Some(path),
+            kind: BufferKind::Directory,
+            ..Self::new()
+        })
+    }
+
+    pub fn insert_char(&mut self, char_idx: usize, c: char) -> Result<(), String> {
+        self.editable()?;
+
+        if c == '\n' {
+            self.shift_folds(char_idx, 1);
+        }
+
+        self.history.record(&self.text, self.cursor);
+        self.text.insert_char(char_idx, c);
+        self.modified = true;
+
+        Ok(())
+    }
+
+    pub fn insert(&mut self, char_idx: usize, text: &str) -> Result<(), String> {
+        self.editable()?;
+
+        self.shift_folds(char_idx, text.matches('\n').count());
+        self.history.record(&self.text, self.cursor);
+        self.text.insert(char_idx, text);
+        self.modified = true;
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, range: Range<usize>) -> Result<(), String> {
+        self.editable()?;
+
+        if !self.folds.is_empty() {
+            let start = self.text.char_to_line(range.start);
+            let end = self.text.char_to_line(range.end);
+
+            self.folds.retain(|fold| !(start + 1..=end).contains(fold));
+
+            for fold in self.folds.iter_mut().filter(|fold| **fold > end) {
+                *fold -= end - start;
+            }
+        }
+
+        self.history.record(&self.text, self.cursor);
+        self.text.remove(range);
+        self.modified = true;
+
+        Ok(())
+    }
+
+    fn editable(&self) -> Result<(), String> {
+        if self.is_large() {
+            return Err("Can't edit a large file, see 'largefile'".into());
+        }
+
+        Ok(())
+    }
+
+    fn shift_folds(&mut self, char_idx: usize, added: usize) {
+        let line = self.text.char_to_line(char_idx);
+
+        for fold in self.folds.iter_mut().filter(|fold| **fold > line) {
+            *fold += added;
+        }
+    }
+
+    pub fn undo(&mut self) -> Option<(usize, (usize, usize))> {
+        self.history.commit(&self.text);
+
+        let (state, undone) = self.history.undo()?;
+
+        self.text = state.text.clone();
+        self.modified = !self.history.is_saved();
+        self.folds.clear();
+
+        Some((undone, self.history.states()[undone].cursor))
+    }
+
+    pub fn redo(&mut self) -> Option<(usize, (usize, usize))> {
+        self.history.commit(&self.text);
+
+        let (state, redone) = self.history.redo()?;
+        let cursor = state.cursor;
+
+        self.text = state.text.clone();
+        self.folds.clear();
+        self.modified = !self.history.is_saved();
+
+        Some((redone, cursor))
+    }
+
+    pub fn goto_state(&mut self, idx: usize) -> Option<(usize, usize)> {
+        self.history.commit(&self.text);
+
+        let state = self.history.goto(idx)?;
+        let cursor = state.cursor;
+
+        self.text = state.text.clone();
+        self.folds.clear();
+        self.modified = !self.history.is_saved();
+
+        Some(cursor)
+    }
+
+    pub fn preview_state(&mut self, idx: usize) -> Option<(usize, usize)> {
+        let state = self.history.states().get(idx)?;
+
+        self.text = state.text.clone();
+
+        Some(state.cursor)
+    }
+
+    pub fn changed_on_disk(&self) -> bool {
+        match (&self.path, self.mtime) {
+            (Some(path), Some(mtime)) => modified_time(path).is_some_and(|now| now != mtime),
+            _ => false,
+        }
+    }
+
+    pub fn acknowledge_disk_change(&mut self) {
+        if let Some(path) = &self.path {
+            self.mtime = modified_time(path);
+        }
+    }
+
+    pub fn reload(&mut self) -> std::io::Result<()> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+
+        let window = self
+            .large
+            .as_ref()
+            .map(|file| (file.start(), file.window()));
+        let fresh = if self.is_hex() {
+            Self::from_file_hex(path)?
+        } else if self.is_large() {
+            Self::from_large_file(path)?
+        } else {
+            Self::from_file(path)?
+        };
+
+        self.text = fresh.text;
+        self.indent = fresh.indent;
+        self.bytes = fresh.bytes;
+        self.lossy = fresh.lossy;
+        self.read_only = fresh.read_only;
+        self.mtime = fresh.mtime;
+        self.modified = false;
+        self.history = History::default();
+        self.large = fresh.large;
+        self.folds.clear();
+
+        if let (Some(file), Some((start, line))) = (&mut self.large, window) {
+            file.move_to(start, line);
+            self.load_window();
+        }
+
+        let last_line = self.text.len_lines().saturating_sub(1);
+        self.cursor.1 = std::cmp::min(self.cursor.1, last_line);
+        self.cursor.0 = std::cmp::min(self.cursor.0, self.text.line(self.cursor.1).len_chars());
+
+        if self.is_hex() {
+            self.set_hex_offset(self.hex_offset());
+        }
+
+        Ok(())
+    }
+
+    pub fn write(&mut self) -> std::io::Result<usize> {
+        let path = self
+            .path
+            .as_deref()
+            .ok_or_else(|| std::io::Error::new(ErrorKind::InvalidInput, "No file name"))?;
+
+        let bytes = self.write_copy(path)?;
+
+        self.mark_written();
+
+        Ok(bytes)
+    }
+
+    pub fn mark_written(&mut self) {
+        self.modified = false;
+        self.lossy = false;
+        self.mtime = self.path.as_deref().and_then(modified_time);
+        self.history.mark_saved(&self.text);
+    }
+
+    pub fn write_copy(&self, path: &Path) -> std::io::Result<usize> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        if self.is_hex() {
+            writer.write_all(&self.bytes)?;
+        } else {
+            self.text.write_to(&mut writer)?;
+        }
+
+        writer.flush()?;
+
+        Ok(self.len_bytes())
+    }
+
+    pub fn len_bytes(&self) -> usize {
+        if self.is_hex() {
+            self.bytes.len()
+        } else if let Some(file) = &self.large {
+            file.len()
+        } else {
+            self.text.len_bytes()
+        }
+    }
+
+    pub fn is_help(&self) -> bool {
+        self.kind == BufferKind::Help
+    }
+
+    pub fn is_tutor(&self) -> bool {
+        self.kind == BufferKind::Tutor
+    }
+
+    pub fn is_hex(&self) -> bool {
+        self.kind == BufferKind::Hex
+    }
+
+    pub fn is_large(&self) -> bool {
+        self.kind == BufferKind::Large
+    }
+
+    pub fn line_offset(&self) -> usize {
+        self.large.as_ref().map_or(0, LargeFile::window)
+    }
+
+    pub fn keep_window(&mut self, height: usize) -> bool {
+        let Some(file) = &mut self.large else {
+            return false;
+        };
+
+        let lines = self.text.len_lines();
+        let near_top = self.view.top_line < largefile::MARGIN && !file.at_start();
+        let near_end = self.view.top_line + height + largefile::MARGIN > lines && !file.at_end();
+
+        if !near_top && !near_end {
+            return false;
+        }
+
+        let moved = file.shift(self.view.top_line as isize - (largefile::WINDOW / 2) as isize);
+
+        self.load_window();
+
+        let last_line = self.text.len_lines().saturating_sub(1);
+
+        self.cursor.1 = std::cmp::min(self.cursor.1.saturating_add_signed(-moved), last_line);
+        self.view.top_line = self.view.top_line.saturating_add_signed(-moved);
+
+        true
+    }
+
+    pub fn goto_large_line(&mut self, line: usize) -> Option<usize> {
+        let local = self.large.as_mut()?.goto_line(line);
+
+        self.load_window();
+
+        Some(local)
+    }
+
+    pub fn goto_large_offset(&mut self, pos: usize) -> Option<usize> {
+        let local = self.large.as_mut()?.goto_offset(pos);
+
+        self.load_window();
+
+        Some(local)
+    }
+
+    fn load_window(&mut self) {
+        if let Some(file) = &self.large {
+            let (text, lossy) = file.text();
+
+            self.text = text.into();
+            self.lossy |= lossy;
+        }
+    }
+
+    pub fn enter_hex(&mut self) {
+        let char_idx = self.text.line_to_char(self.cursor.1) + self.cursor.0;
+        let offset = self.text.char_to_byte(char_idx);
+
+        let original = self
+            .path
+            .as_deref()
+            .filter(|_| self.lossy && !self.modified)
+            .and_then(|path| std::fs::read(path).ok());
+
+        self.bytes = original.unwrap_or_else(|| self.text.to_string().into_bytes());
+        self.lossy = false;
+        self.text = hex::dump(&self.bytes).into();
+        self.kind = BufferKind::Hex;
+        self.history = History::default();
+        self.view = Viewport::default();
+        self.set_hex_offset(offset);
+    }
+
+    pub fn leave_hex(&mut self) -> Result<(), String> {
+        let text = String::from_utf8(self.bytes.clone())
+            .map_err(|_| "Buffer is not valid UTF-8".to_string())?;
+        let offset = self.hex_offset();
+
+        self.text = text.into();
+        self.kind = BufferKind::File;
+        self.bytes = Vec::new();
+        self.history = History::default();
+        self.view = Viewport::default();
+
+        let char_idx = self
+            .text
+            .byte_to_char(std::cmp::min(offset, self.text.len_bytes()));
+        self.cursor.1 = self.text.char_to_line(char_idx);
+        self.cursor.0 = char_idx - self.text.line_to_char(self.cursor.1);
+
+        Ok(())
+    }
+
+    pub fn hex_offset(&self) -> usize {
+        self.cursor.1 * hex::BYTES_PER_LINE + self.cursor.0
+    }
+
+    pub fn set_hex_offset(&mut self, offset: usize) {
+        let offset = std::cmp::min(offset, self.bytes.len());
+
+        self.cursor = (offset % hex::BYTES_PER_LINE, offset / hex::BYTES_PER_LINE);
+        self.cursor_x_remember = self.cursor.0;
+    }
+
+    pub fn set_byte(&mut self, offset: usize, value: u8) {
+        match self.bytes.get_mut(offset) {
+            Some(byte) => *byte = value,
+            None => self.bytes.push(value),
+        }
+
+        self.modified = true;
+
+        let line = offset / hex::BYTES_PER_LINE;
+
+        if line + 1 < self.text.len_lines() {
+            let start = self.text.line_to_char(line);
+            let end = self.text.line_to_char(line + 1) - 1;
+
+            self.text.remove(start..end);
+            self.text.insert(start, &hex::dump_line(&self.bytes, line));
+        } else {
+            self.text = hex::dump(&self.bytes).into();
+        }
+    }
+
+    pub fn remove_byte(&mut self, offset: usize) {
+        if offset < self.bytes.len() {
+            self.bytes.remove(offset);
+            self.text = hex::dump(&self.bytes).into();
+            self.modified = true;
+        }
+    }
+
+    pub fn is_git_status(&self) -> bool {
+        self.kind == BufferKind::GitStatus
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.kind == BufferKind::Directory
+    }
+
+    pub fn refresh_directory(&mut self) -> std::io::Result<()> {
+        let Some(path) = self.path.as_deref().filter(|_| self.is_directory()) else {
+            return Ok(());
+        };
+
+        self.text = directory_listing(path)?.into();
+
+        let last_line = self.text.len_lines().saturating_sub(1);
+        self.cursor.1 = std::cmp::min(self.cursor.1, last_line);
+
+        Ok(())
+    }
+
+    pub fn entry_under_cursor(&self) -> Option<PathBuf> {
+        let dir = self.path.as_deref().filter(|_| self.is_directory())?;
+        let line = self.text.get_line(self.cursor.1)?.to_string();
+        let name = line.trim_end_matches('\n');
+
+        match name {
+            "" => None,
+            "../" => Some(dir.parent().unwrap_or(dir).to_path_buf()),
+            name => Some(dir.join(name.trim_end_matches('/'))),
+        }
+    }
+
+    pub fn replace_line(&mut self, line_idx: usize, before: &str, after: &str) -> bool {
+        if line_idx >= self.text.len_lines() {
+            return false;
+        }
+
+        let line = self.text.line(line_idx).to_string();
+        let content = line.trim_end_matches(['\n', '\r']);
+
+        if content != before {
+            return false;
+        }
+
+        let start = self.text.line_to_char(line_idx);
+        let end = start + content.chars().count();
+
+        self.remove(start..end).is_ok() && self.insert(start, after).is_ok()
+    }
+
+    pub fn lines_text(&self, lines: Range<usize>) -> String {
+        let start = self
+            .text
+            .line_to_char(std::cmp::min(lines.start, self.text.len_lines()));
+        let end = self
+            .text
+            .line_to_char(std::cmp::min(lines.end, self.text.len_lines()));
+
+        self.text.slice(start..end).to_string()
+    }
+
+    pub fn replace_lines(&mut self, lines: Range<usize>, text: &str) -> Result<(), String> {
+        let start = self
+            .text
+            .line_to_char(std::cmp::min(lines.start, self.text.len_lines()));
+        let end = self
+            .text
+            .line_to_char(std::cmp::min(lines.end, self.text.len_lines()));
+
+        let mut text = text.to_string();
+
+        if !text.is_empty() && !text.ends_with(LINE_BREAKS) && end < self.text.len_chars() {
+            text.push('\n');
+        }
+
+        if start < end {
+            self.remove(start..end)?;
+        }
+
+        if !text.is_empty() {
+            self.insert(start, &text)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn is_file(&self, path: &Path) -> bool {
+        match &self.path {
+            Some(own) if !self.is_git_status() => same_file(own, path),
+            _ => false,
+        }
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn is_read_only(path: &Path) -> bool {
+    match OpenOptions::new().append(true).open(path) {
+        Ok(_) => false,
+        Err(e) => matches!(
+            e.kind(),
+            ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem
+        ),
+    }
+}
+
+fn directory_listing(path: &Path) -> std::io::Result<String> {
+    let mut dirs = Vec::new();
+    let mut files = Vec::new();
+
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        if entry.path().is_dir() {
+            dirs.push(format!("{name}/"));
+        } else {
+            files.push(name);
+        }
+    }
+
+    dirs.sort();
+    files.sort();
+
+    let mut lines = vec!["../".to_string()];
+    lines.extend(dirs);
+    lines.extend(files);
+
+    Ok(lines.join("\n"))
+}
@@ -0,0 +1,139 @@
+const CONTROL_NAMES: [&str; 32] = [
+    "NUL", "SOH", "STX", "ETX", "EOT", "ENQ", "ACK", "BEL", "BS", "TAB", "LF", "VT", "FF", "CR",
+    "SO", "SI", "DLE", "DC1", "DC2", "DC3", "DC4", "NAK", "SYN", "ETB", "CAN", "EM", "SUB", "ESC",
+    "FS", "GS", "RS", "US",
+];
+
+const NAMES: &[(char, &str)] = &[
+    (' ', "SPACE"),
+    ('\u{7f}', "DELETE"),
+    ('\u{a0}', "NO-BREAK SPACE"),
+    ('\u{ad}', "SOFT HYPHEN"),
+    ('\u{34f}', "COMBINING GRAPHEME JOINER"),
+    ('\u{61c}', "ARABIC LETTER MARK"),
+    ('\u{115f}', "HANGUL CHOSEONG FILLER"),
+    ('\u{1160}', "HANGUL JUNGSEONG FILLER"),
+    ('\u{180e}', "MONGOLIAN VOWEL SEPARATOR"),
+    ('\u{2000}', "EN QUAD"),
+    ('\u{2001}', "EM QUAD"),
+    ('\u{2002}', "EN SPACE"),
+    ('\u{2003}', "EM SPACE"),
+    ('\u{2004}', "THREE-PER-EM SPACE"),
+    ('\u{2005}', "FOUR-PER-EM SPACE"),
+    ('\u{2006}', "SIX-PER-EM SPACE"),
+    ('\u{2007}', "FIGURE SPACE"),
+    ('\u{2008}', "PUNCTUATION SPACE"),
+    ('\u{2009}', "THIN SPACE"),
+    ('\u{200a}', "HAIR SPACE"),
+    ('\u{200b}', "ZERO WIDTH SPACE"),
+    ('\u{200c}', "ZERO WIDTH NON-JOINER"),
+    ('\u{200d}', "ZERO WIDTH JOINER"),
+    ('\u{200e}', "LEFT-TO-RIGHT MARK"),
+    ('\u{200f}', "RIGHT-TO-LEFT MARK"),
+    ('\u{2010}', "HYPHEN"),
+    ('\u{2011}', "NON-BREAKING HYPHEN"),
+    ('\u{2012}', "FIGURE DASH"),
+    ('\u{2013}', "EN DASH"),
+    ('\u{2014}', "EM DASH"),
+    ('\u{2018}', "LEFT SINGLE QUOTATION MARK"),
+    ('\u{2019}', "RIGHT SINGLE QUOTATION MARK"),
+    ('\u{201c}', "LEFT DOUBLE QUOTATION MARK"),
+    ('\u{201d}', "RIGHT DOUBLE QUOTATION MARK"),
+    ('\u{2026}', "HORIZONTAL ELLIPSIS"),
+    ('\u{2028}', "LINE SEPARATOR"),
+    ('\u{2029}', "PARAGRAPH SEPARATOR"),
+    ('\u{202a}', "LEFT-TO-RIGHT EMBEDDING"),
+    ('\u{202b}', "RIGHT-TO-LEFT EMBEDDING"),
+    ('\u{202c}', "POP DIRECTIONAL FORMATTING"),
+    ('\u{202d}', "LEFT-TO-RIGHT OVERRIDE"),
+    ('\u{202e}', "RIGHT-TO-LEFT OVERRIDE"),
+    ('\u{202f}', "NARROW NO-BREAK SPACE"),
+    ('\u{205f}', "MEDIUM MATHEMATICAL SPACE"),
+    ('\u{2060}', "WORD JOINER"),
+    ('\u{2066}', "LEFT-TO-RIGHT ISOLATE"),
+    ('\u{2067}', "RIGHT-TO-LEFT ISOLATE"),
+    ('\u{2068}', "FIRST STRONG ISOLATE"),
+    ('\u{2069}', "POP DIRECTIONAL ISOLATE"),
+    ('\u{2212}', "MINUS SIGN"),
+    ('\u{3000}', "IDEOGRAPHIC SPACE"),
+    ('\u{3164}', "HANGUL FILLER"),
+    ('\u{fe0e}', "VARIATION SELECTOR-15"),
+    ('\u{fe0f}', "VARIATION SELECTOR-16"),
+    ('\u{feff}', "ZERO WIDTH NO-BREAK SPACE"),
+    ('\u{fffc}', "OBJECT REPLACEMENT CHARACTER"),
+    ('\u{fffd}', "REPLACEMENT CHARACTER"),
+    ('\u{37e}', "GREEK QUESTION MARK"),
+    ('\u{391}', "GREEK CAPITAL LETTER ALPHA"),
+    ('\u{392}', "GREEK CAPITAL LETTER BETA"),
+    ('\u{395}', "GREEK CAPITAL LETTER EPSILON"),
+    ('\u{39f}', "GREEK CAPITAL LETTER OMICRON"),
+    ('\u{3bf}', "GREEK SMALL LETTER OMICRON"),
+    ('\u{410}', "CYRILLIC CAPITAL LETTER A"),
+    ('\u{415}', "CYRILLIC CAPITAL LETTER IE"),
+    ('\u{41e}', "CYRILLIC CAPITAL LETTER O"),
+    ('\u{420}', "CYRILLIC CAPITAL LETTER ER"),
+    ('\u{421}', "CYRILLIC CAPITAL LETTER ES"),
+    ('\u{430}', "CYRILLIC SMALL LETTER A"),
+    ('\u{435}', "CYRILLIC SMALL LETTER IE"),
+    ('\u{43e}', "CYRILLIC SMALL LETTER O"),
+    ('\u{440}', "CYRILLIC SMALL LETTER ER"),
+    ('\u{441}', "CYRILLIC SMALL LETTER ES"),
+    ('\u{445}', "CYRILLIC SMALL LETTER HA"),
+];
+
+pub fn is_combining(c: char) -> bool {
+    matches!(
+        c,
+        '\u{300}'..='\u{36f}'
+            | '\u{1ab0}'..='\u{1aff}'
+            | '\u{1dc0}'..='\u{1dff}'
+            | '\u{200d}'
+            | '\u{20d0}'..='\u{20ff}'
+            | '\u{fe00}'..='\u{fe0f}'
+            | '\u{fe20}'..='\u{fe2f}'
+            | '\u{1f3fb}'..='\u{1f3ff}'
+            | '\u{e0100}'..='\u{e01ef}'
+    )
+}
+
+pub fn name(c: char) -> Option<&'static str> {
+    if let Some(name) = CONTROL_NAMES.get(c as usize) {
+        return Some(name);
+    }
+
+    NAMES
+        .iter()
+        .find(|(named, _)| *named == c)
+        .map(|(_, name)| *name)
+}
+
+pub fn describe(chars: &[char]) -> String {
+    chars
+        .iter()
+        .map(|&c| {
+            let shown = if c.is_ascii_control() {
+                format!("^{}", (c as u8 ^ 0x40) as char)
+            } else if c.is_control() {
+                "?".to_string()
+            } else if is_combining(c) {
+                format!("\u{25cc}{c}")
+            } else {
+                c.to_string()
+            };
+
+            let mut utf8 = [0; 4];
+            let bytes = c
+                .encode_utf8(&mut utf8)
+                .bytes()
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            match name(c) {
+                Some(name) => format!("<{shown}> U+{:04X}, UTF-8 {bytes}, {name}", c as u32),
+                None => format!("<{shown}> U+{:04X}, UTF-8 {bytes}", c as u32),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("  ")
+}
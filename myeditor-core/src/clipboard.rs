@@ -0,0 +1,36 @@
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const MAX_ENCODED: usize = 100_000;
+
+pub fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+pub fn osc52(text: &str) -> Result<String, String> {
+    let encoded = base64(text.as_bytes());
+
+    if encoded.len() > MAX_ENCODED {
+        return Err(format!(
+            "Too large for the terminal clipboard: {} bytes",
+            text.len()
+        ));
+    }
+
+    Ok(format!("\x1b]52;c;{encoded}\x07"))
+}
@@ -0,0 +1,206 @@
+use std::{ops::Range, path::Path};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+const ANSI16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+const SWATCH_EXTENSIONS: [&str; 14] = [
+    "css", "scss", "sass", "less", "html", "htm", "svg", "toml", "yaml", "yml", "json", "ini",
+    "conf", "cfg",
+];
+
+pub fn detect(colorterm: Option<&str>, term: Option<&str>) -> ColorDepth {
+    if colorterm.is_some_and(|value| matches!(value, "truecolor" | "24bit")) {
+        return ColorDepth::TrueColor;
+    }
+
+    match term {
+        Some(term) if term.contains("direct") => ColorDepth::TrueColor,
+        Some(term) if term.contains("256") || term.starts_with("tmux") => ColorDepth::Ansi256,
+        Some(term) if term.starts_with("xterm") || term.starts_with("alacritty") => {
+            ColorDepth::Ansi256
+        }
+        _ => ColorDepth::Ansi16,
+    }
+}
+
+pub fn rgb_to_ansi256((r, g, b): (u8, u8, u8)) -> u8 {
+    let cube = |v: u8| {
+        CUBE.iter()
+            .enumerate()
+            .min_by_key(|(_, level)| level.abs_diff(v))
+            .map_or(0, |(i, _)| i as u8)
+    };
+
+    let (cr, cg, cb) = (cube(r), cube(g), cube(b));
+    let cube_index = 16 + 36 * cr + 6 * cg + cb;
+    let cube_color = (CUBE[cr as usize], CUBE[cg as usize], CUBE[cb as usize]);
+
+    let average = (r as u16 + g as u16 + b as u16) / 3;
+    let grey_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let grey_index = 232 + grey_step;
+    let grey = 8 + 10 * grey_step;
+
+    if distance((r, g, b), (grey, grey, grey)) < distance((r, g, b), cube_color) {
+        grey_index
+    } else {
+        cube_index
+    }
+}
+
+pub fn ansi256_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI16[index as usize],
+        16..=231 => {
+            let i = index - 16;
+
+            (
+                CUBE[(i / 36) as usize],
+                CUBE[(i / 6 % 6) as usize],
+                CUBE[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let grey = 8 + 10 * (index - 232);
+
+            (grey, grey, grey)
+        }
+    }
+}
+
+pub fn nearest_ansi16(rgb: (u8, u8, u8)) -> u8 {
+    ANSI16
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, color)| distance(rgb, **color))
+        .map_or(0, |(i, _)| i as u8)
+}
+
+pub fn has_literals(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SWATCH_EXTENSIONS.contains(&ext))
+}
+
+pub type Swatch = (Range<usize>, (u8, u8, u8));
+
+pub fn is_light((r, g, b): (u8, u8, u8)) -> bool {
+    299 * r as u32 + 587 * g as u32 + 114 * b as u32 > 128_000
+}
+
+pub fn literals(line: &str) -> Vec<Swatch> {
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut found = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let starts_word = i == 0 || !(chars[i - 1].is_alphanumeric() || chars[i - 1] == '_');
+
+        let literal = match chars[i] {
+            '#' if starts_word => hex_literal(&chars[i + 1..]).map(|(len, rgb)| (len + 1, rgb)),
+            'r' if starts_word => rgb_literal(&chars[i..]),
+            _ => None,
+        };
+
+        match literal {
+            Some((len, rgb)) => {
+                found.push((i..i + len, rgb));
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+
+    found
+}
+
+fn hex_literal(chars: &[char]) -> Option<(usize, (u8, u8, u8))> {
+    let digits = chars.iter().take_while(|c| c.is_ascii_hexdigit()).count();
+
+    if chars
+        .get(digits)
+        .is_some_and(|&c| c.is_alphanumeric() || c == '_')
+    {
+        return None;
+    }
+
+    let value = |i: usize, len: usize| {
+        let hex = chars[i..i + len].iter().collect::<String>();
+        let hex = if len == 1 { hex.repeat(2) } else { hex };
+
+        u8::from_str_radix(&hex, 16).ok()
+    };
+
+    let rgb = match digits {
+        3 | 4 => (value(0, 1)?, value(1, 1)?, value(2, 1)?),
+        6 | 8 => (value(0, 2)?, value(2, 2)?, value(4, 2)?),
+        _ => return None,
+    };
+
+    Some((digits, rgb))
+}
+
+fn rgb_literal(chars: &[char]) -> Option<(usize, (u8, u8, u8))> {
+    let start = ["rgba(", "rgb("].into_iter().find_map(|prefix| {
+        chars
+            .iter()
+            .zip(prefix.chars())
+            .all(|(&a, b)| a == b)
+            .then_some(prefix.len())
+            .filter(|&len| chars.len() > len)
+    })?;
+    let close = start + chars[start..].iter().take(40).position(|&c| c == ')')?;
+    let inner = chars[start..close].iter().collect::<String>();
+    let parts = inner
+        .split([',', ' ', '/'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>();
+
+    if !(3..=4).contains(&parts.len()) {
+        return None;
+    }
+
+    let channel = |part: &str| match part.strip_suffix('%') {
+        Some(percent) => percent
+            .parse::<f64>()
+            .ok()
+            .filter(|percent| (0.0..=100.0).contains(percent))
+            .map(|percent| (percent * 2.55).round() as u8),
+        None => part.parse::<u8>().ok(),
+    };
+
+    Some((
+        close + 1,
+        (channel(parts[0])?, channel(parts[1])?, channel(parts[2])?),
+    ))
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
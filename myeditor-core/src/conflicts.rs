@@ -0,0 +1,82 @@
+use ropey::Rope;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conflict {
+    pub start: usize,
+    pub base: Option<usize>,
+    pub middle: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Marker,
+    Ours,
+    Base,
+    Theirs,
+}
+
+impl Conflict {
+    pub fn ours(&self) -> Range<usize> {
+        self.start + 1..self.base.unwrap_or(self.middle)
+    }
+
+    pub fn theirs(&self) -> Range<usize> {
+        self.middle + 1..self.end
+    }
+
+    pub fn lines(&self) -> Range<usize> {
+        self.start..self.end + 1
+    }
+
+    pub fn section(&self, line: usize) -> Option<Section> {
+        if !self.lines().contains(&line) {
+            None
+        } else if [self.start, self.middle, self.end].contains(&line) || self.base == Some(line) {
+            Some(Section::Marker)
+        } else if self.ours().contains(&line) {
+            Some(Section::Ours)
+        } else if line < self.middle {
+            Some(Section::Base)
+        } else {
+            Some(Section::Theirs)
+        }
+    }
+}
+
+pub fn find_conflicts(text: &Rope) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let mut start = None;
+    let mut base = None;
+    let mut middle = None;
+
+    for (idx, line) in text.lines().enumerate() {
+        let marker = |prefix: &str| line.chars().take(7).eq(prefix.chars());
+
+        if marker("<<<<<<<") {
+            start = Some(idx);
+            base = None;
+            middle = None;
+        } else if marker("|||||||") && start.is_some() && middle.is_none() {
+            base = Some(idx);
+        } else if marker("=======") && start.is_some() && middle.is_none() {
+            middle = Some(idx);
+        } else if marker(">>>>>>>") {
+            if let (Some(start), Some(middle)) = (start, middle) {
+                conflicts.push(Conflict {
+                    start,
+                    base,
+                    middle,
+                    end: idx,
+                });
+            }
+
+            start = None;
+            base = None;
+            middle = None;
+        }
+    }
+
+    conflicts
+}
@@ -0,0 +1,63 @@
+use crate::paths;
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const MAX_KEYS: usize = 50;
+
+static KEYS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static FILES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub fn record_key(key: String) {
+    if let Ok(mut keys) = KEYS.lock() {
+        if keys.len() == MAX_KEYS {
+            keys.pop_front();
+        }
+
+        keys.push_back(key);
+    }
+}
+
+pub fn set_files(files: Vec<String>) {
+    if let Ok(mut open) = FILES.lock() {
+        *open = files;
+    }
+}
+
+pub fn write_report(version: &str, error: &str, backtrace: &str) -> Result<PathBuf, String> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let dir = paths::data_dir()
+        .ok_or("No data directory")?
+        .join("crashes");
+    let path = dir.join(format!("crash-{time}.txt"));
+
+    let mut report = format!("myeditor {version} crashed at {time}\n\n{error}\n");
+
+    let _ = write!(report, "\nBacktrace:\n{backtrace}\n");
+
+    report.push_str("\nRecent keys:\n");
+
+    if let Ok(keys) = KEYS.lock() {
+        let _ = writeln!(report, "  {}", Vec::from(keys.clone()).join(" "));
+    }
+
+    report.push_str("\nOpen files:\n");
+
+    if let Ok(files) = FILES.lock() {
+        for file in files.iter() {
+            let _ = writeln!(report, "  {file}");
+        }
+    }
+
+    std::fs::create_dir_all(&dir)
+        .and_then(|()| std::fs::write(&path, report))
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+
+    Ok(path)
+}
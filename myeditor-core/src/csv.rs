@@ -0,0 +1,187 @@
+use ropey::Rope;
+use std::{cmp::Ordering, ops::Range, path::Path};
+
+const MAX_WIDTH: usize = 40;
+const DELIMITERS: [char; 4] = [',', '\t', ';', '|'];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub delimiter: char,
+    pub widths: Vec<usize>,
+}
+
+impl Table {
+    pub fn new(text: &Rope, delimiter: char) -> Self {
+        let mut widths = Vec::new();
+
+        for line in text.lines() {
+            for (i, field) in split(&line.to_string(), delimiter).into_iter().enumerate() {
+                if widths.len() <= i {
+                    widths.push(0);
+                }
+
+                widths[i] = widths[i].max(std::cmp::min(field.len(), MAX_WIDTH));
+            }
+        }
+
+        Self { delimiter, widths }
+    }
+
+    pub fn display(&self, line: &str) -> String {
+        let chars = line.chars().collect::<Vec<_>>();
+        let fields = split(line, self.delimiter);
+        let mut out = String::new();
+
+        for (i, field) in fields.iter().enumerate() {
+            let text = chars[field.clone()]
+                .iter()
+                .map(|&c| if c == '\t' { ' ' } else { c });
+
+            out.extend(text);
+
+            if i + 1 < fields.len() {
+                out.push(if self.delimiter == '\t' {
+                    ' '
+                } else {
+                    self.delimiter
+                });
+                out.push_str(&" ".repeat(self.cell_width(i, field) - field.len() + 1));
+            }
+        }
+
+        out
+    }
+
+    pub fn display_col(&self, line: &str, col: usize) -> usize {
+        let fields = split(line, self.delimiter);
+        let field = field_at(&fields, col);
+        let x = self.starts(&fields)[field];
+
+        x + col - fields[field].start
+    }
+
+    pub fn field_range(&self, line: &str, field: usize) -> Option<Range<usize>> {
+        let fields = split(line, self.delimiter);
+        let x = *self.starts(&fields).get(field)?;
+
+        Some(x..x + self.cell_width(field, &fields[field]))
+    }
+
+    fn cell_width(&self, i: usize, field: &Range<usize>) -> usize {
+        std::cmp::max(field.len(), self.widths.get(i).copied().unwrap_or(0))
+    }
+
+    fn starts(&self, fields: &[Range<usize>]) -> Vec<usize> {
+        let mut x = 0;
+
+        fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let start = x;
+
+                x += self.cell_width(i, field) + 2;
+
+                start
+            })
+            .collect()
+    }
+}
+
+pub fn detect(path: &Path, text: &Rope) -> Option<char> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+
+    match extension.as_str() {
+        "tsv" | "tab" => Some('\t'),
+        "csv" => Some(guess(text)),
+        _ => None,
+    }
+}
+
+pub fn guess(text: &Rope) -> char {
+    let line = text
+        .get_line(0)
+        .map(|line| line.to_string())
+        .unwrap_or_default();
+
+    DELIMITERS
+        .into_iter()
+        .rev()
+        .max_by_key(|&delimiter| split(&line, delimiter).len())
+        .unwrap_or(',')
+}
+
+pub fn split(line: &str, delimiter: char) -> Vec<Range<usize>> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+    let mut quoted = false;
+
+    for c in line.trim_end_matches(['\n', '\r']).chars() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if c == delimiter && !quoted {
+            fields.push(start..end);
+            start = end + 1;
+        }
+
+        end += 1;
+    }
+
+    fields.push(start..end);
+
+    fields
+}
+
+pub fn field_at(fields: &[Range<usize>], col: usize) -> usize {
+    fields
+        .iter()
+        .position(|field| col <= field.end)
+        .unwrap_or(fields.len() - 1)
+}
+
+pub fn field(line: &str, delimiter: char, idx: usize) -> Option<String> {
+    let range = split(line, delimiter).get(idx)?.clone();
+    let text = line
+        .chars()
+        .skip(range.start)
+        .take(range.len())
+        .collect::<String>();
+    let text = text.trim();
+
+    Some(
+        text.strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+            .map_or(text.to_string(), |text| text.replace("\"\"", "\"")),
+    )
+}
+
+pub fn column(header: &str, delimiter: char, name: &str) -> Option<usize> {
+    if let Ok(number) = name.parse::<usize>() {
+        return number.checked_sub(1);
+    }
+
+    (0..split(header, delimiter).len()).find(|&i| {
+        field(header, delimiter, i).is_some_and(|field| field.eq_ignore_ascii_case(name))
+    })
+}
+
+pub fn sort(lines: &[&str], delimiter: char, column: usize) -> Vec<String> {
+    let mut rows = lines
+        .iter()
+        .map(|line| (field(line, delimiter, column).unwrap_or_default(), *line))
+        .collect::<Vec<_>>();
+
+    rows.sort_by(|(a, _), (b, _)| compare(a, b));
+
+    rows.into_iter().map(|(_, line)| line.to_string()).collect()
+}
+
+fn compare(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        _ => a.cmp(b),
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    json::{self, Value},
+    messages::Severity,
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub col: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    files: HashMap<PathBuf, Vec<Diagnostic>>,
+}
+
+impl Diagnostics {
+    pub fn replace(&mut self, files: HashMap<PathBuf, Vec<Diagnostic>>) {
+        self.files = files;
+    }
+
+    pub fn get(&self, path: &Path) -> &[Diagnostic] {
+        self.files.get(path).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.files
+            .values()
+            .flatten()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    }
+}
+
+pub fn cargo_root(path: &Path) -> Option<&Path> {
+    path.ancestors()
+        .skip(1)
+        .find(|dir| dir.join("Cargo.toml").is_file())
+}
+
+pub fn parse_cargo(output: &str, dir: &Path) -> HashMap<PathBuf, Vec<Diagnostic>> {
+    let mut files = HashMap::<PathBuf, Vec<Diagnostic>>::new();
+
+    for line in output.lines().filter(|line| line.starts_with('{')) {
+        let Ok(value) = json::parse(line) else {
+            continue;
+        };
+
+        if value.get("reason").and_then(Value::as_str) != Some("compiler-message") {
+            continue;
+        }
+
+        let Some(message) = value.get("message") else {
+            continue;
+        };
+
+        let severity = match message.get("level").and_then(Value::as_str) {
+            Some("error") => Severity::Error,
+            Some("warning") => Severity::Warning,
+            _ => continue,
+        };
+
+        let Some(Value::Array(spans)) = message.get("spans") else {
+            continue;
+        };
+
+        let Some(span) = spans
+            .iter()
+            .find(|span| span.get("is_primary") == Some(&Value::Bool(true)))
+        else {
+            continue;
+        };
+
+        let (Some(file), Some(line), Some(col)) = (
+            span.get("file_name").and_then(Value::as_str),
+            span.get("line_start").and_then(Value::as_u64),
+            span.get("column_start").and_then(Value::as_u64),
+        ) else {
+            continue;
+        };
+
+        let Some(path) = dir
+            .ancestors()
+            .map(|dir| dir.join(file))
+            .find(|path| path.is_file())
+        else {
+            continue;
+        };
+
+        files
+            .entry(path.canonicalize().unwrap_or(path))
+            .or_default()
+            .push(Diagnostic {
+                line: (line as usize).saturating_sub(1),
+                col: (col as usize).saturating_sub(1),
+                severity,
+                message: message
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            });
+    }
+
+    files
+}
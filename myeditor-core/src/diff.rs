@@ -0,0 +1,290 @@
+use crate::buffer::Buffer;
+use ropey::Rope;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    pub a: Range<usize>,
+    pub b: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowKind {
+    Equal,
+    Changed,
+    Removed,
+    Added,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Row {
+    pub a: Option<usize>,
+    pub b: Option<usize>,
+    pub kind: RowKind,
+}
+
+#[derive(Debug)]
+pub struct DiffView {
+    pub other: Buffer,
+    pub left_active: bool,
+    pub top_row: usize,
+    pub hunks: Vec<Hunk>,
+    pub rows: Vec<Row>,
+}
+
+impl DiffView {
+    pub fn new(other: Buffer) -> Self {
+        Self {
+            other,
+            left_active: true,
+            top_row: 0,
+            hunks: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, active: &Buffer) {
+        let (left, right) = self.sides(active);
+
+        let hunks = diff(&lines(&left.text), &lines(&right.text));
+        let rows = align(&hunks, left.text.len_lines(), right.text.len_lines());
+
+        self.hunks = hunks;
+        self.rows = rows;
+    }
+
+    pub fn sides<'a>(&'a self, active: &'a Buffer) -> (&'a Buffer, &'a Buffer) {
+        if self.left_active {
+            (active, &self.other)
+        } else {
+            (&self.other, active)
+        }
+    }
+
+    pub fn row_of(&self, active_line: usize) -> usize {
+        self.rows
+            .iter()
+            .position(|row| self.active_line(row) == Some(active_line))
+            .unwrap_or(0)
+    }
+
+    pub fn active_line(&self, row: &Row) -> Option<usize> {
+        if self.left_active {
+            row.a
+        } else {
+            row.b
+        }
+    }
+
+    pub fn active_range(&self, hunk: &Hunk) -> Range<usize> {
+        if self.left_active {
+            hunk.a.clone()
+        } else {
+            hunk.b.clone()
+        }
+    }
+
+    pub fn other_range(&self, hunk: &Hunk) -> Range<usize> {
+        if self.left_active {
+            hunk.b.clone()
+        } else {
+            hunk.a.clone()
+        }
+    }
+
+    pub fn hunk_at(&self, line: usize) -> Option<&Hunk> {
+        self.hunks.iter().find(|hunk| {
+            let range = self.active_range(hunk);
+
+            range.contains(&line)
+                || (range.is_empty() && (range.start == line || range.start == line + 1))
+        })
+    }
+
+    pub fn next_hunk(&self, line: usize) -> Option<usize> {
+        self.hunks
+            .iter()
+            .map(|hunk| self.active_range(hunk).start)
+            .find(|&start| start > line)
+    }
+
+    pub fn prev_hunk(&self, line: usize) -> Option<usize> {
+        self.hunks
+            .iter()
+            .rev()
+            .map(|hunk| self.active_range(hunk).start)
+            .find(|&start| start < line)
+    }
+}
+
+fn lines(text: &Rope) -> Vec<String> {
+    text.lines()
+        .map(|line| line.to_string().trim_end_matches(['\n', '\r']).to_string())
+        .collect()
+}
+
+fn diff<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Hunk> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    let mut hunks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    for (x, y) in matches(a_mid, b_mid)
+        .into_iter()
+        .chain(std::iter::once((a_mid.len(), b_mid.len())))
+    {
+        if x > i || y > j {
+            hunks.push(Hunk {
+                a: prefix + i..prefix + x,
+                b: prefix + j..prefix + y,
+            });
+        }
+
+        i = x + 1;
+        j = y + 1;
+    }
+
+    hunks
+}
+
+fn matches<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = n + m;
+    let offset = max + 1;
+
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    let mut trace = Vec::new();
+
+    for d in 0..=max {
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+
+        let mut done = false;
+
+        for k in (-d..=d).step_by(2) {
+            let idx = (offset + k) as usize;
+
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+
+            v[idx] = x;
+
+            if x >= n && y >= m {
+                done = true;
+                break;
+            }
+        }
+
+        if done {
+            break;
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut x, mut y) = (n, m);
+
+    for (d, vs) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+
+        if d == 0 {
+            while x > 0 && y > 0 {
+                x -= 1;
+                y -= 1;
+                pairs.push((x as usize, y as usize));
+            }
+
+            break;
+        }
+
+        let at = |k: isize| vs[(k + d) as usize];
+
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            pairs.push((x as usize, y as usize));
+        }
+
+        x = prev_x;
+        y = prev_y;
+    }
+
+    pairs.reverse();
+    pairs
+}
+
+fn align(hunks: &[Hunk], a_len: usize, b_len: usize) -> Vec<Row> {
+    let mut rows = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    let equal = |rows: &mut Vec<Row>, i: &mut usize, j: &mut usize, until: usize| {
+        while *i < until {
+            rows.push(Row {
+                a: Some(*i),
+                b: Some(*j),
+                kind: RowKind::Equal,
+            });
+            *i += 1;
+            *j += 1;
+        }
+    };
+
+    for hunk in hunks {
+        equal(&mut rows, &mut i, &mut j, hunk.a.start);
+
+        for k in 0..std::cmp::max(hunk.a.len(), hunk.b.len()) {
+            let a = (k < hunk.a.len()).then_some(hunk.a.start + k);
+            let b = (k < hunk.b.len()).then_some(hunk.b.start + k);
+
+            let kind = match (a, b) {
+                (Some(_), Some(_)) => RowKind::Changed,
+                (Some(_), None) => RowKind::Removed,
+                _ => RowKind::Added,
+            };
+
+            rows.push(Row { a, b, kind });
+        }
+
+        i = hunk.a.end;
+        j = hunk.b.end;
+    }
+
+    equal(&mut rows, &mut i, &mut j, a_len);
+
+    while j < b_len {
+        rows.push(Row {
+            a: None,
+            b: Some(j),
+            kind: RowKind::Added,
+        });
+        j += 1;
+    }
+
+    rows
+}
@@ -0,0 +1,197 @@
+const DIGRAPHS: &[(&str, char)] = &[
+    ("a'", 'á'),
+    ("a!", 'à'),
+    ("a>", 'â'),
+    ("a:", 'ä'),
+    ("a?", 'ã'),
+    ("e'", 'é'),
+    ("e!", 'è'),
+    ("e>", 'ê'),
+    ("e:", 'ë'),
+    ("e?", 'ẽ'),
+    ("i'", 'í'),
+    ("i!", 'ì'),
+    ("i>", 'î'),
+    ("i:", 'ï'),
+    ("i?", 'ĩ'),
+    ("o'", 'ó'),
+    ("o!", 'ò'),
+    ("o>", 'ô'),
+    ("o:", 'ö'),
+    ("o?", 'õ'),
+    ("u'", 'ú'),
+    ("u!", 'ù'),
+    ("u>", 'û'),
+    ("u:", 'ü'),
+    ("u?", 'ũ'),
+    ("A'", 'Á'),
+    ("A!", 'À'),
+    ("A>", 'Â'),
+    ("A:", 'Ä'),
+    ("A?", 'Ã'),
+    ("E'", 'É'),
+    ("E!", 'È'),
+    ("E>", 'Ê'),
+    ("E:", 'Ë'),
+    ("E?", 'Ẽ'),
+    ("I'", 'Í'),
+    ("I!", 'Ì'),
+    ("I>", 'Î'),
+    ("I:", 'Ï'),
+    ("I?", 'Ĩ'),
+    ("O'", 'Ó'),
+    ("O!", 'Ò'),
+    ("O>", 'Ô'),
+    ("O:", 'Ö'),
+    ("O?", 'Õ'),
+    ("U'", 'Ú'),
+    ("U!", 'Ù'),
+    ("U>", 'Û'),
+    ("U:", 'Ü'),
+    ("U?", 'Ũ'),
+    ("n'", 'ń'),
+    ("n!", 'ǹ'),
+    ("n?", 'ñ'),
+    ("N'", 'Ń'),
+    ("N!", 'Ǹ'),
+    ("N?", 'Ñ'),
+    ("y'", 'ý'),
+    ("y!", 'ỳ'),
+    ("y>", 'ŷ'),
+    ("y:", 'ÿ'),
+    ("y?", 'ỹ'),
+    ("Y'", 'Ý'),
+    ("Y!", 'Ỳ'),
+    ("Y>", 'Ŷ'),
+    ("Y:", 'Ÿ'),
+    ("Y?", 'Ỹ'),
+    ("c'", 'ć'),
+    ("c>", 'ĉ'),
+    ("C'", 'Ć'),
+    ("C>", 'Ĉ'),
+    ("c,", 'ç'),
+    ("C,", 'Ç'),
+    ("ss", 'ß'),
+    ("ae", 'æ'),
+    ("AE", 'Æ'),
+    ("o/", 'ø'),
+    ("O/", 'Ø'),
+    ("aa", 'å'),
+    ("AA", 'Å'),
+    ("Eu", '€'),
+    ("Pd", '£'),
+    ("Ye", '¥'),
+    ("Ct", '¢'),
+    ("Co", '©'),
+    ("Rg", '®'),
+    ("TM", '™'),
+    ("DG", '°'),
+    ("+-", '±'),
+    ("*X", '×'),
+    ("-:", '÷'),
+    ("My", 'µ'),
+    ("SE", '§'),
+    ("PI", '¶'),
+    ("<<", '«'),
+    (">>", '»'),
+    ("!I", '¡'),
+    ("?I", '¿'),
+    (".M", '·'),
+    ("-N", '–'),
+    ("-M", '—'),
+    ("'6", '‘'),
+    ("'9", '’'),
+    ("\"6", '“'),
+    ("\"9", '”'),
+    (".3", '…'),
+    ("12", '½'),
+    ("14", '¼'),
+    ("34", '¾'),
+    ("1S", '¹'),
+    ("2S", '²'),
+    ("3S", '³'),
+    ("->", '→'),
+    ("<-", '←'),
+    ("-!", '↑'),
+    ("-v", '↓'),
+    ("=>", '⇒'),
+    ("==", '⇔'),
+    ("=<", '≤'),
+    (">=", '≥'),
+    ("!=", '≠'),
+    ("?2", '≈'),
+    ("=3", '≡'),
+    ("00", '∞'),
+    ("FA", '∀'),
+    ("TE", '∃'),
+    ("dP", '∂'),
+    ("NB", '∇'),
+    ("(-", '∈'),
+    ("RT", '√'),
+    ("*P", '∏'),
+    ("+Z", '∑'),
+    ("AN", '∧'),
+    ("OR", '∨'),
+    ("(U", '∩'),
+    (")U", '∪'),
+    ("OK", '✓'),
+    ("XX", '✗'),
+    ("a*", 'α'),
+    ("b*", 'β'),
+    ("g*", 'γ'),
+    ("d*", 'δ'),
+    ("e*", 'ε'),
+    ("z*", 'ζ'),
+    ("y*", 'η'),
+    ("h*", 'θ'),
+    ("i*", 'ι'),
+    ("k*", 'κ'),
+    ("l*", 'λ'),
+    ("m*", 'μ'),
+    ("n*", 'ν'),
+    ("c*", 'ξ'),
+    ("o*", 'ο'),
+    ("p*", 'π'),
+    ("r*", 'ρ'),
+    ("s*", 'σ'),
+    ("t*", 'τ'),
+    ("u*", 'υ'),
+    ("f*", 'φ'),
+    ("x*", 'χ'),
+    ("q*", 'ψ'),
+    ("w*", 'ω'),
+    ("G*", 'Γ'),
+    ("D*", 'Δ'),
+    ("H*", 'Θ'),
+    ("L*", 'Λ'),
+    ("P*", 'Π'),
+    ("S*", 'Σ'),
+    ("F*", 'Φ'),
+    ("Q*", 'Ψ'),
+    ("W*", 'Ω'),
+];
+
+pub fn lookup(first: char, second: char) -> Option<char> {
+    let find = |a: char, b: char| {
+        DIGRAPHS.iter().find_map(|(keys, c)| {
+            let mut chars = keys.chars();
+
+            (chars.next() == Some(a) && chars.next() == Some(b)).then_some(*c)
+        })
+    };
+
+    find(first, second).or_else(|| find(second, first))
+}
+
+pub fn summary() -> String {
+    DIGRAPHS
+        .chunks(8)
+        .map(|row| {
+            row.iter()
+                .map(|(keys, c)| format!("{keys} {c}"))
+                .collect::<Vec<_>>()
+                .join("    ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
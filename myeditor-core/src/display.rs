@@ -0,0 +1,142 @@
+use crate::width;
+use ropey::RopeSlice;
+
+pub const TAB_WIDTH: usize = 4;
+pub const WIDE_TAIL: char = '\0';
+
+pub fn trim_newline(line: RopeSlice<'_>) -> RopeSlice<'_> {
+    let newline = line
+        .chars_at(line.len_chars())
+        .reversed()
+        .take_while(|&c| matches!(c, '\n' | '\r'))
+        .count();
+
+    line.slice(..line.len_chars() - newline)
+}
+
+#[derive(Debug, Clone)]
+pub struct Chars<'a> {
+    chars: ropey::iter::Chars<'a>,
+    tab: usize,
+    width: usize,
+}
+
+impl Iterator for Chars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.width == 0 {
+            return None;
+        }
+
+        let c = if self.tab > 0 {
+            self.tab -= 1;
+            ' '
+        } else {
+            match self.chars.next()? {
+                '\t' => {
+                    self.tab = TAB_WIDTH - 1;
+                    ' '
+                }
+                c => c,
+            }
+        };
+
+        self.width -= 1;
+
+        Some(c)
+    }
+}
+
+pub fn chars(line: RopeSlice<'_>, width: usize) -> Chars<'_> {
+    Chars {
+        chars: trim_newline(line).chars(),
+        tab: 0,
+        width,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cells<'a> {
+    chars: ropey::iter::Chars<'a>,
+    tail: char,
+    rest: usize,
+    col: usize,
+    start: usize,
+    end: usize,
+}
+
+impl Iterator for Cells<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        while self.col < self.end {
+            let c = if self.rest > 0 {
+                self.rest -= 1;
+                self.tail
+            } else {
+                let c = self.chars.next()?;
+
+                self.rest = char_columns(c) - 1;
+
+                match c {
+                    '\t' => {
+                        self.tail = ' ';
+                        ' '
+                    }
+                    c if self.rest > 0 && self.col >= self.start && self.col + 1 < self.end => {
+                        self.tail = WIDE_TAIL;
+                        c
+                    }
+                    _ if self.rest > 0 => {
+                        self.tail = ' ';
+                        ' '
+                    }
+                    c => c,
+                }
+            };
+
+            self.col += 1;
+
+            if self.col > self.start {
+                return Some(c);
+            }
+        }
+
+        None
+    }
+}
+
+pub fn cells(line: RopeSlice<'_>, start: usize, width: usize) -> Cells<'_> {
+    Cells {
+        chars: trim_newline(line).chars(),
+        tail: ' ',
+        rest: 0,
+        col: 0,
+        start,
+        end: start.saturating_add(width),
+    }
+}
+
+pub fn columns(text: RopeSlice) -> usize {
+    text.chars().map(char_columns).sum()
+}
+
+pub fn indent(line: RopeSlice) -> Option<usize> {
+    let line = trim_newline(line);
+
+    line.chars().any(|c| !c.is_whitespace()).then(|| {
+        line.chars()
+            .take_while(|c| c.is_whitespace())
+            .map(char_columns)
+            .sum()
+    })
+}
+
+pub fn char_columns(c: char) -> usize {
+    if c == '\t' {
+        TAB_WIDTH
+    } else {
+        width::char_width(c).max(1)
+    }
+}
@@ -0,0 +1,8609 @@
+use crate::{
+    abbrev::{self, Abbreviations},
+    align, backup,
+    behavior::{self, Behavior, Newline},
+    bookmarks::{Bookmark, Bookmarks},
+    brackets,
+    buffer::{Buffer, BufferKind, LINE_BREAKS},
+    charinfo, clipboard,
+    colors::{self, ColorDepth},
+    conflicts::{self, Conflict, Section},
+    crash,
+    csv::{self, Table},
+    diagnostics::{self, Diagnostic, Diagnostics},
+    diff::{DiffView, RowKind},
+    digraph, display,
+    error::{describe_io_error, EditorError, Result},
+    events::{AppEvent, EventSource, Events, Queue},
+    expr, git,
+    grep::{self, GrepEvent, Search},
+    health, help, hex,
+    indent::{self, Indent},
+    input::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    jobs::Jobs,
+    json::{self, Value},
+    keymap::{self, Keymap, Lookup},
+    links, logger,
+    lsp::{self, CodeActions, Signature},
+    markdown,
+    marks::{GlobalMark, GlobalMarks},
+    messages::{Message, Messages, Severity},
+    minimap,
+    mode::Mode,
+    options::{self, NumberStyle, Options},
+    overlay::{Overlays, Popup, Rect},
+    paths,
+    picker::{Picker, PickerItem, Target},
+    plugins::Plugins,
+    profile,
+    project::{self, ProjectConfig, Trust, Trusted},
+    prompt::{Answer, Prompt, PromptAction},
+    pty::Pty,
+    quickfix::{self, Finished, Quickfix},
+    range::{self, LineRange},
+    recent::RecentFiles,
+    reflow,
+    regex::Regex,
+    registers::{Register, Registers},
+    replace::{self, ReplacePanel, Row},
+    script,
+    scripts::Scripts,
+    search,
+    statusline::{self, Field, Item},
+    style::{Color, CursorStyle, StyledContent, Stylize},
+    substitute::{Flags, Substitute},
+    symbols::document_symbols,
+    tasks::{self, Date},
+    terminal::{Size, Terminal},
+    tutor::{self, Exercise},
+    undo::{self, UndoTree},
+    view,
+    vt::Vt,
+    whitespace, width,
+    wordcount::Counts,
+};
+use ropey::{Rope, RopeSlice};
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Output, Stdio},
+    time::{Duration, Instant, SystemTime},
+};
+
+const MAX_MATCH_COUNT: usize = 999;
+const RAINBOW: [Color; 3] = [Color::Yellow, Color::Magenta, Color::Cyan];
+const MIN_WIDTH: u16 = 10;
+const SHOWN_KEYS: usize = 8;
+const MIN_HEIGHT: u16 = 3;
+const GUTTER_MIN_WIDTH: u16 = 30;
+const GUTTER_MIN_DIGITS: u16 = 3;
+
+#[derive(Debug)]
+struct Term {
+    pty: Pty,
+    vt: Vt,
+    id: usize,
+    exited: bool,
+}
+
+#[derive(Debug)]
+enum InsertPending {
+    Literal,
+    Codepoint(char, String),
+    Digraph(Option<char>),
+    Register,
+}
+
+#[derive(Debug, Clone)]
+enum Repeat {
+    ChangeMatch { forward: bool, text: String },
+    DeleteMatch { forward: bool },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    GotoTop,
+    GotoBottom,
+    CodeActions,
+    CharInfo,
+    WordCount,
+    Suspend,
+    NextHunk,
+    PrevHunk,
+    NextConflict,
+    PrevConflict,
+    DiffGet,
+    DiffPut,
+    SwitchWindow,
+    Lua(usize),
+    RepeatSubstitute,
+    SelectMatch(bool),
+    ChangeMatch(bool),
+    DeleteMatch(bool),
+    RepeatChange,
+    FormatLine,
+    FormatParagraph,
+    Reselect,
+    MoveLines(bool),
+    Undo,
+    Redo,
+    ToggleBookmark,
+    NextCell(bool),
+    OpenLink,
+    GotoFile,
+    CommandPalette,
+    ToggleFold,
+    OpenFold,
+    CloseFold,
+    OpenFolds,
+    CloseFolds,
+}
+
+#[derive(Debug)]
+pub struct Editor {
+    terminal: Terminal,
+    events: Events,
+    mode: Mode,
+    buffer: Buffer,
+    buffers: Vec<Buffer>,
+    command: String,
+    messages: Messages,
+    dirty: bool,
+    picker: Option<Picker>,
+    code_actions: Option<CodeActions>,
+    signature: Option<Signature>,
+    lsp: Vec<lsp::Client>,
+    lsp_failed: Vec<String>,
+    grep: Option<Search>,
+    grep_id: usize,
+    make_id: usize,
+    jobs: Jobs,
+    commit_message: Option<(PathBuf, PathBuf)>,
+    restore: Option<PathBuf>,
+    follow: Option<PathBuf>,
+    check_id: usize,
+    diagnostics: Diagnostics,
+    line_diagnostics: std::collections::HashMap<usize, Diagnostic>,
+    quickfix: Quickfix,
+    replace: Option<ReplacePanel>,
+    undo_tree: Option<UndoTree>,
+    shown_keys: Option<Vec<String>>,
+    cwd: PathBuf,
+    previous_cwd: Option<PathBuf>,
+    recent: RecentFiles,
+    global_marks: GlobalMarks,
+    plugins: Plugins,
+    scripts: Scripts,
+    bookmarks: Bookmarks,
+    projects: Vec<ProjectConfig>,
+    trust: Trust,
+    untrusted: Vec<PathBuf>,
+    pending: Option<char>,
+    insert_pending: Option<InsertPending>,
+    abbreviations: Abbreviations,
+    registers: Registers,
+    last_substitute: Option<Substitute>,
+    last_search: Option<(String, bool)>,
+    search_forward: bool,
+    repeat: Option<Repeat>,
+    change_start: Option<usize>,
+    command_pending_register: bool,
+    expression: Option<String>,
+    visual_start: usize,
+    pager_scroll: usize,
+    search_lines: Option<(String, Vec<usize>)>,
+    keymap: Keymap<Action>,
+    sequence: Vec<String>,
+    sequence_started: Instant,
+    prompt: Option<Prompt>,
+    overlays: Overlays,
+    options: Options,
+    gutter: std::cell::Cell<Option<((usize, NumberStyle), u16)>>,
+    drawn_cursor_line: Option<usize>,
+    drawn_bracket: Option<(usize, usize)>,
+    table: Option<Table>,
+    drawn_field: Option<usize>,
+    folded: Vec<std::ops::Range<usize>>,
+    rainbow: std::collections::HashMap<(usize, usize), Color>,
+    swatches: std::collections::HashMap<usize, Vec<colors::Swatch>>,
+    bookmarked: Vec<usize>,
+    exercises: Vec<Exercise>,
+    preview: bool,
+    zen: bool,
+    term: Option<Term>,
+    term_id: usize,
+    hex_nibble: bool,
+    diff: Option<DiffView>,
+    conflicts: Vec<Conflict>,
+}
+
+impl Editor {
+    pub fn new(terminal: Terminal, events: Events) -> Self {
+        let editor = Self {
+            terminal,
+            events,
+            mode: Mode::Normal,
+            buffer: Buffer::new(),
+            buffers: Vec::new(),
+            command: String::new(),
+            messages: Messages::default(),
+            dirty: true,
+            picker: None,
+            code_actions: None,
+            signature: None,
+            lsp: Vec::new(),
+            lsp_failed: Vec::new(),
+            grep: None,
+            grep_id: 0,
+            make_id: 0,
+            jobs: Jobs::default(),
+            commit_message: None,
+            restore: None,
+            follow: None,
+            check_id: 0,
+            diagnostics: Diagnostics::default(),
+            line_diagnostics: Default::default(),
+            quickfix: Quickfix::default(),
+            replace: None,
+            undo_tree: None,
+            shown_keys: None,
+            cwd: std::env::current_dir().unwrap_or_default(),
+            previous_cwd: None,
+            recent: RecentFiles::load(),
+            global_marks: GlobalMarks::load(),
+            plugins: Plugins::default(),
+            scripts: Scripts::default(),
+            bookmarks: Bookmarks::load(),
+            projects: Vec::new(),
+            trust: Trust::load(),
+            untrusted: Vec::new(),
+            pending: None,
+            insert_pending: None,
+            abbreviations: Abbreviations::default(),
+            registers: Registers::default(),
+            last_substitute: None,
+            last_search: None,
+            search_forward: true,
+            repeat: None,
+            change_start: None,
+            command_pending_register: false,
+            expression: None,
+            visual_start: 0,
+            pager_scroll: 0,
+            search_lines: None,
+            keymap: default_keymap(),
+            sequence: Vec::new(),
+            sequence_started: Instant::now(),
+            prompt: None,
+            overlays: Overlays::default(),
+            options: Options::default(),
+            gutter: std::cell::Cell::new(None),
+            drawn_cursor_line: None,
+            drawn_bracket: None,
+            table: None,
+            drawn_field: None,
+            folded: Vec::new(),
+            rainbow: Default::default(),
+            swatches: Default::default(),
+            bookmarked: Vec::new(),
+            exercises: Vec::new(),
+            preview: false,
+            zen: false,
+            term: None,
+            term_id: 0,
+            hex_nibble: false,
+            diff: None,
+            conflicts: Vec::new(),
+        };
+
+        profile::mark("data files");
+
+        editor
+    }
+
+    pub fn new_with_file(terminal: Terminal, events: Events, path: PathBuf, binary: bool) -> Self {
+        let mut editor = Self::new(terminal, events);
+        let path = editor.cwd.join(path);
+
+        let buffer = if binary {
+            Buffer::from_file_hex(path.clone())
+        } else {
+            editor.read_file(path.clone())
+        };
+
+        profile::mark("read file");
+
+        match buffer {
+            Ok(buffer) => {
+                editor.buffer = buffer;
+                editor.restore_position();
+                editor.warn_if_lossy();
+                editor.note_large_file();
+                editor.load_project_config();
+
+                profile::mark("project config");
+            }
+            Err(e) => {
+                editor.messages.error(format!(
+                    "Can't open {}: {}",
+                    display_path(&path, &editor.cwd),
+                    describe_io_error(&e)
+                ));
+                editor.buffer.path = Some(path);
+                editor.buffer.read_only = true;
+            }
+        }
+
+        editor
+    }
+
+    pub fn new_diff(terminal: Terminal, events: Events, a: PathBuf, b: PathBuf) -> Self {
+        let mut editor = Self::new_with_file(terminal, events, a, false);
+        let b = editor.cwd.join(b);
+
+        let other = Buffer::from_file(b.clone()).unwrap_or_else(|e| {
+            editor.messages.error(format!(
+                "Can't open {}: {}",
+                display_path(&b, &editor.cwd),
+                describe_io_error(&e)
+            ));
+
+            Buffer {
+                path: Some(b),
+                read_only: true,
+                ..Buffer::new()
+            }
+        });
+
+        editor.diff = Some(DiffView::new(other));
+
+        editor
+    }
+
+    pub fn wait_for_input(&mut self, timeout: Duration) -> bool {
+        self.events.wait(timeout)
+    }
+
+    pub fn frame_interval(&self) -> Option<Duration> {
+        (self.options.maxfps > 0).then(|| Duration::from_secs(1) / self.options.maxfps)
+    }
+
+    pub fn handle_event(&mut self) -> Result<Option<EventResult>> {
+        let log_file = logger::log_file();
+
+        self.events.watch(
+            self.buffer
+                .path
+                .clone()
+                .filter(|path| Some(path) != log_file.as_ref()),
+        );
+
+        let timeout = [
+            self.messages.time_left(),
+            self.sequence_time_left(),
+            self.jobs.next_frame(Instant::now()),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+
+        if let Some(timeout) = timeout {
+            if !self.events.wait(timeout) {
+                self.messages.expire();
+
+                if self.sequence_time_left().is_some_and(|left| left.is_zero()) {
+                    self.finish_sequence()?;
+                }
+
+                return Ok(None);
+            }
+        }
+
+        match self.events.recv() {
+            AppEvent::Input(event) => self.handle_input(event),
+            AppEvent::InputClosed => Ok(Some(EventResult::Quit)),
+            AppEvent::Grep(id, event) => {
+                if id == self.grep_id {
+                    self.handle_grep_event(event);
+                }
+
+                Ok(None)
+            }
+            AppEvent::Make(id, finished) => {
+                if self.jobs.finish(id) {
+                    self.make_finished(finished);
+                }
+
+                Ok(None)
+            }
+            AppEvent::Check(id, finished) => {
+                if self.jobs.finish(id) {
+                    self.check_finished(finished);
+                }
+
+                Ok(None)
+            }
+            AppEvent::LineIndex(path) => {
+                for buffer in std::iter::once(&mut self.buffer).chain(&mut self.buffers) {
+                    if let Some(file) = buffer.large.as_mut() {
+                        if buffer.path.as_ref() == Some(&path) {
+                            file.refresh();
+                        }
+                    }
+                }
+
+                self.dirty = true;
+
+                Ok(None)
+            }
+            AppEvent::FileChanged(path) => {
+                if self.buffer.path.as_ref() != Some(&path) {
+                    return Ok(None);
+                }
+
+                if self.follow.as_ref() == Some(&path) && !self.buffer.modified {
+                    if let Err(e) = self.follow_file() {
+                        self.report(e);
+                    }
+                } else if self.prompt.is_none() {
+                    self.check_disk_changes();
+                }
+
+                Ok(None)
+            }
+            AppEvent::Terminal(id, bytes) => {
+                if let Some(term) = self.term.as_mut().filter(|term| term.id == id) {
+                    term.vt.feed(&bytes);
+                    self.dirty = true;
+                }
+
+                Ok(None)
+            }
+            AppEvent::Suspend => {
+                self.suspend()?;
+
+                Ok(None)
+            }
+            AppEvent::TerminalExited(id) => {
+                self.terminal_exited(id)?;
+
+                Ok(None)
+            }
+            AppEvent::Remote(request) => {
+                let (reply, result) = match self.handle_remote(&request.method, &request.params) {
+                    Ok((value, result)) => (Ok(value), result),
+                    Err(e) => (Err(e.to_string()), None),
+                };
+
+                let _ = request.reply.send(reply);
+                self.dirty = true;
+
+                Ok(result)
+            }
+        }
+    }
+
+    pub fn handle_input(&mut self, event: Event) -> Result<Option<EventResult>> {
+        match event {
+            Event::Key(event) if event.kind == KeyEventKind::Release => {
+                self.show_key(&event);
+
+                Ok(None)
+            }
+            Event::Key(event) => {
+                self.show_key(&event);
+                crash::record_key(key_name(&event).unwrap_or_else(|| format!("{:?}", event.code)));
+
+                let result = self.handle_key(self.legacy_key(event));
+
+                self.commit_change();
+                self.keep_window();
+                self.update_folds();
+                self.scroll_to_cursor();
+                self.check_exercises();
+
+                crash::set_files(
+                    std::iter::once(&self.buffer)
+                        .chain(&self.buffers)
+                        .filter_map(|buffer| buffer.path.as_ref())
+                        .map(|path| path.display().to_string())
+                        .collect(),
+                );
+
+                result
+            }
+            Event::FocusGained => {
+                if self.prompt.is_none() {
+                    self.check_disk_changes();
+                }
+
+                self.clear()?;
+                self.dirty = true;
+
+                Ok(None)
+            }
+            Event::FocusLost => {
+                if self.options.autosave {
+                    self.autosave();
+                }
+
+                Ok(None)
+            }
+            Event::Resize(width, height) => {
+                self.terminal.resize(Size {
+                    width: width.max(1),
+                    height: height.max(2),
+                })?;
+                self.dirty = true;
+
+                Ok(None)
+            }
+            Event::Paste(text) => self.paste(&text),
+        }
+    }
+
+    pub fn play(&mut self, source: &mut dyn EventSource) -> Result<Option<EventResult>> {
+        while let Ok(Some(event)) = source.read(Duration::ZERO) {
+            if let Some(result) = self.handle_input(event)? {
+                return Ok(Some(result));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn paste(&mut self, text: &str) -> Result<Option<EventResult>> {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+        if matches!(self.mode, Mode::Insert) {
+            self.insert_text(&text)?;
+            self.commit_change();
+
+            return Ok(None);
+        }
+
+        for c in text.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\t' => KeyCode::Tab,
+                c => KeyCode::Char(c),
+            };
+
+            if let Some(result) =
+                self.handle_input(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))?
+            {
+                return Ok(Some(result));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn handle_remote(
+        &mut self,
+        method: &str,
+        params: &Value,
+    ) -> Result<(Value, Option<EventResult>)> {
+        let string = |name: &str| {
+            params
+                .get(name)
+                .and_then(Value::as_str)
+                .ok_or_else(|| EditorError::Command(format!("Missing string parameter {name:?}")))
+        };
+
+        match method {
+            "open" => {
+                self.open_file(self.resolve_path(string("path")?))?;
+
+                if let Some(line) = params.get("line").and_then(Value::as_u64) {
+                    self.goto_line(line as usize);
+                }
+
+                Ok((Value::Null, None))
+            }
+            "command" => {
+                self.command = string("command")?.to_string();
+
+                let result = self.run_command();
+
+                self.command.clear();
+                self.commit_change();
+
+                Ok((Value::Null, result?))
+            }
+            "keys" => {
+                let mut keys = Queue::new(script::parse(string("keys")?)?);
+
+                Ok((Value::Null, self.play(&mut keys)?))
+            }
+            "paste" => {
+                let text = string("text")?.to_string();
+
+                Ok((Value::Null, self.handle_input(Event::Paste(text))?))
+            }
+            "resize" => {
+                let number = |name: &str| {
+                    params
+                        .get(name)
+                        .and_then(Value::as_u64)
+                        .and_then(|n| u16::try_from(n).ok())
+                        .ok_or_else(|| {
+                            EditorError::Command(format!("Missing number parameter {name:?}"))
+                        })
+                };
+                let event = Event::Resize(number("width")?, number("height")?);
+
+                Ok((Value::Null, self.handle_input(event)?))
+            }
+            "buffer" => Ok((
+                Value::object([
+                    (
+                        "path",
+                        self.buffer
+                            .path
+                            .as_ref()
+                            .map_or(Value::Null, |path| path.display().to_string().into()),
+                    ),
+                    ("text", self.buffer.text.to_string().into()),
+                    ("line", (self.buffer.cursor.1 + 1).into()),
+                    ("col", (self.buffer.cursor.0 + 1).into()),
+                    ("modified", self.buffer.modified.into()),
+                ]),
+                None,
+            )),
+            _ => Err(format!("Unknown method: {method}").into()),
+        }
+    }
+
+    pub fn goto_line(&mut self, line: usize) {
+        self.jump_to(line.saturating_sub(1), 0);
+    }
+
+    pub fn follow(&mut self) -> Result<()> {
+        let Some(path) = self
+            .buffer
+            .path
+            .clone()
+            .filter(|_| self.buffer.kind == BufferKind::File)
+        else {
+            return Err("Can only follow a file".into());
+        };
+
+        self.messages
+            .info(format!("Following {}", display_path(&path, &self.cwd)));
+        self.follow = Some(path);
+
+        if self.buffer.changed_on_disk() && !self.buffer.modified {
+            self.reload_buffer()?;
+        }
+
+        self.scroll_to_end();
+
+        Ok(())
+    }
+
+    fn follow_file(&mut self) -> Result<()> {
+        let at_end = self.buffer.cursor.1 >= self.last_text_line();
+
+        self.reload_buffer()?;
+
+        if at_end {
+            self.scroll_to_end();
+        }
+
+        Ok(())
+    }
+
+    fn scroll_to_end(&mut self) {
+        let last = self.last_text_line();
+        let height = (self.terminal.size().height as usize).saturating_sub(1);
+
+        self.jump_to(last, 0);
+        self.buffer.view.top_line = (last + 1).saturating_sub(height);
+        self.dirty = true;
+    }
+
+    fn last_text_line(&self) -> usize {
+        let last = self.buffer.text.len_lines().saturating_sub(1);
+
+        if last > 0 && self.buffer.text.line(last).len_chars() == 0 {
+            last - 1
+        } else {
+            last
+        }
+    }
+
+    fn handle_key(&mut self, event: KeyEvent) -> Result<Option<EventResult>> {
+        log::trace!(
+            "key {:?} {:?} in {}",
+            event.code,
+            event.modifiers,
+            self.mode
+        );
+
+        if event.code == KeyCode::Char('c')
+            && event.modifiers == KeyModifiers::CONTROL
+            && !matches!(self.mode, Mode::Terminal)
+            && self.cancel_job()
+        {
+            return Ok(None);
+        }
+
+        if self.pager_active() && self.handle_pager_key(event) {
+            return Ok(None);
+        }
+
+        if self
+            .messages
+            .current()
+            .is_some_and(|message| self.is_long_message(message))
+        {
+            self.messages.dismiss();
+            self.pager_scroll = 0;
+        }
+
+        if self.prompt.is_some() {
+            return Ok(self.handle_prompt_key(event));
+        }
+
+        if self.expression.is_some() {
+            self.handle_expression_key(event)?;
+
+            return Ok(None);
+        }
+
+        if self.picker.is_some() {
+            return Ok(self.handle_picker_key(event));
+        }
+
+        if self.replace.is_some() {
+            self.handle_replace_key(event);
+
+            return Ok(None);
+        }
+
+        if self.code_actions.is_some() {
+            self.handle_code_action_key(event);
+
+            return Ok(None);
+        }
+
+        if self.undo_tree.is_some() {
+            self.handle_undo_tree_key(event);
+
+            return Ok(None);
+        }
+
+        if !self.sequence.is_empty() {
+            return self.handle_sequence_key(event).map(|_| None);
+        }
+
+        if matches!(self.mode, Mode::Normal)
+            && self.pending.is_none()
+            && self.buffer.is_directory()
+            && self.handle_directory_key(event)?
+        {
+            return Ok(None);
+        }
+
+        if matches!(self.mode, Mode::Normal)
+            && self.pending.is_none()
+            && self.buffer.is_help()
+            && self.handle_help_key(event)
+        {
+            return Ok(None);
+        }
+
+        if matches!(self.mode, Mode::Normal)
+            && self.pending.is_none()
+            && self.buffer.is_git_status()
+            && self.handle_git_status_key(event)?
+        {
+            return Ok(None);
+        }
+
+        if matches!(self.mode, Mode::Normal)
+            && self.pending.is_none()
+            && self.buffer.is_hex()
+            && self.handle_hex_key(event)
+        {
+            return Ok(None);
+        }
+
+        if matches!(self.mode, Mode::Normal) && self.pending.is_none() && self.plugin_key(&event) {
+            return Ok(None);
+        }
+
+        if matches!(self.mode, Mode::Normal)
+            && self.pending.is_none()
+            && self.handle_sequence_key(event)?
+        {
+            return Ok(None);
+        }
+
+        if matches!(self.mode, Mode::Command | Mode::Search)
+            && self.handle_command_register_key(event)
+        {
+            return Ok(None);
+        }
+
+        match self.mode {
+            Mode::Normal if self.pending.is_some() => {
+                if let (Some(prefix), KeyCode::Char(c)) = (self.pending.take(), event.code) {
+                    if let Err(e) = self.handle_pending(prefix, c) {
+                        self.report(e);
+                    }
+                }
+            }
+            Mode::Normal => match event.code {
+                KeyCode::Char(c) => match c {
+                    'i' => self.insert_mode()?,
+                    'I' => {
+                        self.buffer.cursor.0 = 0;
+                        self.insert_mode()?;
+                    }
+                    'a' => {
+                        self.insert_mode()?;
+                        self.move_cursor_right();
+                    }
+                    'A' => {
+                        self.buffer.cursor.0 =
+                            self.line_len(self.buffer.text.line(self.buffer.cursor.1));
+                        self.insert_mode()?;
+                        self.move_cursor_right();
+                    }
+                    'h' => self.move_cursor_left(),
+                    'j' => self.move_cursor_down(),
+                    'k' => self.move_cursor_up(),
+                    'l' => self.move_cursor_right(),
+                    ':' => {
+                        self.messages.dismiss();
+                        self.command_mode()?;
+                    }
+                    '/' | '?' => {
+                        self.messages.dismiss();
+                        self.search_mode(c == '/')?;
+                    }
+                    'n' | 'N' => {
+                        if let Err(e) = self.search_next(c == 'n') {
+                            self.report(e);
+                        }
+                    }
+                    '*' | '#' => {
+                        if let Err(e) = self.search_word(c == '*') {
+                            self.report(e);
+                        }
+                    }
+                    'm' | '\'' | '`' => self.pending = Some(c),
+                    'V' if !self.buffer.is_hex() => self.visual_mode(self.buffer.cursor.1),
+                    '%' => self.jump_to_matching_bracket(),
+                    '0' => self.move_to_line_start(),
+                    '^' => self.move_to_first_non_blank(),
+                    '$' => {
+                        self.buffer.cursor.0 =
+                            self.line_len(self.buffer.text.line(self.buffer.cursor.1))
+                    }
+                    _ => {}
+                },
+                KeyCode::Left => self.move_cursor_left(),
+                KeyCode::Down => self.move_cursor_down(),
+                KeyCode::Up => self.move_cursor_up(),
+                KeyCode::Right => self.move_cursor_right(),
+                KeyCode::Home => self.move_to_line_start(),
+                _ => {}
+            },
+            Mode::Visual => {
+                if let Err(e) = self.handle_visual_key(event) {
+                    self.report(e);
+                }
+            }
+            Mode::Insert if self.insert_pending.is_some() => {
+                if let Some(event) = self.handle_insert_pending(event)? {
+                    return self.handle_key(event);
+                }
+            }
+            Mode::Insert => match event.code {
+                KeyCode::Esc => {
+                    self.expand_abbreviation()?;
+                    self.finish_change();
+                    self.move_cursor_left();
+                    self.normal_mode()?;
+                }
+                KeyCode::Backspace => self.backspace()?,
+                KeyCode::Enter => {
+                    self.expand_abbreviation()?;
+                    self.enter()?;
+                }
+                KeyCode::Left => self.move_cursor_left(),
+                KeyCode::Down => self.move_cursor_down(),
+                KeyCode::Up => self.move_cursor_up(),
+                KeyCode::Right => self.move_cursor_right(),
+                KeyCode::Home => self.move_to_line_start(),
+                KeyCode::Char('v') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.insert_pending = Some(InsertPending::Literal);
+                }
+                KeyCode::Char('k') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.insert_pending = Some(InsertPending::Digraph(None));
+                }
+                KeyCode::Char('r') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.insert_pending = Some(InsertPending::Register);
+                }
+                KeyCode::Char(c) => {
+                    if !abbrev::is_word_char(c) {
+                        self.expand_abbreviation()?;
+                    }
+
+                    self.insert_char(c)?;
+
+                    if !c.is_whitespace() {
+                        self.auto_wrap()?;
+                    }
+
+                    match c {
+                        '(' | ',' => self.signature_help(c),
+                        ')' => self.signature = None,
+                        _ => {}
+                    }
+                }
+                KeyCode::Tab => {
+                    self.expand_abbreviation()?;
+                    self.insert_tab()?;
+                }
+                _ => {}
+            },
+            Mode::Terminal => {
+                if matches!(event.code, KeyCode::Char('\\' | '4'))
+                    && event.modifiers.contains(KeyModifiers::CONTROL)
+                {
+                    self.normal_mode()?;
+                } else if let (Some(term), Some(bytes)) = (&mut self.term, key_bytes(&event)) {
+                    term.pty.write(&bytes)?;
+                }
+            }
+            Mode::Command => match event.code {
+                KeyCode::Char(c) => self.command.push(c),
+                KeyCode::Esc => {
+                    self.command.clear();
+                    self.normal_mode()?;
+                }
+                KeyCode::Enter => {
+                    match self.run_command() {
+                        Ok(res) => {
+                            if let Some(res) = res {
+                                return Ok(Some(res));
+                            }
+                        }
+                        Err(e) => self.report(e),
+                    }
+
+                    self.command.clear();
+
+                    if matches!(self.mode, Mode::Command) {
+                        self.normal_mode()?;
+                    }
+                }
+                KeyCode::Backspace => {
+                    if self.command.is_empty() {
+                        self.normal_mode()?;
+                    } else {
+                        self.command.pop();
+                    }
+                }
+                _ => {}
+            },
+            Mode::Search => match event.code {
+                KeyCode::Char(c) => self.command.push(c),
+                KeyCode::Esc => {
+                    self.command.clear();
+                    self.normal_mode()?;
+                }
+                KeyCode::Enter => {
+                    let pattern = std::mem::take(&mut self.command);
+
+                    self.normal_mode()?;
+
+                    if let Err(e) = self.search(pattern, self.search_forward) {
+                        self.report(e);
+                    }
+                }
+                KeyCode::Backspace => {
+                    if self.command.is_empty() {
+                        self.normal_mode()?;
+                    } else {
+                        self.command.pop();
+                    }
+                }
+                _ => {}
+            },
+        }
+
+        Ok(None)
+    }
+
+    fn handle_directory_key(&mut self, event: KeyEvent) -> Result<bool> {
+        let dir = self.buffer.path.clone().unwrap_or_default();
+
+        match event.code {
+            KeyCode::Enter => {
+                if let Some(entry) = self.buffer.entry_under_cursor() {
+                    if let Err(e) = self.open_file(entry) {
+                        self.report(e);
+                    }
+                }
+            }
+            KeyCode::Char('-') => {
+                let name = dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned());
+
+                if let Some(parent) = dir.parent() {
+                    match self.open_file(parent.to_path_buf()) {
+                        Ok(()) => self.select_directory_entry(name),
+                        Err(e) => self.report(e),
+                    }
+                }
+            }
+            KeyCode::Char('%') => self.prefill_command(format!("e {}/", dir.display()))?,
+            KeyCode::Char('d') => self.prefill_command(format!("mkdir {}/", dir.display()))?,
+            KeyCode::Char('i' | 'I' | 'a' | 'A') => {
+                self.messages.error("Directory buffers are read-only");
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    fn handle_git_status_key(&mut self, event: KeyEvent) -> Result<bool> {
+        let root = self.buffer.path.clone().unwrap_or_default();
+        let entry = self
+            .buffer
+            .text
+            .get_line(self.buffer.cursor.1)
+            .and_then(|line| git::parse_status_line(line.to_string().trim_end_matches('\n')));
+
+        match (event.code, entry) {
+            (KeyCode::Enter, Some(entry)) => self.open_file(root.join(entry.path))?,
+            (KeyCode::Char('s'), Some(entry)) => {
+                git::stage(&root, &entry.path).map_err(EditorError::Command)?;
+                self.refresh_git_status()?;
+            }
+            (KeyCode::Char('u'), Some(entry)) => {
+                git::unstage(&root, &entry.path).map_err(EditorError::Command)?;
+                self.refresh_git_status()?;
+            }
+            (KeyCode::Char('c'), _) => self.open_commit_message()?,
+            (KeyCode::Char('R'), _) => self.refresh_git_status()?,
+            (KeyCode::Char('q'), _) => self.close_special_buffer(),
+            (KeyCode::Char('i' | 'I' | 'a' | 'A'), _) => {
+                self.messages.error("The git status buffer is read-only");
+            }
+            (KeyCode::Enter | KeyCode::Char('s' | 'u'), None) => {
+                self.messages.error("No file on this line");
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    fn git_root(&self) -> Result<PathBuf> {
+        let dir = match &self.buffer.path {
+            Some(path) if self.buffer.is_git_status() => path.clone(),
+            Some(path) if path.is_dir() => path.clone(),
+            Some(path) => path.parent().unwrap_or(&self.cwd).to_path_buf(),
+            None => self.cwd.clone(),
+        };
+
+        git::root(&dir).map_err(EditorError::Command)
+    }
+
+    fn open_git_status(&mut self) -> Result<()> {
+        let root = self.git_root()?;
+        let (branch, entries) = git::status(&root).map_err(EditorError::Command)?;
+        let text = git::status_text(&branch, &entries);
+
+        if !self.buffer.is_git_status() {
+            self.remember_position();
+
+            if let Some(idx) = self.buffers.iter().position(Buffer::is_git_status) {
+                self.buffers.remove(idx);
+            }
+
+            let previous = std::mem::replace(&mut self.buffer, Buffer::from_git_status(root, ""));
+
+            if previous.path.is_some() || previous.text.len_chars() > 0 {
+                self.buffers.push(previous);
+            }
+        } else {
+            self.buffer.path = Some(root);
+        }
+
+        self.buffer.text = text.into();
+        self.jump_to(3, 0);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn refresh_git_status(&mut self) -> Result<()> {
+        let Some(root) = self
+            .buffer
+            .path
+            .clone()
+            .filter(|_| self.buffer.is_git_status())
+        else {
+            return Ok(());
+        };
+
+        let (branch, entries) = git::status(&root).map_err(EditorError::Command)?;
+
+        self.buffer.text = git::status_text(&branch, &entries).into();
+
+        let (col, line) = self.buffer.cursor;
+        self.jump_to(line, col);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn close_special_buffer(&mut self) {
+        match self.buffers.pop() {
+            Some(previous) => {
+                self.buffer = previous;
+                self.check_disk_changes();
+            }
+            None => self.buffer = Buffer::new(),
+        }
+
+        self.dirty = true;
+    }
+
+    fn open_git_diff(&mut self) -> Result<()> {
+        if self.diff.is_some() {
+            return Err("Already in diff mode, :diffoff first".into());
+        }
+
+        let path = self.buffer.path.clone().ok_or("No file name")?;
+        let path = path.canonicalize().unwrap_or(path);
+        let root = self.git_root()?;
+        let root = root.canonicalize().unwrap_or(root);
+        let text = git::show_head(&root, &path).map_err(EditorError::Command)?;
+
+        let other = Buffer {
+            text: text.as_str().into(),
+            read_only: true,
+            ..Buffer::new()
+        };
+
+        self.diff = Some(DiffView::new(other));
+        self.dirty = true;
+        self.messages.info("Left: working tree, right: HEAD");
+
+        Ok(())
+    }
+
+    fn open_commit_message(&mut self) -> Result<()> {
+        let root = self.git_root()?;
+        let (_, entries) = git::status(&root).map_err(EditorError::Command)?;
+        let path = git::commit_message_file(&root).map_err(EditorError::Command)?;
+
+        if std::iter::once(&self.buffer)
+            .chain(&self.buffers)
+            .any(|buffer| buffer.modified && buffer.is_file(&path))
+        {
+            return Err("The commit message has unsaved changes, :w commits it".into());
+        }
+
+        std::fs::write(&path, git::commit_template(&entries))
+            .map_err(|e| format!("Can't write {}: {}", path.display(), describe_io_error(&e)))?;
+
+        if let Some(idx) = self.buffers.iter().position(|buffer| buffer.is_file(&path)) {
+            self.buffers.remove(idx);
+        }
+
+        if self.buffer.is_file(&path) {
+            self.buffer.reload()?;
+        } else {
+            self.open_file(path.clone())?;
+        }
+
+        self.jump_to(0, 0);
+        self.commit_message = Some((path, root));
+        self.insert_mode()?;
+
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<bool> {
+        let Some((path, root)) = self.commit_message.clone() else {
+            return Ok(false);
+        };
+
+        if !self.buffer.is_file(&path) {
+            return Ok(false);
+        }
+
+        let summary = git::commit(&root, &path).map_err(EditorError::Command)?;
+
+        self.commit_message = None;
+        self.close_special_buffer();
+        self.refresh_git_status()?;
+        self.messages.info(summary);
+
+        Ok(true)
+    }
+
+    fn handle_help_key(&mut self, event: KeyEvent) -> bool {
+        match event.code {
+            KeyCode::Enter => {}
+            KeyCode::Char(']') if event.modifiers.contains(KeyModifiers::CONTROL) => {}
+            KeyCode::Char('i' | 'I' | 'a' | 'A') => {
+                self.messages.error("Help buffers are read-only");
+
+                return true;
+            }
+            _ => return false,
+        }
+
+        let line = self.buffer.text.line(self.buffer.cursor.1).to_string();
+
+        let result = match help::reference_at(&line, self.buffer.cursor.0) {
+            Some(topic) => self.open_help(&topic),
+            None => Err("No tag under the cursor".into()),
+        };
+
+        if let Err(e) = result {
+            self.report(e);
+        }
+
+        true
+    }
+
+    fn open_help(&mut self, topic: &str) -> Result<()> {
+        let location = help::find(topic).ok_or_else(|| format!("Sorry, no help for {topic}"))?;
+
+        if !self.buffer.is_help() {
+            let buffer = match self.buffers.iter().position(Buffer::is_help) {
+                Some(idx) => self.buffers.remove(idx),
+                None => Buffer::from_help(location.text),
+            };
+
+            self.remember_position();
+
+            let previous = std::mem::replace(&mut self.buffer, buffer);
+
+            if previous.path.is_some() || previous.text.len_chars() > 0 {
+                self.buffers.push(previous);
+            }
+        }
+
+        if self.buffer.text != location.text {
+            self.buffer.text = location.text.into();
+        }
+
+        self.buffer.view.top_line = location.line;
+        self.jump_to(location.line, location.col);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    pub fn open_tutor(&mut self) {
+        if self.buffer.is_tutor() {
+            return;
+        }
+
+        let buffer = match self.buffers.iter().position(Buffer::is_tutor) {
+            Some(idx) => self.buffers.remove(idx),
+            None => Buffer::from_tutor(tutor::TEXT),
+        };
+
+        self.remember_position();
+
+        let previous = std::mem::replace(&mut self.buffer, buffer);
+
+        if previous.path.is_some() || previous.text.len_chars() > 0 {
+            self.buffers.push(previous);
+        }
+
+        self.exercises = tutor::exercises(&self.buffer.text);
+        self.dirty = true;
+    }
+
+    fn check_exercises(&mut self) {
+        if !self.buffer.is_tutor() {
+            self.exercises.clear();
+            return;
+        }
+
+        let done = |exercises: &[Exercise]| exercises.iter().filter(|e| e.done).count();
+        let before = done(&self.exercises);
+
+        self.exercises = tutor::exercises(&self.buffer.text);
+
+        let after = done(&self.exercises);
+
+        if after > before {
+            self.messages.info(format!(
+                "Well done! {after} of {} exercises",
+                self.exercises.len()
+            ));
+        }
+    }
+
+    fn close_help(&mut self) -> bool {
+        if !self.buffer.is_help() {
+            return false;
+        }
+
+        match self.buffers.pop() {
+            Some(previous) => {
+                self.buffer = previous;
+                self.check_disk_changes();
+            }
+            None => self.buffer = Buffer::new(),
+        }
+
+        self.dirty = true;
+
+        true
+    }
+
+    fn handle_hex_key(&mut self, event: KeyEvent) -> bool {
+        let offset = self.buffer.hex_offset();
+
+        let target = match event.code {
+            KeyCode::Char('h') | KeyCode::Left => offset.saturating_sub(1),
+            KeyCode::Char('l') | KeyCode::Right => offset + 1,
+            KeyCode::Char('k') | KeyCode::Up => offset.saturating_sub(hex::BYTES_PER_LINE),
+            KeyCode::Char('j') | KeyCode::Down => offset + hex::BYTES_PER_LINE,
+            KeyCode::Char('0') => offset - offset % hex::BYTES_PER_LINE,
+            KeyCode::Char('$') => offset - offset % hex::BYTES_PER_LINE + hex::BYTES_PER_LINE - 1,
+            KeyCode::Char('x') if !self.buffer.read_only => {
+                self.buffer.remove_byte(offset);
+                self.dirty = true;
+                offset
+            }
+            KeyCode::Char(c) if hex::digit(c).is_some() => {
+                if self.buffer.read_only {
+                    self.messages.error("Buffer is read-only");
+
+                    return true;
+                }
+
+                let digit = hex::digit(c).unwrap_or_default();
+                let byte = self.buffer.bytes.get(offset).copied().unwrap_or_default();
+
+                if self.hex_nibble {
+                    self.buffer.set_byte(offset, (byte & 0xf0) | digit);
+                    self.hex_nibble = false;
+                    offset + 1
+                } else {
+                    self.buffer.set_byte(offset, (digit << 4) | (byte & 0x0f));
+                    self.hex_nibble = true;
+                    self.dirty = true;
+
+                    return true;
+                }
+            }
+            KeyCode::Char('i' | 'I' | 'a' | 'A') => {
+                self.messages.warn("Type hex digits to edit bytes");
+
+                return true;
+            }
+            _ => return false,
+        };
+
+        self.hex_nibble = false;
+        self.dirty = true;
+
+        let target = std::cmp::min(target, self.buffer.bytes.len());
+        self.jump_to(target / hex::BYTES_PER_LINE, target % hex::BYTES_PER_LINE);
+
+        true
+    }
+
+    fn toggle_hex(&mut self) -> Result<()> {
+        if self.buffer.is_directory() {
+            return Err("Directory buffers can't be shown as hex".into());
+        }
+
+        if self.buffer.is_large() {
+            return Err("Large files can't be shown as hex".into());
+        }
+
+        if self.buffer.is_hex() {
+            self.buffer.leave_hex()?;
+        } else {
+            self.buffer.enter_hex();
+        }
+
+        let (col, line) = self.buffer.cursor;
+        self.hex_nibble = false;
+        self.jump_to(line, col);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn select_directory_entry(&mut self, name: Option<String>) {
+        let Some(name) = name else {
+            return;
+        };
+
+        let line = self
+            .buffer
+            .text
+            .lines()
+            .position(|line| line.to_string().trim_end_matches(['\n', '/']) == name);
+
+        if let Some(line) = line {
+            self.jump_to(line, 0);
+        }
+    }
+
+    fn prefill_command(&mut self, command: String) -> Result<()> {
+        self.messages.dismiss();
+        self.command = command;
+        self.command_mode()
+    }
+
+    fn make_dir(&mut self, path: &str) -> Result<()> {
+        if path.is_empty() {
+            return Err("No directory name".into());
+        }
+
+        let path = self.resolve_path(path);
+
+        std::fs::create_dir_all(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+        self.buffer.refresh_directory()?;
+
+        self.messages
+            .info(format!("Created {}", display_path(&path, &self.cwd)));
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn legacy_key(&self, event: KeyEvent) -> KeyEvent {
+        if event.modifiers != KeyModifiers::CONTROL {
+            return event;
+        }
+
+        let code = match event.code {
+            KeyCode::Char('[') => KeyCode::Esc,
+            KeyCode::Char('i') => KeyCode::Tab,
+            KeyCode::Char('m') => KeyCode::Enter,
+            _ => return event,
+        };
+
+        let bound =
+            key_name(&event).is_some_and(|key| !matches!(self.keymap.lookup(&[key]), Lookup::None));
+
+        if bound {
+            event
+        } else {
+            KeyEvent::new(code, KeyModifiers::NONE)
+        }
+    }
+
+    fn handle_pending(&mut self, prefix: char, c: char) -> Result<()> {
+        match prefix {
+            'm' => self.set_mark(c),
+            '\'' => self.jump_to_mark(c, false),
+            '`' => self.jump_to_mark(c, true),
+            _ => Ok(()),
+        }
+    }
+
+    fn handle_sequence_key(&mut self, event: KeyEvent) -> Result<bool> {
+        let pending = !self.sequence.is_empty();
+
+        let Some(key) = key_name(&event) else {
+            self.sequence.clear();
+
+            return Ok(pending);
+        };
+
+        self.sequence.push(key);
+
+        match self.keymap.lookup(&self.sequence) {
+            Lookup::Action(&action) => {
+                self.sequence.clear();
+                self.run_action(action)?;
+
+                Ok(true)
+            }
+            Lookup::Prefix => {
+                self.sequence_started = Instant::now();
+
+                Ok(true)
+            }
+            Lookup::None => {
+                self.sequence.clear();
+
+                Ok(pending)
+            }
+        }
+    }
+
+    fn sequence_time_left(&self) -> Option<Duration> {
+        if self.sequence.is_empty() || self.options.timeoutlen == 0 {
+            return None;
+        }
+
+        let timeout = Duration::from_millis(self.options.timeoutlen);
+
+        Some(timeout.saturating_sub(self.sequence_started.elapsed()))
+    }
+
+    fn finish_sequence(&mut self) -> Result<()> {
+        let action = self.keymap.action(&self.sequence).copied();
+
+        self.sequence.clear();
+
+        match action {
+            Some(action) => self.run_action(action),
+            None => Ok(()),
+        }
+    }
+
+    fn run_action(&mut self, action: Action) -> Result<()> {
+        match action {
+            Action::GotoTop => {
+                self.goto_large_line(0, 0);
+
+                Ok(())
+            }
+            Action::GotoBottom => {
+                if self.buffer.is_large() {
+                    self.goto_large_offset(usize::MAX, 0);
+                } else {
+                    self.jump_to(self.last_text_line(), 0);
+                }
+
+                Ok(())
+            }
+            Action::CodeActions => self.code_actions(),
+            Action::CharInfo => {
+                self.show_char_info();
+
+                Ok(())
+            }
+            Action::WordCount => {
+                self.show_word_count();
+
+                Ok(())
+            }
+            Action::Suspend => self.suspend(),
+            Action::NextHunk => self.jump_to_hunk(true),
+            Action::PrevHunk => self.jump_to_hunk(false),
+            Action::NextConflict => self.jump_to_conflict(true),
+            Action::PrevConflict => self.jump_to_conflict(false),
+            Action::DiffGet => self.diff_get(),
+            Action::DiffPut => self.diff_put(),
+            Action::SwitchWindow => self.switch_diff_window(),
+            Action::Lua(id) => self.run_lua_mapping(id),
+            Action::RepeatSubstitute => self.repeat_substitute(None, ""),
+            Action::SelectMatch(forward) => {
+                let found = self.search_match(forward)?;
+
+                self.jump_to(found.line, found.col);
+
+                Ok(())
+            }
+            Action::ChangeMatch(forward) => self.change_match(forward),
+            Action::DeleteMatch(forward) => {
+                self.remove_match(forward)?;
+                self.repeat = Some(Repeat::DeleteMatch { forward });
+
+                Ok(())
+            }
+            Action::RepeatChange => self.repeat_change(),
+            Action::FormatLine => self.format_lines(LineRange {
+                start: self.buffer.cursor.1,
+                end: self.buffer.cursor.1,
+            }),
+            Action::FormatParagraph => {
+                let paragraph = self.paragraph_at(self.buffer.cursor.1)?;
+
+                self.format_lines(paragraph)
+            }
+            Action::Reselect => self.reselect(),
+            Action::ToggleBookmark => self.toggle_bookmark(),
+            Action::NextCell(forward) => self.next_cell(forward),
+            Action::OpenLink => self.open_link(),
+            Action::GotoFile => self.goto_file(),
+            Action::CommandPalette => {
+                self.open_palette();
+
+                Ok(())
+            }
+            Action::ToggleFold => self.fold(None),
+            Action::OpenFold => self.fold(Some(false)),
+            Action::CloseFold => self.fold(Some(true)),
+            Action::OpenFolds => {
+                self.buffer.folds.clear();
+                self.dirty = true;
+
+                Ok(())
+            }
+            Action::CloseFolds => self.close_folds(),
+            Action::Undo => self.undo(false),
+            Action::Redo => self.undo(true),
+            Action::MoveLines(down) => {
+                let (col, line) = self.buffer.cursor;
+                let moved = self.move_by(
+                    LineRange {
+                        start: line,
+                        end: line,
+                    },
+                    down,
+                )?;
+
+                self.jump_to(moved.start, col);
+
+                Ok(())
+            }
+        }
+    }
+
+    fn jump_to_hunk(&mut self, forward: bool) -> Result<()> {
+        let diff = self.diff.as_ref().ok_or("Not in diff mode")?;
+        let line = self.buffer.cursor.1;
+
+        let target = if forward {
+            diff.next_hunk(line)
+        } else {
+            diff.prev_hunk(line)
+        };
+
+        match target {
+            Some(target) => {
+                self.jump_to(target, 0);
+                Ok(())
+            }
+            None => Err("No more changes".into()),
+        }
+    }
+
+    fn jump_to_conflict(&mut self, forward: bool) -> Result<()> {
+        let line = self.buffer.cursor.1;
+        let starts = conflicts::find_conflicts(&self.buffer.text)
+            .into_iter()
+            .map(|conflict| conflict.start);
+
+        let target = if forward {
+            starts.clone().find(|&start| start > line)
+        } else {
+            starts.rev().find(|&start| start < line)
+        };
+
+        match target {
+            Some(target) => {
+                self.jump_to(target, 0);
+                Ok(())
+            }
+            None => Err("No more conflicts".into()),
+        }
+    }
+
+    fn resolve_conflict(&mut self, keep: &str) -> Result<()> {
+        let line = self.buffer.cursor.1;
+        let conflict = conflicts::find_conflicts(&self.buffer.text)
+            .into_iter()
+            .find(|conflict| conflict.lines().contains(&line))
+            .ok_or("No conflict under the cursor")?;
+
+        let ours = self.buffer.lines_text(conflict.ours());
+        let theirs = self.buffer.lines_text(conflict.theirs());
+
+        let text = match keep {
+            "ours" => ours,
+            "theirs" => theirs,
+            "both" => ours + &theirs,
+            "none" => String::new(),
+            _ => return Err("Usage: :conflict ours|theirs|both|none".into()),
+        };
+
+        self.buffer.replace_lines(conflict.lines(), &text)?;
+        self.jump_to(conflict.start, 0);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn diff_get(&mut self) -> Result<()> {
+        let diff = self.diff.as_ref().ok_or("Not in diff mode")?;
+        let hunk = diff.hunk_at(self.buffer.cursor.1).ok_or("No change here")?;
+
+        let active = diff.active_range(hunk);
+        let text = diff.other.lines_text(diff.other_range(hunk));
+
+        if self.buffer.read_only {
+            return Err("Buffer is read-only".into());
+        }
+
+        self.buffer.replace_lines(active.clone(), &text)?;
+        self.jump_to(active.start, 0);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn diff_put(&mut self) -> Result<()> {
+        let diff = self.diff.as_mut().ok_or("Not in diff mode")?;
+        let hunk = diff
+            .hunk_at(self.buffer.cursor.1)
+            .ok_or("No change here")?
+            .clone();
+
+        let text = self.buffer.lines_text(diff.active_range(&hunk));
+        let other = diff.other_range(&hunk);
+
+        if diff.other.read_only {
+            return Err("Other buffer is read-only".into());
+        }
+
+        diff.other.replace_lines(other, &text)?;
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn switch_diff_window(&mut self) -> Result<()> {
+        let diff = self.diff.as_mut().ok_or("Only one window")?;
+
+        std::mem::swap(&mut self.buffer, &mut diff.other);
+        diff.left_active = !diff.left_active;
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn diff_off(&mut self) {
+        self.restore = None;
+
+        if let Some(diff) = self.diff.take() {
+            if diff.other.path.is_some() {
+                self.buffers.push(diff.other);
+            }
+
+            self.dirty = true;
+        }
+    }
+
+    fn scroll_diff(&mut self) {
+        let height = (self.terminal.size().height as usize).saturating_sub(1);
+        let cursor_line = self.buffer.cursor.1;
+
+        let Some(diff) = &mut self.diff else {
+            return;
+        };
+
+        let row = diff.row_of(cursor_line);
+
+        let top = if row < diff.top_row {
+            if cursor_line == 0 {
+                0
+            } else {
+                row
+            }
+        } else if row >= diff.top_row + height {
+            row + 1 - height
+        } else {
+            diff.top_row
+        };
+
+        if top != diff.top_row {
+            diff.top_row = top;
+            self.dirty = true;
+        }
+    }
+
+    fn diff_gutter(&self) -> u16 {
+        let lines = std::cmp::max(
+            self.buffer.text.len_lines(),
+            self.diff
+                .as_ref()
+                .map_or(0, |diff| diff.other.text.len_lines()),
+        );
+
+        gutter_width(lines)
+    }
+
+    fn draw_diff_row(&mut self, row: u16) -> Result<()> {
+        let width = self.terminal.size().width;
+        let half = width.saturating_sub(1) / 2;
+        let gutter = self.diff_gutter();
+
+        let Some(diff) = &self.diff else {
+            return Ok(());
+        };
+
+        let (left, right) = diff.sides(&self.buffer);
+        let cells = diff.rows.get(diff.top_row + row as usize).map(|row| {
+            (
+                diff_side(left, row.a, row.kind, gutter, half),
+                diff_side(right, row.b, row.kind, gutter, width - half - 1),
+            )
+        });
+
+        self.terminal.clear_line(row)?;
+
+        let Some((left, right)) = cells else {
+            self.terminal
+                .print_styled_at((0, row), format!("{:>1$}", "~", gutter as usize - 1).blue())?;
+            self.terminal
+                .print_styled_at((half, row), "│".dark_grey())?;
+
+            return self.terminal.print_styled_at(
+                (half + 1, row),
+                format!("{:>1$}", "~", gutter as usize - 1).blue(),
+            );
+        };
+
+        self.terminal.goto(0, row)?;
+
+        for span in left {
+            self.terminal.print_styled(span)?;
+        }
+
+        self.terminal
+            .print_styled_at((half, row), "│".dark_grey())?;
+
+        for span in right {
+            self.terminal.print_styled(span)?;
+        }
+
+        Ok(())
+    }
+
+    fn set_mark(&mut self, name: char) -> Result<()> {
+        let (col, line) = self.buffer.cursor;
+
+        if name.is_ascii_lowercase() {
+            self.buffer.marks.insert(name, (line, col));
+        } else if name.is_ascii_uppercase() {
+            let path = self.buffer.path.clone().ok_or("No file name")?;
+            let path = path.canonicalize().unwrap_or(path);
+
+            self.global_marks.set(name, GlobalMark { path, line, col });
+            self.global_marks.save()?;
+        } else {
+            return Err(format!("Invalid mark name: {name}").into());
+        }
+
+        Ok(())
+    }
+
+    fn bookmark_position(&self) -> Result<(PathBuf, usize)> {
+        let path = self.buffer.path.clone().ok_or("No file name")?;
+
+        Ok((path.canonicalize().unwrap_or(path), self.buffer.cursor.1))
+    }
+
+    fn toggle_bookmark(&mut self) -> Result<()> {
+        let (path, line) = self.bookmark_position()?;
+
+        if self.bookmarks.remove(&path, line) {
+            self.messages
+                .info(format!("Removed bookmark on line {}", line + 1));
+        } else {
+            self.bookmarks.set(Bookmark {
+                project: paths::project_root(&self.cwd),
+                path,
+                line,
+                note: String::new(),
+            });
+            self.messages.info(format!("Bookmarked line {}", line + 1));
+        }
+
+        self.dirty = true;
+        self.bookmarks.save()?;
+
+        Ok(())
+    }
+
+    fn behavior(&self) -> Option<&'static dyn Behavior> {
+        if self.buffer.kind != BufferKind::File {
+            return None;
+        }
+
+        behavior::for_path(self.buffer.path.as_deref())
+    }
+
+    fn open_link(&mut self) -> Result<()> {
+        let (col, line_idx) = self.buffer.cursor;
+        let line = self.buffer.text.line(line_idx).to_string();
+
+        if let Some(url) = links::url_at(&line, col) {
+            links::open_url(&url)?;
+            self.messages.info(format!("Opening {url}"));
+
+            return Ok(());
+        }
+
+        if self.behavior().is_none() {
+            return Err("No URL under the cursor".into());
+        }
+
+        self.toggle_checkbox()
+    }
+
+    fn goto_file(&mut self) -> Result<()> {
+        let (col, line_idx) = self.buffer.cursor;
+        let line = self.buffer.text.line(line_idx).to_string();
+        let (name, line) = links::path_at(&line, col).ok_or("No file name under the cursor")?;
+
+        let mut dirs = Vec::new();
+        dirs.extend(
+            self.buffer
+                .path
+                .as_deref()
+                .and_then(Path::parent)
+                .map(Path::to_path_buf),
+        );
+        dirs.extend(self.options.path.iter().map(|dir| self.resolve_path(dir)));
+        dirs.push(self.cwd.clone());
+
+        let path = if name.starts_with('~') {
+            Some(self.resolve_path(&name)).filter(|path| path.is_file())
+        } else {
+            links::find_file(&name, &dirs)
+        };
+
+        self.open_file(path.ok_or_else(|| format!("Can't find file \"{name}\" in path"))?)?;
+
+        if let Some(line) = line {
+            self.jump_to(line, 0);
+        }
+
+        Ok(())
+    }
+
+    fn toggle_checkbox(&mut self) -> Result<()> {
+        if self.buffer.read_only {
+            return Err("Buffer is read-only".into());
+        }
+
+        let line_idx = self.buffer.cursor.1;
+        let line = self.buffer.text.line(line_idx).to_string();
+        let Some(toggled) = self
+            .behavior()
+            .and_then(|behavior| behavior.toggle(line.trim_end_matches(['\n', '\r'])))
+        else {
+            return Err("No list item here".into());
+        };
+
+        self.buffer
+            .replace_lines(line_idx..line_idx + 1, &toggled)?;
+        self.buffer.cursor.0 = std::cmp::min(
+            self.buffer.cursor.0,
+            self.line_len(self.buffer.text.line(line_idx)),
+        );
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn fold(&mut self, close: Option<bool>) -> Result<()> {
+        let line = self.buffer.cursor.1;
+        let Some(fold) = self
+            .behavior()
+            .map(|behavior| behavior.folds(&self.buffer.text))
+            .unwrap_or_default()
+            .into_iter()
+            .rfind(|fold| fold.contains(&line))
+        else {
+            return Err("No fold here".into());
+        };
+
+        let closed = self.buffer.folds.contains(&fold.start);
+
+        if close.unwrap_or(!closed) {
+            if !closed {
+                self.buffer.folds.push(fold.start);
+            }
+
+            self.jump_to(fold.start, 0);
+        } else {
+            self.buffer.folds.retain(|&header| header != fold.start);
+        }
+
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn close_folds(&mut self) -> Result<()> {
+        let folds = self
+            .behavior()
+            .map(|behavior| behavior.folds(&self.buffer.text))
+            .unwrap_or_default();
+
+        if folds.is_empty() {
+            return Err("No folds in this buffer".into());
+        }
+
+        let line = self.buffer.cursor.1;
+        let outer = folds
+            .iter()
+            .find(|fold| fold.contains(&line))
+            .map_or(line, |fold| fold.start);
+
+        self.buffer.folds = folds.into_iter().map(|fold| fold.start).collect();
+        self.jump_to(outer, 0);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn update_folds(&mut self) {
+        if self.buffer.folds.is_empty() {
+            self.folded.clear();
+            return;
+        }
+
+        let line = self.buffer.cursor.1;
+        let folds = self
+            .behavior()
+            .map(|behavior| behavior.folds(&self.buffer.text))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|fold| self.buffer.folds.contains(&fold.start))
+            .filter(|fold| !(fold.start + 1..fold.end).contains(&line))
+            .collect::<Vec<_>>();
+
+        self.buffer
+            .folds
+            .retain(|&header| folds.iter().any(|fold| fold.start == header));
+
+        let mut folded: Vec<std::ops::Range<usize>> = Vec::new();
+
+        for fold in folds {
+            match folded.last_mut() {
+                Some(last) if fold.start < last.end => last.end = last.end.max(fold.end),
+                _ => folded.push(fold.start + 1..fold.end),
+            }
+        }
+
+        if folded != self.folded {
+            self.folded = folded;
+            self.dirty = true;
+        }
+    }
+
+    fn scroll_to_cursor(&mut self) {
+        if self.diff.is_some() || self.zen_active() {
+            return;
+        }
+
+        let cols = if self.buffer.is_hex() {
+            0..1
+        } else {
+            let col = self.cursor_column();
+            let width = match self
+                .buffer
+                .text
+                .line(self.buffer.cursor.1)
+                .get_char(self.buffer.cursor.0)
+            {
+                Some('\t') | None => 1,
+                Some(c) => display::char_columns(c),
+            };
+
+            col..col + width
+        };
+        let size = (
+            self.text_area_width(),
+            (self.terminal.size().height as usize).saturating_sub(1),
+        );
+
+        if self.buffer.view.scroll_to(
+            self.buffer.cursor.1,
+            cols,
+            size,
+            self.options.scrolloff,
+            &self.folded,
+        ) {
+            self.dirty = true;
+        }
+    }
+
+    fn next_visible(&self, line: usize) -> usize {
+        view::next_visible(&self.folded, line)
+    }
+
+    fn prev_visible(&self, line: usize) -> usize {
+        view::prev_visible(&self.folded, line)
+    }
+
+    fn screen_line(&self, row: usize) -> usize {
+        self.buffer.view.screen_line(row, &self.folded)
+    }
+
+    fn screen_row(&self, line: usize) -> Option<usize> {
+        self.buffer.view.screen_row(line, &self.folded)
+    }
+
+    fn set_bookmark(&mut self, note: &str) -> Result<()> {
+        let (path, line) = self.bookmark_position()?;
+
+        self.bookmarks.set(Bookmark {
+            project: paths::project_root(&self.cwd),
+            path,
+            line,
+            note: note.to_string(),
+        });
+        self.dirty = true;
+        self.bookmarks.save()?;
+
+        Ok(())
+    }
+
+    fn bookmarked_lines(&self) -> Vec<usize> {
+        let Some(path) = &self.buffer.path else {
+            return Vec::new();
+        };
+        let path = path.canonicalize().unwrap_or(path.clone());
+
+        self.bookmarks
+            .project(&paths::project_root(&self.cwd))
+            .filter(|bookmark| bookmark.path == path)
+            .map(|bookmark| bookmark.line)
+            .collect()
+    }
+
+    fn bookmark_preview(&self, bookmark: &Bookmark) -> String {
+        let line = match std::iter::once(&self.buffer)
+            .chain(&self.buffers)
+            .find(|buffer| buffer.is_file(&bookmark.path))
+        {
+            Some(buffer) => buffer
+                .text
+                .get_line(bookmark.line)
+                .map(|line| line.to_string())
+                .unwrap_or_default(),
+            None => std::fs::read_to_string(&bookmark.path)
+                .ok()
+                .and_then(|text| text.lines().nth(bookmark.line).map(str::to_string))
+                .unwrap_or_default(),
+        };
+
+        line.trim().to_string()
+    }
+
+    fn open_bookmarks(&mut self) -> Result<()> {
+        let project = paths::project_root(&self.cwd);
+        let items = self
+            .bookmarks
+            .project(&project)
+            .filter(|bookmark| bookmark.path.exists())
+            .map(|bookmark| PickerItem {
+                label: match bookmark.note.as_str() {
+                    "" => format!(
+                        "{}:{}",
+                        display_path(&bookmark.path, &self.cwd),
+                        bookmark.line + 1
+                    ),
+                    note => format!(
+                        "{}:{}  {note}",
+                        display_path(&bookmark.path, &self.cwd),
+                        bookmark.line + 1
+                    ),
+                },
+                detail: self.bookmark_preview(bookmark),
+                target: Target::File {
+                    path: bookmark.path.clone(),
+                    line: bookmark.line,
+                    col: 0,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        if items.is_empty() {
+            return Err("No bookmarks".into());
+        }
+
+        self.picker = Some(Picker::new("Bookmarks", items));
+
+        Ok(())
+    }
+
+    fn open_agenda(&mut self) -> Result<()> {
+        let today = Date::today();
+        let items = tasks::collect(&paths::project_root(&self.cwd))
+            .into_iter()
+            .map(|task| PickerItem {
+                label: match task.due {
+                    Some(due) => format!("{due}  {}", task.text.trim()),
+                    None => format!("{:10}  {}", "", task.text.trim()),
+                },
+                detail: format!(
+                    "{}:{}{}",
+                    display_path(&task.path, &self.cwd),
+                    task.line + 1,
+                    if task.due.is_some_and(|due| due < today) {
+                        "  overdue"
+                    } else {
+                        ""
+                    }
+                ),
+                target: Target::File {
+                    path: task.path,
+                    line: task.line,
+                    col: 0,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        if items.is_empty() {
+            return Err("No open tasks".into());
+        }
+
+        self.picker = Some(Picker::new("Agenda", items));
+
+        Ok(())
+    }
+
+    fn set_due(&mut self, args: &str) -> Result<()> {
+        if self.buffer.read_only {
+            return Err("Buffer is read-only".into());
+        }
+
+        let line_idx = self.buffer.cursor.1;
+        let line = self.buffer.text.line(line_idx).to_string();
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        if tasks::state(line).is_none() {
+            return Err("No task here".into());
+        }
+
+        let due = match args {
+            "" => None,
+            args => Some(
+                tasks::parse_due(args, Date::today())
+                    .ok_or_else(|| format!("Invalid date: {args}"))?,
+            ),
+        };
+
+        self.buffer
+            .replace_lines(line_idx..line_idx + 1, &tasks::set_due(line, due))?;
+        self.buffer.cursor.0 = std::cmp::min(
+            self.buffer.cursor.0,
+            self.line_len(self.buffer.text.line(line_idx)),
+        );
+        self.dirty = true;
+
+        match due {
+            Some(due) => self.messages.info(format!("Due {due}")),
+            None => self.messages.info("Removed the due date"),
+        }
+
+        Ok(())
+    }
+
+    fn jump_to_mark(&mut self, name: char, exact: bool) -> Result<()> {
+        let (line, col) = if name.is_ascii_uppercase() {
+            let mark = self
+                .global_marks
+                .get(name)
+                .cloned()
+                .ok_or(format!("Mark not set: {name}"))?;
+
+            self.open_file(mark.path)?;
+
+            (mark.line, mark.col)
+        } else {
+            *self
+                .buffer
+                .marks
+                .get(&name)
+                .ok_or(format!("Mark not set: {name}"))?
+        };
+
+        if exact {
+            self.jump_to(line, col);
+        } else {
+            let line = std::cmp::min(line, self.buffer.text.len_lines().saturating_sub(1));
+
+            self.jump_to(line, self.first_non_blank(line));
+        }
+
+        Ok(())
+    }
+
+    fn first_non_blank(&self, line_idx: usize) -> usize {
+        self.buffer
+            .text
+            .line(line_idx)
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .count()
+    }
+
+    fn move_to_line_start(&mut self) {
+        let first = self.first_non_blank(self.buffer.cursor.1);
+
+        self.buffer.cursor.0 = if self.options.smarthome && self.buffer.cursor.0 != first {
+            std::cmp::min(
+                first,
+                self.line_len(self.buffer.text.line(self.buffer.cursor.1)),
+            )
+        } else {
+            0
+        };
+        self.buffer.cursor_x_remember = self.buffer.cursor.0;
+    }
+
+    fn move_to_first_non_blank(&mut self) {
+        let first = self.first_non_blank(self.buffer.cursor.1);
+
+        self.buffer.cursor.0 = std::cmp::min(
+            first,
+            self.line_len(self.buffer.text.line(self.buffer.cursor.1)),
+        );
+        self.buffer.cursor_x_remember = self.buffer.cursor.0;
+    }
+
+    fn handle_picker_key(&mut self, event: KeyEvent) -> Option<EventResult> {
+        let Some(picker) = &mut self.picker else {
+            return None;
+        };
+
+        match event.code {
+            KeyCode::Esc => self.close_picker(),
+            KeyCode::Enter => {
+                let target = picker.selected_item().map(|item| item.target.clone());
+
+                self.close_picker();
+
+                if let Some(target) = target {
+                    return self.jump_to_target(target);
+                }
+            }
+            KeyCode::Up => picker.select_prev(),
+            KeyCode::Down => picker.select_next(),
+            KeyCode::Char('p') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                picker.select_prev()
+            }
+            KeyCode::Char('n') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                picker.select_next()
+            }
+            KeyCode::Char(c) => picker.push_char(c),
+            KeyCode::Backspace => picker.pop_char(),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn handle_code_action_key(&mut self, event: KeyEvent) {
+        let Some(actions) = &mut self.code_actions else {
+            return;
+        };
+
+        let chosen = match event.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.code_actions = None;
+                false
+            }
+            KeyCode::Enter => true,
+            KeyCode::Char(c @ '1'..='9') => actions.select(c as usize - '1' as usize),
+            KeyCode::Up | KeyCode::Char('k') => {
+                actions.select_prev();
+                false
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                actions.select_next();
+                false
+            }
+            _ => false,
+        };
+
+        if let Some(actions) = self.code_actions.take_if(|_| chosen) {
+            if let Err(e) = self.apply_code_action(actions.take_selected()) {
+                self.report(e);
+            }
+        }
+
+        self.dirty = true;
+    }
+
+    fn handle_replace_key(&mut self, event: KeyEvent) {
+        let Some(panel) = &mut self.replace else {
+            return;
+        };
+
+        match event.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.replace = None;
+                self.grep = None;
+                self.jobs.finish(self.grep_id);
+            }
+            KeyCode::Enter => {
+                if panel.is_searching() {
+                    return;
+                }
+
+                if let Some(panel) = self.replace.take() {
+                    self.apply_replacements(&panel);
+                }
+
+                self.dirty = true;
+            }
+            KeyCode::Up | KeyCode::Char('k') => panel.select_prev(),
+            KeyCode::Down | KeyCode::Char('j') => panel.select_next(),
+            KeyCode::Char(' ') => panel.toggle(),
+            KeyCode::Char('f') => panel.toggle_file(),
+            _ => {}
+        }
+    }
+
+    fn handle_undo_tree_key(&mut self, event: KeyEvent) {
+        let Some(tree) = &mut self.undo_tree else {
+            return;
+        };
+
+        match event.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                let cursor = tree.cursor;
+                let current = self.buffer.history.current();
+
+                self.undo_tree = None;
+                self.buffer.preview_state(current);
+                self.jump_to(cursor.1, cursor.0);
+            }
+            KeyCode::Enter => {
+                let state = tree.selected_state();
+
+                self.undo_tree = None;
+
+                if let Some((col, line)) = self.buffer.goto_state(state) {
+                    self.jump_to(line, col);
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => tree.select_prev(),
+            KeyCode::Down | KeyCode::Char('j') => tree.select_next(),
+            KeyCode::Home | KeyCode::Char('g') => tree.select_first(),
+            KeyCode::End | KeyCode::Char('G') => tree.select_last(),
+            _ => {}
+        }
+
+        if let Some(tree) = &self.undo_tree {
+            if let Some((col, line)) = self.buffer.preview_state(tree.selected_state()) {
+                self.jump_to(line, col);
+            }
+        }
+
+        self.dirty = true;
+    }
+
+    fn apply_replacements(&mut self, panel: &ReplacePanel) {
+        let mut modified = Vec::new();
+        let mut errors = Vec::new();
+        let mut count = 0;
+
+        for (path, hunks) in panel.accepted_by_file() {
+            let applied = match self.buffer_for_path_mut(&path) {
+                Some(buffer) => Ok(hunks
+                    .iter()
+                    .filter(|hunk| buffer.replace_line(hunk.line, &hunk.before, &hunk.after))
+                    .count()),
+                None => replace::apply_to_file(&path, &hunks),
+            };
+
+            match applied {
+                Ok(0) => {}
+                Ok(applied) => {
+                    count += applied;
+                    modified.push(path);
+                }
+                Err(e) => errors.push(format!("{}: {e}", path.display())),
+            }
+        }
+
+        if !errors.is_empty() {
+            self.messages.error(errors.join(", "));
+            return;
+        }
+
+        let names = modified
+            .iter()
+            .map(|path| display_path(path, &self.cwd))
+            .collect::<Vec<_>>();
+
+        self.messages.info(format!(
+            "Replaced {count} lines in {} files: {}",
+            modified.len(),
+            names.join(", ")
+        ));
+    }
+
+    fn buffer_for_path_mut(&mut self, path: &Path) -> Option<&mut Buffer> {
+        if self.buffer.is_file(path) {
+            return Some(&mut self.buffer);
+        }
+
+        self.buffers.iter_mut().find(|buffer| buffer.is_file(path))
+    }
+
+    fn close_picker(&mut self) {
+        self.picker = None;
+    }
+
+    fn jump_to_target(&mut self, target: Target) -> Option<EventResult> {
+        match target {
+            Target::Position { line, col } => self.jump_to(line, col),
+            Target::File { path, line, col } => match self.open_file(path) {
+                Ok(()) => self.jump_to(line, col),
+                Err(e) => self.report(e),
+            },
+            Target::Diff { path } => {
+                if let Err(e) = self.diff_with_backup(path) {
+                    self.report(e);
+                }
+            }
+            Target::Command {
+                command,
+                prefill: true,
+            } => {
+                if let Err(e) = self.prefill_command(command) {
+                    self.report(e);
+                }
+            }
+            Target::Command { command, .. } => {
+                self.command = command;
+
+                let result = self.run_command();
+
+                self.command.clear();
+
+                match result {
+                    Ok(result) => return result,
+                    Err(e) => self.report(e),
+                }
+            }
+            Target::Keys { keys } => {
+                let action = self.keymap.action(&keymap::parse_keys(&keys)).copied();
+
+                if let Some(Err(e)) = action.map(|action| self.run_action(action)) {
+                    self.report(e);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn open_palette(&mut self) {
+        let commands = help::commands().into_iter().map(|command| PickerItem {
+            label: if command.args {
+                command.summary
+            } else {
+                format!(":{}  {}", command.name, command.summary)
+            },
+            detail: String::new(),
+            target: Target::Command {
+                command: if command.args {
+                    format!("{} ", command.name)
+                } else {
+                    command.name
+                },
+                prefill: command.args,
+            },
+        });
+
+        let mut seen = std::collections::HashSet::new();
+        let actions = self
+            .keymap
+            .bindings()
+            .into_iter()
+            .filter(|(_, description)| seen.insert(description.clone()))
+            .map(|(keys, description)| PickerItem {
+                label: description,
+                detail: keys.clone(),
+                target: Target::Keys { keys },
+            });
+
+        self.picker = Some(Picker::new("Commands", commands.chain(actions).collect()));
+    }
+
+    fn jump_to_matching_bracket(&mut self) {
+        let text = &self.buffer.text;
+
+        let Some(partner) = brackets::next_bracket_on_line(text, self.cursor_to_char_idx())
+            .and_then(|bracket| brackets::matching_bracket(text, bracket, 0..text.len_chars()))
+        else {
+            return;
+        };
+
+        let line = text.char_to_line(partner);
+        let col = partner - text.line_to_char(line);
+
+        self.jump_to(line, col);
+    }
+
+    fn keep_window(&mut self) {
+        let height = (self.terminal.size().height as usize).saturating_sub(1);
+
+        if self.buffer.keep_window(height) {
+            self.dirty = true;
+        }
+    }
+
+    fn goto_large_line(&mut self, line: usize, col: usize) {
+        match self.buffer.goto_large_line(line) {
+            Some(local) => {
+                self.jump_to(local, col);
+                self.dirty = true;
+            }
+            None => self.jump_to(line, col),
+        }
+    }
+
+    fn goto_large_offset(&mut self, pos: usize, col: usize) {
+        if let Some(local) = self.buffer.goto_large_offset(pos) {
+            self.jump_to(local, col);
+            self.dirty = true;
+        }
+    }
+
+    fn jump_to(&mut self, line: usize, col: usize) {
+        self.buffer.cursor.1 = std::cmp::min(line, self.buffer.text.len_lines().saturating_sub(1));
+        self.buffer.cursor.0 = std::cmp::min(
+            col,
+            self.line_len(self.buffer.text.line(self.buffer.cursor.1)),
+        );
+        self.buffer.cursor_x_remember = self.buffer.cursor.0;
+
+        let height = (self.terminal.size().height as usize).saturating_sub(1);
+
+        if self.buffer.view.center_on(self.buffer.cursor.1, height) {
+            self.dirty = true;
+        }
+    }
+
+    fn run_command(&mut self) -> Result<Option<EventResult>> {
+        let command = self.command.clone();
+
+        if self.buffer.is_large() {
+            if command.trim() == "$" {
+                self.goto_large_offset(usize::MAX, 0);
+
+                return Ok(None);
+            }
+
+            if let Ok(line) = command.trim().parse::<usize>() {
+                self.goto_large_line(line.saturating_sub(1), 0);
+
+                return Ok(None);
+            }
+        }
+
+        let last = self.last_line();
+        let (range, command) =
+            range::parse(command.trim_start(), self.buffer.cursor.1, last, |name| {
+                self.buffer.marks.get(&name).map(|(line, _)| *line)
+            })
+            .map_err(EditorError::Command)?;
+
+        let command = command.trim_start();
+        let shift = std::cmp::max(
+            command.len() - command.trim_start_matches('>').len(),
+            command.len() - command.trim_start_matches('<').len(),
+        );
+
+        let substitute = command
+            .strip_prefix("substitute")
+            .or_else(|| command.strip_prefix('s'))
+            .filter(|args| {
+                args.chars()
+                    .next()
+                    .is_none_or(|c| !c.is_alphanumeric() && !c.is_whitespace())
+            });
+
+        let transfer = ["copy", "move", "co", "m", "t"]
+            .into_iter()
+            .find_map(|name| {
+                command
+                    .strip_prefix(name)
+                    .filter(|args| {
+                        args.chars()
+                            .next()
+                            .is_some_and(|c| !c.is_alphabetic() && !c.is_whitespace())
+                    })
+                    .map(|args| (name, args.trim()))
+            });
+
+        let (name, args) = match command.split_once(' ') {
+            _ if shift > 0 => (&command[..shift], command[shift..].trim()),
+            _ if command.starts_with('&') => ("&", command[1..].trim()),
+            _ if substitute.is_some() => ("s", substitute.unwrap_or_default()),
+            _ if transfer.is_some() => transfer.unwrap_or_default(),
+            Some((name, args)) => (name, args.trim()),
+            None => (command, ""),
+        };
+
+        log::debug!("command {range:?} {name:?} {args:?}");
+
+        if let Some(range) = range {
+            if range.end > last && !name.is_empty() {
+                return Err(format!("Invalid range: line {} doesn't exist", range.end + 1).into());
+            }
+
+            match name {
+                "" => self.jump_to(std::cmp::min(range.end, last), 0),
+                "d" | "delete" => self.delete_lines(Some(range), args)?,
+                "y" | "yank" => self.yank_lines(Some(range), args)?,
+                "s" | "substitute" => self.substitute(Some(range), args)?,
+                "&" => self.repeat_substitute(Some(range), args)?,
+                "align" => self.align_lines(Some(range), args)?,
+                "fixwhitespace" => self.fix_whitespace(Some(range))?,
+                "m" | "move" => self.move_command(Some(range), args)?,
+                "t" | "co" | "copy" => self.copy_lines(Some(range), args)?,
+                "count" => self.count_lines(Some(range)),
+                "yank-link" => self.yank_link(Some(range))?,
+                "jsonfmt" => self.format_json(Some(range), args)?,
+                _ if shift > 0 => self.shift_lines(Some(range), name, args)?,
+                _ => return Err(format!("No range allowed: {name}").into()),
+            }
+
+            return Ok(None);
+        }
+
+        match name {
+            "q" | "quit" if self.close_help() => {}
+            "q" | "quit" => return Ok(self.quit(false)),
+            "q!" | "quit!" => return Ok(self.quit(true)),
+            "w" | "write" => self.write_buffer(args, false, false)?,
+            "w!" | "write!" => self.write_buffer(args, true, false)?,
+            "wq" | "x" => {
+                self.write_buffer(args, false, true)?;
+
+                if self.prompt.is_none() {
+                    return Ok(self.quit(false));
+                }
+            }
+            "sav" | "saveas" => self.save_as(args, false)?,
+            "sav!" | "saveas!" => self.save_as(args, true)?,
+            "e!" | "edit!" => self.reload_buffer()?,
+            "checktime" => self.check_disk_changes(),
+            "follow" if self.follow.is_some() && self.follow == self.buffer.path => {
+                self.follow = None;
+                self.messages.info("Stopped following");
+            }
+            "follow" => self.follow()?,
+            "csv" => self.csv_command(args)?,
+            "jsonfmt" => self.format_json(None, args)?,
+            "log" => self.open_log()?,
+            "palette" => self.open_palette(),
+            "tutor" => self.open_tutor(),
+            "e" | "edit" => {
+                if args.is_empty() {
+                    return Err("No file name".into());
+                }
+
+                self.open_file(self.resolve_path(args))?;
+            }
+            "cd" => self.change_dir(args)?,
+            "mkdir" => self.make_dir(args)?,
+            "pwd" => self.messages.info(self.cwd.display().to_string()),
+            "outline" => self.open_outline()?,
+            "oldfiles" => self.open_oldfiles()?,
+            "rg" | "grep" => self.start_grep(args)?,
+            "rgreplace" => self.start_replace(args)?,
+            "set" => self.set_options(args)?,
+            "dig" | "digraphs" => self.messages.info(digraph::summary()),
+            "iab" | "iabbrev" => self.abbreviate(args)?,
+            "iuna" | "iunabbrev" => self
+                .abbreviations
+                .remove(args)
+                .map_err(EditorError::Command)?,
+            "preview" => self.toggle_preview()?,
+            "zen" => self.toggle_zen(),
+            "undotree" => self.open_undo_tree()?,
+            "term" | "terminal" => self.open_terminal(args)?,
+            "termclose" => self.close_terminal(),
+            "hex" => self.toggle_hex()?,
+            "diffoff" => self.diff_off(),
+            "conflict" => self.resolve_conflict(args)?,
+            "h" | "help" => self.open_help(args.trim())?,
+            "mes" | "messages" => self.show_messages(),
+            "plugins" => self.show_plugins(),
+            "lua" => self.run_lua(args)?,
+            "marks" => self.show_marks(args),
+            "bookmark" => self.set_bookmark(args)?,
+            "bookmarks" => self.open_bookmarks()?,
+            "agenda" => self.open_agenda()?,
+            "due" => self.set_due(args)?,
+            "format" => self.format_buffer()?,
+            "restore" => self.open_backups()?,
+            "restore!" => self.restore_backup()?,
+            "Git" | "Gstatus" => self.open_git_status()?,
+            "Gdiff" => self.open_git_diff()?,
+            "Gcommit" => self.open_commit_message()?,
+            "mak" | "make" => self.start_make(args),
+            "cn" | "cnext" => self.quickfix_step(true)?,
+            "cp" | "cprev" | "cprevious" => self.quickfix_step(false)?,
+            "cope" | "copen" => self.open_quickfix()?,
+            "project" => self.show_project_config()?,
+            "checkhealth" => self.check_health(),
+            "showkeys" => self.toggle_showkeys(),
+            "d" | "delete" => self.delete_lines(None, args)?,
+            "y" | "yank" => self.yank_lines(None, args)?,
+            "s" | "substitute" => self.substitute(None, args)?,
+            "&" => self.repeat_substitute(None, args)?,
+            "align" => self.align_lines(None, args)?,
+            "fixwhitespace" => self.fix_whitespace(None)?,
+            "m" | "move" => self.move_command(None, args)?,
+            "t" | "co" | "copy" => self.copy_lines(None, args)?,
+            "count" => self.count_lines(None),
+            "yank-link" => self.yank_link(None)?,
+            "reg" | "registers" => self.show_registers(),
+            _ if shift > 0 => self.shift_lines(None, name, args)?,
+            _ => {
+                if let Some(result) = self.scripts.run_command(
+                    name,
+                    args,
+                    &mut self.buffer,
+                    &mut self.messages,
+                    &mut self.options,
+                ) {
+                    self.bind_lua_mappings();
+                    result?;
+                } else if !self.plugins.run_command(
+                    name,
+                    args,
+                    &mut self.buffer,
+                    &mut self.messages,
+                ) {
+                    return Err(format!("Unknown command: {}", self.command).into());
+                }
+
+                self.dirty = true;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn toggle_preview(&mut self) -> Result<()> {
+        if !self.preview && !markdown::is_markdown(self.buffer.path.as_deref()) {
+            return Err("Preview is only available for markdown buffers".into());
+        }
+
+        self.preview = !self.preview;
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn commit_change(&mut self) {
+        if !matches!(self.mode, Mode::Insert) {
+            self.buffer.history.commit(&self.buffer.text);
+        }
+    }
+
+    fn undo(&mut self, redo: bool) -> Result<()> {
+        if self.buffer.is_hex() {
+            return Err("Undo is not available in hex mode".into());
+        }
+
+        let (state, (col, line)) = if redo {
+            self.buffer.redo().ok_or("Already at newest change")?
+        } else {
+            self.buffer.undo().ok_or("Already at oldest change")?
+        };
+
+        let time = self.buffer.history.states()[state].time;
+
+        self.jump_to(line, col);
+        self.dirty = true;
+        self.messages.info(format!(
+            "{} #{state}  {}",
+            if redo { "After" } else { "Before" },
+            undo::age(time, SystemTime::now())
+        ));
+
+        Ok(())
+    }
+
+    fn open_undo_tree(&mut self) -> Result<()> {
+        if self.buffer.is_hex() {
+            return Err("Undo is not available in hex mode".into());
+        }
+
+        self.buffer.history.commit(&self.buffer.text);
+
+        if self.buffer.history.states().len() < 2 {
+            return Err("No changes to undo".into());
+        }
+
+        self.undo_tree = Some(UndoTree::new(&self.buffer.history, self.buffer.cursor));
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn toggle_zen(&mut self) {
+        self.zen = !self.zen;
+        self.dirty = true;
+    }
+
+    fn zen_active(&self) -> bool {
+        self.zen && self.diff.is_none() && !self.buffer.is_hex()
+    }
+
+    fn zen_width(&self) -> usize {
+        std::cmp::min(self.options.zenwidth, self.text_width() as usize)
+    }
+
+    fn zen_margin(&self) -> u16 {
+        (self.text_width() as usize).saturating_sub(self.options.zenwidth) as u16 / 2
+    }
+
+    fn zen_cursor(&self) -> (usize, usize) {
+        let width = self.zen_width();
+        let rows = (self.buffer.view.top_line..self.buffer.cursor.1)
+            .map(|line_idx| reflow::soft_wrap(&self.display_line(line_idx), width).len())
+            .sum::<usize>();
+
+        let col = self.cursor_column();
+        let starts = reflow::soft_wrap(&self.display_line(self.buffer.cursor.1), width);
+        let segment = starts.iter().rposition(|&start| start <= col).unwrap_or(0);
+
+        (rows + segment, col - starts[segment])
+    }
+
+    fn scroll_zen(&mut self) {
+        let height = (self.terminal.size().height as usize).saturating_sub(1);
+
+        if self.buffer.cursor.1 < self.buffer.view.top_line {
+            self.buffer.view.top_line = self.buffer.cursor.1;
+            self.dirty = true;
+        }
+
+        while self.buffer.view.top_line < self.buffer.cursor.1 && self.zen_cursor().0 >= height {
+            self.buffer.view.top_line += 1;
+            self.dirty = true;
+        }
+    }
+
+    fn draw_zen_rows(&mut self) -> Result<()> {
+        let height = self.terminal.size().height - 1;
+        let width = self.zen_width();
+        let margin = self.zen_margin();
+        let mut line_idx = self.buffer.view.top_line;
+        let mut row = 0;
+
+        while row < height {
+            let segments = if line_idx < self.buffer.text.len_lines() {
+                let line = self.display_line(line_idx).chars().collect::<Vec<_>>();
+                let mut starts = reflow::soft_wrap(&line.iter().collect::<String>(), width);
+
+                starts.push(line.len());
+
+                starts
+                    .windows(2)
+                    .map(|pair| line[pair[0]..pair[1]].iter().collect::<String>())
+                    .collect()
+            } else {
+                vec![String::new()]
+            };
+
+            for segment in segments.iter().take((height - row) as usize) {
+                self.terminal.clear_line(row)?;
+                self.terminal.print_at((margin, row), segment.trim_end())?;
+
+                if self.preview_visible() {
+                    self.draw_preview_row(row, self.buffer.view.top_line + row as usize)?;
+                } else if self.term_visible() {
+                    self.draw_term_row(row)?;
+                }
+
+                row += 1;
+            }
+
+            line_idx += 1;
+        }
+
+        Ok(())
+    }
+
+    fn display_line(&self, line_idx: usize) -> String {
+        match (self.buffer.text.get_line(line_idx), &self.table) {
+            (Some(line), Some(table)) => table.display(&line.to_string()),
+            (Some(line), None) => display::chars(line, usize::MAX).collect(),
+            _ => String::new(),
+        }
+    }
+
+    fn open_terminal(&mut self, command: &str) -> Result<()> {
+        if self.diff.is_some() {
+            return Err("Not available in diff mode".into());
+        }
+
+        if command.is_empty() && self.term.as_ref().is_some_and(|term| !term.exited) {
+            return self.terminal_mode();
+        }
+
+        let (cols, rows) = self.term_size();
+        let command = (!command.is_empty()).then_some(command);
+
+        self.term_id += 1;
+
+        let pty = Pty::spawn(command, (cols, rows), self.term_id, self.events.sender())?;
+
+        self.term = Some(Term {
+            pty,
+            vt: Vt::new(cols as usize, rows as usize),
+            id: self.term_id,
+            exited: false,
+        });
+        self.dirty = true;
+
+        self.terminal_mode()
+    }
+
+    fn close_terminal(&mut self) {
+        if self.term.take().is_some() && matches!(self.mode, Mode::Terminal) {
+            self.mode = Mode::Normal;
+        }
+
+        self.dirty = true;
+    }
+
+    fn terminal_exited(&mut self, id: usize) -> Result<()> {
+        let Some(term) = self.term.as_mut().filter(|term| term.id == id) else {
+            return Ok(());
+        };
+
+        let status = term
+            .pty
+            .wait()
+            .map_or_else(|| "unknown".to_string(), |status| status.to_string());
+
+        term.vt.feed(format!("\r\n[Process {status}]").as_bytes());
+        term.exited = true;
+        self.dirty = true;
+
+        if matches!(self.mode, Mode::Terminal) {
+            self.normal_mode()?;
+        }
+
+        Ok(())
+    }
+
+    fn term_size(&self) -> (u16, u16) {
+        let size = self.terminal.size();
+
+        (
+            size.width.saturating_sub(size.width / 2 + 2).max(1),
+            size.height.saturating_sub(1).max(1),
+        )
+    }
+
+    fn resize_term(&mut self) {
+        let (cols, rows) = self.term_size();
+
+        if let Some(term) = &mut self.term {
+            if term.vt.size() != (cols as usize, rows as usize) {
+                term.vt.resize(cols as usize, rows as usize);
+                term.pty.resize(cols, rows);
+            }
+        }
+    }
+
+    fn term_visible(&self) -> bool {
+        self.term.is_some() && self.diff.is_none()
+    }
+
+    fn preview_visible(&self) -> bool {
+        self.preview
+            && self.term.is_none()
+            && self.diff.is_none()
+            && !self.buffer.is_hex()
+            && markdown::is_markdown(self.buffer.path.as_deref())
+    }
+
+    fn text_width(&self) -> u16 {
+        if self.preview_visible() || self.term_visible() {
+            self.terminal.size().width / 2
+        } else if self.minimap_visible() {
+            self.terminal.size().width - minimap::WIDTH as u16
+        } else {
+            self.terminal.size().width
+        }
+    }
+
+    fn set_options(&mut self, args: &str) -> Result<()> {
+        if args.trim().is_empty() {
+            self.messages.info(self.options.summary());
+
+            return Ok(());
+        }
+
+        let mut shown = Vec::new();
+
+        for arg in options::split_args(args) {
+            if let Some(value) = self.options.set(&arg).map_err(EditorError::Config)? {
+                shown.push(value);
+            }
+
+            let name = arg.split(['=', '!']).next().unwrap_or_default();
+
+            if ["shiftwidth", "sw", "expandtab", "et", "noexpandtab", "noet"].contains(&name) {
+                self.buffer.indent = None;
+            }
+        }
+
+        if !shown.is_empty() {
+            self.messages.info(shown.join("  "));
+        }
+
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn handle_insert_pending(&mut self, event: KeyEvent) -> Result<Option<KeyEvent>> {
+        let Some(pending) = self.insert_pending.take() else {
+            return Ok(None);
+        };
+
+        let KeyCode::Char(c) = event.code else {
+            match (pending, event.code) {
+                (InsertPending::Literal, KeyCode::Tab) => self.insert_char('\t')?,
+                (InsertPending::Literal, KeyCode::Enter) => self.enter()?,
+                (InsertPending::Codepoint(prefix, digits), _) => {
+                    self.insert_codepoint(prefix, &digits)?;
+
+                    return Ok(Some(event));
+                }
+                _ => {}
+            }
+
+            return Ok(None);
+        };
+
+        match pending {
+            InsertPending::Literal if c == 'u' || c == 'U' => {
+                self.insert_pending = Some(InsertPending::Codepoint(c, String::new()));
+            }
+            InsertPending::Literal => self.insert_char(c)?,
+            InsertPending::Codepoint(prefix, mut digits) if c.is_ascii_hexdigit() => {
+                digits.push(c);
+
+                if digits.len() == if prefix == 'u' { 4 } else { 8 } {
+                    self.insert_codepoint(prefix, &digits)?;
+                } else {
+                    self.insert_pending = Some(InsertPending::Codepoint(prefix, digits));
+                }
+            }
+            InsertPending::Codepoint(prefix, digits) => {
+                self.insert_codepoint(prefix, &digits)?;
+
+                return Ok(Some(event));
+            }
+            InsertPending::Digraph(None) => {
+                self.insert_pending = Some(InsertPending::Digraph(Some(c)));
+            }
+            InsertPending::Register if c == '=' => self.expression = Some(String::new()),
+            InsertPending::Register => match self.register_text(c) {
+                Some(text) => self.insert_text(&text)?,
+                None => self.messages.error(format!("Register {c} is empty")),
+            },
+            InsertPending::Digraph(Some(first)) => {
+                self.insert_char(digraph::lookup(first, c).unwrap_or(c))?;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn insert_codepoint(&mut self, prefix: char, digits: &str) -> Result<()> {
+        match u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+        {
+            Some(c) => self.insert_char(c)?,
+            None if digits.is_empty() => self.insert_char(prefix)?,
+            None => self
+                .messages
+                .error(format!("Invalid codepoint: U+{digits}")),
+        }
+
+        Ok(())
+    }
+
+    fn show_char_info(&mut self) {
+        let text = &self.buffer.text;
+        let idx = self.cursor_to_char_idx();
+
+        let Some(c) = text.get_char(idx) else {
+            self.messages.info("NUL");
+
+            return;
+        };
+
+        let chars = std::iter::once(c)
+            .chain(
+                text.chars_at(idx + 1)
+                    .take_while(|c| charinfo::is_combining(*c)),
+            )
+            .collect::<Vec<_>>();
+
+        self.messages.info(charinfo::describe(&chars));
+    }
+
+    fn show_word_count(&mut self) {
+        let text = &self.buffer.text;
+        let total = Counts::of(text.slice(..));
+
+        if total.chars == 0 {
+            self.messages.info(total.to_string());
+
+            return;
+        }
+
+        let idx = std::cmp::min(self.cursor_to_char_idx(), text.len_chars() - 1);
+        let before = Counts::of(text.slice(..=idx));
+
+        self.messages.info(format!(
+            "Line {} of {}; Word {} of {}; Char {} of {}; Byte {} of {}",
+            self.buffer.cursor.1 + 1,
+            total.lines,
+            before.words,
+            total.words,
+            idx + 1,
+            total.chars,
+            text.char_to_byte(idx) + 1,
+            total.bytes,
+        ));
+    }
+
+    fn count_lines(&mut self, range: Option<LineRange>) {
+        let text = &self.buffer.text;
+
+        let counts = match range {
+            Some(range) => {
+                let start = text.line_to_char(range.start);
+                let end = text.line_to_char(std::cmp::min(range.end + 1, text.len_lines()));
+
+                Counts::of(text.slice(start..end))
+            }
+            None => Counts::of(text.slice(..)),
+        };
+
+        self.messages.info(counts.to_string());
+    }
+
+    fn abbreviate(&mut self, args: &str) -> Result<()> {
+        let Some((lhs, rhs)) = args.split_once(char::is_whitespace) else {
+            if !args.is_empty() {
+                return Err("Usage: :iabbrev {lhs} {rhs}".into());
+            }
+
+            if self.abbreviations.is_empty() {
+                self.messages.info("No abbreviation found");
+            } else {
+                self.messages.info(self.abbreviations.summary());
+            }
+
+            return Ok(());
+        };
+
+        self.abbreviations
+            .define(lhs, rhs.trim_start())
+            .map_err(EditorError::Command)
+    }
+
+    fn expand_abbreviation(&mut self) -> Result<()> {
+        if self.abbreviations.is_empty() {
+            return Ok(());
+        }
+
+        let line_idx = self.buffer.cursor.1;
+        let line = self.buffer.text.line(line_idx);
+        let col = std::cmp::min(self.buffer.cursor.0, line.len_chars());
+        let before = line.slice(..col).to_string();
+
+        let Some((len, rhs)) = self.abbreviations.expansion(&before) else {
+            return Ok(());
+        };
+
+        let rhs = rhs.to_string();
+        let end = self.buffer.text.line_to_char(line_idx) + col;
+
+        self.buffer.remove(end - len..end)?;
+        self.buffer.insert(end - len, &rhs)?;
+
+        self.buffer.cursor.0 = col - len + rhs.chars().count();
+        self.buffer.cursor_x_remember = self.buffer.cursor.0;
+
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn quit(&mut self, force: bool) -> Option<EventResult> {
+        let modified = self.modified_buffer_names();
+
+        if force || modified.is_empty() {
+            self.remember_positions();
+
+            return Some(EventResult::Quit);
+        }
+
+        self.prompt = Some(Prompt::yes_no_cancel(
+            format!("Save changes to {}?", modified.join(", ")),
+            PromptAction::SaveAndQuit,
+        ));
+
+        None
+    }
+
+    fn modified_buffer_names(&self) -> Vec<String> {
+        std::iter::once(&self.buffer)
+            .chain(&self.buffers)
+            .chain(self.diff.as_ref().map(|diff| &diff.other))
+            .filter(|buffer| buffer.modified && !buffer.is_directory() && !buffer.is_tutor())
+            .map(|buffer| match &buffer.path {
+                Some(path) => display_path(path, &self.cwd),
+                None => "[No Name]".to_string(),
+            })
+            .collect()
+    }
+
+    fn write_all_modified(&mut self) -> Result<()> {
+        for buffer in std::iter::once(&mut self.buffer)
+            .chain(&mut self.buffers)
+            .chain(self.diff.as_mut().map(|diff| &mut diff.other))
+        {
+            if !buffer.modified || buffer.is_directory() {
+                continue;
+            }
+
+            let display = match &buffer.path {
+                Some(path) => display_path(path, &self.cwd),
+                None => return Err("No file name for [No Name] buffer".into()),
+            };
+
+            if buffer.lossy {
+                return Err(format!("{display} has invalid UTF-8; write it with :w!").into());
+            }
+
+            if let Some(path) = &buffer.path {
+                back_up(path, self.options.backups);
+            }
+
+            buffer
+                .write()
+                .map_err(|e| format!("Can't write {display}: {}", describe_io_error(&e)))?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_prompt_key(&mut self, event: KeyEvent) -> Option<EventResult> {
+        let prompt = self.prompt.as_ref()?;
+
+        let answer = match event.code {
+            KeyCode::Esc => prompt.dismiss(),
+            KeyCode::Char(c) => prompt.answer(c)?,
+            _ => return None,
+        };
+
+        let action = self.prompt.take()?.action;
+
+        let result = match (action, answer) {
+            (_, Answer::Cancel) => Ok(None),
+            (PromptAction::SaveAndQuit, Answer::Yes) => {
+                self.write_all_modified().map(|()| self.quit(true))
+            }
+            (PromptAction::SaveAndQuit, Answer::No) => Ok(self.quit(true)),
+            (PromptAction::Overwrite { then_quit }, Answer::Yes) => self
+                .write_current()
+                .map(|()| if then_quit { self.quit(false) } else { None }),
+            (PromptAction::Overwrite { .. }, Answer::No) => Ok(None),
+            (
+                PromptAction::WriteAs {
+                    path,
+                    rebind,
+                    then_quit,
+                },
+                Answer::Yes,
+            ) => self
+                .write_as(path, rebind)
+                .map(|()| if then_quit { self.quit(false) } else { None }),
+            (PromptAction::WriteAs { .. }, Answer::No) => Ok(None),
+            (PromptAction::Reload, Answer::Yes) => self.reload_buffer().map(|()| None),
+            (PromptAction::Reload, Answer::No) => {
+                self.buffer.acknowledge_disk_change();
+                Ok(None)
+            }
+            (PromptAction::TrustProject { path, text }, Answer::Yes) => {
+                self.trust.trust(path.clone(), &text);
+                self.read_project_config(path, &text);
+                self.trust.save().map(|()| None).map_err(Into::into)
+            }
+            (PromptAction::TrustProject { path, .. }, Answer::No) => {
+                self.messages
+                    .info(format!("Ignoring {}", display_path(&path, &self.cwd)));
+                self.untrusted.push(path);
+                Ok(None)
+            }
+        };
+
+        match result {
+            Ok(result) => result,
+            Err(e) => {
+                self.report(e);
+                None
+            }
+        }
+    }
+
+    fn check_disk_changes(&mut self) {
+        if !self.buffer.changed_on_disk() {
+            return;
+        }
+
+        let display = match &self.buffer.path {
+            Some(path) => display_path(path, &self.cwd),
+            None => return,
+        };
+
+        let message = if self.buffer.modified {
+            format!("{display} changed on disk. Reload and discard your changes?")
+        } else {
+            format!("{display} changed on disk. Reload?")
+        };
+
+        self.prompt = Some(Prompt::yes_no(message, PromptAction::Reload));
+    }
+
+    fn load_project_config(&mut self) {
+        let Some(path) = self
+            .buffer
+            .path
+            .as_ref()
+            .and_then(|path| path.canonicalize().ok())
+            .and_then(|path| project::find(path.parent()?))
+        else {
+            return;
+        };
+
+        if self.projects.iter().any(|config| config.path == path) {
+            self.apply_project_config();
+            return;
+        }
+
+        let text = match ProjectConfig::read(&path) {
+            Ok(text) => text,
+            Err(e) => return self.report(EditorError::Config(e)),
+        };
+
+        match self.trust.check(&path, &text) {
+            Trusted::Yes => self.read_project_config(path, &text),
+            _ if self.untrusted.contains(&path) || self.prompt.is_some() => {}
+            trusted => {
+                let display = display_path(&path, &self.cwd);
+                let message = if trusted == Trusted::Changed {
+                    format!("{display} changed since it was trusted. Trust it? It can run commands")
+                } else {
+                    format!("Trust {display}? It can run commands")
+                };
+
+                self.prompt = Some(Prompt::yes_no(
+                    message,
+                    PromptAction::TrustProject { path, text },
+                ));
+            }
+        }
+    }
+
+    fn read_project_config(&mut self, path: PathBuf, text: &str) {
+        match ProjectConfig::from_text(&path, text) {
+            Ok(config) => {
+                self.projects.push(config);
+                self.apply_project_config();
+                self.dirty = true;
+            }
+            Err(e) => self.report(EditorError::Config(e)),
+        }
+    }
+
+    fn project_config(&self) -> Option<&ProjectConfig> {
+        let path = self.buffer.path.as_ref()?.canonicalize().ok()?;
+
+        self.projects
+            .iter()
+            .filter(|config| config.contains(&path))
+            .max_by_key(|config| config.root().components().count())
+    }
+
+    fn apply_project_config(&mut self) {
+        let current = Indent {
+            tabs: !self.expandtab(),
+            width: self.shiftwidth(),
+        };
+
+        if let Some(indent) = self
+            .project_config()
+            .and_then(|config| config.indent(current))
+        {
+            self.buffer.indent = Some(indent);
+        }
+    }
+
+    fn format_buffer(&mut self) -> Result<()> {
+        if self.buffer.read_only || self.buffer.is_directory() {
+            return Err("Buffer is read-only".into());
+        }
+
+        let filetype = self
+            .buffer
+            .path
+            .as_deref()
+            .and_then(statusline::filetype)
+            .ok_or("No file type for this buffer")?;
+        let config = self
+            .project_config()
+            .ok_or(format!("No {} for this buffer", project::FILE_NAME))?;
+        let command = config
+            .format
+            .get(filetype)
+            .cloned()
+            .ok_or(format!("No formatter for {filetype}"))?;
+
+        let output = run_filter(&command, &self.buffer.text, config.root())
+            .map_err(|e| format!("{command}: {}", describe_io_error(&e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().next().unwrap_or_default();
+
+            return Err(format!("{command}: exited with {} {reason}", output.status).into());
+        }
+
+        let text = String::from_utf8(output.stdout)
+            .map_err(|_| format!("{command}: output is not UTF-8"))?;
+
+        if self.buffer.text == text.as_str() {
+            self.messages.info("Already formatted");
+            return Ok(());
+        }
+
+        let (col, line) = self.buffer.cursor;
+
+        self.buffer
+            .replace_lines(0..self.buffer.text.len_lines(), &text)?;
+        self.jump_to(line, col);
+        self.dirty = true;
+        self.messages.info(format!("Formatted with {command}"));
+
+        Ok(())
+    }
+
+    fn start_make(&mut self, args: &str) {
+        let config = self.project_config();
+        let program = config
+            .and_then(|config| config.makeprg.as_deref())
+            .unwrap_or(&self.options.makeprg);
+        let command = match args {
+            "" => program.to_string(),
+            args => format!("{program} {args}"),
+        };
+        let dir = config.map_or(self.cwd.clone(), |config| config.root().to_path_buf());
+
+        self.jobs.cancel(self.make_id);
+
+        let (id, progress) = self.jobs.start(command.clone());
+        let tx = self.events.sender();
+
+        self.make_id = id;
+        self.messages.info(format!("Running {command} …"));
+
+        quickfix::run(command, dir, progress, move |finished| {
+            let _ = tx.send(AppEvent::Make(id, finished));
+        });
+    }
+
+    fn make_finished(&mut self, finished: Finished) {
+        let Finished {
+            command,
+            dir,
+            status,
+            output,
+        } = finished;
+
+        let status = match status {
+            Ok(status) => status,
+            Err(e) => {
+                self.messages
+                    .error(format!("{command}: {}", describe_io_error(&e)));
+                return;
+            }
+        };
+
+        self.quickfix = Quickfix::new(quickfix::parse(&output, &dir));
+        self.dirty = true;
+
+        if !self.quickfix.entries().is_empty() {
+            self.jump_to_quickfix();
+        } else if status.success() {
+            self.messages.info(format!("{command}: no errors"));
+        } else {
+            let last = output.lines().rev().find(|line| !line.trim().is_empty());
+
+            self.messages.error(format!(
+                "{command}: exited with {status} {}",
+                last.unwrap_or_default().trim()
+            ));
+        }
+    }
+
+    fn jump_to_quickfix(&mut self) {
+        let Some((idx, entry)) = self.quickfix.current() else {
+            return;
+        };
+
+        let count = self.quickfix.entries().len();
+        let entry = entry.clone();
+
+        match self.open_file(entry.path) {
+            Ok(()) => {
+                self.jump_to(entry.line, entry.col);
+                self.messages
+                    .info(format!("({} of {count}) {}", idx + 1, entry.message));
+            }
+            Err(e) => self.report(e),
+        }
+    }
+
+    fn quickfix_step(&mut self, forward: bool) -> Result<()> {
+        if self.quickfix.entries().is_empty() {
+            return Err("No errors".into());
+        }
+
+        let moved = if forward {
+            self.quickfix.select_next().is_some()
+        } else {
+            self.quickfix.select_prev().is_some()
+        };
+
+        if !moved {
+            return Err(if forward {
+                "No more items"
+            } else {
+                "Already at the first item"
+            }
+            .into());
+        }
+
+        self.jump_to_quickfix();
+
+        Ok(())
+    }
+
+    fn open_quickfix(&mut self) -> Result<()> {
+        let items = self
+            .quickfix
+            .entries()
+            .iter()
+            .map(|entry| PickerItem {
+                label: format!(
+                    "{}:{}:{}",
+                    display_path(&entry.path, &self.cwd),
+                    entry.line + 1,
+                    entry.col + 1
+                ),
+                detail: entry.message.clone(),
+                target: Target::File {
+                    path: entry.path.clone(),
+                    line: entry.line,
+                    col: entry.col,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        if items.is_empty() {
+            return Err("No errors".into());
+        }
+
+        self.picker = Some(Picker::new("Errors", items));
+
+        Ok(())
+    }
+
+    fn open_backups(&mut self) -> Result<()> {
+        let path = self.buffer.path.clone().ok_or("No file name")?;
+        let path = path.canonicalize().unwrap_or(path);
+        let now = SystemTime::now();
+
+        let items = backup::list(&path)
+            .into_iter()
+            .map(|backup| PickerItem {
+                label: undo::age(backup.time, now),
+                detail: std::fs::metadata(&backup.path)
+                    .map(|metadata| format!("{} bytes", metadata.len()))
+                    .unwrap_or_default(),
+                target: Target::Diff { path: backup.path },
+            })
+            .collect::<Vec<_>>();
+
+        if items.is_empty() {
+            return Err(format!("No backups of {}", display_path(&path, &self.cwd)).into());
+        }
+
+        self.picker = Some(Picker::new("Backups", items));
+
+        Ok(())
+    }
+
+    fn diff_with_backup(&mut self, path: PathBuf) -> Result<()> {
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Can't read the backup: {}", describe_io_error(&e)))?;
+
+        self.diff_off();
+        self.diff = Some(DiffView::new(Buffer {
+            text: text.as_str().into(),
+            read_only: true,
+            ..Buffer::new()
+        }));
+        self.restore = Some(path);
+        self.dirty = true;
+        self.messages
+            .info("Right: backup. do gets a change, :restore! takes the whole backup");
+
+        Ok(())
+    }
+
+    fn restore_backup(&mut self) -> Result<()> {
+        if self.restore.is_none() {
+            return Err("Pick a backup with :restore first".into());
+        }
+
+        if self.buffer.read_only {
+            return Err("Buffer is read-only".into());
+        }
+
+        let text = self
+            .diff
+            .as_ref()
+            .map(|diff| diff.other.text.to_string())
+            .unwrap_or_default();
+        let (col, line) = self.buffer.cursor;
+
+        self.buffer
+            .replace_lines(0..self.buffer.text.len_lines(), &text)?;
+        self.diff_off();
+        self.jump_to(line, col);
+        self.messages.info("Restored the backup, :w to keep it");
+
+        Ok(())
+    }
+
+    fn show_project_config(&mut self) -> Result<()> {
+        let config = self
+            .project_config()
+            .ok_or(format!("No {} for this buffer", project::FILE_NAME))?;
+
+        let mut rows = vec![display_path(&config.path, &self.cwd)];
+
+        if let Some(width) = config.shiftwidth {
+            rows.push(format!("  shiftwidth = {width}"));
+        }
+
+        if let Some(expandtab) = config.expandtab {
+            rows.push(format!("  expandtab = {expandtab}"));
+        }
+
+        if let Some(makeprg) = &config.makeprg {
+            rows.push(format!("  makeprg = {makeprg}"));
+        }
+
+        for (table, entries) in [("format", &config.format), ("lsp", &config.lsp)] {
+            rows.extend(
+                entries
+                    .iter()
+                    .map(|(filetype, command)| format!("  {table}.{filetype} = {command}")),
+            );
+        }
+
+        self.messages.show(rows.join("\n"));
+
+        Ok(())
+    }
+
+    fn check_health(&mut self) {
+        let status = |ok: bool| if ok { "OK   " } else { "WARN " };
+        let mut rows = vec!["Terminal".to_string()];
+
+        let (ok, colors) = match self.terminal.color_depth() {
+            ColorDepth::TrueColor => (true, "truecolor"),
+            ColorDepth::Ansi256 => (true, "256 colors, set COLORTERM=truecolor for more"),
+            ColorDepth::Ansi16 => (false, "16 colors"),
+        };
+        rows.push(format!("  {}colors: {colors}", status(ok)));
+
+        let keys = if self.terminal.is_headless() {
+            "none, running headless"
+        } else if self.terminal.enhanced_keys() {
+            "kitty keyboard protocol"
+        } else {
+            "legacy, keys like CTRL-SHIFT-P look like CTRL-P"
+        };
+        rows.push(format!(
+            "  {}keyboard: {keys}",
+            status(self.terminal.enhanced_keys())
+        ));
+
+        rows.push(if self.options.osc52 {
+            format!(
+                "  {}clipboard: OSC 52, if the terminal allows it",
+                status(true)
+            )
+        } else {
+            format!("  {}clipboard: off, see :help 'osc52'", status(false))
+        });
+
+        rows.push("Config".to_string());
+
+        let config = self
+            .buffer
+            .path
+            .as_ref()
+            .and_then(|path| path.canonicalize().ok())
+            .and_then(|path| project::find(path.parent()?));
+
+        match &config {
+            Some(path) => {
+                let name = display_path(path, &self.cwd);
+
+                rows.push(match ProjectConfig::read(path) {
+                    Err(e) => format!("  ERROR {e}"),
+                    Ok(text) => match self.trust.check(path, &text) {
+                        Trusted::No => format!("  {}{name}: not trusted", status(false)),
+                        Trusted::Changed => {
+                            format!("  {}{name}: changed since it was trusted", status(false))
+                        }
+                        Trusted::Yes => match ProjectConfig::from_text(path, &text) {
+                            Ok(_) => format!("  {}{name}: loaded", status(true)),
+                            Err(e) => format!("  ERROR {e}"),
+                        },
+                    },
+                });
+            }
+            None => rows.push(format!(
+                "  {}no {} for this buffer",
+                status(true),
+                project::FILE_NAME
+            )),
+        }
+
+        rows.push("Programs".to_string());
+
+        let project = self.project_config();
+        let mut programs = vec![(
+            "makeprg".to_string(),
+            project
+                .and_then(|config| config.makeprg.clone())
+                .unwrap_or_else(|| self.options.makeprg.clone()),
+        )];
+
+        programs.push(("git".to_string(), "git".to_string()));
+
+        for (table, entries) in project
+            .iter()
+            .flat_map(|config| [("format", &config.format), ("lsp", &config.lsp)])
+        {
+            programs.extend(
+                entries
+                    .iter()
+                    .map(|(filetype, command)| (format!("{table}.{filetype}"), command.clone())),
+            );
+        }
+
+        for (name, command) in programs {
+            let program = command.split_whitespace().next().unwrap_or_default();
+
+            rows.push(match health::find_program(&command) {
+                Some(path) => format!("  {}{name}: {}", status(true), path.display()),
+                None => format!("  {}{name}: {program} not found on PATH", status(false)),
+            });
+        }
+
+        rows.push("Data directory".to_string());
+        rows.push(match paths::data_dir() {
+            Some(dir) => match health::check_writable(&dir) {
+                Ok(()) => format!("  {}{}: writable", status(true), dir.display()),
+                Err(e) => format!("  ERROR {e}"),
+            },
+            None => "  ERROR no data directory, set HOME or XDG_DATA_HOME".to_string(),
+        });
+
+        self.messages.show(rows.join("\n"));
+    }
+
+    fn reload_buffer(&mut self) -> Result<()> {
+        if self.buffer.is_directory() {
+            return Ok(self.buffer.refresh_directory()?);
+        }
+
+        self.buffer.reload()?;
+        self.index_large_file(&self.buffer);
+        self.apply_project_config();
+        self.dirty = true;
+
+        if let Some(path) = &self.buffer.path {
+            log::info!("reloaded {}", path.display());
+        }
+
+        Ok(())
+    }
+
+    fn write_buffer(&mut self, args: &str, force: bool, then_quit: bool) -> Result<()> {
+        if self.buffer.is_directory() {
+            return Err("Can't write a directory buffer".into());
+        }
+
+        if self.buffer.is_large() {
+            return Err("Can't write a large file, see 'largefile'".into());
+        }
+
+        if self.buffer.is_help() {
+            return Err("Can't write a help buffer".into());
+        }
+
+        if let Some(command) = args.strip_prefix('!') {
+            return self.write_through_command(command.trim());
+        }
+
+        if !args.is_empty() {
+            let path = self.resolve_path(args);
+
+            if self.buffer.path.as_ref() != Some(&path) {
+                let rebind = self.buffer.path.is_none();
+
+                return self.write_to(path, force, rebind, then_quit);
+            }
+        }
+
+        let path = self.buffer.path.clone().ok_or("No file name")?;
+        let display = display_path(&path, &self.cwd);
+
+        if self.buffer.read_only && !force {
+            return Err(format!(
+                "{display} is read-only (use :w! to force, or :w ! to write with 'writeprg')"
+            )
+            .into());
+        }
+
+        if self.buffer.lossy && !force {
+            return Err(format!("{display} has invalid UTF-8; :w! replaces those bytes").into());
+        }
+
+        if self.buffer.changed_on_disk() && !force {
+            self.prompt = Some(Prompt::yes_no(
+                format!("{display} changed on disk since reading. Overwrite?"),
+                PromptAction::Overwrite { then_quit },
+            ));
+
+            return Ok(());
+        }
+
+        self.write_current()
+    }
+
+    fn save_as(&mut self, args: &str, force: bool) -> Result<()> {
+        if self.buffer.is_directory() {
+            return Err("Can't write a directory buffer".into());
+        }
+
+        if self.buffer.is_large() {
+            return Err("Can't write a large file, see 'largefile'".into());
+        }
+
+        if self.buffer.is_help() {
+            return Err("Can't write a help buffer".into());
+        }
+
+        if args.is_empty() {
+            return Err("No file name".into());
+        }
+
+        self.write_to(self.resolve_path(args), force, true, false)
+    }
+
+    fn write_to(
+        &mut self,
+        path: PathBuf,
+        force: bool,
+        rebind: bool,
+        then_quit: bool,
+    ) -> Result<()> {
+        let display = display_path(&path, &self.cwd);
+
+        if path.is_dir() {
+            return Err(format!("{display} is a directory").into());
+        }
+
+        if self.buffer.lossy && !force {
+            return Err("The buffer has invalid UTF-8; use ! to replace those bytes".into());
+        }
+
+        if let Some(dir) = path.parent().filter(|dir| !dir.exists()) {
+            if !force {
+                return Err(format!(
+                    "{} doesn't exist (use ! to create it)",
+                    display_path(dir, &self.cwd)
+                )
+                .into());
+            }
+
+            std::fs::create_dir_all(dir).map_err(|e| {
+                format!(
+                    "Can't create {}: {}",
+                    display_path(dir, &self.cwd),
+                    describe_io_error(&e)
+                )
+            })?;
+        }
+
+        if path.exists() && !force {
+            self.prompt = Some(Prompt::yes_no(
+                format!("{display} exists. Overwrite?"),
+                PromptAction::WriteAs {
+                    path,
+                    rebind,
+                    then_quit,
+                },
+            ));
+
+            return Ok(());
+        }
+
+        self.write_as(path, rebind)
+    }
+
+    fn write_as(&mut self, path: PathBuf, rebind: bool) -> Result<()> {
+        if rebind {
+            self.buffer.path = Some(path);
+            self.dirty = true;
+
+            return self.write_current();
+        }
+
+        let display = display_path(&path, &self.cwd);
+
+        let bytes = self
+            .buffer
+            .write_copy(&path)
+            .map_err(|e| format!("Can't write {display}: {}", describe_io_error(&e)))?;
+
+        log::info!("wrote {} ({bytes} bytes)", path.display());
+        self.messages.info(format!(
+            "\"{display}\" {} lines, {bytes} bytes written",
+            self.buffer.text.len_lines()
+        ));
+
+        Ok(())
+    }
+
+    fn write_current(&mut self) -> Result<()> {
+        let path = self.buffer.path.clone().ok_or("No file name")?;
+        let display = display_path(&path, &self.cwd);
+
+        back_up(&path, self.options.backups);
+
+        let bytes = self
+            .buffer
+            .write()
+            .map_err(|e| format!("Can't write {display}: {}", describe_io_error(&e)))?;
+
+        self.buffer.read_only = false;
+        log::info!("wrote {} ({bytes} bytes)", path.display());
+        self.messages.info(format!(
+            "\"{display}\" {} lines, {bytes} bytes written",
+            self.buffer.text.len_lines()
+        ));
+        self.plugins.on_save(
+            &path.display().to_string(),
+            &mut self.buffer,
+            &mut self.messages,
+        );
+
+        if self.commit()? {
+            return Ok(());
+        }
+
+        if self.options.cargocheck && statusline::filetype(&path) == Some("rust") {
+            self.start_cargo_check(&path);
+        }
+
+        Ok(())
+    }
+
+    fn start_cargo_check(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or(path.to_path_buf());
+        let Some(dir) = diagnostics::cargo_root(&path) else {
+            return;
+        };
+
+        self.jobs.cancel(self.check_id);
+
+        let (id, progress) = self.jobs.start("cargo check");
+        let tx = self.events.sender();
+
+        self.check_id = id;
+
+        quickfix::run(
+            "cargo check --message-format=json".to_string(),
+            dir.to_path_buf(),
+            progress,
+            move |finished| {
+                let _ = tx.send(AppEvent::Check(id, finished));
+            },
+        );
+    }
+
+    fn check_finished(&mut self, finished: Finished) {
+        if let Err(e) = &finished.status {
+            self.messages
+                .error(format!("{}: {}", finished.command, describe_io_error(e)));
+            return;
+        }
+
+        self.diagnostics
+            .replace(diagnostics::parse_cargo(&finished.output, &finished.dir));
+        self.dirty = true;
+
+        let errors = self.diagnostics.count(Severity::Error);
+        let warnings = self.diagnostics.count(Severity::Warning);
+
+        if errors + warnings > 0 {
+            self.messages
+                .info(format!("cargo check: {errors} errors, {warnings} warnings"));
+        }
+    }
+
+    fn buffer_diagnostics(&self) -> std::collections::HashMap<usize, Diagnostic> {
+        let mut lines = std::collections::HashMap::<usize, Diagnostic>::new();
+        let Some(path) = &self.buffer.path else {
+            return lines;
+        };
+        let path = path.canonicalize().unwrap_or(path.clone());
+
+        for diagnostic in self.diagnostics.get(&path) {
+            match lines.get(&diagnostic.line) {
+                Some(shown) if shown.severity >= diagnostic.severity => {}
+                _ => {
+                    lines.insert(diagnostic.line, diagnostic.clone());
+                }
+            }
+        }
+
+        lines
+    }
+
+    fn suspend(&mut self) -> Result<()> {
+        if self.terminal.is_headless() {
+            return Ok(());
+        }
+
+        log::debug!("suspending");
+
+        self.events.pause();
+        self.terminal.suspend()?;
+
+        unsafe {
+            libc::raise(libc::SIGSTOP);
+        }
+
+        self.terminal.resume()?;
+        self.events.resume();
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn autosave(&mut self) {
+        let buffers = std::iter::once(&mut self.buffer).chain(self.buffers.iter_mut());
+
+        for buffer in buffers {
+            let Some(path) = buffer.path.clone() else {
+                continue;
+            };
+
+            if !buffer.modified
+                || buffer.is_help()
+                || buffer.is_directory()
+                || buffer.read_only
+                || buffer.lossy
+                || buffer.changed_on_disk()
+            {
+                continue;
+            }
+
+            back_up(&path, self.options.backups);
+
+            match buffer.write() {
+                Ok(bytes) => log::info!("autosaved {} ({bytes} bytes)", path.display()),
+                Err(e) => self.messages.error(format!(
+                    "Can't write {}: {}",
+                    display_path(&path, &self.cwd),
+                    describe_io_error(&e)
+                )),
+            }
+        }
+
+        self.dirty = true;
+    }
+
+    fn write_through_command(&mut self, command: &str) -> Result<()> {
+        let command = match command {
+            "" => self.options.writeprg.clone(),
+            command => command.to_string(),
+        };
+
+        let to_file = command.contains('%');
+        let command = match &self.buffer.path {
+            Some(path) => command.replace('%', &shell_quote(&path.display().to_string())),
+            None if to_file => return Err("No file name".into()),
+            None => command,
+        };
+
+        self.events.pause();
+        self.terminal.suspend()?;
+
+        let result = run_with_stdin(&command, &self.buffer.text);
+
+        self.terminal.resume()?;
+        self.events.resume();
+        self.dirty = true;
+
+        match result {
+            Ok(status) if status.success() => {
+                if to_file {
+                    self.buffer.mark_written();
+                }
+
+                self.messages
+                    .info(format!("Wrote buffer through: {command}"));
+                Ok(())
+            }
+            Ok(status) => Err(format!("{command}: exited with {status}").into()),
+            Err(e) => Err(format!("{command}: {}", describe_io_error(&e)).into()),
+        }
+    }
+
+    fn resolve_path(&self, path: &str) -> PathBuf {
+        let path = match (path.strip_prefix('~'), std::env::var_os("HOME")) {
+            (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+                PathBuf::from(home).join(rest.trim_start_matches('/'))
+            }
+            _ => PathBuf::from(path),
+        };
+
+        self.cwd.join(path)
+    }
+
+    fn change_dir(&mut self, path: &str) -> Result<()> {
+        let target = if path.is_empty() {
+            self.resolve_path("~")
+        } else if path == "-" {
+            self.previous_cwd.clone().ok_or("No previous directory")?
+        } else {
+            self.resolve_path(path)
+        };
+
+        let target = target
+            .canonicalize()
+            .map_err(|e| format!("{}: {e}", target.display()))?;
+
+        if !target.is_dir() {
+            return Err(format!("Not a directory: {}", target.display()).into());
+        }
+
+        self.previous_cwd = Some(std::mem::replace(&mut self.cwd, target));
+        self.messages.info(self.cwd.display().to_string());
+
+        Ok(())
+    }
+
+    fn open_file(&mut self, path: PathBuf) -> Result<()> {
+        if self.buffer.is_file(&path) {
+            return Ok(());
+        }
+
+        let (buffer, loaded) = match self.buffers.iter().position(|buffer| buffer.is_file(&path)) {
+            Some(idx) => (self.buffers.remove(idx), false),
+            None => {
+                log::info!("opening {}", path.display());
+
+                (self.read_file(path)?, true)
+            }
+        };
+
+        self.remember_position();
+
+        let previous = std::mem::replace(&mut self.buffer, buffer);
+
+        if previous.path.is_some() || previous.text.len_chars() > 0 {
+            self.buffers.push(previous);
+        }
+
+        if loaded {
+            self.restore_position();
+            self.warn_if_lossy();
+            self.note_large_file();
+            self.load_project_config();
+        } else {
+            self.check_disk_changes();
+        }
+
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn open_log(&mut self) -> Result<()> {
+        let path = logger::log_file().ok_or("No data directory for the log")?;
+
+        if !log::log_enabled!(log::Level::Debug) && !path.exists() {
+            return Err("Logging is off, start with --verbose".into());
+        }
+
+        log::logger().flush();
+
+        if self.buffer.is_file(&path) {
+            return self.reload_buffer();
+        }
+
+        self.open_file(path)
+    }
+
+    fn read_file(&self, path: PathBuf) -> std::io::Result<Buffer> {
+        let limit = self.options.largefile as u64 * 1024 * 1024;
+        let large = self.options.largefile > 0
+            && std::fs::metadata(&path).is_ok_and(|meta| meta.is_file() && meta.len() >= limit);
+
+        if !large {
+            return Buffer::from_file(path);
+        }
+
+        let buffer = Buffer::from_large_file(path)?;
+
+        self.index_large_file(&buffer);
+
+        Ok(buffer)
+    }
+
+    fn index_large_file(&self, buffer: &Buffer) {
+        let (Some(file), Some(path)) = (&buffer.large, buffer.path.clone()) else {
+            return;
+        };
+
+        let tx = self.events.sender();
+
+        file.build_index(move || {
+            let _ = tx.send(AppEvent::LineIndex(path));
+        });
+    }
+
+    fn note_large_file(&mut self) {
+        if self.buffer.is_large() {
+            self.messages.info(format!(
+                "{}: over {} MB, opened read-only",
+                self.buffer_name(),
+                self.options.largefile
+            ));
+        }
+    }
+
+    fn warn_if_lossy(&mut self) {
+        if self.buffer.lossy && !self.buffer.is_large() {
+            self.messages.warn(format!(
+                "{}: invalid UTF-8 shown as \u{FFFD}, use :hex for raw bytes",
+                self.buffer_name()
+            ));
+        }
+    }
+
+    fn remember_position(&mut self) {
+        if self.buffer.is_directory() {
+            return;
+        }
+
+        if let Some(path) = &self.buffer.path {
+            self.recent
+                .record(path, self.buffer.cursor.1, self.buffer.cursor.0);
+            let _ = self.recent.save();
+        }
+    }
+
+    fn remember_positions(&mut self) {
+        for buffer in self
+            .buffers
+            .iter()
+            .rev()
+            .chain(std::iter::once(&self.buffer))
+        {
+            if buffer.is_directory() || buffer.is_git_status() {
+                continue;
+            }
+
+            if let Some(path) = &buffer.path {
+                self.recent.record(path, buffer.cursor.1, buffer.cursor.0);
+            }
+        }
+
+        let _ = self.recent.save();
+    }
+
+    fn restore_position(&mut self) {
+        let position = self
+            .buffer
+            .path
+            .as_deref()
+            .and_then(|path| self.recent.position(path));
+
+        if let Some((line, col)) = position {
+            self.jump_to(line, col);
+        }
+    }
+
+    fn open_oldfiles(&mut self) -> Result<()> {
+        let items = self
+            .recent
+            .entries()
+            .iter()
+            .filter(|entry| entry.path.exists())
+            .map(|entry| PickerItem {
+                label: display_path(&entry.path, &self.cwd),
+                detail: format!(":{}", entry.line + 1),
+                target: Target::File {
+                    path: entry.path.clone(),
+                    line: entry.line,
+                    col: entry.col,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        if items.is_empty() {
+            return Err("No recent files".into());
+        }
+
+        self.picker = Some(Picker::new("Recent files", items));
+
+        Ok(())
+    }
+
+    fn start_grep(&mut self, pattern: &str) -> Result<()> {
+        if pattern.is_empty() {
+            return Err("No search pattern".into());
+        }
+
+        let regex = Regex::new(pattern, false)?;
+        self.spawn_grep(regex, format!("rg {pattern}"));
+        self.picker = Some(Picker::new(format!("rg {pattern} …"), Vec::new()));
+
+        Ok(())
+    }
+
+    fn start_replace(&mut self, args: &str) -> Result<()> {
+        let mut chars = args.chars();
+
+        let delimiter = chars
+            .next()
+            .ok_or("Usage: rgreplace /pattern/replacement/")?;
+        let mut parts = chars.as_str().split(delimiter);
+
+        let (Some(pattern), Some(replacement)) = (parts.next(), parts.next()) else {
+            return Err("Usage: rgreplace /pattern/replacement/".into());
+        };
+
+        if pattern.is_empty() {
+            return Err("No search pattern".into());
+        }
+
+        let regex = Regex::new(pattern, false)?;
+        self.spawn_grep(regex.clone(), format!("rg {pattern}"));
+        self.replace = Some(ReplacePanel::new(regex, replacement.to_string()));
+
+        Ok(())
+    }
+
+    fn spawn_grep(&mut self, regex: Regex, name: String) {
+        self.jobs.cancel(self.grep_id);
+
+        let (id, progress) = self.jobs.start(name);
+        let tx = self.events.sender();
+
+        self.grep_id = id;
+        self.grep = Some(grep::spawn(
+            self.cwd.clone(),
+            regex,
+            progress,
+            move |event| tx.send(AppEvent::Grep(id, event)).is_ok(),
+        ));
+    }
+
+    fn handle_grep_event(&mut self, event: GrepEvent) {
+        if self.grep.is_none() {
+            return;
+        }
+
+        let (matches, mut done) = match event {
+            GrepEvent::Match(m) => (vec![m], false),
+            GrepEvent::Done => (Vec::new(), true),
+        };
+
+        if let Some(panel) = &mut self.replace {
+            for m in matches {
+                panel.push(m);
+            }
+
+            if done {
+                panel.finish_search();
+            }
+        } else if let Some(picker) = &mut self.picker {
+            let items = matches
+                .into_iter()
+                .map(|m| {
+                    let display = display_path(&m.path, &self.cwd);
+
+                    PickerItem {
+                        label: format!("{}:{}: {}", display, m.line + 1, m.text.trim()),
+                        detail: String::new(),
+                        target: Target::File {
+                            path: m.path,
+                            line: m.line,
+                            col: m.col,
+                        },
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            if !items.is_empty() {
+                picker.extend(items);
+            }
+
+            if done {
+                let title = picker.title().trim_end_matches(" …").to_string();
+                picker.set_title(title);
+            }
+        } else {
+            done = true;
+        }
+
+        if done {
+            self.grep = None;
+            self.jobs.finish(self.grep_id);
+        }
+    }
+
+    fn cancel_job(&mut self) -> bool {
+        let Some(job) = self.jobs.cancel_last() else {
+            return false;
+        };
+
+        if job.id == self.grep_id {
+            self.handle_grep_event(GrepEvent::Done);
+        }
+
+        self.messages.info(format!("Cancelled {}", job.name));
+
+        true
+    }
+
+    fn open_outline(&mut self) -> Result<()> {
+        let symbols = document_symbols(&self.buffer.text, self.buffer.path.as_deref());
+
+        if symbols.is_empty() {
+            return Err("No symbols found".into());
+        }
+
+        let items = symbols
+            .into_iter()
+            .map(|symbol| PickerItem {
+                label: symbol.name,
+                detail: format!("{} :{}", symbol.kind, symbol.line + 1),
+                target: Target::Position {
+                    line: symbol.line,
+                    col: symbol.col,
+                },
+            })
+            .collect();
+
+        self.picker = Some(Picker::new("Outline", items));
+
+        Ok(())
+    }
+
+    fn lsp_client(&mut self) -> Result<(usize, PathBuf)> {
+        let path = self
+            .buffer
+            .path
+            .as_ref()
+            .and_then(|path| path.canonicalize().ok())
+            .ok_or("No file for this buffer")?;
+        let (language, command, root) = self
+            .lsp_server(&path)
+            .ok_or("No language server for this file type")?;
+
+        self.lsp.retain_mut(lsp::Client::is_running);
+
+        let idx = match self
+            .lsp
+            .iter()
+            .position(|client| client.command == command && client.root == root)
+        {
+            Some(idx) => idx,
+            None => {
+                let client = lsp::Client::start(&command, &root)
+                    .inspect_err(|_| self.lsp_failed.push(command.clone()))?;
+
+                self.lsp_failed.retain(|failed| *failed != command);
+                self.lsp.push(client);
+                self.lsp.len() - 1
+            }
+        };
+
+        self.lsp[idx].sync(&path, &self.buffer.text, language)?;
+
+        Ok((idx, path))
+    }
+
+    fn lsp_server(&self, path: &Path) -> Option<(&'static str, String, PathBuf)> {
+        let filetype = statusline::filetype(path)?;
+        let project = self.project_config().and_then(|config| {
+            let command = config.lsp.get(filetype)?;
+
+            Some((command.clone(), config.root().to_path_buf()))
+        });
+
+        let (command, root) = match project {
+            Some(server) => server,
+            None => (lsp::server(filetype)?.to_string(), lsp::root(path)),
+        };
+
+        Some((filetype, command, root))
+    }
+
+    fn code_actions(&mut self) -> Result<()> {
+        let (idx, path) = self.lsp_client()?;
+        let cursor = self.cursor_to_char_idx();
+        let line = self.buffer.cursor.1 as u64;
+        let client = &mut self.lsp[idx];
+
+        if !client.supports("codeActionProvider") {
+            return Err(format!("{} has no code actions", client.command).into());
+        }
+
+        let diagnostics = client
+            .diagnostics(&path)
+            .iter()
+            .filter(|diagnostic| {
+                let range = diagnostic.get("range");
+                let line_of = |end: &str| range?.get(end)?.get("line")?.as_u64();
+
+                matches!(
+                    (line_of("start"), line_of("end")),
+                    (Some(start), Some(end)) if (start..=end).contains(&line)
+                )
+            })
+            .cloned()
+            .collect();
+
+        let params = Value::object([
+            (
+                "textDocument",
+                Value::object([("uri", lsp::uri(&path).into())]),
+            ),
+            ("range", lsp::range(&self.buffer.text, cursor..cursor)),
+            (
+                "context",
+                Value::object([
+                    ("diagnostics", Value::Array(diagnostics)),
+                    ("triggerKind", 1.into()),
+                ]),
+            ),
+        ]);
+
+        let result = client.request("textDocument/codeAction", params, lsp::REQUEST_TIMEOUT)?;
+        let actions = result.as_array().map(<[Value]>::to_vec).unwrap_or_default();
+
+        if actions.is_empty() {
+            self.messages.info("No code actions here");
+            return Ok(());
+        }
+
+        self.code_actions = Some(CodeActions::new(actions));
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn signature_help(&mut self, trigger: char) {
+        let Some((_, command, _)) = self
+            .buffer
+            .path
+            .as_ref()
+            .and_then(|path| path.canonicalize().ok())
+            .and_then(|path| self.lsp_server(&path))
+        else {
+            return;
+        };
+
+        if self.lsp_failed.contains(&command) {
+            return;
+        }
+
+        match self.request_signature(trigger) {
+            Ok(signature) => self.signature = signature,
+            Err(e) => {
+                self.signature = None;
+                self.report(e);
+            }
+        }
+
+        self.dirty = true;
+    }
+
+    fn request_signature(&mut self, trigger: char) -> Result<Option<Signature>> {
+        let (idx, path) = self.lsp_client()?;
+        let cursor = self.cursor_to_char_idx();
+        let client = &mut self.lsp[idx];
+
+        if !client.supports("signatureHelpProvider") {
+            return Ok(None);
+        }
+
+        let params = Value::object([
+            (
+                "textDocument",
+                Value::object([("uri", lsp::uri(&path).into())]),
+            ),
+            ("position", lsp::position(&self.buffer.text, cursor)),
+            (
+                "context",
+                Value::object([
+                    ("triggerKind", 2.into()),
+                    ("triggerCharacter", trigger.to_string().into()),
+                    ("isRetrigger", self.signature.is_some().into()),
+                ]),
+            ),
+        ]);
+
+        let help = client.request("textDocument/signatureHelp", params, lsp::SIGNATURE_TIMEOUT)?;
+
+        Ok(Signature::from_help(&help, self.buffer.cursor.1))
+    }
+
+    fn apply_code_action(&mut self, action: Value) -> Result<()> {
+        let title = action
+            .get("title")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let command = match action.get("command") {
+            Some(Value::String(_)) => Some(action.clone()),
+            command => command.cloned(),
+        };
+
+        let mut files = match action.get("edit") {
+            Some(edit) => self.apply_workspace_edit(edit)?,
+            None => 0,
+        };
+
+        if let Some(command) = command {
+            let (idx, _) = self.lsp_client()?;
+            let params = Value::object([
+                (
+                    "command",
+                    command.get("command").cloned().unwrap_or(Value::Null),
+                ),
+                (
+                    "arguments",
+                    command
+                        .get("arguments")
+                        .cloned()
+                        .unwrap_or(Value::Array(Vec::new())),
+                ),
+            ]);
+
+            self.lsp[idx].request("workspace/executeCommand", params, lsp::REQUEST_TIMEOUT)?;
+
+            for edit in self.lsp[idx].take_edits() {
+                files += self.apply_workspace_edit(&edit)?;
+            }
+        }
+
+        if files > 1 {
+            self.messages
+                .info(format!("Applied {title} in {files} files"));
+        } else {
+            self.messages.info(format!("Applied {title}"));
+        }
+
+        Ok(())
+    }
+
+    fn apply_workspace_edit(&mut self, edit: &Value) -> Result<usize> {
+        let mut changes = Vec::new();
+
+        for (path, edits) in lsp::workspace_edit(edit)? {
+            let text = match self.buffer_for_path_mut(&path) {
+                Some(buffer) => buffer.text.clone(),
+                None => std::fs::read_to_string(&path)
+                    .map_err(|e| format!("{}: {e}", path.display()))?
+                    .into(),
+            };
+
+            let edits = lsp::text_edits(&text, &edits)?;
+
+            changes.push((path, text, edits));
+        }
+
+        let files = changes.len();
+
+        for (path, mut text, edits) in changes {
+            match self.buffer_for_path_mut(&path) {
+                Some(buffer) => {
+                    for (range, new_text) in edits {
+                        buffer.remove(range.clone())?;
+                        buffer.insert(range.start, &new_text)?;
+                    }
+                }
+                None => {
+                    lsp::apply(&mut text, &edits);
+
+                    std::fs::write(&path, text.to_string())
+                        .map_err(|e| format!("{}: {e}", path.display()))?;
+                }
+            }
+        }
+
+        let (col, line) = self.buffer.cursor;
+
+        self.jump_to(line, col);
+        self.dirty = true;
+
+        Ok(files)
+    }
+
+    fn insert_char(&mut self, c: char) -> Result<()> {
+        self.buffer.insert_char(self.cursor_to_char_idx(), c)?;
+
+        self.buffer.cursor.0 += 1;
+        self.buffer.cursor_x_remember = self.buffer.cursor.0;
+
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn insert_tab(&mut self) -> Result<()> {
+        let width = self.shiftwidth();
+
+        if !self.expandtab() || width == 0 {
+            return self.insert_char('\t');
+        }
+
+        let col = self
+            .buffer
+            .text
+            .line(self.buffer.cursor.1)
+            .chars()
+            .take(self.buffer.cursor.0)
+            .map(|c| if c == '\t' { indent::TAB_WIDTH } else { 1 })
+            .sum::<usize>();
+
+        for _ in 0..width - col % width {
+            self.insert_char(' ')?;
+        }
+
+        Ok(())
+    }
+
+    fn shiftwidth(&self) -> usize {
+        self.buffer
+            .indent
+            .map_or(self.options.shiftwidth, |indent| indent.width)
+    }
+
+    fn expandtab(&self) -> bool {
+        self.buffer
+            .indent
+            .map_or(self.options.expandtab, |indent| !indent.tabs)
+    }
+
+    fn insert_mode(&mut self) -> Result<()> {
+        if self.buffer.is_large() {
+            return Err("Can't edit a large file, see 'largefile'".into());
+        }
+
+        self.messages.dismiss();
+        self.mode = Mode::Insert;
+        self.terminal.change_cursor_style(CursorStyle::SteadyBar)
+    }
+
+    fn visual_mode(&mut self, anchor: usize) {
+        self.messages.dismiss();
+        self.visual_start = anchor;
+        self.mode = Mode::Visual;
+        self.dirty = true;
+    }
+
+    fn visual_range(&self) -> LineRange {
+        let (a, b) = (self.visual_start, self.buffer.cursor.1);
+
+        LineRange {
+            start: std::cmp::min(a, b),
+            end: std::cmp::max(a, b),
+        }
+    }
+
+    fn leave_visual(&mut self) -> Result<LineRange> {
+        let range = self.visual_range();
+
+        self.buffer.marks.insert('<', (range.start, 0));
+        self.buffer.marks.insert('>', (range.end, 0));
+        self.dirty = true;
+        self.normal_mode()?;
+
+        Ok(range)
+    }
+
+    fn handle_visual_key(&mut self, event: KeyEvent) -> Result<()> {
+        let KeyCode::Char(c) = event.code else {
+            match event.code {
+                KeyCode::Esc => {
+                    self.leave_visual()?;
+                }
+                KeyCode::Left => self.move_cursor_left(),
+                KeyCode::Down => self.move_cursor_down(),
+                KeyCode::Up => self.move_cursor_up(),
+                KeyCode::Right => self.move_cursor_right(),
+                KeyCode::Home => self.move_to_line_start(),
+                _ => {}
+            }
+
+            self.dirty = true;
+
+            return Ok(());
+        };
+
+        if event.modifiers.contains(KeyModifiers::ALT) && matches!(c, 'j' | 'k') {
+            let range = self.visual_range();
+            let moved = self.move_by(range, c == 'j')?;
+            let line = self.buffer.cursor.1 - range.start + moved.start;
+
+            self.visual_start = self.visual_start - range.start + moved.start;
+            self.jump_to(line, self.buffer.cursor.0);
+
+            return Ok(());
+        }
+
+        match c {
+            'h' => self.move_cursor_left(),
+            'j' => self.move_cursor_down(),
+            'k' => self.move_cursor_up(),
+            'l' => self.move_cursor_right(),
+            '0' => self.move_to_line_start(),
+            '^' => self.move_to_first_non_blank(),
+            '$' => {
+                self.buffer.cursor.0 = self.line_len(self.buffer.text.line(self.buffer.cursor.1))
+            }
+            'o' => {
+                let line = std::mem::replace(&mut self.visual_start, self.buffer.cursor.1);
+
+                self.jump_to(line, self.buffer.cursor.0);
+            }
+            'V' => {
+                self.leave_visual()?;
+            }
+            ':' => {
+                self.leave_visual()?;
+                self.prefill_command("'<,'>".to_string())?;
+            }
+            'd' | 'x' => {
+                let range = self.leave_visual()?;
+
+                self.delete_lines(Some(range), "")?;
+            }
+            'y' => {
+                let range = self.leave_visual()?;
+
+                self.yank_lines(Some(range), "")?;
+                self.jump_to(range.start, self.buffer.cursor.0);
+            }
+            '>' | '<' => {
+                let range = self.leave_visual()?;
+
+                self.shift_lines(Some(range), &c.to_string(), "")?;
+            }
+            _ => {}
+        }
+
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn reselect(&mut self) -> Result<()> {
+        let marks = &self.buffer.marks;
+        let (Some(&(start, _)), Some(&(end, _))) = (marks.get(&'<'), marks.get(&'>')) else {
+            return Err("No previous visual selection".into());
+        };
+
+        let last = self.last_line();
+
+        self.visual_mode(std::cmp::min(start, last));
+        self.jump_to(std::cmp::min(end, last), self.buffer.cursor.0);
+
+        Ok(())
+    }
+
+    fn normal_mode(&mut self) -> Result<()> {
+        if self.signature.take().is_some() {
+            self.dirty = true;
+        }
+
+        self.mode = Mode::Normal;
+        self.terminal.change_cursor_style(CursorStyle::SteadyBlock)
+    }
+
+    fn terminal_mode(&mut self) -> Result<()> {
+        self.messages.dismiss();
+        self.mode = Mode::Terminal;
+        self.terminal.change_cursor_style(CursorStyle::SteadyBlock)
+    }
+
+    fn search_mode(&mut self, forward: bool) -> Result<()> {
+        self.mode = Mode::Search;
+        self.search_forward = forward;
+        self.terminal.change_cursor_style(CursorStyle::SteadyBar)
+    }
+
+    fn command_mode(&mut self) -> Result<()> {
+        self.mode = Mode::Command;
+        self.terminal.change_cursor_style(CursorStyle::SteadyBar)
+    }
+
+    fn backspace(&mut self) -> Result<()> {
+        let idx = self.cursor_to_char_idx();
+
+        if self.buffer.cursor.0 > 0 {
+            self.buffer.remove(idx - 1..idx)?;
+
+            self.buffer.cursor.0 -= 1;
+            self.buffer.cursor_x_remember = self.buffer.cursor.0;
+
+            self.dirty = true;
+        } else if self.buffer.cursor.0 == 0 && self.buffer.cursor.1 > 0 {
+            let line_len = self.line_len(self.buffer.text.line(self.buffer.cursor.1 - 1));
+
+            let start = self.buffer.text.line_to_char(self.buffer.cursor.1 - 1) + line_len;
+
+            self.buffer.remove(start..idx)?;
+
+            self.buffer.cursor.1 -= 1;
+            self.buffer.cursor.0 = line_len;
+
+            self.buffer.cursor_x_remember = self.buffer.cursor.0;
+
+            self.dirty = true;
+        }
+
+        Ok(())
+    }
+
+    fn enter(&mut self) -> Result<()> {
+        let (col, line) = self.buffer.cursor;
+        let text = self.buffer.text.line(line).to_string();
+        let (before, after) = text.split_at(
+            text.char_indices()
+                .nth(col)
+                .map_or(text.len(), |(idx, _)| idx),
+        );
+        let newline = self
+            .behavior()
+            .and_then(|behavior| behavior.newline(before));
+
+        if newline == Some(Newline::End) && after.trim().is_empty() {
+            let start = self.buffer.text.line_to_char(line);
+
+            self.buffer.remove(start..start + col)?;
+            self.buffer.cursor.0 = 0;
+            self.buffer.cursor_x_remember = 0;
+            self.dirty = true;
+
+            return Ok(());
+        }
+
+        self.buffer.insert_char(self.cursor_to_char_idx(), '\n')?;
+
+        self.buffer.cursor.1 += 1;
+        self.buffer.cursor.0 = 0;
+
+        if let Some(Newline::Continue(prefix)) = newline {
+            self.buffer.insert(self.cursor_to_char_idx(), &prefix)?;
+            self.buffer.cursor.0 = prefix.chars().count();
+        }
+
+        self.buffer.cursor_x_remember = self.buffer.cursor.0;
+
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn insert_text(&mut self, text: &str) -> Result<()> {
+        let idx = self.cursor_to_char_idx();
+
+        self.buffer.insert(idx, text)?;
+
+        let end = idx + text.chars().count();
+        let line = self.buffer.text.char_to_line(end);
+
+        self.jump_to(line, end - self.buffer.text.line_to_char(line));
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn register_text(&self, name: char) -> Option<String> {
+        match name {
+            '%' => self
+                .buffer
+                .path
+                .as_ref()
+                .map(|path| display_path(path, &self.cwd)),
+            '/' => self
+                .last_search
+                .as_ref()
+                .map(|(pattern, _)| pattern.clone()),
+            name => self.registers.get(name).map(|reg| reg.text.clone()),
+        }
+    }
+
+    fn handle_expression_key(&mut self, event: KeyEvent) -> Result<()> {
+        let Some(expression) = &mut self.expression else {
+            return Ok(());
+        };
+
+        match event.code {
+            KeyCode::Char(c) => expression.push(c),
+            KeyCode::Backspace if expression.is_empty() => self.expression = None,
+            KeyCode::Backspace => {
+                expression.pop();
+            }
+            KeyCode::Esc => self.expression = None,
+            KeyCode::Enter => {
+                let result = self.expression.take().map(|text| expr::evaluate(&text));
+
+                match result {
+                    Some(Ok(value)) if matches!(self.mode, Mode::Insert) => {
+                        self.insert_text(&expr::format(value))?;
+                    }
+                    Some(Ok(value)) => self.command.push_str(&expr::format(value)),
+                    Some(Err(e)) => self.messages.error(e),
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn handle_command_register_key(&mut self, event: KeyEvent) -> bool {
+        if std::mem::take(&mut self.command_pending_register) {
+            if let KeyCode::Char(c) = event.code {
+                match self.register_text(c) {
+                    _ if c == '=' => self.expression = Some(String::new()),
+                    Some(text) => self
+                        .command
+                        .push_str(&text.trim_end_matches('\n').replace('\n', " ")),
+                    None => self.messages.error(format!("Register {c} is empty")),
+                }
+            }
+
+            return true;
+        }
+
+        if event.code == KeyCode::Char('r') && event.modifiers.contains(KeyModifiers::CONTROL) {
+            self.command_pending_register = true;
+
+            return true;
+        }
+
+        false
+    }
+
+    fn cursor_to_char_idx(&self) -> usize {
+        self.buffer.text.line_to_char(self.buffer.cursor.1) + self.buffer.cursor.0
+    }
+
+    pub fn draw(&mut self) -> Result<()> {
+        self.terminal.hide_cursor()?;
+        self.terminal.set_title(&self.title())?;
+
+        if self.too_small() {
+            return self.draw_too_small();
+        }
+
+        self.resize_term();
+
+        if self.diff.is_some() {
+            if self.dirty {
+                if let Some(diff) = &mut self.diff {
+                    diff.update(&self.buffer);
+                }
+            }
+
+            self.scroll_diff();
+        }
+
+        if self.zen_active() {
+            self.scroll_zen();
+        }
+
+        self.keep_window();
+        self.update_folds();
+        self.scroll_to_cursor();
+
+        if self.dirty {
+            self.conflicts = conflicts::find_conflicts(&self.buffer.text);
+            self.rainbow = self.rainbow_brackets();
+            self.swatches = self.color_swatches();
+            self.bookmarked = self.bookmarked_lines();
+            self.line_diagnostics = self.buffer_diagnostics();
+            self.table = self
+                .buffer
+                .table
+                .map(|delimiter| Table::new(&self.buffer.text, delimiter));
+        }
+
+        let field = self.table_field();
+
+        if field != self.drawn_field {
+            self.drawn_field = field;
+            self.dirty = true;
+        }
+
+        let bracket = self.bracket_match();
+        let mut stale = Vec::new();
+
+        if self.options.cursorline && self.drawn_cursor_line != Some(self.buffer.cursor.1) {
+            stale.extend(self.drawn_cursor_line);
+            stale.push(self.buffer.cursor.1);
+        }
+
+        if bracket != self.drawn_bracket {
+            stale.extend(self.drawn_bracket.map(|(line, _)| line));
+            stale.extend(bracket.map(|(line, _)| line));
+        }
+
+        if self.options.number == NumberStyle::Relative
+            && self.drawn_cursor_line != Some(self.buffer.cursor.1)
+        {
+            self.dirty = true;
+        }
+
+        self.drawn_cursor_line = Some(self.buffer.cursor.1);
+        self.drawn_bracket = bracket;
+
+        if self.zen_active() {
+            if self.dirty || !stale.is_empty() {
+                self.draw_zen_rows()?;
+            }
+        } else if self.dirty {
+            for i in 0..self.terminal.size().height - 1 {
+                self.draw_text_row(i)?;
+            }
+        } else if self.diff.is_none() {
+            stale.sort_unstable();
+            stale.dedup();
+
+            for line in stale {
+                if let Some(row) = self.screen_row(line) {
+                    self.draw_text_row(row as u16)?;
+                }
+            }
+        }
+
+        if self.dirty && self.minimap_visible() {
+            self.draw_minimap()?;
+        }
+
+        self.draw_scrollbar()?;
+
+        self.draw_status_bar()?;
+
+        self.draw_overlays()?;
+
+        self.dirty = false;
+
+        self.draw_cursor()?;
+
+        self.terminal.show_cursor()?;
+
+        Ok(())
+    }
+
+    fn minimap_visible(&self) -> bool {
+        self.options.minimap
+            && self.terminal.size().width as usize >= minimap::WIDTH * 5
+            && !self.preview_visible()
+            && !self.term_visible()
+            && !self.zen_active()
+            && !self.buffer.is_hex()
+    }
+
+    fn draw_minimap(&mut self) -> Result<()> {
+        let height = (self.terminal.size().height as usize).saturating_sub(1);
+        let text = &self.buffer.text;
+        let lines = text.len_lines();
+        let top = self.buffer.view.top_line;
+        let first = minimap::first_line(lines, height, top);
+        let x = self.text_width();
+
+        let symbols = document_symbols(text, self.buffer.path.as_deref())
+            .into_iter()
+            .map(|symbol| symbol.line)
+            .collect::<std::collections::HashSet<_>>();
+
+        let line = |idx: usize| self.display_line(idx).chars().collect::<Vec<_>>();
+        let color = |idx: usize| {
+            let line = self.display_line(idx);
+            let line = line.trim_start();
+
+            if symbols.contains(&idx) {
+                Color::Cyan
+            } else if line.starts_with("//") || line.starts_with('#') || line.starts_with("--") {
+                Color::DarkGrey
+            } else {
+                Color::Grey
+            }
+        };
+
+        let mut rows = Vec::new();
+
+        for row in 0..height {
+            let idx = first + row * 2;
+
+            let (cells, fg) = if idx < lines {
+                let (upper, lower) = (line(idx), line(idx + 1));
+                let fg = if upper.iter().all(|c| c.is_whitespace()) {
+                    color(idx + 1)
+                } else {
+                    color(idx)
+                };
+
+                let cells = (0..minimap::WIDTH)
+                    .map(|col| minimap::cell(&upper, &lower, col))
+                    .collect::<String>();
+
+                (cells, fg)
+            } else {
+                (" ".repeat(minimap::WIDTH), Color::Grey)
+            };
+
+            rows.push((cells, fg, idx < top + height && idx + 2 > top));
+        }
+
+        for (row, (cells, fg, in_view)) in rows.into_iter().enumerate() {
+            let bg = if in_view {
+                Color::AnsiValue(238)
+            } else {
+                Color::AnsiValue(234)
+            };
+
+            self.terminal
+                .print_styled_at((x, row as u16), cells.with(fg).on(bg))?;
+        }
+
+        Ok(())
+    }
+
+    fn text_area_width(&self) -> usize {
+        self.text_width()
+            .saturating_sub(self.text_start() + u16::from(self.scrollbar_visible()))
+            as usize
+    }
+
+    fn scrollbar_visible(&self) -> bool {
+        let height = (self.terminal.size().height as usize).saturating_sub(1);
+
+        self.options.scrollbar
+            && self.buffer.text.len_lines() > height
+            && !self.zen_active()
+            && self.diff.is_none()
+            && self.text_start() > 0
+            && !self.minimap_visible()
+    }
+
+    fn draw_scrollbar(&mut self) -> Result<()> {
+        let height = (self.terminal.size().height as usize).saturating_sub(1);
+        let lines = self.buffer.text.len_lines();
+
+        if !self.scrollbar_visible() {
+            return Ok(());
+        }
+
+        if self.dirty {
+            self.search_lines = None;
+        }
+
+        if let Some((pattern, _)) = &self.last_search {
+            if self
+                .search_lines
+                .as_ref()
+                .is_none_or(|(key, _)| key != pattern)
+            {
+                let matches = Regex::new(pattern, self.ignore_case(pattern))
+                    .map(|regex| search::matching_lines(&self.buffer.text, &regex))
+                    .unwrap_or_default();
+
+                self.search_lines = Some((pattern.clone(), matches));
+            }
+        }
+
+        let row_of = |line: usize| line * height / lines;
+        let (thumb_start, thumb_end) = match &self.buffer.large {
+            Some(file) => {
+                let row = file.percent(self.buffer.view.top_line, lines) * height / 100;
+                let row = std::cmp::min(row, height - 1);
+
+                (row, row + 1)
+            }
+            None => {
+                let start = row_of(self.buffer.view.top_line);
+
+                (
+                    start,
+                    std::cmp::max(row_of(self.buffer.view.top_line + height), start + 1),
+                )
+            }
+        };
+
+        let mut marks = vec![None; height];
+
+        if let Some((_, matches)) = self
+            .search_lines
+            .as_ref()
+            .filter(|_| !self.buffer.is_large())
+        {
+            for &line in matches {
+                marks[row_of(line)] = Some(Color::Yellow);
+            }
+        }
+
+        for conflict in self.conflicts.iter().filter(|_| !self.buffer.is_large()) {
+            marks[row_of(conflict.start)] = Some(Color::Red);
+        }
+
+        let x = self.text_width() - 1;
+
+        for (row, mark) in marks.into_iter().enumerate() {
+            let track = if (thumb_start..thumb_end).contains(&row) {
+                Color::AnsiValue(244)
+            } else {
+                Color::AnsiValue(238)
+            };
+
+            let cell = match mark {
+                Some(color) => "─".with(color).on(track),
+                None => " ".on(track),
+            };
+
+            self.terminal.print_styled_at((x, row as u16), cell)?;
+        }
+
+        Ok(())
+    }
+
+    fn too_small(&self) -> bool {
+        let size = self.terminal.size();
+
+        size.width < MIN_WIDTH || size.height < MIN_HEIGHT
+    }
+
+    fn draw_too_small(&mut self) -> Result<()> {
+        let size = self.terminal.size();
+        let text = if size.width >= 18 {
+            "Terminal too small"
+        } else {
+            "Too small"
+        };
+
+        self.terminal.clear()?;
+        self.terminal.print_at(
+            (
+                size.width.saturating_sub(text.len() as u16) / 2,
+                size.height / 2,
+            ),
+            text.chars().take(size.width as usize).collect::<String>(),
+        )?;
+
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn draw_text_row(&mut self, row: u16) -> Result<()> {
+        if row >= self.terminal.size().height - 1 {
+            return Ok(());
+        }
+
+        if self.diff.is_some() {
+            return self.draw_diff_row(row);
+        }
+
+        let text_start = self.text_start();
+        let line_idx = self.screen_line(row as usize);
+        let number = match self.options.number {
+            NumberStyle::Relative if line_idx != self.buffer.cursor.1 => {
+                line_idx.abs_diff(self.buffer.cursor.1)
+            }
+            _ => line_idx + self.buffer.line_offset() + 1,
+        };
+
+        self.terminal.clear_line(row)?;
+
+        if text_start == 0 {
+            if line_idx >= self.buffer.text.len_lines() {
+                self.terminal.print_styled_at((0, row), "~".blue())?;
+            }
+        } else {
+            self.terminal.print_at(
+                (0, row),
+                if let Some(diagnostic) = self.line_diagnostics.get(&line_idx) {
+                    format!("{:>1$}", number, text_start as usize - 1)
+                        .with(severity_color(diagnostic.severity))
+                        .on_dark_grey()
+                } else if self
+                    .exercises
+                    .iter()
+                    .any(|exercise| exercise.done && exercise.line == line_idx)
+                {
+                    format!("{:>1$}", number, text_start as usize - 1)
+                        .green()
+                        .on_dark_grey()
+                } else if self.bookmarked.contains(&line_idx) {
+                    format!("{:>1$}", number, text_start as usize - 1)
+                        .yellow()
+                        .on_dark_grey()
+                } else if line_idx < self.buffer.text.len_lines() {
+                    format!("{:>1$}", number, text_start as usize - 1).on_dark_grey()
+                } else {
+                    format!("{:>1$}", "~", text_start as usize - 1)
+                        .blue()
+                        .on_dark_grey()
+                },
+            )?;
+        }
+
+        let guides = self.indent_guides(line_idx);
+        let warnings = self.whitespace_warnings(line_idx);
+        let column = self.table_column(line_idx);
+        let table_line = self.table.as_ref().map(|_| self.display_line(line_idx));
+        let line = match &table_line {
+            Some(line) => RopeSlice::from(line.as_str()),
+            None => self
+                .buffer
+                .text
+                .get_line(line_idx)
+                .unwrap_or_else(|| RopeSlice::from("")),
+        };
+        let width = self.text_area_width();
+        let left = self.buffer.view.left_col;
+        let len = display::cells(line, left, width).count();
+        let mut cells = display::cells(line, left, width)
+            .chain(std::iter::repeat(' '))
+            .take(width)
+            .enumerate()
+            .map(|(col, c)| {
+                if guides.contains(&(left + col)) {
+                    '│'
+                } else {
+                    c
+                }
+            });
+        let mut col = 0;
+
+        while col < width {
+            let style = |col| self.cell_style(line_idx, left + col, &guides, &warnings, &column);
+            let (background, foreground) = style(col);
+            let mut run = 1;
+
+            while col + run < width && style(col + run) == (background, foreground) {
+                run += 1;
+            }
+
+            let segment = cells.by_ref().take(run).collect::<String>();
+            let x = text_start + col as u16;
+
+            match (background, foreground) {
+                (None, None) => self.terminal.print_at((x, row), segment.trim_end())?,
+                (Some(bg), None) => self.terminal.print_styled_at((x, row), segment.on(bg))?,
+                (None, Some(fg)) => self.terminal.print_styled_at((x, row), segment.with(fg))?,
+                (Some(bg), Some(fg)) => self
+                    .terminal
+                    .print_styled_at((x, row), segment.with(fg).on(bg))?,
+            }
+
+            col += run;
+        }
+
+        let folded = self
+            .folded
+            .iter()
+            .find(|hidden| hidden.start == line_idx + 1)
+            .map(|hidden| hidden.len());
+
+        if let Some(lines) = folded {
+            let start = len + 1;
+
+            if start < width {
+                let marker = match lines {
+                    1 => "··· 1 line".to_string(),
+                    n => format!("··· {n} lines"),
+                }
+                .chars()
+                .take(width - start)
+                .collect::<String>();
+
+                self.terminal
+                    .print_styled_at((text_start + start as u16, row), marker.dark_grey())?;
+            }
+        } else if let Some(diagnostic) = self.line_diagnostics.get(&line_idx) {
+            let start = len + 2;
+
+            if start < width {
+                let message = format!(
+                    "● {}",
+                    diagnostic.message.lines().next().unwrap_or_default()
+                )
+                .chars()
+                .take(width - start)
+                .collect::<String>();
+
+                self.terminal.print_styled_at(
+                    (text_start + start as u16, row),
+                    message.with(severity_color(diagnostic.severity)),
+                )?;
+            }
+        }
+
+        if self.preview_visible() {
+            self.draw_preview_row(row, line_idx)?;
+        } else if self.term_visible() {
+            self.draw_term_row(row)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_term_row(&mut self, row: u16) -> Result<()> {
+        let x = self.text_width();
+        let width = self.terminal.size().width.saturating_sub(x + 2) as usize;
+        let line = self
+            .term
+            .as_ref()
+            .and_then(|term| term.vt.line(row as usize))
+            .unwrap_or_default();
+
+        self.terminal.print_styled_at((x, row), "│ ".dark_grey())?;
+        self.terminal
+            .print(line.chars().take(width).collect::<String>())
+    }
+
+    fn draw_preview_row(&mut self, row: u16, line_idx: usize) -> Result<()> {
+        let x = self.text_width();
+        let mut remaining = self.terminal.size().width.saturating_sub(x + 2) as usize;
+
+        self.terminal.print_styled_at((x, row), "│ ".dark_grey())?;
+
+        for span in markdown::render_line(&self.buffer.text, line_idx) {
+            if remaining == 0 {
+                break;
+            }
+
+            let content: String = span.content().chars().take(remaining).collect();
+            remaining -= content.chars().count();
+
+            self.terminal
+                .print_styled(StyledContent::new(*span.style(), content))?;
+        }
+
+        Ok(())
+    }
+
+    fn indent_guides(&self, line_idx: usize) -> Vec<usize> {
+        let width = self.shiftwidth();
+
+        if !self.options.indentguides
+            || width == 0
+            || self.buffer.kind != BufferKind::File
+            || self.table.is_some()
+            || line_idx >= self.buffer.text.len_lines()
+        {
+            return Vec::new();
+        }
+
+        let indent = |idx: usize| display::indent(self.buffer.text.line(idx));
+
+        let height = self.terminal.size().height as usize;
+        let lines = self.buffer.text.len_lines();
+
+        let indent = indent(line_idx).unwrap_or_else(|| {
+            let above = (line_idx.saturating_sub(height)..line_idx)
+                .rev()
+                .find_map(indent);
+            let below = (line_idx + 1..std::cmp::min(line_idx + height, lines)).find_map(indent);
+
+            std::cmp::min(above.unwrap_or(0), below.unwrap_or(0))
+        });
+
+        (0..indent).step_by(width).collect()
+    }
+
+    fn whitespace_warnings(&self, line_idx: usize) -> Vec<std::ops::Range<usize>> {
+        if !self.options.lintwhitespace
+            || self.buffer.kind != BufferKind::File
+            || self.table.is_some()
+            || line_idx >= self.buffer.text.len_lines()
+        {
+            return Vec::new();
+        }
+
+        let line = display::trim_newline(self.buffer.text.line(line_idx));
+        let len = line.len_chars();
+        let blank = |c: &char| matches!(c, ' ' | '\t');
+
+        let mut warnings = Vec::new();
+
+        let indent = line.chars().take_while(blank).count();
+        let indent = line.slice(..indent);
+
+        if indent.chars().any(|c| c == ' ')
+            && indent.chars().any(|c| c == '\t')
+            && line.chars().any(|c| !c.is_whitespace())
+        {
+            warnings.push(0..display::columns(indent));
+        }
+
+        let typing = matches!(self.mode, Mode::Insert) && line_idx == self.buffer.cursor.1;
+        let trailing = line.chars_at(len).reversed().take_while(blank).count();
+
+        if trailing > 0 && !typing {
+            let start = display::columns(line.slice(..len - trailing));
+
+            warnings.push(start..start + display::columns(line.slice(len - trailing..)));
+        }
+
+        warnings
+    }
+
+    fn color_swatches(&self) -> std::collections::HashMap<usize, Vec<colors::Swatch>> {
+        if self.buffer.kind != BufferKind::File
+            || self.diff.is_some()
+            || self.table.is_some()
+            || !self
+                .buffer
+                .path
+                .as_deref()
+                .is_some_and(colors::has_literals)
+        {
+            return Default::default();
+        }
+
+        let height = (self.terminal.size().height as usize).saturating_sub(1);
+
+        (0..height)
+            .map(|row| self.screen_line(row))
+            .take_while(|&line_idx| line_idx < self.buffer.text.len_lines())
+            .map(|line_idx| (line_idx, colors::literals(&self.display_line(line_idx))))
+            .filter(|(_, literals)| !literals.is_empty())
+            .collect()
+    }
+
+    fn rainbow_brackets(&self) -> std::collections::HashMap<(usize, usize), Color> {
+        if !self.options.rainbow
+            || self.buffer.kind != BufferKind::File
+            || self.diff.is_some()
+            || self.table.is_some()
+        {
+            return Default::default();
+        }
+
+        let text = &self.buffer.text;
+        let height = (self.terminal.size().height as usize).saturating_sub(1);
+        let last_line = std::cmp::min(self.buffer.view.top_line + height, text.len_lines());
+        let viewport = text.line_to_char(self.buffer.view.top_line)..text.line_to_char(last_line);
+
+        brackets::nesting(text, viewport)
+            .into_iter()
+            .map(|(idx, depth)| {
+                let line = text.char_to_line(idx);
+                let col = view::column(text.line(line), idx - text.line_to_char(line));
+                let color = depth.map_or(Color::Red, |depth| RAINBOW[depth % RAINBOW.len()]);
+
+                ((line, col), color)
+            })
+            .collect()
+    }
+
+    fn bracket_match(&self) -> Option<(usize, usize)> {
+        if !matches!(self.mode, Mode::Normal | Mode::Insert | Mode::Visual)
+            || self.buffer.kind != BufferKind::File
+            || self.diff.is_some()
+            || self.table.is_some()
+        {
+            return None;
+        }
+
+        let text = &self.buffer.text;
+        let height = (self.terminal.size().height as usize).saturating_sub(1);
+        let last_line = std::cmp::min(self.buffer.view.top_line + height, text.len_lines());
+        let viewport = text.line_to_char(self.buffer.view.top_line)..text.line_to_char(last_line);
+
+        let bracket = brackets::bracket_near(text, self.cursor_to_char_idx())?;
+        let partner = brackets::matching_bracket(text, bracket, viewport)?;
+
+        let line = text.char_to_line(partner);
+        let col = view::column(text.line(line), partner - text.line_to_char(line));
+
+        Some((line, col))
+    }
+
+    fn cell_style(
+        &self,
+        line_idx: usize,
+        col: usize,
+        guides: &[usize],
+        warnings: &[std::ops::Range<usize>],
+        column: &Option<std::ops::Range<usize>>,
+    ) -> (Option<Color>, Option<Color>) {
+        let selected =
+            matches!(self.mode, Mode::Visual) && self.visual_range().lines().contains(&line_idx);
+        let swatch = self.swatches.get(&line_idx).and_then(|literals| {
+            literals
+                .iter()
+                .find(|(range, _)| range.contains(&col))
+                .map(|&(_, rgb)| rgb)
+        });
+
+        if let Some((r, g, b)) = swatch.filter(|_| !selected) {
+            let foreground = if colors::is_light((r, g, b)) {
+                Color::Black
+            } else {
+                Color::White
+            };
+
+            return (Some(Color::Rgb { r, g, b }), Some(foreground));
+        }
+
+        let foreground = if guides.contains(&col) {
+            Some(Color::AnsiValue(239))
+        } else {
+            self.rainbow.get(&(line_idx, col)).copied()
+        };
+
+        (
+            self.cell_background(line_idx, col, warnings, column),
+            foreground,
+        )
+    }
+
+    fn cell_background(
+        &self,
+        line_idx: usize,
+        col: usize,
+        warnings: &[std::ops::Range<usize>],
+        column: &Option<std::ops::Range<usize>>,
+    ) -> Option<Color> {
+        if line_idx >= self.buffer.text.len_lines() {
+            return None;
+        }
+
+        if self.drawn_bracket == Some((line_idx, col)) {
+            Some(Color::DarkCyan)
+        } else if matches!(self.mode, Mode::Visual)
+            && self.visual_range().lines().contains(&line_idx)
+        {
+            Some(Color::AnsiValue(239))
+        } else if warnings.iter().any(|range| range.contains(&col)) {
+            Some(Color::AnsiValue(130))
+        } else if self.options.colorcolumn.contains(&(col + 1)) {
+            Some(Color::AnsiValue(52))
+        } else if (self.options.cursorline && line_idx == self.buffer.cursor.1)
+            || column.as_ref().is_some_and(|column| column.contains(&col))
+        {
+            Some(Color::AnsiValue(236))
+        } else {
+            self.conflicts
+                .iter()
+                .find_map(|conflict| conflict.section(line_idx))
+                .map(|section| match section {
+                    Section::Marker => Color::AnsiValue(238),
+                    Section::Ours => Color::AnsiValue(22),
+                    Section::Base => Color::AnsiValue(58),
+                    Section::Theirs => Color::AnsiValue(17),
+                })
+        }
+    }
+
+    fn draw_status_bar(&mut self) -> Result<()> {
+        let shows_statusline = matches!(
+            self.mode,
+            Mode::Normal | Mode::Insert | Mode::Visual | Mode::Terminal
+        ) && self.prompt.is_none()
+            && self.expression.is_none()
+            && self
+                .messages
+                .current()
+                .filter(|m| !self.is_long_message(m))
+                .is_none();
+
+        if shows_statusline && self.zen_active() {
+            return self.terminal.clear_line(self.terminal.size().height - 1);
+        }
+
+        if shows_statusline {
+            return self.draw_statusline();
+        }
+
+        let y = self.terminal.size().height - 1;
+
+        self.terminal.print_styled_at(
+            (0, y),
+            " ".repeat(self.terminal.size().width as usize)
+                .on_dark_grey(),
+        )?;
+
+        self.terminal.print_styled_at(
+            (0, y),
+            format!(
+                "{:<1$}",
+                match self.mode {
+                    _ if self.prompt.is_some() => self.prompt_line(),
+                    _ if self.expression.is_some() => {
+                        format!("={}", self.expression.clone().unwrap_or_default().blue())
+                    }
+                    Mode::Command => format!("{} | {}", self.mode, self.command.clone().blue()),
+                    Mode::Search => format!(
+                        "{} | {}{}",
+                        self.mode,
+                        if self.search_forward { '/' } else { '?' },
+                        self.command.clone().blue()
+                    ),
+                    _ => match self.messages.current().filter(|m| !self.is_long_message(m)) {
+                        Some(message) => {
+                            let text = width::truncate_end(
+                                &message.text,
+                                (self.terminal.size().width as usize).saturating_sub(6),
+                            );
+
+                            format!(
+                                "{} | {}",
+                                self.mode,
+                                match message.severity {
+                                    Severity::Error => text.red(),
+                                    Severity::Warning => text.yellow(),
+                                    Severity::Info => text.reset(),
+                                }
+                            )
+                        }
+                        None => String::new(),
+                    },
+                },
+                self.terminal.size().width as usize
+            )
+            .on_dark_grey(),
+        )
+    }
+
+    fn draw_statusline(&mut self) -> Result<()> {
+        let width = self.terminal.size().width as usize;
+        let y = self.terminal.size().height - 1;
+        let items = statusline::parse(&self.options.statusline)
+            .unwrap_or_else(|_| statusline::parse(statusline::DEFAULT).unwrap_or_default());
+
+        let mut parts = Vec::new();
+        let mut on_right = false;
+
+        for item in items {
+            match item {
+                Item::Text(text) => parts.push((on_right, None, text)),
+                Item::Field(field) => {
+                    parts.push((on_right, Some(field), self.statusline_field(field)))
+                }
+                Item::Split => on_right = true,
+            }
+        }
+
+        let split = parts
+            .iter()
+            .position(|(on_right, _, _)| *on_right)
+            .unwrap_or(parts.len());
+
+        parts.splice(
+            split..split,
+            self.plugins
+                .status()
+                .map(|segment| (false, None, format!(" | {segment}"))),
+        );
+
+        let total = |parts: &[(bool, Option<Field>, String)]| {
+            let gap = parts.iter().any(|(on_right, _, _)| *on_right);
+
+            parts
+                .iter()
+                .map(|(_, _, text)| width::str_width(text))
+                .sum::<usize>()
+                + usize::from(gap)
+        };
+
+        if total(&parts) > width {
+            let others = total(&parts)
+                - parts
+                    .iter()
+                    .filter(|(_, field, _)| *field == Some(Field::File))
+                    .map(|(_, _, text)| width::str_width(text))
+                    .sum::<usize>();
+
+            for (_, field, text) in &mut parts {
+                if *field == Some(Field::File) {
+                    *text = width::truncate_middle(
+                        text,
+                        std::cmp::max(width.saturating_sub(others), 8),
+                    );
+                }
+            }
+        }
+
+        if total(&parts) > width {
+            parts.retain(|(on_right, _, _)| !on_right);
+        }
+
+        let right_width = parts
+            .iter()
+            .filter(|(on_right, _, _)| *on_right)
+            .map(|(_, _, text)| width::str_width(text))
+            .sum::<usize>();
+
+        let mut modes = Vec::new();
+        let mut left = String::new();
+        let mut right = String::new();
+        let mut x = 0;
+
+        for (on_right, field, text) in &parts {
+            if *on_right && right.is_empty() {
+                x = width.saturating_sub(right_width);
+            }
+
+            if *field == Some(Field::Mode) {
+                modes.push(x);
+            }
+
+            x += width::str_width(text);
+
+            if *on_right {
+                right.push_str(text);
+            } else {
+                left.push_str(text);
+            }
+        }
+
+        let left = width::truncate_end(&left, width.saturating_sub(right_width));
+        let gap = width.saturating_sub(width::str_width(&left) + right_width);
+
+        self.terminal.print_styled_at(
+            (0, y),
+            format!("{left}{}{right}", " ".repeat(gap)).on_dark_grey(),
+        )?;
+
+        let color = match self.mode {
+            Mode::Normal => return Ok(()),
+            Mode::Insert => Color::DarkGreen,
+            Mode::Visual => Color::DarkYellow,
+            Mode::Command | Mode::Search => Color::DarkBlue,
+            Mode::Terminal => Color::DarkMagenta,
+        };
+
+        for x in modes {
+            if x + self.mode.to_string().len() <= width {
+                self.terminal
+                    .print_styled_at((x as u16, y), self.mode.to_string().black().on(color))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn statusline_field(&self, field: Field) -> String {
+        let buffer = &self.buffer;
+        let flag = |on: bool, text: &str| if on { text.to_string() } else { String::new() };
+
+        match field {
+            Field::Mode => self.mode.to_string(),
+            Field::File => self.file_name() + &self.follow_flag(),
+            Field::Modified => flag(buffer.modified, " [+]"),
+            Field::ReadOnly => flag(buffer.read_only && !buffer.is_help(), " [RO]"),
+            Field::Hex => flag(buffer.is_hex(), " [hex]"),
+            Field::FileType => buffer
+                .path
+                .as_deref()
+                .and_then(statusline::filetype)
+                .unwrap_or_default()
+                .to_string(),
+            Field::Encoding => flag(true, "utf-8") + &flag(buffer.lossy, " [lossy]"),
+            Field::Indent if buffer.kind != BufferKind::File => String::new(),
+            Field::Indent => Indent {
+                tabs: !self.expandtab(),
+                width: self.shiftwidth(),
+            }
+            .to_string(),
+            Field::Percent => match &buffer.large {
+                Some(file) => file
+                    .percent(buffer.cursor.1 + 1, buffer.text.len_lines())
+                    .to_string(),
+                None => ((buffer.cursor.1 + 1) * 100 / std::cmp::max(buffer.text.len_lines(), 1))
+                    .to_string(),
+            },
+            Field::Viewport => self.viewport_position(),
+            Field::Ruler => format!(
+                "{}:{} {}",
+                self.line_number(),
+                buffer.cursor.0 + 1,
+                self.viewport_position()
+            ),
+            Field::Line => self.line_number(),
+            Field::Column => (buffer.cursor.0 + 1).to_string(),
+            Field::Lines => match &buffer.large {
+                Some(file) if file.known_lines().is_none() => {
+                    format!("~{}", file.estimated_lines())
+                }
+                Some(file) => file.estimated_lines().to_string(),
+                None => buffer.text.len_lines().to_string(),
+            },
+            Field::Bytes => buffer.len_bytes().to_string(),
+            Field::Jobs => self
+                .jobs
+                .status(Instant::now())
+                .map(|status| format!("{status}  "))
+                .unwrap_or_default(),
+            Field::JsonPath => self
+                .json_path()
+                .map(|path| format!("{path}  "))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn line_number(&self) -> String {
+        let line = self.buffer.cursor.1 + self.buffer.line_offset() + 1;
+
+        match &self.buffer.large {
+            Some(file) if !file.is_exact() => format!("~{line}"),
+            _ => line.to_string(),
+        }
+    }
+
+    fn viewport_position(&self) -> String {
+        let height = (self.terminal.size().height as usize).saturating_sub(1);
+        let above = self.buffer.view.top_line;
+        let below = self.buffer.text.len_lines().saturating_sub(above + height);
+
+        if let Some(file) = self.buffer.large.as_ref().filter(|file| !file.at_end()) {
+            return match above + self.buffer.line_offset() {
+                0 => "Top".to_string(),
+                _ => format!("{}%", file.percent(above, self.buffer.text.len_lines())),
+            };
+        }
+
+        let above = above + self.buffer.line_offset();
+
+        match (above, below) {
+            (0, 0) => "All".to_string(),
+            (0, _) => "Top".to_string(),
+            (_, 0) => "Bot".to_string(),
+            _ => format!("{}%", above * 100 / (above + below)),
+        }
+    }
+
+    fn is_long_message(&self, message: &Message) -> bool {
+        message.is_multiline()
+            || message.severity == Severity::Info
+                && width::str_width(&message.text) + 6 > self.terminal.size().width as usize
+    }
+
+    fn toggle_showkeys(&mut self) {
+        self.shown_keys = match self.shown_keys {
+            Some(_) => None,
+            None => Some(Vec::new()),
+        };
+        self.dirty = true;
+    }
+
+    fn show_key(&mut self, event: &KeyEvent) {
+        let Some(keys) = &mut self.shown_keys else {
+            return;
+        };
+
+        let modifiers = [
+            (KeyModifiers::CONTROL, "Ctrl"),
+            (KeyModifiers::SHIFT, "Shift"),
+            (KeyModifiers::ALT, "Alt"),
+            (KeyModifiers::SUPER, "Super"),
+            (KeyModifiers::HYPER, "Hyper"),
+            (KeyModifiers::META, "Meta"),
+        ]
+        .into_iter()
+        .filter(|&(modifier, _)| event.modifiers.contains(modifier))
+        .map(|(_, name)| name)
+        .collect::<Vec<_>>();
+
+        let modifiers = if modifiers.is_empty() {
+            "-".to_string()
+        } else {
+            modifiers.join("+")
+        };
+
+        keys.push(format!(
+            "{:<12} {:<10} {:<7} {}",
+            format!("{:?}", event.code),
+            modifiers,
+            format!("{:?}", event.kind),
+            key_name(event).unwrap_or_else(|| "-".to_string())
+        ));
+
+        if keys.len() > SHOWN_KEYS {
+            keys.remove(0);
+        }
+
+        self.dirty = true;
+    }
+
+    fn showkeys_popup(&self) -> Option<Popup> {
+        let keys = self.shown_keys.as_ref()?;
+
+        let size = self.terminal.size();
+        let lines = if keys.is_empty() {
+            vec!["Press a key".to_string()]
+        } else {
+            keys.clone()
+        };
+
+        let width = lines.iter().map(|line| line.chars().count()).max()? as u16 + 4;
+        let width = std::cmp::min(width, size.width);
+        let height = std::cmp::min(lines.len() as u16 + 2, size.height.saturating_sub(1));
+
+        Some(
+            Popup::new(Rect::new(size.width - width, 0, width, height), 20)
+                .title("Keys")
+                .lines(lines.into_iter().map(|line| line.reset()).collect()),
+        )
+    }
+
+    fn sequence_popup(&self) -> Option<Popup> {
+        if self.sequence.is_empty() || !self.options.whichkey {
+            return None;
+        }
+
+        let lines = self
+            .keymap
+            .continuations(&self.sequence)
+            .into_iter()
+            .map(|(key, description)| format!("{key:<6}{description}"))
+            .collect::<Vec<_>>();
+
+        let size = self.terminal.size();
+        let width = lines.iter().map(|line| line.chars().count()).max()? as u16 + 4;
+        let width = std::cmp::min(width, size.width);
+        let height = std::cmp::min(lines.len() as u16 + 2, size.height.saturating_sub(1));
+
+        let rect = Rect::new(size.width - width, size.height - 1 - height, width, height);
+
+        Some(
+            Popup::new(rect, 15)
+                .title(self.sequence.concat())
+                .lines(lines.into_iter().map(|line| line.reset()).collect()),
+        )
+    }
+
+    fn message_lines(&self) -> Vec<String> {
+        let width = self.terminal.size().width as usize;
+
+        self.messages
+            .current()
+            .map(|message| {
+                message
+                    .text
+                    .lines()
+                    .flat_map(|line| {
+                        let chars = line.chars().collect::<Vec<_>>();
+
+                        chars
+                            .chunks(width)
+                            .map(|chunk| chunk.iter().collect::<String>())
+                            .collect::<Vec<_>>()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn pager_height(&self) -> usize {
+        (self.terminal.size().height as usize).saturating_sub(2)
+    }
+
+    fn pager_page(&self) -> usize {
+        std::cmp::max(self.pager_height().saturating_sub(1), 1)
+    }
+
+    fn pager_active(&self) -> bool {
+        self.messages
+            .current()
+            .is_some_and(|message| self.is_long_message(message))
+            && self.message_lines().len() > self.pager_height()
+    }
+
+    fn handle_pager_key(&mut self, event: KeyEvent) -> bool {
+        let page = self.pager_page();
+        let max = self.message_lines().len().saturating_sub(page);
+
+        let scroll = match (event.code, event.modifiers) {
+            (KeyCode::Char('q') | KeyCode::Esc, _) => {
+                self.messages.dismiss();
+                self.pager_scroll = 0;
+                self.dirty = true;
+
+                return true;
+            }
+            (KeyCode::Char('j') | KeyCode::Down | KeyCode::Enter, _) => self.pager_scroll + 1,
+            (KeyCode::Char('k') | KeyCode::Up, _) => self.pager_scroll.saturating_sub(1),
+            (KeyCode::Char('f' | 'd'), KeyModifiers::CONTROL)
+            | (KeyCode::Char(' ') | KeyCode::PageDown, _) => self.pager_scroll + page,
+            (KeyCode::Char('b' | 'u'), KeyModifiers::CONTROL)
+            | (KeyCode::Char('b') | KeyCode::PageUp, _) => self.pager_scroll.saturating_sub(page),
+            (KeyCode::Char('g') | KeyCode::Home, _) => 0,
+            (KeyCode::Char('G') | KeyCode::End, _) => max,
+            _ => return false,
+        };
+
+        self.pager_scroll = std::cmp::min(scroll, max);
+
+        true
+    }
+
+    fn message_popup(&self) -> Option<Popup> {
+        let message = self
+            .messages
+            .current()
+            .filter(|message| self.is_long_message(message))?;
+
+        let width = self.terminal.size().width;
+        let max_lines = self.pager_height();
+        let mut lines = self.message_lines();
+
+        if lines.len() > max_lines {
+            let page = self.pager_page();
+            let scroll = std::cmp::min(self.pager_scroll, lines.len() - page);
+            let status = if scroll + page >= lines.len() {
+                "-- End -- k/b scroll, q quit".to_string()
+            } else {
+                format!(
+                    "-- More {}% -- j/k/space/b scroll, q quit",
+                    (scroll + page) * 100 / lines.len()
+                )
+            };
+
+            lines = lines.into_iter().skip(scroll).take(page).collect();
+            lines.push(status);
+        }
+
+        let height = lines.len() as u16;
+        let y = self.terminal.size().height - 1 - height;
+        let pager = self.pager_active();
+
+        let lines = lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| match message.severity {
+                _ if pager && i + 1 == height as usize => line.green(),
+                Severity::Error => line.red(),
+                Severity::Warning => line.yellow(),
+                Severity::Info => line.reset(),
+            })
+            .collect();
+
+        Some(
+            Popup::new(Rect::new(0, y, width, height), 20)
+                .borderless()
+                .lines(lines),
+        )
+    }
+
+    fn last_line(&self) -> usize {
+        let text = &self.buffer.text;
+        let lines = text.len_lines();
+
+        if lines > 1 && LINE_BREAKS.contains(&text.char(text.len_chars() - 1)) {
+            lines - 2
+        } else {
+            lines - 1
+        }
+    }
+
+    fn command_lines(&self, range: Option<LineRange>, count: Option<usize>) -> LineRange {
+        let last = self.last_line();
+        let range = range.unwrap_or(LineRange {
+            start: self.buffer.cursor.1,
+            end: self.buffer.cursor.1,
+        });
+
+        match count {
+            Some(count) => LineRange {
+                start: range.end,
+                end: std::cmp::min(range.end + count - 1, last),
+            },
+            None => range,
+        }
+    }
+
+    fn linewise_register(&self, lines: LineRange) -> Register {
+        let mut text = self.buffer.lines_text(lines.lines());
+
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+
+        Register {
+            text,
+            linewise: true,
+        }
+    }
+
+    fn copy_to_clipboard(&mut self, name: Option<char>, text: &str) -> Result<()> {
+        match name {
+            Some(name) if Registers::is_clipboard(name) && !self.options.osc52 => {
+                Err("No clipboard available, see :help 'osc52'".into())
+            }
+            Some(name) if !Registers::is_clipboard(name) && name != '"' => Ok(()),
+            _ if !self.options.osc52 => Ok(()),
+            _ => Ok(self.terminal.write_raw(&clipboard::osc52(text)?)?),
+        }
+    }
+
+    fn yank_lines(&mut self, range: Option<LineRange>, args: &str) -> Result<()> {
+        let (name, count) = register_and_count(args)?;
+        let lines = self.command_lines(range, count);
+        let register = self.linewise_register(lines);
+
+        self.copy_to_clipboard(name, &register.text)?;
+        self.registers.yank(name, register);
+
+        self.messages.info(match (lines.count(), name) {
+            (1, None) => "1 line yanked".to_string(),
+            (1, Some(name)) => format!("1 line yanked into \"{name}"),
+            (n, None) => format!("{n} lines yanked"),
+            (n, Some(name)) => format!("{n} lines yanked into \"{name}"),
+        });
+
+        Ok(())
+    }
+
+    fn yank_link(&mut self, range: Option<LineRange>) -> Result<()> {
+        let path = self.buffer.path.clone().ok_or("No file name")?;
+        let path = path.canonicalize().unwrap_or(path);
+        let root = self.git_root()?;
+        let root = root.canonicalize().unwrap_or(root);
+        let relative = path
+            .strip_prefix(&root)
+            .map_err(|_| format!("{} is outside the repository", path.display()))?;
+
+        let remote = git::remote_url(&root).map_err(EditorError::Command)?;
+        let commit = git::head_commit(&root).map_err(EditorError::Command)?;
+        let lines = self.command_lines(range, None);
+
+        let url = git::permalink(
+            &remote,
+            &commit,
+            &relative.to_string_lossy(),
+            lines.start + 1,
+            lines.end + 1,
+        )
+        .ok_or(format!("Unrecognized remote: {remote}"))?;
+
+        self.copy_to_clipboard(None, &url)?;
+        self.registers.yank(
+            None,
+            Register {
+                text: url.clone(),
+                linewise: false,
+            },
+        );
+        self.messages.info(url);
+
+        Ok(())
+    }
+
+    fn delete_lines(&mut self, range: Option<LineRange>, args: &str) -> Result<()> {
+        let (name, count) = register_and_count(args)?;
+
+        if self.buffer.read_only || self.buffer.is_directory() {
+            return Err("Buffer is read-only".into());
+        }
+
+        let lines = self.command_lines(range, count);
+        let register = self.linewise_register(lines);
+
+        self.copy_to_clipboard(name, &register.text)?;
+        self.registers.delete(name, register);
+
+        let text = &self.buffer.text;
+        let mut start = text.line_to_char(lines.start);
+        let end = text.line_to_char(std::cmp::min(lines.end + 1, text.len_lines()));
+
+        if end == text.len_chars() && start > 0 && (start == end || text.char(end - 1) != '\n') {
+            start -= 1;
+        }
+
+        if start < end {
+            self.buffer.remove(start..end)?;
+        }
+
+        self.jump_to(lines.start, 0);
+        self.dirty = true;
+
+        if lines.count() > 1 {
+            self.messages.info(format!("{} fewer lines", lines.count()));
+        }
+
+        Ok(())
+    }
+
+    fn shift_lines(&mut self, range: Option<LineRange>, name: &str, args: &str) -> Result<()> {
+        let count = match args {
+            "" => None,
+            args => Some(parse_count(args)?),
+        };
+
+        if self.buffer.read_only || self.buffer.is_directory() {
+            return Err("Buffer is read-only".into());
+        }
+
+        let lines = self.command_lines(range, count);
+        let times = name.len();
+        let width = self.shiftwidth();
+        let tabs = !self.expandtab();
+
+        for line_idx in lines.lines() {
+            let line = self.buffer.text.line(line_idx);
+            let start = self.buffer.text.line_to_char(line_idx);
+
+            if name.starts_with('>') {
+                if line.chars().all(|c| c == '\n' || c == '\r') {
+                    continue;
+                }
+
+                let indent = if tabs || line.chars().next() == Some('\t') {
+                    "\t".repeat(times)
+                } else {
+                    " ".repeat(width * times)
+                };
+
+                self.buffer.insert(start, &indent)?;
+            } else {
+                let mut chars = line.chars().peekable();
+                let mut remove = 0;
+
+                for _ in 0..times {
+                    if chars.next_if_eq(&'\t').is_some() {
+                        remove += 1;
+                        continue;
+                    }
+
+                    let mut spaces = 0;
+
+                    while spaces < width && chars.next_if_eq(&' ').is_some() {
+                        spaces += 1;
+                    }
+
+                    remove += spaces;
+                }
+
+                if remove > 0 {
+                    self.buffer.remove(start..start + remove)?;
+                }
+            }
+        }
+
+        self.jump_to(lines.end, 0);
+        self.dirty = true;
+
+        let direction = if name.starts_with('>') { '>' } else { '<' };
+
+        let lines = match lines.count() {
+            1 => "1 line".to_string(),
+            n => format!("{n} lines"),
+        };
+        let times = match times {
+            1 => "1 time".to_string(),
+            n => format!("{n} times"),
+        };
+
+        self.messages.info(format!("{lines} {direction}ed {times}"));
+
+        Ok(())
+    }
+
+    fn substitute(&mut self, range: Option<LineRange>, args: &str) -> Result<()> {
+        if args.chars().all(|c| matches!(c, '&' | 'g' | 'i' | 'I')) {
+            return self.repeat_substitute(range, args);
+        }
+
+        let substitute = Substitute::parse(args)?;
+        let flags = substitute.flags;
+
+        self.last_substitute = Some(substitute.clone());
+        self.run_substitute(range, &substitute, flags)
+    }
+
+    fn repeat_substitute(&mut self, range: Option<LineRange>, flags: &str) -> Result<()> {
+        let substitute = self
+            .last_substitute
+            .clone()
+            .ok_or("No previous substitute")?;
+
+        let (base, flags) = match flags.strip_prefix('&') {
+            Some(flags) => (substitute.flags, flags),
+            None => (Flags::default(), flags),
+        };
+
+        let flags = base.merge(Flags::parse(flags.trim())?);
+
+        self.run_substitute(range, &substitute, flags)
+    }
+
+    fn run_substitute(
+        &mut self,
+        range: Option<LineRange>,
+        substitute: &Substitute,
+        flags: Flags,
+    ) -> Result<()> {
+        if self.buffer.read_only || self.buffer.is_directory() {
+            return Err("Buffer is read-only".into());
+        }
+
+        let regex = substitute.regex(flags, self.ignore_case(&substitute.pattern))?;
+        let lines = self.command_lines(range, None);
+        let mut changed = Vec::new();
+
+        for line_idx in lines.lines().rev() {
+            let line = self.buffer.text.line(line_idx).to_string();
+            let content = line.trim_end_matches(['\n', '\r']);
+
+            if let Some(after) = substitute.apply(&regex, flags, content) {
+                self.buffer.replace_line(line_idx, content, &after);
+                changed.push(line_idx);
+            }
+        }
+
+        let Some(&first) = changed.first() else {
+            return Err(format!("Pattern not found: {}", substitute.pattern).into());
+        };
+
+        self.jump_to(first, 0);
+        self.dirty = true;
+
+        if changed.len() > 1 {
+            self.messages
+                .info(format!("Substituted on {} lines", changed.len()));
+        }
+
+        Ok(())
+    }
+
+    fn ignore_case(&self, pattern: &str) -> bool {
+        search::ignore_case(pattern, self.options.ignorecase, self.options.smartcase)
+    }
+
+    fn search(&mut self, pattern: String, forward: bool) -> Result<()> {
+        let pattern = match (pattern.is_empty(), &self.last_search) {
+            (false, _) => pattern,
+            (true, Some((pattern, _))) => pattern.clone(),
+            (true, None) => return Err("No previous search pattern".into()),
+        };
+
+        self.last_search = Some((pattern, forward));
+        self.search_next(true)
+    }
+
+    fn search_next(&mut self, same_direction: bool) -> Result<()> {
+        let (pattern, forward) = self
+            .last_search
+            .clone()
+            .ok_or("No previous search pattern")?;
+        let forward = forward == same_direction;
+
+        let regex = Regex::new(&pattern, self.ignore_case(&pattern))?;
+        let cursor = (self.buffer.cursor.1, self.buffer.cursor.0);
+
+        if self.buffer.is_large() {
+            return self.search_large_file(&regex, &pattern, forward);
+        }
+
+        let found = search::find(&self.buffer.text, &regex, cursor, forward)
+            .ok_or(format!("Pattern not found: {pattern}"))?;
+
+        self.jump_to(found.line, found.col);
+
+        let count = search::count(
+            &self.buffer.text,
+            &regex,
+            (found.line, found.col),
+            MAX_MATCH_COUNT,
+        );
+
+        match (found.wrapped, forward) {
+            (false, true) => self.messages.info(format!("/{pattern} {count}")),
+            (false, false) => self.messages.info(format!("?{pattern} {count}")),
+            (true, true) => self
+                .messages
+                .warn(format!("Search hit BOTTOM, continuing at TOP {count}")),
+            (true, false) => self
+                .messages
+                .warn(format!("Search hit TOP, continuing at BOTTOM {count}")),
+        }
+
+        Ok(())
+    }
+
+    fn search_large_file(&mut self, regex: &Regex, pattern: &str, forward: bool) -> Result<()> {
+        let (col, line) = self.buffer.cursor;
+
+        let found = self
+            .buffer
+            .large
+            .as_ref()
+            .and_then(|file| file.find(regex, file.offset(line), col, forward))
+            .ok_or(format!("Pattern not found: {pattern}"))?;
+
+        self.goto_large_offset(found.pos, found.col);
+
+        match (found.wrapped, forward) {
+            (false, true) => self.messages.info(format!("/{pattern}")),
+            (false, false) => self.messages.info(format!("?{pattern}")),
+            (true, true) => self.messages.warn("Search hit BOTTOM, continuing at TOP"),
+            (true, false) => self.messages.warn("Search hit TOP, continuing at BOTTOM"),
+        }
+
+        Ok(())
+    }
+
+    fn search_word(&mut self, forward: bool) -> Result<()> {
+        let chars = self
+            .buffer
+            .text
+            .line(self.buffer.cursor.1)
+            .chars()
+            .collect::<Vec<_>>();
+
+        let start = (self.buffer.cursor.0..chars.len())
+            .find(|&i| abbrev::is_word_char(chars[i]))
+            .ok_or("No word under the cursor")?;
+        let start = (0..start)
+            .rev()
+            .take_while(|&i| abbrev::is_word_char(chars[i]))
+            .last()
+            .unwrap_or(start);
+        let end = (start..chars.len())
+            .find(|&i| !abbrev::is_word_char(chars[i]))
+            .unwrap_or(chars.len());
+
+        let word = chars[start..end].iter().collect::<String>();
+
+        self.buffer.cursor.0 = start;
+        self.search(format!("\\b{word}\\b"), forward)
+    }
+
+    fn search_match(&self, forward: bool) -> Result<search::Found> {
+        let (pattern, _) = self
+            .last_search
+            .as_ref()
+            .ok_or("No previous search pattern")?;
+
+        let regex = Regex::new(pattern, self.ignore_case(pattern))?;
+        let cursor = (self.buffer.cursor.1, self.buffer.cursor.0);
+
+        search::find_covering(&self.buffer.text, &regex, cursor, forward)
+            .ok_or_else(|| format!("Pattern not found: {pattern}").into())
+    }
+
+    fn remove_match(&mut self, forward: bool) -> Result<()> {
+        if self.buffer.read_only || self.buffer.is_directory() {
+            return Err("Buffer is read-only".into());
+        }
+
+        let found = self.search_match(forward)?;
+        let start = self.buffer.text.line_to_char(found.line);
+
+        if found.col < found.end {
+            self.buffer.remove(start + found.col..start + found.end)?;
+        }
+
+        self.jump_to(found.line, 0);
+        self.buffer.cursor.0 = found.col;
+        self.buffer.cursor_x_remember = found.col;
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn change_match(&mut self, forward: bool) -> Result<()> {
+        self.remove_match(forward)?;
+
+        self.change_start = Some(self.cursor_to_char_idx());
+        self.repeat = Some(Repeat::ChangeMatch {
+            forward,
+            text: String::new(),
+        });
+
+        self.insert_mode()
+    }
+
+    fn finish_change(&mut self) {
+        let Some(start) = self.change_start.take() else {
+            return;
+        };
+
+        let end = self.cursor_to_char_idx();
+
+        if let Some(Repeat::ChangeMatch { text, .. }) = &mut self.repeat {
+            *text = if end >= start {
+                self.buffer.text.slice(start..end).to_string()
+            } else {
+                String::new()
+            };
+        }
+    }
+
+    fn repeat_change(&mut self) -> Result<()> {
+        match self.repeat.clone().ok_or("Nothing to repeat")? {
+            Repeat::ChangeMatch { forward, text } => {
+                self.remove_match(forward)?;
+
+                let idx = self.cursor_to_char_idx();
+                let len = text.chars().count();
+
+                self.buffer.insert(idx, &text)?;
+                self.buffer.cursor.0 += len.saturating_sub(1);
+                self.buffer.cursor_x_remember = self.buffer.cursor.0;
+
+                Ok(())
+            }
+            Repeat::DeleteMatch { forward } => self.remove_match(forward),
+        }
+    }
+
+    fn text_width_option(&self) -> usize {
+        match self.options.textwidth {
+            0 => 79,
+            width => width,
+        }
+    }
+
+    fn paragraph_at(&self, line_idx: usize) -> Result<LineRange> {
+        let line = |idx: usize| self.buffer.text.line(idx).to_string();
+        let last = self.last_line();
+
+        if line_idx > last || reflow::is_blank(&line(line_idx)) {
+            return Err("No paragraph here".into());
+        }
+
+        let mut start = line_idx;
+
+        while start > 0 && !reflow::starts_item(&line(start)) && !reflow::is_blank(&line(start - 1))
+        {
+            start -= 1;
+        }
+
+        let mut end = line_idx;
+
+        while end < last
+            && !reflow::is_blank(&line(end + 1))
+            && !reflow::starts_item(&line(end + 1))
+        {
+            end += 1;
+        }
+
+        Ok(LineRange { start, end })
+    }
+
+    fn format_lines(&mut self, lines: LineRange) -> Result<()> {
+        if self.buffer.read_only || self.buffer.is_directory() {
+            return Err("Buffer is read-only".into());
+        }
+
+        let original = self.buffer.lines_text(lines.lines());
+        let width = self.text_width_option();
+
+        let formatted = self
+            .behavior()
+            .and_then(|behavior| behavior.format(&original.lines().collect::<Vec<_>>()))
+            .unwrap_or_else(|| reflow_lines(&original, width));
+
+        let mut text = formatted.join("\n");
+
+        if original.ends_with('\n') {
+            text.push('\n');
+        }
+
+        if text != original {
+            self.buffer.replace_lines(lines.lines(), &text)?;
+            self.dirty = true;
+        }
+
+        self.jump_to(lines.start + formatted.len().saturating_sub(1), 0);
+
+        Ok(())
+    }
+
+    fn line_address(&self, args: &str) -> Result<usize> {
+        if args == "0" {
+            return Ok(0);
+        }
+
+        let last = self.last_line();
+        let (range, rest) = range::parse(args, self.buffer.cursor.1, last, |name| {
+            self.buffer.marks.get(&name).map(|(line, _)| *line)
+        })
+        .map_err(EditorError::Command)?;
+
+        if !rest.trim().is_empty() {
+            return Err(format!("Trailing characters: {rest}").into());
+        }
+
+        let line = range.ok_or("Missing address")?.end;
+
+        if line > last {
+            return Err(format!("Invalid range: line {} doesn't exist", line + 1).into());
+        }
+
+        Ok(line + 1)
+    }
+
+    fn move_command(&mut self, range: Option<LineRange>, args: &str) -> Result<()> {
+        let lines = self.command_lines(range, None);
+        let below = self.line_address(args)?;
+        let moved = self.move_lines(lines, below)?;
+
+        self.jump_to(moved.end, 0);
+
+        if lines.count() > 1 {
+            self.messages.info(format!("{} lines moved", lines.count()));
+        }
+
+        Ok(())
+    }
+
+    fn move_by(&mut self, lines: LineRange, down: bool) -> Result<LineRange> {
+        let below = match down {
+            true if lines.end < self.last_line() => lines.end + 2,
+            false if lines.start > 0 => lines.start - 1,
+            _ => return Ok(lines),
+        };
+
+        let moved = self.move_lines(lines, below)?;
+
+        if self.options.moveindent {
+            self.reindent_lines(moved)?;
+        }
+
+        Ok(moved)
+    }
+
+    fn move_lines(&mut self, lines: LineRange, below: usize) -> Result<LineRange> {
+        if self.buffer.read_only || self.buffer.is_directory() {
+            return Err("Buffer is read-only".into());
+        }
+
+        if below > lines.start && below <= lines.end {
+            return Err("Cannot move a range of lines into itself".into());
+        }
+
+        let last = self.last_line();
+        let lines = LineRange {
+            start: std::cmp::min(lines.start, last),
+            end: std::cmp::min(lines.end, last),
+        };
+        let below = std::cmp::min(below, last + 1);
+
+        let start = std::cmp::min(lines.start, below);
+        let end = std::cmp::max(lines.end + 1, below);
+        let original = self.buffer.lines_text(start..end);
+
+        let (mut rows, breaks): (Vec<_>, Vec<_>) = (start..end)
+            .map(|idx| {
+                let line = self.buffer.text.line(idx).to_string();
+                let (row, line_break) = split_line_break(&line);
+
+                (row.to_string(), line_break.to_string())
+            })
+            .unzip();
+
+        let block = rows
+            .drain(lines.start - start..lines.end + 1 - start)
+            .collect::<Vec<_>>();
+
+        let moved_start = if below <= lines.start {
+            below
+        } else {
+            below - lines.count()
+        };
+
+        rows.splice(moved_start - start..moved_start - start, block);
+
+        let text = rows
+            .iter()
+            .zip(&breaks)
+            .map(|(row, line_break)| format!("{row}{line_break}"))
+            .collect::<String>();
+
+        if text != original {
+            self.buffer.replace_lines(start..end, &text)?;
+            self.dirty = true;
+        }
+
+        Ok(LineRange {
+            start: moved_start,
+            end: moved_start + lines.count() - 1,
+        })
+    }
+
+    fn reindent_lines(&mut self, lines: LineRange) -> Result<()> {
+        let indent_of = |line: &str| line[..line.len() - line.trim_start().len()].to_string();
+
+        let above = (0..lines.start)
+            .rev()
+            .map(|idx| self.buffer.text.line(idx).to_string())
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or_default();
+
+        let mut target = indent_of(&above);
+
+        if above.trim_end().ends_with(['{', '(', '[', ':']) {
+            if target.starts_with('\t') || !self.expandtab() {
+                target.push('\t');
+            } else {
+                target.push_str(&" ".repeat(self.shiftwidth()));
+            }
+        }
+
+        let original = self.buffer.lines_text(lines.lines());
+        let current = indent_of(original.lines().next().unwrap_or_default());
+
+        if current == target {
+            return Ok(());
+        }
+
+        let text = lines
+            .lines()
+            .filter(|&idx| idx < self.buffer.text.len_lines())
+            .map(|idx| {
+                let line = self.buffer.text.line(idx).to_string();
+                let (line, line_break) = split_line_break(&line);
+
+                match line.strip_prefix(current.as_str()) {
+                    _ if line.trim().is_empty() => format!("{line}{line_break}"),
+                    Some(rest) => format!("{target}{rest}{line_break}"),
+                    None => format!("{target}{}{line_break}", line.trim_start()),
+                }
+            })
+            .collect::<String>();
+
+        self.buffer.replace_lines(lines.lines(), &text)?;
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn copy_lines(&mut self, range: Option<LineRange>, args: &str) -> Result<()> {
+        if self.buffer.read_only || self.buffer.is_directory() {
+            return Err("Buffer is read-only".into());
+        }
+
+        let lines = self.command_lines(range, None);
+        let below = self.line_address(args)?;
+        let mut text = self.linewise_register(lines).text;
+
+        let buffer = &self.buffer.text;
+        let idx = buffer.line_to_char(std::cmp::min(below, buffer.len_lines()));
+
+        if idx == buffer.len_chars() && idx > 0 && buffer.char(idx - 1) != '\n' {
+            text.pop();
+            text.insert(0, '\n');
+        }
+
+        self.buffer.insert(idx, &text)?;
+        self.jump_to(below + lines.count() - 1, 0);
+        self.dirty = true;
+
+        if lines.count() > 1 {
+            self.messages.info(format!("{} more lines", lines.count()));
+        }
+
+        Ok(())
+    }
+
+    fn fix_whitespace(&mut self, range: Option<LineRange>) -> Result<()> {
+        if self.buffer.read_only || self.buffer.is_directory() {
+            return Err("Buffer is read-only".into());
+        }
+
+        let lines = range.unwrap_or(LineRange {
+            start: 0,
+            end: self.last_line(),
+        });
+
+        let tabs = !self.expandtab();
+        let width = self.shiftwidth();
+
+        let mut fixed = 0;
+        let text = lines
+            .lines()
+            .filter(|&idx| idx < self.buffer.text.len_lines())
+            .map(|idx| {
+                let line = self.buffer.text.line(idx).to_string();
+                let (line, line_break) = split_line_break(&line);
+                let after = whitespace::fix(line, tabs, width);
+
+                fixed += usize::from(after != line);
+
+                format!("{after}{line_break}")
+            })
+            .collect::<String>();
+
+        if fixed == 0 {
+            self.messages.info("No whitespace to fix");
+            return Ok(());
+        }
+
+        self.buffer.replace_lines(lines.lines(), &text)?;
+        self.jump_to(self.buffer.cursor.1, self.buffer.cursor.0);
+        self.dirty = true;
+        self.messages.info(match fixed {
+            1 => "Fixed 1 line".to_string(),
+            n => format!("Fixed {n} lines"),
+        });
+
+        Ok(())
+    }
+
+    fn align_lines(&mut self, range: Option<LineRange>, delimiter: &str) -> Result<()> {
+        if delimiter.is_empty() {
+            return Err("Usage: align {delimiter}".into());
+        }
+
+        if self.buffer.read_only || self.buffer.is_directory() {
+            return Err("Buffer is read-only".into());
+        }
+
+        let blank = |idx: usize| self.buffer.text.line(idx).chars().all(char::is_whitespace);
+        let last = self.last_line();
+
+        let lines = match range {
+            Some(range) => range,
+            None => {
+                let line_idx = self.buffer.cursor.1;
+
+                if blank(line_idx) {
+                    return Err("No paragraph here".into());
+                }
+
+                let mut start = line_idx;
+                let mut end = line_idx;
+
+                while start > 0 && !blank(start - 1) {
+                    start -= 1;
+                }
+
+                while end < last && !blank(end + 1) {
+                    end += 1;
+                }
+
+                LineRange { start, end }
+            }
+        };
+
+        let original = self.buffer.lines_text(lines.lines());
+        let aligned = align::align(&original.lines().collect::<Vec<_>>(), delimiter);
+
+        let mut text = aligned.join("\n");
+
+        if original.ends_with('\n') {
+            text.push('\n');
+        }
+
+        if text == original {
+            self.messages
+                .info(format!("Nothing to align on {delimiter}"));
+            return Ok(());
+        }
+
+        self.buffer.replace_lines(lines.lines(), &text)?;
+        self.dirty = true;
+        self.jump_to(lines.start, 0);
+
+        Ok(())
+    }
+
+    fn format_json(&mut self, range: Option<LineRange>, args: &str) -> Result<()> {
+        let minify = match args {
+            "" => false,
+            "min" => true,
+            _ => return Err("Usage: jsonfmt [min]".into()),
+        };
+
+        if self.buffer.read_only || self.buffer.kind != BufferKind::File {
+            return Err("Buffer is read-only".into());
+        }
+
+        let lines = range.map_or(0..self.buffer.text.len_lines(), |range| range.lines());
+        let original = self.buffer.lines_text(lines.clone());
+        let (key, value, comma) = json::split_member(&original);
+        let value = json::parse(value).map_err(|e| format!("Invalid JSON: {e}"))?;
+
+        let indent = if self.expandtab() {
+            " ".repeat(self.shiftwidth())
+        } else {
+            "\t".to_string()
+        };
+        let base = whitespace::indent(&original);
+        let separator = if key.is_empty() { "" } else { " " };
+
+        let mut text = if minify {
+            value.to_string()
+        } else {
+            json::pretty(&value, &indent)
+        }
+        .lines()
+        .map(|line| format!("{base}{line}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+        text.insert_str(base.len(), &format!("{key}{separator}"));
+        text.push_str(comma);
+
+        if original.ends_with('\n') {
+            text.push('\n');
+        }
+
+        if text == original {
+            self.messages.info("Already formatted");
+            return Ok(());
+        }
+
+        self.buffer.replace_lines(lines.clone(), &text)?;
+        self.dirty = true;
+        self.jump_to(lines.start, 0);
+
+        Ok(())
+    }
+
+    fn json_path(&self) -> Option<String> {
+        let path = self.buffer.path.as_deref()?;
+
+        if self.buffer.kind != BufferKind::File || statusline::filetype(path) != Some("json") {
+            return None;
+        }
+
+        let (col, line) = self.buffer.cursor;
+        let pos = self.buffer.text.line_to_char(line) + col;
+
+        Some(json::path_at(self.buffer.text.chars(), pos))
+    }
+
+    fn csv_command(&mut self, args: &str) -> Result<()> {
+        if self.buffer.kind != BufferKind::File {
+            return Err("Can only show a file as a table".into());
+        }
+
+        match args.split_once(' ').unwrap_or((args, "")) {
+            ("", _) => {
+                self.buffer.table = match self.buffer.table {
+                    Some(_) => None,
+                    None => Some(csv::guess(&self.buffer.text)),
+                };
+                self.dirty = true;
+            }
+            ("sort-by", column) => self.sort_table(column.trim())?,
+            _ => return Err("Usage: csv [sort-by {column}]".into()),
+        }
+
+        Ok(())
+    }
+
+    fn sort_table(&mut self, column: &str) -> Result<()> {
+        if column.is_empty() {
+            return Err("Usage: csv sort-by {column}".into());
+        }
+
+        if self.buffer.read_only {
+            return Err("Buffer is read-only".into());
+        }
+
+        let delimiter = self
+            .buffer
+            .table
+            .unwrap_or_else(|| csv::guess(&self.buffer.text));
+        let header = self.buffer.text.line(0).to_string();
+        let Some(idx) = csv::column(&header, delimiter, column) else {
+            return Err(format!("No such column: {column}").into());
+        };
+
+        let rows = 1..self.buffer.text.len_lines();
+        let original = self.buffer.lines_text(rows.clone());
+        let mut text = csv::sort(&original.lines().collect::<Vec<_>>(), delimiter, idx).join("\n");
+
+        if original.ends_with('\n') {
+            text.push('\n');
+        }
+
+        if text != original {
+            self.buffer.replace_lines(rows, &text)?;
+            self.dirty = true;
+        }
+
+        self.messages.info(format!(
+            "Sorted by {}",
+            csv::field(&header, delimiter, idx).unwrap_or_default()
+        ));
+
+        Ok(())
+    }
+
+    fn next_cell(&mut self, forward: bool) -> Result<()> {
+        let Some(delimiter) = self.buffer.table else {
+            return Err("Not a table, see :csv".into());
+        };
+
+        let (col, line) = self.buffer.cursor;
+        let fields = csv::split(&self.buffer.text.line(line).to_string(), delimiter);
+        let field = csv::field_at(&fields, col);
+
+        let target = if forward {
+            fields.get(field + 1).map(|next| next.start)
+        } else if col > fields[field].start {
+            Some(fields[field].start)
+        } else {
+            field.checked_sub(1).map(|prev| fields[prev].start)
+        };
+
+        match target {
+            Some(col) => self.buffer.cursor.0 = col,
+            None if forward && line < self.last_line() => {
+                self.move_cursor_down();
+                self.buffer.cursor.0 = 0;
+            }
+            None if !forward && line > 0 => {
+                self.move_cursor_up();
+
+                let fields = csv::split(&self.buffer.text.line(line - 1).to_string(), delimiter);
+
+                self.buffer.cursor.0 = fields.last().map_or(0, |last| last.start);
+            }
+            None => {}
+        }
+
+        self.buffer.cursor.0 = std::cmp::min(
+            self.buffer.cursor.0,
+            self.line_len(self.buffer.text.line(self.buffer.cursor.1)),
+        );
+        self.buffer.cursor_x_remember = self.buffer.cursor.0;
+
+        Ok(())
+    }
+
+    fn table_field(&self) -> Option<usize> {
+        let table = self.table.as_ref()?;
+        let (col, line) = self.buffer.cursor;
+        let fields = csv::split(&self.buffer.text.line(line).to_string(), table.delimiter);
+
+        Some(csv::field_at(&fields, col))
+    }
+
+    fn table_column(&self, line_idx: usize) -> Option<std::ops::Range<usize>> {
+        let table = self.table.as_ref()?;
+        let line = self.buffer.text.get_line(line_idx)?.to_string();
+
+        table.field_range(&line, self.table_field()?)
+    }
+
+    fn auto_wrap(&mut self) -> Result<()> {
+        let width = self.options.textwidth;
+        let path = self.buffer.path.as_deref();
+
+        if width == 0
+            || !(markdown::is_markdown(path)
+                || path
+                    .and_then(Path::extension)
+                    .is_some_and(|ext| ext == "txt"))
+        {
+            return Ok(());
+        }
+
+        let line_idx = self.buffer.cursor.1;
+        let line = self.buffer.text.line(line_idx).to_string();
+        let chars = line
+            .trim_end_matches(['\n', '\r'])
+            .chars()
+            .collect::<Vec<_>>();
+
+        if chars.len() <= width {
+            return Ok(());
+        }
+
+        let leader = reflow::leader(&line);
+        let min = leader.first.chars().count();
+
+        let Some(space) = (min..=width)
+            .rev()
+            .find(|&i| chars.get(i).is_some_and(|c| c.is_whitespace()))
+        else {
+            return Ok(());
+        };
+
+        if self.buffer.cursor.0 <= space {
+            return Ok(());
+        }
+
+        let mut start = space;
+
+        while start > min && chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+
+        let base = self.buffer.text.line_to_char(line_idx);
+
+        self.buffer.remove(base + start..base + space + 1)?;
+        self.buffer
+            .insert(base + start, &format!("\n{}", leader.rest))?;
+
+        let col = self.buffer.cursor.0 - (space + 1) + leader.rest.chars().count();
+
+        self.jump_to(line_idx + 1, col);
+
+        Ok(())
+    }
+
+    fn show_registers(&mut self) {
+        let summary = self.registers.summary();
+
+        if summary.is_empty() {
+            self.messages.show("No registers set");
+        } else {
+            self.messages.show(format!("type name content\n{summary}"));
+        }
+    }
+
+    fn show_marks(&mut self, names: &str) {
+        let wanted = |name: char| names.is_empty() || names.contains(name);
+
+        let mut local = self
+            .buffer
+            .marks
+            .iter()
+            .filter(|(name, _)| wanted(**name))
+            .collect::<Vec<_>>();
+
+        local.sort_unstable();
+
+        let local = local.into_iter().map(|(name, &(line, col))| {
+            let text = self
+                .buffer
+                .text
+                .get_line(line)
+                .map(|line| line.to_string().trim().to_string())
+                .unwrap_or_default();
+
+            (*name, line, col, text)
+        });
+
+        let global = self
+            .global_marks
+            .iter()
+            .filter(|(name, _)| wanted(*name))
+            .map(|(name, mark)| {
+                (
+                    name,
+                    mark.line,
+                    mark.col,
+                    display_path(&mark.path, &self.cwd),
+                )
+            });
+
+        let rows = local
+            .chain(global)
+            .map(|(name, line, col, text)| format!(" {name} {:>6} {col:>4} {text}", line + 1))
+            .collect::<Vec<_>>();
+
+        if rows.is_empty() {
+            self.messages.show("No marks set");
+        } else {
+            self.messages
+                .show(format!("mark   line  col file/text\n{}", rows.join("\n")));
+        }
+    }
+
+    fn show_messages(&mut self) {
+        let history = self
+            .messages
+            .history()
+            .map(|message| message.text.as_str())
+            .collect::<Vec<_>>();
+
+        if history.is_empty() {
+            self.messages.show("No messages");
+        } else {
+            self.messages.show(history.join("\n"));
+        }
+    }
+
+    fn show_plugins(&mut self) {
+        let plugins = self.plugins.describe();
+
+        if plugins.is_empty() {
+            self.messages.show("No plugins loaded");
+        } else {
+            self.messages.show(plugins.join("\n"));
+        }
+    }
+
+    fn load_plugins(&mut self) {
+        if let Some(dir) = paths::config_file("plugins") {
+            self.plugins = Plugins::load(&dir, &mut self.buffer, &mut self.messages);
+        }
+    }
+
+    fn load_init_lua(&mut self) {
+        let Some(path) = paths::config_file("init.lua") else {
+            return;
+        };
+
+        if let Err(e) = self.scripts.load(
+            &path,
+            &mut self.buffer,
+            &mut self.messages,
+            &mut self.options,
+        ) {
+            self.messages.error(e);
+        }
+
+        self.bind_lua_mappings();
+    }
+
+    fn run_lua(&mut self, code: &str) -> Result<()> {
+        if code.trim().is_empty() {
+            return Err("Usage: :lua {code}".into());
+        }
+
+        let result = self.scripts.run(
+            code,
+            &mut self.buffer,
+            &mut self.messages,
+            &mut self.options,
+        );
+
+        self.bind_lua_mappings();
+        self.dirty = true;
+
+        Ok(result?)
+    }
+
+    fn run_lua_mapping(&mut self, id: usize) -> Result<()> {
+        let result =
+            self.scripts
+                .run_mapping(id, &mut self.buffer, &mut self.messages, &mut self.options);
+
+        self.bind_lua_mappings();
+        self.dirty = true;
+
+        Ok(result?)
+    }
+
+    fn bind_lua_mappings(&mut self) {
+        for binding in self.scripts.take_bindings() {
+            self.keymap
+                .bind(&binding.keys, Action::Lua(binding.id), &binding.description);
+        }
+    }
+
+    fn plugin_key(&mut self, event: &KeyEvent) -> bool {
+        let Some(key) = key_name(event) else {
+            return false;
+        };
+
+        if !self
+            .plugins
+            .on_key(&key, &mut self.buffer, &mut self.messages)
+        {
+            return false;
+        }
+
+        self.dirty = true;
+
+        true
+    }
+
+    pub fn report(&mut self, error: EditorError) {
+        log::warn!("{error:?}");
+
+        match error.severity() {
+            Severity::Error => self.messages.error(error.to_string()),
+            Severity::Warning => self.messages.warn(error.to_string()),
+            Severity::Info => self.messages.info(error.to_string()),
+        }
+    }
+
+    fn title(&self) -> String {
+        let name = match &self.buffer.path {
+            Some(path) if !self.buffer.is_help() => path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().to_string(),
+            ),
+            _ => self.buffer_name(),
+        };
+
+        if self.buffer.modified {
+            format!("{name} (+) — myeditor")
+        } else {
+            format!("{name} — myeditor")
+        }
+    }
+
+    fn file_name(&self) -> String {
+        if let Some(root) = self
+            .buffer
+            .path
+            .as_ref()
+            .filter(|_| self.buffer.is_git_status())
+        {
+            return format!("[Git] {}", root.display());
+        }
+
+        if self.buffer.is_tutor() {
+            let done = self.exercises.iter().filter(|e| e.done).count();
+
+            return format!("[Tutor] {done}/{}", self.exercises.len());
+        }
+
+        if self.buffer.is_help() {
+            let doc = self.buffer.text.line(0).to_string();
+            let doc = doc.split_whitespace().next().unwrap_or_default();
+
+            return format!("[Help] {}", doc.trim_matches('*'));
+        }
+
+        match &self.buffer.path {
+            Some(path) => display_path(path, &self.cwd),
+            None => "[No Name]".to_string(),
+        }
+    }
+
+    fn follow_flag(&self) -> String {
+        if self.follow.is_none() || self.follow != self.buffer.path {
+            String::new()
+        } else if self.buffer.cursor.1 >= self.last_text_line() {
+            " [follow]".to_string()
+        } else {
+            " [paused]".to_string()
+        }
+    }
+
+    fn buffer_name(&self) -> String {
+        let mut name = self.file_name();
+
+        if self.buffer.is_help() || self.buffer.is_tutor() {
+            return name;
+        }
+
+        if self.buffer.modified {
+            name.push_str(" [+]");
+        }
+
+        if self.buffer.read_only {
+            name.push_str(" [RO]");
+        }
+
+        if self.buffer.is_hex() {
+            name.push_str(" [hex]");
+        }
+
+        name
+    }
+
+    fn prompt_line(&self) -> String {
+        match &self.prompt {
+            Some(prompt) => format!("{} {}", prompt.message, prompt.choices()),
+            None => String::new(),
+        }
+    }
+
+    fn panel_rect(&self) -> (u16, u16, u16, u16) {
+        let size = self.terminal.size();
+
+        let width = std::cmp::min(size.width.saturating_sub(4), 100);
+        let height = std::cmp::min(size.height.saturating_sub(3), 24);
+
+        ((size.width - width) / 2, 1, width, height)
+    }
+
+    fn picker_popup(&self) -> Option<Popup> {
+        let picker = self.picker.as_ref()?;
+
+        let (x, y, width, height) = self.panel_rect();
+        let inner = width.saturating_sub(2) as usize;
+
+        let title = format!(
+            "{} ({}/{})",
+            picker.title(),
+            picker.match_count(),
+            picker.item_count()
+        );
+
+        let popup = Popup::new(Rect::new(x, y, width, height), 10)
+            .title(title)
+            .header(format!("> {}", picker.query()));
+
+        let lines = picker
+            .visible(popup.content_height())
+            .map(|(item, selected)| {
+                let detail_width = item.detail.chars().count();
+                let label_width = inner.saturating_sub(detail_width + 3);
+                let label: String = item.label.chars().take(label_width).collect();
+
+                let line = format!(" {:<1$} {2} ", label, label_width, item.detail);
+
+                if selected {
+                    line.black().on_white()
+                } else {
+                    line.reset()
+                }
+            })
+            .collect();
+
+        Some(popup.lines(lines))
+    }
+
+    fn replace_popup(&self) -> Option<Popup> {
+        let panel = self.replace.as_ref()?;
+
+        let (x, y, width, height) = self.panel_rect();
+        let list_height = (height as usize).saturating_sub(4);
+
+        let title = format!(
+            "Replace ({}/{} accepted{})",
+            panel.accepted_count(),
+            panel.hunks().len(),
+            if panel.is_searching() { " …" } else { "" }
+        );
+        let header = "space: toggle  f: toggle file  enter: apply  esc: cancel".to_string();
+
+        let rows = panel.rows();
+
+        let selected_row = rows
+            .iter()
+            .position(|row| matches!(row, Row::Before(idx) if *idx == panel.selected()))
+            .unwrap_or(0);
+        let start = (selected_row + 2).saturating_sub(list_height);
+
+        let rows = rows
+            .into_iter()
+            .skip(start)
+            .take(list_height)
+            .map(|row| match row {
+                Row::File(idx) => display_path(&panel.hunks()[idx].path, &self.cwd).bold(),
+                Row::Before(idx) => {
+                    let hunk = &panel.hunks()[idx];
+                    let mark = if hunk.accepted { 'x' } else { ' ' };
+                    let line = format!("[{mark}] {:>5} - {}", hunk.line + 1, hunk.before.trim());
+
+                    if idx == panel.selected() {
+                        line.black().on_white()
+                    } else {
+                        line.red()
+                    }
+                }
+                Row::After(idx) => {
+                    let hunk = &panel.hunks()[idx];
+                    let line = format!("          + {}", hunk.after.trim());
+
+                    if hunk.accepted {
+                        line.green()
+                    } else {
+                        line.dark_grey()
+                    }
+                }
+            })
+            .collect();
+
+        Some(
+            Popup::new(Rect::new(x, y, width, height), 10)
+                .title(title)
+                .header(header)
+                .lines(rows),
+        )
+    }
+
+    fn code_actions_popup(&self) -> Option<Popup> {
+        let actions = self.code_actions.as_ref()?;
+        let size = self.terminal.size();
+
+        let titles = actions
+            .titles()
+            .enumerate()
+            .map(|(i, title)| match i {
+                0..=8 => format!("{}. {title}", i + 1),
+                _ => format!("   {title}"),
+            })
+            .collect::<Vec<_>>();
+
+        let widest = titles.iter().map(|title| title.chars().count()).max();
+        let width = std::cmp::min(size.width, widest.unwrap_or(0).max(14) as u16 + 2);
+        let height = std::cmp::min(size.height.saturating_sub(1), titles.len() as u16 + 2);
+        let list_height = (height as usize).saturating_sub(2);
+        let start = (actions.selected() + 1).saturating_sub(list_height);
+
+        let (x, y) = self.cursor_cell();
+        let x = std::cmp::min(x, size.width - width);
+        let y = if y + 1 + height < size.height {
+            y + 1
+        } else {
+            y.saturating_sub(height)
+        };
+
+        let lines = titles
+            .into_iter()
+            .enumerate()
+            .skip(start)
+            .take(list_height)
+            .map(|(i, title)| {
+                if i == actions.selected() {
+                    title.black().on_white()
+                } else {
+                    title.reset()
+                }
+            })
+            .collect();
+
+        Some(
+            Popup::new(Rect::new(x, y, width, height), 10)
+                .title("Code actions")
+                .lines(lines),
+        )
+    }
+
+    fn undo_tree_popup(&self) -> Option<Popup> {
+        let tree = self.undo_tree.as_ref()?;
+        let history = &self.buffer.history;
+        let states = history.states();
+
+        let size = self.terminal.size();
+        let width = std::cmp::min(size.width.saturating_sub(2), 44);
+        let height = std::cmp::min(size.height.saturating_sub(3), tree.rows().len() as u16 + 4);
+        let list_height = (height as usize).saturating_sub(4);
+
+        let title = format!("Undo tree ({} changes)", states.len() - 1);
+        let header = "j/k: preview  enter: jump  esc: cancel".to_string();
+
+        let start = (tree.selected() + 1).saturating_sub(list_height);
+        let now = SystemTime::now();
+
+        let lines = tree
+            .rows()
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(list_height)
+            .map(|(i, row)| {
+                let state = &states[row.state];
+                let marker = if row.state == history.current() {
+                    '*'
+                } else {
+                    'o'
+                };
+
+                let change = match state.parent {
+                    Some(parent) => format!(
+                        "{:+} chars",
+                        state.text.len_chars() as isize - states[parent].text.len_chars() as isize
+                    ),
+                    None => "original".to_string(),
+                };
+
+                let saved = if history.saved() == Some(row.state) {
+                    " saved"
+                } else {
+                    ""
+                };
+
+                let line = format!(
+                    "{}{marker} #{:<4} {:>7}  {change}{saved}",
+                    "  ".repeat(row.depth),
+                    row.state,
+                    undo::age(state.time, now)
+                );
+
+                if i == tree.selected() {
+                    line.black().on_white()
+                } else if row.state == history.current() {
+                    line.bold()
+                } else {
+                    line.reset()
+                }
+            })
+            .collect();
+
+        Some(
+            Popup::new(Rect::new(size.width - width, 1, width, height), 10)
+                .title(title)
+                .header(header)
+                .lines(lines),
+        )
+    }
+
+    fn signature_rect(&self) -> Option<Rect> {
+        let signature = self
+            .signature
+            .as_ref()
+            .filter(|signature| signature.line == self.buffer.cursor.1)?;
+
+        let size = self.terminal.size();
+        let width = std::cmp::min(size.width, signature.label.chars().count() as u16 + 2);
+
+        let (x, y) = self.cursor_cell();
+        let x = std::cmp::min(x, size.width - width);
+        let y = if y >= 3 {
+            y - 3
+        } else if y + 4 < size.height {
+            y + 1
+        } else {
+            return None;
+        };
+
+        Some(Rect::new(x, y, width, 3))
+    }
+
+    fn signature_popup(&self) -> Option<Popup> {
+        let rect = self.signature_rect()?;
+        let label = self.signature.as_ref()?.label.clone();
+
+        Some(Popup::new(rect, 10).lines(vec![label.reset()]))
+    }
+
+    fn draw_signature_parameter(&mut self) -> Result<()> {
+        let (Some(rect), Some(signature)) = (self.signature_rect(), &self.signature) else {
+            return Ok(());
+        };
+
+        let Some(active) = &signature.active else {
+            return Ok(());
+        };
+
+        let inner = rect.width.saturating_sub(2) as usize;
+        let parameter = signature
+            .label
+            .chars()
+            .take(std::cmp::min(active.end, inner))
+            .skip(active.start)
+            .collect::<String>();
+
+        if parameter.is_empty() {
+            return Ok(());
+        }
+
+        self.terminal.print_styled_at(
+            (rect.x + 1 + active.start as u16, rect.y + 1),
+            parameter.bold().underlined(),
+        )
+    }
+
+    fn draw_overlays(&mut self) -> Result<()> {
+        let popups = [
+            self.picker_popup(),
+            self.replace_popup(),
+            self.code_actions_popup(),
+            self.signature_popup(),
+            self.undo_tree_popup(),
+            self.message_popup(),
+            self.sequence_popup(),
+            self.showkeys_popup(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        self.overlays.set(popups);
+
+        if !self.dirty {
+            for row in self.overlays.exposed_rows() {
+                self.draw_text_row(row)?;
+            }
+        }
+
+        for popup in self.overlays.popups() {
+            for (i, line) in popup.render().into_iter().enumerate() {
+                self.terminal
+                    .print_styled_at((popup.rect.x, popup.rect.y + i as u16), line)?;
+            }
+        }
+
+        self.draw_signature_parameter()
+    }
+
+    fn cursor_cell(&self) -> (u16, u16) {
+        let x = self.text_start()
+            + self
+                .buffer
+                .view
+                .screen_col(self.cursor_column())
+                .unwrap_or(0) as u16;
+        let y = self.screen_row(self.buffer.cursor.1).unwrap_or(0) as u16;
+
+        (x, y)
+    }
+
+    fn draw_cursor(&mut self) -> Result<()> {
+        if self.prompt.is_some() {
+            let x = self.prompt_line().chars().count() as u16;
+            let y = self.terminal.size().height - 1;
+
+            return self.terminal.move_cursor(x, y);
+        }
+
+        if let Some(expression) = &self.expression {
+            let x = 1 + expression.chars().count() as u16;
+            let y = self.terminal.size().height - 1;
+
+            return self.terminal.move_cursor(x, y);
+        }
+
+        if let Some(picker) = &self.picker {
+            let (x, y, _, _) = self.panel_rect();
+
+            return self
+                .terminal
+                .move_cursor(x + 3 + picker.query().chars().count() as u16, y + 1);
+        }
+
+        let (x, y) = match self.mode {
+            Mode::Normal if self.buffer.is_hex() => {
+                let x = self.text_start()
+                    + hex::hex_column(self.buffer.hex_offset()) as u16
+                    + u16::from(self.hex_nibble);
+                let y = self.buffer.cursor.1 as u16 - self.buffer.view.top_line as u16;
+
+                (x, y)
+            }
+            Mode::Normal | Mode::Insert | Mode::Visual if self.diff.is_some() => {
+                let (row, top_row, left_active) = self
+                    .diff
+                    .as_ref()
+                    .map(|diff| {
+                        (
+                            diff.row_of(self.buffer.cursor.1),
+                            diff.top_row,
+                            diff.left_active,
+                        )
+                    })
+                    .unwrap_or_default();
+                let side = if left_active {
+                    0
+                } else {
+                    (self.terminal.size().width.saturating_sub(1)) / 2 + 1
+                };
+
+                let x = side + self.diff_gutter() + self.cursor_column() as u16;
+                let y = row.saturating_sub(top_row) as u16;
+
+                (x, y)
+            }
+            Mode::Normal | Mode::Insert | Mode::Visual if self.zen_active() => {
+                let (row, col) = self.zen_cursor();
+
+                (self.zen_margin() + col as u16, row as u16)
+            }
+            Mode::Normal | Mode::Insert | Mode::Visual => self.cursor_cell(),
+            Mode::Terminal => {
+                let (x, y) = self
+                    .term
+                    .as_ref()
+                    .map(|term| term.vt.cursor())
+                    .unwrap_or_default();
+
+                (self.text_width() + 2 + x as u16, y as u16)
+            }
+            Mode::Search => {
+                let x = 7 + self.command.len() as u16;
+                let y = self.terminal.size().height - 1;
+
+                (x, y)
+            }
+            Mode::Command => {
+                let x = 6 + self.command.len() as u16;
+                let y = self.terminal.size().height - 1;
+
+                (x, y)
+            }
+        };
+
+        self.terminal.move_cursor(x, y)
+    }
+
+    fn text_start(&self) -> u16 {
+        if self.terminal.size().width < GUTTER_MIN_WIDTH {
+            return 0;
+        }
+
+        let lines = self.buffer.line_offset() + self.buffer.text.len_lines();
+        let key = (lines, self.options.number);
+
+        if let Some((_, width)) = self.gutter.get().filter(|&(cached, _)| cached == key) {
+            return width;
+        }
+
+        let width = match self.options.number {
+            NumberStyle::None => 0,
+            NumberStyle::Absolute | NumberStyle::Relative => gutter_width(lines),
+        };
+
+        self.gutter.set(Some((key, width)));
+
+        width
+    }
+
+    pub fn start(&mut self) -> Result<()> {
+        self.terminal.start()?;
+        self.load_plugins();
+        self.load_init_lua();
+
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> Result<()> {
+        self.terminal.finish()
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        self.terminal.clear()
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.terminal.flush()
+    }
+
+    pub fn snapshot(&mut self) -> Option<String> {
+        self.terminal.snapshot()
+    }
+
+    pub fn contents(&self) -> String {
+        self.buffer.text.to_string()
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        if self.buffer.cursor.0 > 0 {
+            self.buffer.cursor.0 -= 1;
+        }
+
+        self.buffer.cursor_x_remember = self.buffer.cursor.0;
+    }
+
+    pub fn move_cursor_right(&mut self) {
+        let line_len = self.line_len(self.buffer.text.line(self.buffer.cursor.1));
+
+        if self.buffer.cursor.0 < line_len {
+            self.buffer.cursor.0 += 1;
+        }
+
+        self.buffer.cursor_x_remember = self.buffer.cursor.0;
+    }
+
+    pub fn move_cursor_up(&mut self) {
+        if self.buffer.cursor.1 > 0 {
+            self.buffer.cursor.1 = self.prev_visible(self.buffer.cursor.1);
+
+            let line_len = self.line_len(self.buffer.text.line(self.buffer.cursor.1));
+
+            self.buffer.cursor.0 = std::cmp::min(self.buffer.cursor_x_remember, line_len);
+        }
+    }
+
+    pub fn move_cursor_down(&mut self) {
+        let next = self.next_visible(self.buffer.cursor.1);
+
+        if next < self.buffer.text.len_lines() {
+            self.buffer.cursor.1 = next;
+
+            let line_len = self.line_len(self.buffer.text.line(self.buffer.cursor.1));
+
+            self.buffer.cursor.0 = std::cmp::min(self.buffer.cursor_x_remember, line_len);
+        }
+    }
+
+    fn line_len(&self, line: RopeSlice) -> usize {
+        line.len_chars()
+            .saturating_sub(match self.mode {
+                Mode::Insert => 0,
+                _ => 1,
+            })
+            .saturating_sub(line_break_len(line))
+    }
+
+    fn cursor_column(&self) -> usize {
+        let (col, line_idx) = self.buffer.cursor;
+        let line = self.buffer.text.line(line_idx);
+
+        match &self.table {
+            Some(table) => table.display_col(&line.to_string(), col),
+            None => view::column(line, col),
+        }
+    }
+}
+
+fn gutter_width(lines: usize) -> u16 {
+    let digits = lines.checked_ilog10().map_or(1, |digits| digits as u16 + 1);
+
+    std::cmp::max(digits, GUTTER_MIN_DIGITS) + 1
+}
+
+fn diff_side(
+    buffer: &Buffer,
+    line: Option<usize>,
+    kind: RowKind,
+    gutter: u16,
+    width: u16,
+) -> Vec<StyledContent<String>> {
+    let gutter = gutter as usize;
+    let text_width = (width as usize).saturating_sub(gutter);
+
+    let Some(line) = line else {
+        return vec![
+            format!("{:>1$} ", "", gutter - 1).on_dark_grey(),
+            "-".repeat(text_width).dark_grey(),
+        ];
+    };
+
+    let text = buffer
+        .text
+        .get_line(line)
+        .map(|text| {
+            text.to_string()
+                .trim_end_matches(['\n', '\r'])
+                .replace('\t', "    ")
+        })
+        .unwrap_or_default();
+    let text: String = text.chars().take(text_width).collect();
+    let text = format!("{text:<text_width$}");
+
+    let text = match kind {
+        RowKind::Equal => text.reset(),
+        RowKind::Changed => text.on(Color::AnsiValue(17)),
+        RowKind::Removed => text.on(Color::AnsiValue(52)),
+        RowKind::Added => text.on(Color::AnsiValue(22)),
+    };
+
+    vec![
+        format!("{:>1$} ", line + 1, gutter - 1).on_dark_grey(),
+        text,
+    ]
+}
+
+fn run_filter(command: &str, text: &Rope, dir: &Path) -> std::io::Result<Output> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let text = text.clone();
+
+        std::thread::spawn(move || text.write_to(&mut stdin));
+    }
+
+    child.wait_with_output()
+}
+
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+fn run_with_stdin(command: &str, text: &Rope) -> std::io::Result<ExitStatus> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        text.write_to(&mut stdin)?;
+    }
+
+    child.wait()
+}
+
+fn parse_count(count: &str) -> Result<usize> {
+    count
+        .parse::<usize>()
+        .ok()
+        .filter(|count| *count > 0)
+        .ok_or_else(|| format!("Invalid count: {count}").into())
+}
+
+fn register_and_count(args: &str) -> Result<(Option<char>, Option<usize>)> {
+    let mut parts = args.split_whitespace().peekable();
+
+    let name = parts
+        .next_if(|part| {
+            let mut chars = part.chars();
+
+            matches!(
+                (chars.next(), chars.next()),
+                (Some(name), None) if !name.is_ascii_digit()
+            )
+        })
+        .and_then(|part| part.chars().next());
+
+    if let Some(name) = name.filter(|name| !Registers::is_valid(*name)) {
+        return Err(format!("Invalid register name: {name}").into());
+    }
+
+    let count = parts.next().map(parse_count).transpose()?;
+
+    if let Some(extra) = parts.next() {
+        return Err(format!("Unexpected argument: {extra}").into());
+    }
+
+    Ok((name, count))
+}
+
+fn default_keymap() -> Keymap<Action> {
+    let mut keymap = Keymap::default();
+
+    keymap.bind("gg", Action::GotoTop, "first line");
+    keymap.bind("G", Action::GotoBottom, "last line");
+    keymap.bind("ga", Action::CharInfo, "character info");
+    keymap.bind("gra", Action::CodeActions, "code actions");
+    keymap.bind("g<C-g>", Action::WordCount, "word count");
+    keymap.bind("<C-z>", Action::Suspend, "suspend");
+    keymap.bind("]c", Action::NextHunk, "next change");
+    keymap.bind("[c", Action::PrevHunk, "previous change");
+    keymap.bind("]x", Action::NextConflict, "next conflict");
+    keymap.bind("[x", Action::PrevConflict, "previous conflict");
+    keymap.bind("do", Action::DiffGet, "get change");
+    keymap.bind("dp", Action::DiffPut, "put change");
+    keymap.bind("&", Action::RepeatSubstitute, "repeat :s");
+    keymap.bind("gn", Action::SelectMatch(true), "next match");
+    keymap.bind("gN", Action::SelectMatch(false), "previous match");
+    keymap.bind("cgn", Action::ChangeMatch(true), "change next match");
+    keymap.bind("cgN", Action::ChangeMatch(false), "change previous match");
+    keymap.bind("dgn", Action::DeleteMatch(true), "delete next match");
+    keymap.bind("dgN", Action::DeleteMatch(false), "delete previous match");
+    keymap.bind(".", Action::RepeatChange, "repeat change");
+    keymap.bind("gqq", Action::FormatLine, "format line");
+    keymap.bind("gqip", Action::FormatParagraph, "format paragraph");
+    keymap.bind("gv", Action::Reselect, "reselect");
+    keymap.bind("gqap", Action::FormatParagraph, "format paragraph");
+    keymap.bind("u", Action::Undo, "undo");
+    keymap.bind("<C-r>", Action::Redo, "redo");
+    keymap.bind("<A-j>", Action::MoveLines(true), "move line down");
+    keymap.bind("<A-k>", Action::MoveLines(false), "move line up");
+    keymap.bind("M", Action::ToggleBookmark, "toggle bookmark");
+    keymap.bind("<Tab>", Action::NextCell(true), "next cell");
+    keymap.bind("<S-Tab>", Action::NextCell(false), "previous cell");
+    keymap.bind("gx", Action::OpenLink, "open URL or toggle checkbox");
+    keymap.bind("gf", Action::GotoFile, "go to file");
+    keymap.bind("<C-p>", Action::CommandPalette, "command palette");
+    keymap.bind("<C-P>", Action::CommandPalette, "command palette");
+    keymap.bind("za", Action::ToggleFold, "toggle fold");
+    keymap.bind("zo", Action::OpenFold, "open fold");
+    keymap.bind("zc", Action::CloseFold, "close fold");
+    keymap.bind("zR", Action::OpenFolds, "open all folds");
+    keymap.bind("zM", Action::CloseFolds, "close all folds");
+
+    for key in ["w", "h", "l", "p"] {
+        keymap.bind(&format!("<C-w>{key}"), Action::SwitchWindow, "other window");
+    }
+
+    keymap
+}
+
+fn key_bytes(event: &KeyEvent) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    if event.modifiers.contains(KeyModifiers::ALT) {
+        bytes.push(0x1b);
+    }
+
+    match event.code {
+        KeyCode::Char(c) if event.modifiers.contains(KeyModifiers::CONTROL) && c.is_ascii() => {
+            bytes.push(c.to_ascii_lowercase() as u8 & 0x1f)
+        }
+        KeyCode::Char(c) => bytes.extend(c.to_string().bytes()),
+        KeyCode::Enter => bytes.push(b'\r'),
+        KeyCode::Backspace => bytes.push(0x7f),
+        KeyCode::Tab => bytes.push(b'\t'),
+        KeyCode::Esc => bytes.push(0x1b),
+        code => bytes.extend_from_slice(match code {
+            KeyCode::BackTab => b"\x1b[Z",
+            KeyCode::Up => b"\x1b[A",
+            KeyCode::Down => b"\x1b[B",
+            KeyCode::Right => b"\x1b[C",
+            KeyCode::Left => b"\x1b[D",
+            KeyCode::Home => b"\x1b[H",
+            KeyCode::End => b"\x1b[F",
+            KeyCode::Delete => b"\x1b[3~",
+            KeyCode::PageUp => b"\x1b[5~",
+            KeyCode::PageDown => b"\x1b[6~",
+            _ => return None,
+        }),
+    }
+
+    Some(bytes)
+}
+
+fn key_name(event: &KeyEvent) -> Option<String> {
+    match event.code {
+        KeyCode::Char(c) if event.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(format!("<C-{c}>"))
+        }
+        KeyCode::Char(c) if event.modifiers.contains(KeyModifiers::ALT) => Some(format!("<A-{c}>")),
+        KeyCode::Char(' ') => Some("<Space>".to_string()),
+        KeyCode::Char(c) => Some(c.to_string()),
+        KeyCode::Tab => Some("<Tab>".to_string()),
+        KeyCode::BackTab => Some("<S-Tab>".to_string()),
+        KeyCode::Enter => Some("<CR>".to_string()),
+        _ => None,
+    }
+}
+
+fn line_break_len(line: RopeSlice) -> usize {
+    let len = line.len_chars();
+    let back = |n: usize| len.checked_sub(n).map(|i| line.char(i));
+
+    match (back(2), back(1)) {
+        (Some('\r'), Some('\n')) => 2,
+        (_, Some(c)) if LINE_BREAKS.contains(&c) => 1,
+        _ => 0,
+    }
+}
+
+fn split_line_break(line: &str) -> (&str, &str) {
+    line.split_at(line.trim_end_matches(LINE_BREAKS).len())
+}
+
+fn reflow_lines(text: &str, width: usize) -> Vec<String> {
+    let mut formatted = Vec::new();
+    let mut paragraph = Vec::new();
+
+    for line in text.lines() {
+        if reflow::is_blank(line) || reflow::starts_item(line) {
+            if !paragraph.is_empty() {
+                formatted.extend(reflow::reflow(&paragraph, width));
+                paragraph.clear();
+            }
+
+            if reflow::is_blank(line) {
+                formatted.push(line.trim_end().to_string());
+                continue;
+            }
+        }
+
+        paragraph.push(line);
+    }
+
+    if !paragraph.is_empty() {
+        formatted.extend(reflow::reflow(&paragraph, width));
+    }
+
+    formatted
+}
+
+fn back_up(path: &Path, keep: usize) {
+    let path = path.canonicalize().unwrap_or(path.to_path_buf());
+
+    if let Err(e) = backup::save(&path, keep) {
+        log::warn!("can't back up {}: {e}", path.display());
+    }
+}
+
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Error => Color::Red,
+        Severity::Warning => Color::DarkYellow,
+        Severity::Info => Color::Blue,
+    }
+}
+
+fn display_path(path: &Path, cwd: &Path) -> String {
+    path.strip_prefix(cwd).unwrap_or(path).display().to_string()
+}
+
+#[cfg(feature = "fuzzing")]
+impl Editor {
+    pub fn set_text(&mut self, text: &str) {
+        self.buffer.text = Rope::from_str(text);
+        self.buffer.history = Default::default();
+        self.dirty = true;
+    }
+
+    pub fn assert_invariants(&self) {
+        let (col, line) = self.buffer.cursor;
+        let text = &self.buffer.text;
+
+        assert!(line < text.len_lines(), "cursor line {line} past end");
+
+        let len = text.line(line).len_chars() - line_break_len(text.line(line));
+
+        assert!(col <= len, "cursor column {col} past line length {len}");
+        assert!(
+            self.buffer.view.top_line <= line,
+            "cursor above the top line"
+        );
+        assert!(
+            self.buffer.view.left_col <= self.cursor_column(),
+            "cursor left of the view"
+        );
+    }
+
+    pub fn assert_history(&self, original: &str) {
+        let Some(buffer) = std::iter::once(&self.buffer)
+            .chain(&self.buffers)
+            .find(|buffer| buffer.kind == BufferKind::File && buffer.path.is_none())
+        else {
+            return;
+        };
+
+        let mut history = buffer.history.clone();
+        let mut text = buffer.text.clone();
+        let mut undone = 0;
+
+        history.commit(&buffer.text);
+
+        while let Some((state, _)) = history.undo() {
+            text = state.text.clone();
+            undone += 1;
+        }
+
+        assert_eq!(text, original, "undo to the root didn't restore the text");
+
+        for _ in 0..undone {
+            let (state, _) = history.redo().expect("redo stopped before the last edit");
+
+            text = state.text.clone();
+        }
+
+        assert_eq!(text, buffer.text, "redo didn't reapply the edits");
+    }
+}
+
+#[derive(Debug)]
+pub enum EventResult {
+    Quit,
+}
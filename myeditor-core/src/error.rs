@@ -0,0 +1,71 @@
+use crate::messages::Severity;
+use std::fmt;
+
+pub type Result<T, E = EditorError> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum EditorError {
+    Terminal(std::io::Error),
+    Io(std::io::Error),
+    Command(String),
+    Config(String),
+}
+
+impl EditorError {
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, Self::Terminal(_))
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::Config(_) => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for EditorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Terminal(e) => write!(f, "Terminal error: {e}"),
+            Self::Io(e) => write!(f, "{}", describe_io_error(e)),
+            Self::Command(message) | Self::Config(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for EditorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Terminal(e) | Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for EditorError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<String> for EditorError {
+    fn from(message: String) -> Self {
+        Self::Command(message)
+    }
+}
+
+impl From<&str> for EditorError {
+    fn from(message: &str) -> Self {
+        Self::Command(message.to_string())
+    }
+}
+
+pub fn describe_io_error(e: &std::io::Error) -> String {
+    match e.kind() {
+        std::io::ErrorKind::PermissionDenied => "permission denied".to_string(),
+        std::io::ErrorKind::ReadOnlyFilesystem => "read-only file system".to_string(),
+        std::io::ErrorKind::NotFound => "no such file or directory".to_string(),
+        _ => e.to_string(),
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{grep::GrepEvent, input::Event, quickfix::Finished, remote::Request};
+use signal_hook::{consts::SIGTSTP, iterator::Signals};
+use std::{
+    collections::VecDeque,
+    io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+const INPUT_POLL: Duration = Duration::from_millis(100);
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub enum AppEvent {
+    Input(Event),
+    InputClosed,
+    Grep(usize, GrepEvent),
+    Make(usize, Finished),
+    Check(usize, Finished),
+    FileChanged(PathBuf),
+    LineIndex(PathBuf),
+    Remote(Request),
+    Terminal(usize, Vec<u8>),
+    TerminalExited(usize),
+    Suspend,
+}
+
+pub trait EventSource: Send {
+    fn read(&mut self, timeout: Duration) -> io::Result<Option<Event>>;
+}
+
+#[derive(Debug, Default)]
+pub struct Queue(VecDeque<Event>);
+
+impl Queue {
+    pub fn new(events: Vec<Event>) -> Self {
+        Self(events.into())
+    }
+}
+
+impl EventSource for Queue {
+    fn read(&mut self, _timeout: Duration) -> io::Result<Option<Event>> {
+        self.0
+            .pop_front()
+            .map(Some)
+            .ok_or_else(|| io::ErrorKind::UnexpectedEof.into())
+    }
+}
+
+#[derive(Debug)]
+pub struct Events {
+    tx: Sender<AppEvent>,
+    rx: Receiver<AppEvent>,
+    next: Option<AppEvent>,
+    paused: Arc<AtomicBool>,
+    watched: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl Events {
+    pub fn terminal(mut source: Box<dyn EventSource>) -> Self {
+        let events = Self::new();
+
+        let tx = events.tx.clone();
+        let paused = events.paused.clone();
+
+        thread::spawn(move || loop {
+            if paused.load(Ordering::Relaxed) {
+                thread::sleep(INPUT_POLL);
+                continue;
+            }
+
+            let event = match source.read(INPUT_POLL) {
+                Ok(None) => continue,
+                Ok(Some(event)) => AppEvent::Input(event),
+                Err(_) => AppEvent::InputClosed,
+            };
+
+            let closed = matches!(event, AppEvent::InputClosed);
+
+            if tx.send(event).is_err() || closed {
+                return;
+            }
+        });
+
+        if let Ok(mut signals) = Signals::new([SIGTSTP]) {
+            let tx = events.tx.clone();
+
+            thread::spawn(move || {
+                for _ in signals.forever() {
+                    if tx.send(AppEvent::Suspend).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+
+        let tx = events.tx.clone();
+        let watched = events.watched.clone();
+
+        thread::spawn(move || {
+            let mut seen: Option<(PathBuf, Option<SystemTime>)> = None;
+
+            loop {
+                thread::sleep(WATCH_INTERVAL);
+
+                let Some(path) = watched.lock().map_or(None, |watched| watched.clone()) else {
+                    seen = None;
+                    continue;
+                };
+
+                let mtime = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+
+                let changed = seen.as_ref().is_some_and(|(seen_path, seen_mtime)| {
+                    *seen_path == path && *seen_mtime != mtime && mtime.is_some()
+                });
+
+                if changed && tx.send(AppEvent::FileChanged(path.clone())).is_err() {
+                    return;
+                }
+
+                seen = Some((path, mtime));
+            }
+        });
+
+        events
+    }
+
+    pub fn script(mut source: Box<dyn EventSource>) -> Self {
+        let events = Self::new();
+
+        while let Ok(Some(event)) = source.read(Duration::ZERO) {
+            let _ = events.tx.send(AppEvent::Input(event));
+        }
+
+        let _ = events.tx.send(AppEvent::InputClosed);
+
+        events
+    }
+
+    fn new() -> Self {
+        let (tx, rx) = channel();
+
+        Self {
+            tx,
+            rx,
+            next: None,
+            paused: Arc::new(AtomicBool::new(false)),
+            watched: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn sender(&self) -> Sender<AppEvent> {
+        self.tx.clone()
+    }
+
+    pub fn wait(&mut self, timeout: Duration) -> bool {
+        if self.next.is_none() {
+            self.next = match self.rx.recv_timeout(timeout) {
+                Ok(event) => Some(event),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => Some(AppEvent::InputClosed),
+            };
+        }
+
+        self.next.is_some()
+    }
+
+    pub fn recv(&mut self) -> AppEvent {
+        self.next
+            .take()
+            .or_else(|| self.rx.recv().ok())
+            .unwrap_or(AppEvent::InputClosed)
+    }
+
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn watch(&self, path: Option<PathBuf>) {
+        if let Ok(mut watched) = self.watched.lock() {
+            *watched = path;
+        }
+    }
+}
@@ -0,0 +1,172 @@
+const MAX_DEPTH: usize = 100;
+
+pub fn evaluate(text: &str) -> Result<f64, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        depth: 0,
+    };
+
+    if text.trim().is_empty() {
+        return Err("Empty expression".to_string());
+    }
+
+    let value = parser.expr()?;
+
+    if let Some(c) = parser.peek() {
+        return Err(format!("Unexpected {c} in expression"));
+    }
+
+    if !value.is_finite() {
+        return Err("Result is not a number".to_string());
+    }
+
+    Ok(value)
+}
+
+pub fn format(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{value:.0}")
+    } else {
+        value.to_string()
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<f64, String>) -> Result<f64, String> {
+        if self.depth == MAX_DEPTH {
+            return Err("Expression is nested too deeply".to_string());
+        }
+
+        self.depth += 1;
+
+        let value = parse(self);
+
+        self.depth -= 1;
+
+        value
+    }
+
+    fn expr(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.power()?;
+
+        loop {
+            let op = match self.peek() {
+                Some(op @ ('*' | '/' | '%')) => op,
+                _ => return Ok(value),
+            };
+
+            self.pos += 1;
+
+            let rhs = self.power()?;
+
+            if op != '*' && rhs == 0.0 {
+                return Err("Division by zero".to_string());
+            }
+
+            value = match op {
+                '*' => value * rhs,
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+    }
+
+    fn power(&mut self) -> Result<f64, String> {
+        let base = self.unary()?;
+
+        if self.eat('^') {
+            let exponent = self.nested(Self::power)?;
+
+            return Ok(base.powf(exponent));
+        }
+
+        Ok(base)
+    }
+
+    fn unary(&mut self) -> Result<f64, String> {
+        if self.eat('-') {
+            return Ok(-self.nested(Self::unary)?);
+        }
+
+        if self.eat('+') {
+            return self.nested(Self::unary);
+        }
+
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<f64, String> {
+        if self.eat('(') {
+            let value = self.nested(Self::expr)?;
+
+            if !self.eat(')') {
+                return Err("Missing ) in expression".to_string());
+            }
+
+            return Ok(value);
+        }
+
+        self.peek();
+
+        let start = self.pos;
+
+        while self
+            .chars
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_digit() || *c == '.')
+        {
+            self.pos += 1;
+        }
+
+        let number = self.chars[start..self.pos].iter().collect::<String>();
+
+        if number.is_empty() {
+            return Err(match self.peek() {
+                Some(c) => format!("Unexpected {c} in expression"),
+                None => "Unexpected end of expression".to_string(),
+            });
+        }
+
+        number
+            .parse()
+            .map_err(|_| format!("Invalid number: {number}"))
+    }
+}
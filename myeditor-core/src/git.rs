@@ -0,0 +1,203 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+pub const STATUS_HELP: &str = "s stage, u unstage, Enter open, c commit, R refresh, q close";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusEntry {
+    pub index: char,
+    pub worktree: char,
+    pub path: String,
+}
+
+pub fn run(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("git: {e}"))?;
+
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    Err(stderr
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .map_or_else(
+            || format!("git {}: {}", args[0], output.status),
+            str::to_string,
+        ))
+}
+
+pub fn root(dir: &Path) -> Result<PathBuf, String> {
+    run(dir, &["rev-parse", "--show-toplevel"]).map(|root| PathBuf::from(root.trim_end()))
+}
+
+pub fn status(root: &Path) -> Result<(String, Vec<StatusEntry>), String> {
+    let output = run(root, &["status", "--porcelain=v1", "--branch"])?;
+    let mut lines = output.lines();
+
+    let branch = lines
+        .next()
+        .and_then(|line| line.strip_prefix("## "))
+        .map(|branch| branch.split("...").next().unwrap_or(branch))
+        .unwrap_or_default()
+        .to_string();
+
+    let entries = lines.filter_map(parse_status_line).collect();
+
+    Ok((branch, entries))
+}
+
+pub fn status_text(branch: &str, entries: &[StatusEntry]) -> String {
+    let mut text = format!("Head: {branch}\n{STATUS_HELP}\n\n");
+
+    if entries.is_empty() {
+        text.push_str("Nothing to commit, working tree clean\n");
+    }
+
+    for entry in entries {
+        text.push_str(&format!(
+            "{}{} {}\n",
+            entry.index, entry.worktree, entry.path
+        ));
+    }
+
+    text
+}
+
+pub fn parse_status_line(line: &str) -> Option<StatusEntry> {
+    let mut chars = line.chars();
+    let index = chars.next()?;
+    let worktree = chars.next()?;
+    let path = line.get(3..).filter(|path| !path.is_empty())?;
+
+    if chars.next() != Some(' ') {
+        return None;
+    }
+
+    let path = path.rsplit(" -> ").next().unwrap_or(path);
+
+    Some(StatusEntry {
+        index,
+        worktree,
+        path: path.trim_matches('"').to_string(),
+    })
+}
+
+pub fn show_head(root: &Path, path: &Path) -> Result<String, String> {
+    let relative = path
+        .strip_prefix(root)
+        .map_err(|_| format!("{} is outside the repository", path.display()))?;
+
+    run(root, &["show", &format!("HEAD:{}", relative.display())])
+}
+
+pub fn stage(root: &Path, path: &str) -> Result<(), String> {
+    run(root, &["add", "--", path]).map(drop)
+}
+
+pub fn unstage(root: &Path, path: &str) -> Result<(), String> {
+    run(root, &["reset", "-q", "--", path]).map(drop)
+}
+
+pub fn commit_message_file(root: &Path) -> Result<PathBuf, String> {
+    run(root, &["rev-parse", "--git-path", "COMMIT_EDITMSG"]).map(|path| root.join(path.trim_end()))
+}
+
+pub fn commit_template(entries: &[StatusEntry]) -> String {
+    let mut text = String::from(
+        "\n# Write the commit message above and :w to commit. Lines\n\
+         # starting with # are left out, an empty message aborts.\n#\n",
+    );
+
+    let staged = entries
+        .iter()
+        .filter(|entry| !matches!(entry.index, ' ' | '?' | '!'))
+        .collect::<Vec<_>>();
+
+    if staged.is_empty() {
+        text.push_str("# Nothing is staged.\n");
+    } else {
+        text.push_str("# Changes to be committed:\n");
+    }
+
+    for entry in staged {
+        text.push_str(&format!("#   {} {}\n", entry.index, entry.path));
+    }
+
+    text
+}
+
+pub fn commit(root: &Path, message: &Path) -> Result<String, String> {
+    let message = message.display().to_string();
+
+    run(root, &["commit", "--cleanup=strip", "-F", &message])
+        .map(|output| output.lines().next().unwrap_or_default().to_string())
+}
+
+pub fn remote_url(root: &Path) -> Result<String, String> {
+    let remotes = run(root, &["remote"])?;
+    let remote = remotes
+        .lines()
+        .find(|remote| *remote == "origin")
+        .or_else(|| remotes.lines().next())
+        .ok_or("No git remote")?;
+
+    run(root, &["remote", "get-url", remote]).map(|url| url.trim_end().to_string())
+}
+
+pub fn head_commit(root: &Path) -> Result<String, String> {
+    run(root, &["rev-parse", "HEAD"]).map(|commit| commit.trim_end().to_string())
+}
+
+pub fn permalink(
+    remote: &str,
+    commit: &str,
+    path: &str,
+    first: usize,
+    last: usize,
+) -> Option<String> {
+    let remote = remote.trim_end_matches('/').trim_end_matches(".git");
+
+    let (host, repo) = if let Some(rest) = remote
+        .strip_prefix("https://")
+        .or_else(|| remote.strip_prefix("http://"))
+        .or_else(|| remote.strip_prefix("ssh://"))
+        .or_else(|| remote.strip_prefix("git://"))
+    {
+        let rest = rest.rsplit_once('@').map_or(rest, |(_, rest)| rest);
+        let (host, repo) = rest.split_once('/')?;
+
+        (host.split(':').next()?, repo)
+    } else {
+        let rest = remote.rsplit_once('@').map_or(remote, |(_, rest)| rest);
+
+        rest.split_once(':')?
+    };
+
+    let path = path
+        .replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('#', "%23");
+    let base = format!("https://{host}/{repo}");
+
+    let (blob, range) = match host {
+        "github.com" => ("blob", format!("L{first}-L{last}")),
+        "gitlab.com" => ("-/blob", format!("L{first}-{last}")),
+        "codeberg.org" => ("src/commit", format!("L{first}-L{last}")),
+        _ => return None,
+    };
+    let anchor = if first == last {
+        format!("L{first}")
+    } else {
+        range
+    };
+
+    Some(format!("{base}/{blob}/{commit}/{path}#{anchor}"))
+}
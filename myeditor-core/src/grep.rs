@@ -0,0 +1,157 @@
+use crate::{jobs::Progress, regex::Regex};
+use std::{
+    cell::Cell,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+const MAX_MATCHES: usize = 10_000;
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules"];
+
+#[derive(Debug)]
+pub enum GrepEvent {
+    Match(GrepMatch),
+    Done,
+}
+
+#[derive(Debug, Clone)]
+pub struct GrepMatch {
+    pub path: PathBuf,
+    pub line: usize,
+    pub col: usize,
+    pub text: String,
+}
+
+#[derive(Debug)]
+pub struct Search {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Drop for Search {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+pub fn spawn(
+    root: PathBuf,
+    regex: Regex,
+    progress: Progress,
+    send: impl Fn(GrepEvent) -> bool + Send + 'static,
+) -> Search {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let search = Search {
+        cancelled: cancelled.clone(),
+    };
+
+    thread::spawn(move || {
+        let mut count = 0;
+        let found = Cell::new(0);
+        let send = |event| {
+            if matches!(event, GrepEvent::Match(_)) {
+                found.set(found.get() + 1);
+                progress.report(match found.get() {
+                    1 => "1 match".to_string(),
+                    n => format!("{n} matches"),
+                });
+            }
+
+            !cancelled.load(Ordering::Relaxed) && !progress.is_cancelled() && send(event)
+        };
+
+        search_dir(&root, &regex, &send, &mut count);
+
+        send(GrepEvent::Done);
+    });
+
+    search
+}
+
+fn search_dir(
+    dir: &Path,
+    regex: &Regex,
+    send: &dyn Fn(GrepEvent) -> bool,
+    count: &mut usize,
+) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return true;
+    };
+
+    let mut entries = entries.filter_map(Result::ok).collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        let keep_going = if file_type.is_dir() {
+            SKIPPED_DIRS.contains(&name.as_ref()) || search_dir(&entry.path(), regex, send, count)
+        } else if file_type.is_file() {
+            search_file(&entry.path(), regex, send, count)
+        } else {
+            true
+        };
+
+        if !keep_going {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn search_file(
+    path: &Path,
+    regex: &Regex,
+    send: &dyn Fn(GrepEvent) -> bool,
+    count: &mut usize,
+) -> bool {
+    let Ok(bytes) = std::fs::read(path) else {
+        return true;
+    };
+
+    if bytes.iter().take(8192).any(|&b| b == 0) {
+        return true;
+    }
+
+    let Ok(text) = String::from_utf8(bytes) else {
+        return true;
+    };
+
+    for (line_idx, line) in text.lines().enumerate() {
+        let chars = line.chars().collect::<Vec<_>>();
+
+        if let Some(m) = regex.find_at(&chars, 0) {
+            let event = GrepEvent::Match(GrepMatch {
+                path: path.to_path_buf(),
+                line: line_idx,
+                col: m.start,
+                text: line.to_string(),
+            });
+
+            if !send(event) {
+                return false;
+            }
+
+            *count += 1;
+
+            if *count >= MAX_MATCHES {
+                return false;
+            }
+        }
+    }
+
+    true
+}
@@ -0,0 +1,30 @@
+use std::{
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+pub fn find_program(command: &str) -> Option<PathBuf> {
+    let program = command.split_whitespace().next()?;
+
+    if program.contains('/') {
+        return Some(PathBuf::from(program)).filter(|path| is_executable(path));
+    }
+
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| is_executable(path))
+}
+
+pub fn check_writable(dir: &Path) -> Result<(), String> {
+    let error = |e: std::io::Error| format!("{}: {e}", dir.display());
+    let probe = dir.join(format!(".health-{}", std::process::id()));
+
+    std::fs::create_dir_all(dir).map_err(error)?;
+    std::fs::write(&probe, "").map_err(error)?;
+    std::fs::remove_file(&probe).map_err(error)
+}
+
+fn is_executable(path: &Path) -> bool {
+    path.metadata()
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
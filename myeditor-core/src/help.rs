@@ -0,0 +1,199 @@
+const DOCS: [&str; 3] = [
+    include_str!("../../doc/help.txt"),
+    include_str!("../../doc/commands.txt"),
+    include_str!("../../doc/options.txt"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandHelp {
+    pub name: String,
+    pub summary: String,
+    pub args: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Location {
+    pub text: &'static str,
+    pub line: usize,
+    pub col: usize,
+}
+
+pub fn find(topic: &str) -> Option<Location> {
+    let topic = if topic.is_empty() { "help" } else { topic };
+    let tags = tags();
+
+    let candidates = [topic.to_string(), format!(":{topic}"), format!("'{topic}'")];
+
+    candidates
+        .iter()
+        .find_map(|candidate| tags.iter().find(|(tag, _)| tag == candidate))
+        .or_else(|| tags.iter().find(|(tag, _)| tag.contains(topic)))
+        .map(|&(_, location)| location)
+}
+
+pub fn commands() -> Vec<CommandHelp> {
+    let lines = DOCS[1].lines().collect::<Vec<_>>();
+    let mut commands = Vec::new();
+
+    for (idx, line) in lines.iter().enumerate() {
+        let mut rest = *line;
+        let mut names = Vec::new();
+
+        while let Some(after) = rest.strip_prefix("*:") {
+            let Some(end) = after.find('*') else {
+                break;
+            };
+
+            names.push(&after[..end]);
+            rest = after[end + 1..].trim_start();
+        }
+
+        let Some(name) = names
+            .into_iter()
+            .filter(|name| !name.contains('_'))
+            .reduce(|a, b| if b.len() > a.len() { b } else { a })
+        else {
+            continue;
+        };
+
+        let text = std::iter::once(rest)
+            .chain(
+                lines[idx + 1..]
+                    .iter()
+                    .take_while(|line| line.starts_with(' '))
+                    .map(|line| line.trim()),
+            )
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = strip_references(&text);
+
+        let summary = match text.find(". ") {
+            Some(end) => &text[..=end],
+            None => &text,
+        };
+
+        commands.push(CommandHelp {
+            name: name.to_string(),
+            summary: summary.to_string(),
+            args: summary.starts_with(':'),
+        });
+    }
+
+    commands
+}
+
+pub fn reference_at(line: &str, col: usize) -> Option<String> {
+    let chars = line.chars().collect::<Vec<_>>();
+    let col = std::cmp::min(col, chars.len().checked_sub(1)?);
+
+    let bars = chars
+        .iter()
+        .enumerate()
+        .filter(|(_, &c)| c == '|')
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+
+    let reference = bars
+        .chunks_exact(2)
+        .find(|pair| pair[0] <= col && col <= pair[1])
+        .map(|pair| chars[pair[0] + 1..pair[1]].iter().collect::<String>());
+
+    if reference.is_some() {
+        return reference;
+    }
+
+    if chars[col].is_whitespace() {
+        return None;
+    }
+
+    let start = chars[..col]
+        .iter()
+        .rposition(|c| c.is_whitespace())
+        .map_or(0, |idx| idx + 1);
+    let end = chars[col..]
+        .iter()
+        .position(|c| c.is_whitespace())
+        .map_or(chars.len(), |idx| col + idx);
+
+    let word = chars[start..end]
+        .iter()
+        .collect::<String>()
+        .trim_matches(['|', '*'])
+        .to_string();
+
+    (!word.is_empty()).then_some(word)
+}
+
+fn strip_references(text: &str) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let end = chars[i + 1..]
+            .iter()
+            .position(|&c| c == '|' || c.is_whitespace())
+            .map(|len| i + 1 + len)
+            .filter(|&end| chars[end] == '|' && end > i + 1);
+        let outside = |idx: Option<usize>| {
+            idx.and_then(|idx| chars.get(idx))
+                .is_none_or(|c| !c.is_alphanumeric())
+        };
+
+        match end {
+            Some(end) if chars[i] == '|' && outside(i.checked_sub(1)) && outside(Some(end + 1)) => {
+                out.extend(&chars[i + 1..end]);
+                i = end + 1;
+            }
+            _ => {
+                out.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
+
+fn tags() -> Vec<(String, Location)> {
+    let mut tags = Vec::new();
+
+    for text in DOCS {
+        for (line_idx, line) in text.lines().enumerate() {
+            let mut rest = line;
+            let mut offset = 0;
+
+            while let Some(start) = rest.find('*') {
+                let after = &rest[start + 1..];
+
+                let Some(len) = after.find('*') else {
+                    break;
+                };
+
+                let tag = &after[..len];
+
+                if !tag.is_empty() && !tag.contains(char::is_whitespace) {
+                    let col = line[..offset + start].chars().count();
+
+                    tags.push((
+                        tag.to_string(),
+                        Location {
+                            text,
+                            line: line_idx,
+                            col,
+                        },
+                    ));
+
+                    offset += start + len + 2;
+                    rest = &after[len + 1..];
+                } else {
+                    offset += start + 1;
+                    rest = after;
+                }
+            }
+        }
+    }
+
+    tags
+}
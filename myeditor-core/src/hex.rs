@@ -0,0 +1,53 @@
+pub const BYTES_PER_LINE: usize = 16;
+
+const OFFSET_WIDTH: usize = 10;
+
+pub fn dump(bytes: &[u8]) -> String {
+    (0..line_count(bytes.len()))
+        .map(|line| dump_line(bytes, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn dump_line(bytes: &[u8], line: usize) -> String {
+    let start = std::cmp::min(line * BYTES_PER_LINE, bytes.len());
+    let end = std::cmp::min(start + BYTES_PER_LINE, bytes.len());
+    let chunk = &bytes[start..end];
+
+    let mut out = format!("{:08x}: ", line * BYTES_PER_LINE);
+
+    for i in 0..BYTES_PER_LINE {
+        if i == BYTES_PER_LINE / 2 {
+            out.push(' ');
+        }
+
+        match chunk.get(i) {
+            Some(byte) => out.push_str(&format!("{byte:02x} ")),
+            None => out.push_str("   "),
+        }
+    }
+
+    out.extend(chunk.iter().map(|&byte| {
+        if byte.is_ascii_graphic() || byte == b' ' {
+            byte as char
+        } else {
+            '.'
+        }
+    }));
+
+    out
+}
+
+pub fn line_count(len: usize) -> usize {
+    len / BYTES_PER_LINE + 1
+}
+
+pub fn hex_column(offset: usize) -> usize {
+    let i = offset % BYTES_PER_LINE;
+
+    OFFSET_WIDTH + i * 3 + usize::from(i >= BYTES_PER_LINE / 2)
+}
+
+pub fn digit(c: char) -> Option<u8> {
+    c.to_digit(16).map(|d| d as u8)
+}
@@ -0,0 +1,71 @@
+use ropey::Rope;
+
+pub const TAB_WIDTH: usize = 4;
+
+const SAMPLE_LINES: usize = 1000;
+const MAX_WIDTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Indent {
+    pub tabs: bool,
+    pub width: usize,
+}
+
+impl std::fmt::Display for Indent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.tabs {
+            write!(f, "tabs:{}", self.width)
+        } else {
+            write!(f, "spaces:{}", self.width)
+        }
+    }
+}
+
+pub fn detect(text: &Rope) -> Option<Indent> {
+    let mut tabs = 0;
+    let mut spaces = 0;
+    let mut steps = [0usize; MAX_WIDTH + 1];
+    let mut previous = 0;
+
+    for line in text.lines().take(SAMPLE_LINES) {
+        let mut chars = line.chars().peekable();
+        let mut width = 0;
+
+        if chars.peek() == Some(&'\t') {
+            tabs += 1;
+            previous = 0;
+            continue;
+        }
+
+        while chars.next_if_eq(&' ').is_some() {
+            width += 1;
+        }
+
+        match chars.next() {
+            None | Some('\n' | '\r' | '\t') => continue,
+            Some('*') if width > 0 => continue,
+            _ => {}
+        }
+
+        if width > 0 {
+            spaces += 1;
+        }
+
+        if width > previous && width - previous <= MAX_WIDTH {
+            steps[width - previous] += 1;
+        }
+
+        previous = width;
+    }
+
+    if tabs > spaces {
+        return Some(Indent {
+            tabs: true,
+            width: TAB_WIDTH,
+        });
+    }
+
+    let width = (1..=MAX_WIDTH).max_by_key(|&width| (steps[width], std::cmp::Reverse(width)))?;
+
+    (steps[width] > 0).then_some(Indent { tabs: false, width })
+}
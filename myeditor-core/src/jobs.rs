@@ -0,0 +1,123 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const FRAME: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    cancelled: Arc<AtomicBool>,
+    detail: Arc<Mutex<String>>,
+}
+
+impl Progress {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn report(&self, detail: impl Into<String>) {
+        if let Ok(mut current) = self.detail.lock() {
+            *current = detail.into();
+        }
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    fn detail(&self) -> String {
+        self.detail
+            .lock()
+            .map(|detail| detail.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+pub struct Job {
+    pub id: usize,
+    pub name: String,
+    pub started: Instant,
+    progress: Progress,
+}
+
+#[derive(Debug, Default)]
+pub struct Jobs {
+    jobs: Vec<Job>,
+    next_id: usize,
+}
+
+impl Jobs {
+    pub fn start(&mut self, name: impl Into<String>) -> (usize, Progress) {
+        self.next_id += 1;
+
+        let progress = Progress::default();
+
+        self.jobs.push(Job {
+            id: self.next_id,
+            name: name.into(),
+            started: Instant::now(),
+            progress: progress.clone(),
+        });
+
+        (self.next_id, progress)
+    }
+
+    pub fn finish(&mut self, id: usize) -> bool {
+        let before = self.jobs.len();
+
+        self.jobs.retain(|job| job.id != id);
+
+        self.jobs.len() != before
+    }
+
+    pub fn cancel(&mut self, id: usize) -> Option<Job> {
+        let idx = self.jobs.iter().position(|job| job.id == id)?;
+        let job = self.jobs.remove(idx);
+
+        job.progress.cancel();
+
+        Some(job)
+    }
+
+    pub fn cancel_last(&mut self) -> Option<Job> {
+        let id = self.jobs.last()?.id;
+
+        self.cancel(id)
+    }
+
+    pub fn status(&self, now: Instant) -> Option<String> {
+        let job = self.jobs.last()?;
+        let elapsed = now.duration_since(job.started);
+        let frame = elapsed.as_millis() / FRAME.as_millis();
+        let spinner = SPINNER[frame as usize % SPINNER.len()];
+        let detail = job.progress.detail();
+
+        let mut status = format!("{spinner} {}", job.name);
+
+        if !detail.is_empty() {
+            status.push(' ');
+            status.push_str(&detail);
+        } else if elapsed.as_secs() > 0 {
+            status.push_str(&format!(" {}s", elapsed.as_secs()));
+        }
+
+        if self.jobs.len() > 1 {
+            status.push_str(&format!(" +{}", self.jobs.len() - 1));
+        }
+
+        Some(status)
+    }
+
+    pub fn next_frame(&self, now: Instant) -> Option<Duration> {
+        let job = self.jobs.last()?;
+        let elapsed = now.duration_since(job.started).as_millis() % FRAME.as_millis();
+
+        Some(FRAME - Duration::from_millis(elapsed as u64))
+    }
+}
@@ -0,0 +1,565 @@
+use std::fmt;
+
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Object(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::Number(n) => n.parse().ok().or_else(|| {
+                let n = n.parse::<f64>().ok()?;
+
+                (n >= 0.0 && n.fract() == 0.0 && n <= u64::MAX as f64).then_some(n as u64)
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn object(entries: impl IntoIterator<Item = (&'static str, Value)>) -> Self {
+        Self::Object(
+            entries
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        )
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(n: usize) -> Self {
+        Self::Number(n.to_string())
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Number(n) => write!(f, "{n}"),
+            Self::String(s) => write_string(f, s),
+            Self::Array(values) => {
+                write!(f, "[")?;
+
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+
+                    write!(f, "{value}")?;
+                }
+
+                write!(f, "]")
+            }
+            Self::Object(entries) => {
+                write!(f, "{{")?;
+
+                for (i, (name, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+
+                    write_string(f, name)?;
+                    write!(f, ":{value}")?;
+                }
+
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+
+    write!(f, "\"")
+}
+
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        depth: 0,
+    };
+
+    let value = parser.value()?;
+
+    parser.skip_whitespace();
+
+    if parser.pos < parser.chars.len() {
+        return Err(format!("Trailing characters at {}", parser.pos));
+    }
+
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|c| matches!(c, ' ' | '\t' | '\n' | '\r'))
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!(
+                "Expected {expected:?} at {}, found {c:?}",
+                self.pos - 1
+            )),
+            None => Err(format!("Expected {expected:?}, found end of input")),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        for expected in word.chars() {
+            self.expect(expected)?;
+        }
+
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('n') => self.keyword("null", Value::Null),
+            Some('t') => self.keyword("true", Value::Bool(true)),
+            Some('f') => self.keyword("false", Value::Bool(false)),
+            Some('"') => self.string().map(Value::String),
+            Some(c @ ('[' | '{')) => {
+                if self.depth == MAX_DEPTH {
+                    return Err(format!("Nested deeper than {MAX_DEPTH} at {}", self.pos));
+                }
+
+                self.depth += 1;
+
+                let value = if c == '[' {
+                    self.array()
+                } else {
+                    self.object()
+                };
+
+                self.depth -= 1;
+
+                value
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => Err(format!("Unexpected {c:?} at {}", self.pos)),
+            None => Err("Unexpected end of input".to_string()),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+
+        let mut valid = if self.peek() == Some('0') {
+            self.pos += 1;
+            true
+        } else {
+            self.digits() > 0
+        };
+
+        if valid && self.peek() == Some('.') {
+            self.pos += 1;
+            valid = self.digits() > 0;
+        }
+
+        if valid && matches!(self.peek(), Some('e' | 'E')) {
+            self.pos += 1;
+
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.pos += 1;
+            }
+
+            valid = self.digits() > 0;
+        }
+
+        if !valid {
+            return Err(format!("Invalid number at {start}"));
+        }
+
+        Ok(Value::Number(self.chars[start..self.pos].iter().collect()))
+    }
+
+    fn digits(&mut self) -> usize {
+        let start = self.pos;
+
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+
+        self.pos - start
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+
+        let mut s = String::new();
+
+        loop {
+            match self.next().ok_or("Unterminated string")? {
+                '"' => return Ok(s),
+                '\\' => match self.next().ok_or("Unterminated string")? {
+                    '"' => s.push('"'),
+                    '\\' => s.push('\\'),
+                    '/' => s.push('/'),
+                    'b' => s.push('\u{8}'),
+                    'f' => s.push('\u{c}'),
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    'u' => s.push(self.unicode_escape()?),
+                    c => return Err(format!("Invalid escape \\{c} at {}", self.pos - 1)),
+                },
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| "Invalid unicode escape".to_string());
+        }
+
+        self.expect('\\')?;
+        self.expect('u')?;
+
+        let low = self.hex4()?;
+
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err("Invalid surrogate pair".to_string());
+        }
+
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+            .ok_or_else(|| "Invalid unicode escape".to_string())
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+
+        for _ in 0..4 {
+            let digit = self
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or("Invalid unicode escape")?;
+
+            code = code * 16 + digit;
+        }
+
+        Ok(code)
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        self.skip_whitespace();
+
+        let mut values = Vec::new();
+
+        if self.peek() == Some(']') {
+            self.pos += 1;
+
+            return Ok(Value::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(values)),
+                _ => {
+                    return Err(format!(
+                        "Expected ',' or ']' at {}",
+                        self.pos.saturating_sub(1)
+                    ))
+                }
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        self.skip_whitespace();
+
+        let mut entries = Vec::new();
+
+        if self.peek() == Some('}') {
+            self.pos += 1;
+
+            return Ok(Value::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+
+            let name = self.string()?;
+
+            self.skip_whitespace();
+            self.expect(':')?;
+
+            entries.push((name, self.value()?));
+            self.skip_whitespace();
+
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Object(entries)),
+                _ => {
+                    return Err(format!(
+                        "Expected ',' or '}}' at {}",
+                        self.pos.saturating_sub(1)
+                    ))
+                }
+            }
+        }
+    }
+}
+
+pub fn pretty(value: &Value, indent: &str) -> String {
+    let mut out = String::new();
+
+    write_pretty(&mut out, value, indent, 0);
+
+    out
+}
+
+fn write_pretty(out: &mut String, value: &Value, indent: &str, depth: usize) {
+    let newline = |out: &mut String, depth: usize| {
+        out.push('\n');
+        out.push_str(&indent.repeat(depth));
+    };
+
+    match value {
+        Value::Array(values) if !values.is_empty() => {
+            out.push('[');
+
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+
+                newline(out, depth + 1);
+                write_pretty(out, value, indent, depth + 1);
+            }
+
+            newline(out, depth);
+            out.push(']');
+        }
+        Value::Object(entries) if !entries.is_empty() => {
+            out.push('{');
+
+            for (i, (name, value)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+
+                newline(out, depth + 1);
+                out.push_str(&format!("{}: ", Value::String(name.clone())));
+                write_pretty(out, value, indent, depth + 1);
+            }
+
+            newline(out, depth);
+            out.push('}');
+        }
+        value => out.push_str(&value.to_string()),
+    }
+}
+
+pub fn split_member(text: &str) -> (&str, &str, &str) {
+    let mut value = text.trim();
+    let mut key = "";
+
+    if value.starts_with('"') {
+        let mut escaped = false;
+        let close = value[1..].char_indices().find_map(|(i, c)| {
+            let found = c == '"' && !escaped;
+
+            escaped = c == '\\' && !escaped;
+
+            found.then_some(i + 2)
+        });
+
+        if let Some(rest) = close.and_then(|i| value[i..].trim_start().strip_prefix(':')) {
+            key = &value[..value.len() - rest.len()];
+            value = rest;
+        }
+    }
+
+    let comma = if value.ends_with(',') { "," } else { "" };
+
+    (key, value[..value.len() - comma.len()].trim(), comma)
+}
+
+enum Step {
+    Key(Option<String>),
+    Index(usize),
+}
+
+pub fn path_at(chars: impl Iterator<Item = char>, pos: usize) -> String {
+    let mut stack = Vec::new();
+    let mut string: Option<String> = None;
+    let mut last = String::new();
+    let mut escaped = false;
+
+    for (i, c) in chars.enumerate() {
+        if let Some(s) = &mut string {
+            if escaped {
+                s.push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                last = string.take().unwrap_or_default();
+
+                if i >= pos {
+                    if let Some(Step::Key(key @ None)) = stack.last_mut() {
+                        *key = Some(std::mem::take(&mut last));
+                    }
+
+                    break;
+                }
+            } else {
+                s.push(c);
+            }
+
+            continue;
+        }
+
+        if i >= pos && c != '"' {
+            break;
+        }
+
+        match c {
+            '"' => string = Some(String::new()),
+            '{' => stack.push(Step::Key(None)),
+            '[' => stack.push(Step::Index(0)),
+            '}' | ']' => {
+                stack.pop();
+            }
+            ',' => match stack.last_mut() {
+                Some(Step::Index(i)) => *i += 1,
+                Some(Step::Key(key)) => *key = None,
+                None => {}
+            },
+            ':' => {
+                if let Some(Step::Key(key)) = stack.last_mut() {
+                    *key = Some(std::mem::take(&mut last));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let path = stack
+        .iter()
+        .map(|step| match step {
+            Step::Key(Some(key)) if is_identifier(key) => format!(".{key}"),
+            Step::Key(Some(key)) => format!("[{}]", Value::String(key.clone())),
+            Step::Key(None) => String::new(),
+            Step::Index(i) => format!("[{i}]"),
+        })
+        .collect::<String>();
+
+    if path.is_empty() {
+        ".".to_string()
+    } else {
+        path
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    key.chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Lookup<'a, A> {
+    Action(&'a A),
+    Prefix,
+    None,
+}
+
+#[derive(Debug)]
+pub struct Keymap<A> {
+    root: Node<A>,
+}
+
+#[derive(Debug)]
+struct Node<A> {
+    binding: Option<(A, String)>,
+    children: BTreeMap<String, Node<A>>,
+}
+
+impl<A> Default for Node<A> {
+    fn default() -> Self {
+        Self {
+            binding: None,
+            children: BTreeMap::new(),
+        }
+    }
+}
+
+impl<A> Default for Keymap<A> {
+    fn default() -> Self {
+        Self {
+            root: Node::default(),
+        }
+    }
+}
+
+impl<A> Keymap<A> {
+    pub fn bind(&mut self, keys: &str, action: A, description: &str) {
+        let node = parse_keys(keys)
+            .into_iter()
+            .fold(&mut self.root, |node, key| {
+                node.children.entry(key).or_default()
+            });
+
+        node.binding = Some((action, description.to_string()));
+    }
+
+    pub fn lookup(&self, keys: &[String]) -> Lookup<'_, A> {
+        match self.node(keys) {
+            Some(node) if !node.children.is_empty() => Lookup::Prefix,
+            Some(Node {
+                binding: Some((action, _)),
+                ..
+            }) => Lookup::Action(action),
+            _ => Lookup::None,
+        }
+    }
+
+    pub fn action(&self, keys: &[String]) -> Option<&A> {
+        self.node(keys)?.binding.as_ref().map(|(action, _)| action)
+    }
+
+    pub fn continuations(&self, keys: &[String]) -> Vec<(String, String)> {
+        let Some(node) = self.node(keys) else {
+            return Vec::new();
+        };
+
+        node.children
+            .iter()
+            .map(|(key, child)| {
+                let description = match &child.binding {
+                    Some((_, description)) => description.clone(),
+                    None => format!("+{} more", child.children.len()),
+                };
+
+                (key.clone(), description)
+            })
+            .collect()
+    }
+
+    pub fn bindings(&self) -> Vec<(String, String)> {
+        let mut bindings = Vec::new();
+        let mut stack = vec![(String::new(), &self.root)];
+
+        while let Some((keys, node)) = stack.pop() {
+            if let Some((_, description)) = &node.binding {
+                bindings.push((keys.clone(), description.clone()));
+            }
+
+            stack.extend(
+                node.children
+                    .iter()
+                    .rev()
+                    .map(|(key, child)| (format!("{keys}{key}"), child)),
+            );
+        }
+
+        bindings
+    }
+
+    fn node(&self, keys: &[String]) -> Option<&Node<A>> {
+        keys.iter()
+            .try_fold(&self.root, |node, key| node.children.get(key))
+    }
+}
+
+pub fn parse_keys(text: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let len = match rest.find('>') {
+            Some(end) if c == '<' && end > 1 => end + 1,
+            _ => c.len_utf8(),
+        };
+
+        keys.push(rest[..len].to_string());
+        rest = &rest[len..];
+    }
+
+    keys
+}
@@ -0,0 +1,556 @@
+use crate::regex::Regex;
+use std::{
+    fs::File,
+    io,
+    os::unix::fs::FileExt,
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+const BLOCK: usize = 64 * 1024;
+const STEP: usize = 1024;
+const BATCH: usize = 64 * STEP;
+
+pub const WINDOW: usize = 4096;
+pub const MARGIN: usize = WINDOW / 4;
+
+#[derive(Debug)]
+struct Map {
+    file: File,
+    len: usize,
+}
+
+struct Reader<'a> {
+    file: &'a File,
+    len: usize,
+    start: usize,
+    block: Vec<u8>,
+}
+
+impl Map {
+    fn new(file: File) -> io::Result<Self> {
+        let len = file.metadata()?.len() as usize;
+
+        Ok(Self { file, len })
+    }
+
+    fn reader(&self) -> Reader<'_> {
+        Reader {
+            file: &self.file,
+            len: self.len,
+            start: usize::MAX,
+            block: Vec::new(),
+        }
+    }
+}
+
+impl Reader<'_> {
+    fn block(&mut self, pos: usize) -> (usize, &[u8]) {
+        let start = pos / BLOCK * BLOCK;
+
+        if start != self.start {
+            self.start = start;
+            self.block
+                .resize(std::cmp::min(BLOCK, self.len.saturating_sub(start)), 0);
+
+            let mut filled = 0;
+
+            while filled < self.block.len() {
+                match self
+                    .file
+                    .read_at(&mut self.block[filled..], (start + filled) as u64)
+                {
+                    Ok(0) => break,
+                    Ok(n) => filled += n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(_) => break,
+                }
+            }
+
+            self.block.truncate(filled);
+        }
+
+        (start, &self.block)
+    }
+
+    fn bytes(&mut self, start: usize, end: usize) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(end.saturating_sub(start));
+        let mut pos = start;
+
+        while pos < end {
+            let (block_start, block) = self.block(pos);
+            let until = std::cmp::min(end - block_start, block.len());
+
+            if until <= pos - block_start {
+                break;
+            }
+
+            bytes.extend_from_slice(&block[pos - block_start..until]);
+            pos = block_start + until;
+        }
+
+        bytes
+    }
+
+    fn next_newline(&mut self, mut pos: usize) -> Option<usize> {
+        while pos < self.len {
+            let (start, block) = self.block(pos);
+
+            if block.len() <= pos - start {
+                return None;
+            }
+
+            if let Some(i) = block[pos - start..].iter().position(|&b| b == b'\n') {
+                return Some(pos + i);
+            }
+
+            pos = start + block.len();
+        }
+
+        None
+    }
+
+    fn prev_newline(&mut self, pos: usize) -> Option<usize> {
+        let mut end = std::cmp::min(pos, self.len);
+
+        while end > 0 {
+            let (start, block) = self.block(end - 1);
+            let until = std::cmp::min(end - start, block.len());
+
+            if let Some(i) = block[..until].iter().rposition(|&b| b == b'\n') {
+                return Some(start + i);
+            }
+
+            end = start;
+        }
+
+        None
+    }
+
+    fn count_newlines(&mut self, start: usize, end: usize) -> usize {
+        let (mut count, mut pos) = (0, start);
+
+        while pos < end {
+            let (block_start, block) = self.block(pos);
+            let until = std::cmp::min(end - block_start, block.len());
+
+            if until <= pos - block_start {
+                break;
+            }
+
+            count += block[pos - block_start..until]
+                .iter()
+                .filter(|&&b| b == b'\n')
+                .count();
+            pos = block_start + until;
+        }
+
+        count
+    }
+
+    fn line_start_at(&mut self, pos: usize) -> usize {
+        self.prev_newline(pos).map_or(0, |i| i + 1)
+    }
+
+    fn lines_forward(&mut self, mut pos: usize, count: usize) -> (usize, usize) {
+        let mut moved = 0;
+
+        while moved < count {
+            match self.next_newline(pos) {
+                Some(i) => pos = i + 1,
+                None => break,
+            }
+
+            moved += 1;
+        }
+
+        (pos, moved)
+    }
+
+    fn lines_back(&mut self, mut pos: usize, count: usize) -> (usize, usize) {
+        let mut moved = 0;
+
+        while moved < count && pos > 0 {
+            pos = self.line_start_at(pos - 1);
+            moved += 1;
+        }
+
+        (pos, moved)
+    }
+
+    fn line_bytes(&mut self, pos: usize) -> Vec<u8> {
+        let end = self.next_newline(pos).unwrap_or(self.len);
+        let mut line = self.bytes(pos, end);
+
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+
+        line
+    }
+}
+
+#[derive(Debug)]
+struct Index {
+    checkpoints: Vec<usize>,
+    scanned: (usize, usize),
+    lines: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit {
+    pub pos: usize,
+    pub col: usize,
+    pub end: usize,
+    pub wrapped: bool,
+}
+
+#[derive(Debug)]
+pub struct LargeFile {
+    map: Arc<Map>,
+    index: Arc<Mutex<Index>>,
+    window: usize,
+    exact: bool,
+    start: usize,
+    end: usize,
+}
+
+impl LargeFile {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let map = Map::new(File::open(path)?)?;
+        let index = Index {
+            checkpoints: vec![0],
+            scanned: (0, 0),
+            lines: None,
+        };
+
+        let mut file = Self {
+            map: Arc::new(map),
+            index: Arc::new(Mutex::new(index)),
+            window: 0,
+            exact: true,
+            start: 0,
+            end: 0,
+        };
+
+        file.place(0, Some(0));
+
+        Ok(file)
+    }
+
+    pub fn build_index(&self, done: impl FnOnce() + Send + 'static) {
+        let map = self.map.clone();
+        let index = self.index.clone();
+
+        thread::spawn(move || {
+            let mut reader = map.reader();
+            let mut checkpoints = Vec::new();
+            let (mut lines, mut pos) = (0, 0);
+
+            while let Some(i) = reader.next_newline(pos) {
+                pos = i + 1;
+                lines += 1;
+
+                if lines % STEP == 0 {
+                    checkpoints.push(pos);
+                }
+
+                if lines % BATCH == 0 {
+                    let Ok(mut index) = index.lock() else {
+                        return;
+                    };
+
+                    index.checkpoints.append(&mut checkpoints);
+                    index.scanned = (lines, pos);
+                }
+            }
+
+            if let Ok(mut index) = index.lock() {
+                index.checkpoints.append(&mut checkpoints);
+                index.scanned = (lines, pos);
+                index.lines = Some(lines + 1);
+            }
+
+            done();
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.len == 0
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+
+    pub fn at_start(&self) -> bool {
+        self.start == 0
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.end >= self.len()
+    }
+
+    pub fn known_lines(&self) -> Option<usize> {
+        self.index.lock().ok()?.lines
+    }
+
+    pub fn estimated_lines(&self) -> usize {
+        self.known_lines()
+            .unwrap_or_else(|| self.estimate_line(self.len()) + 1)
+    }
+
+    pub fn percent(&self, line: usize, lines: usize) -> usize {
+        let pos = self.start + (self.end - self.start) * line / std::cmp::max(lines, 1);
+
+        pos * 100 / std::cmp::max(self.len(), 1)
+    }
+
+    pub fn text(&self) -> (String, bool) {
+        let bytes = self.map.reader().bytes(self.start, self.end);
+
+        match String::from_utf8(bytes) {
+            Ok(text) => (text, false),
+            Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), true),
+        }
+    }
+
+    pub fn offset(&self, local: usize) -> usize {
+        self.map.reader().lines_forward(self.start, local).0
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn move_to(&mut self, start: usize, line: usize) {
+        let start = self
+            .map
+            .reader()
+            .line_start_at(std::cmp::min(start, self.len()));
+
+        self.place(start, Some(line));
+    }
+
+    pub fn shift(&mut self, delta: isize) -> isize {
+        let mut reader = self.map.reader();
+
+        let (start, moved) = if delta < 0 {
+            let (start, moved) = reader.lines_back(self.start, delta.unsigned_abs());
+
+            (start, -(moved as isize))
+        } else {
+            let (start, moved) = reader.lines_forward(self.start, delta as usize);
+
+            (start, moved as isize)
+        };
+
+        self.place(start, Some(self.window.saturating_add_signed(moved)));
+
+        moved
+    }
+
+    pub fn goto_line(&mut self, line: usize) -> usize {
+        if self.exact && line >= self.window {
+            let (pos, moved) = self
+                .map
+                .reader()
+                .lines_forward(self.start, line - self.window);
+
+            if moved == line - self.window && pos <= self.end {
+                return moved;
+            }
+        }
+
+        let first = line.saturating_sub(WINDOW / 2);
+        let start = self.line_start(first).unwrap_or_else(|| {
+            let pos = std::cmp::min(self.estimate_offset(first), self.len());
+
+            self.map.reader().line_start_at(pos)
+        });
+
+        self.place(start, Some(first));
+
+        line.saturating_sub(self.window)
+    }
+
+    pub fn goto_offset(&mut self, pos: usize) -> usize {
+        let mut reader = self.map.reader();
+        let pos = reader.line_start_at(std::cmp::min(pos, self.len()));
+
+        if (self.start..=self.end).contains(&pos) {
+            return reader.count_newlines(self.start, pos);
+        }
+
+        let (start, moved) = reader.lines_back(pos, WINDOW / 2);
+
+        self.place(start, None);
+
+        moved
+    }
+
+    pub fn refresh(&mut self) -> bool {
+        if self.exact {
+            return false;
+        }
+
+        match self.line_of(self.start) {
+            Some(line) => {
+                self.window = line;
+                self.exact = true;
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn find(&self, regex: &Regex, start: usize, col: usize, forward: bool) -> Option<Hit> {
+        let mut reader = self.map.reader();
+        let prefix = regex.prefix().into_bytes();
+        let mut pos = start;
+        let mut wrapped = false;
+
+        loop {
+            let line = reader.line_bytes(pos);
+            let first = pos == start && !wrapped;
+
+            let found = if prefix.is_empty() || line.windows(prefix.len()).any(|w| w == prefix) {
+                let chars = String::from_utf8_lossy(&line).chars().collect::<Vec<_>>();
+
+                if forward {
+                    let from = if first { col + 1 } else { 0 };
+
+                    (from <= chars.len())
+                        .then(|| regex.find_at(&chars, from))
+                        .flatten()
+                        .map(|m| (m.start, m.end))
+                } else {
+                    last_match(regex, &chars, if first { col } else { usize::MAX })
+                }
+            } else {
+                None
+            };
+
+            if let Some((col, end)) = found {
+                return Some(Hit {
+                    pos,
+                    col,
+                    end,
+                    wrapped,
+                });
+            }
+
+            if wrapped && pos == start {
+                return None;
+            }
+
+            if forward {
+                match reader.next_newline(pos) {
+                    Some(i) => pos = i + 1,
+                    None => {
+                        pos = 0;
+                        wrapped = true;
+                    }
+                }
+            } else if pos == 0 {
+                pos = reader.line_start_at(self.len());
+                wrapped = true;
+            } else {
+                pos = reader.line_start_at(pos - 1);
+            }
+        }
+    }
+
+    fn place(&mut self, start: usize, guess: Option<usize>) {
+        let mut reader = self.map.reader();
+        let (last, _) = reader.lines_forward(start, WINDOW - 1);
+
+        self.start = start;
+        self.end = reader.next_newline(last).unwrap_or(self.len());
+
+        (self.window, self.exact) = match self.line_of(start) {
+            Some(line) => (line, true),
+            None => (guess.unwrap_or_else(|| self.estimate_line(start)), false),
+        };
+    }
+
+    fn line_start(&self, line: usize) -> Option<usize> {
+        let index = self.index.lock().ok()?;
+
+        if line > index.scanned.0 || index.lines.is_some_and(|lines| line >= lines) {
+            return None;
+        }
+
+        let checkpoint = index.checkpoints[line / STEP];
+        let (pos, moved) = self.map.reader().lines_forward(checkpoint, line % STEP);
+
+        (moved == line % STEP).then_some(pos)
+    }
+
+    fn line_of(&self, pos: usize) -> Option<usize> {
+        let index = self.index.lock().ok()?;
+
+        if pos > index.scanned.1 && index.lines.is_none() {
+            return None;
+        }
+
+        let i = index.checkpoints.partition_point(|&start| start <= pos) - 1;
+        let start = index.checkpoints[i];
+
+        Some(i * STEP + self.map.reader().count_newlines(start, pos))
+    }
+
+    fn rate(&self) -> (f64, f64) {
+        let scanned = self.index.lock().map_or((0, 0), |index| index.scanned);
+
+        if scanned.0 > 0 {
+            return (scanned.1 as f64, scanned.0 as f64);
+        }
+
+        let lines = self.map.reader().count_newlines(self.start, self.end);
+
+        ((self.end - self.start) as f64, lines as f64 + 1.0)
+    }
+
+    fn estimate_line(&self, pos: usize) -> usize {
+        let (bytes, lines) = self.rate();
+
+        (pos as f64 * lines / bytes.max(1.0)) as usize
+    }
+
+    fn estimate_offset(&self, line: usize) -> usize {
+        let (bytes, lines) = self.rate();
+
+        (line as f64 * bytes / lines.max(1.0)) as usize
+    }
+}
+
+fn last_match(regex: &Regex, chars: &[char], limit: usize) -> Option<(usize, usize)> {
+    let mut last = None;
+    let mut pos = 0;
+
+    while let Some(m) = regex.find_at(chars, pos) {
+        if m.start >= limit {
+            break;
+        }
+
+        last = Some((m.start, m.end));
+        pos = m.start + 1;
+
+        if pos > chars.len() {
+            break;
+        }
+    }
+
+    last
+}
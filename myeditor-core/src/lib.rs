@@ -0,0 +1,77 @@
+pub mod abbrev;
+pub mod align;
+pub mod backend;
+pub mod backup;
+pub mod behavior;
+pub mod bookmarks;
+pub mod brackets;
+pub mod buffer;
+pub mod charinfo;
+pub mod clipboard;
+pub mod colors;
+pub mod conflicts;
+pub mod crash;
+pub mod csv;
+pub mod diagnostics;
+pub mod diff;
+pub mod digraph;
+pub mod display;
+pub mod editor;
+pub mod error;
+pub mod events;
+pub mod expr;
+pub mod git;
+pub mod grep;
+pub mod health;
+pub mod help;
+pub mod hex;
+pub mod indent;
+pub mod input;
+pub mod jobs;
+pub mod json;
+pub mod keymap;
+pub mod largefile;
+pub mod links;
+pub mod logger;
+pub mod lsp;
+pub mod lua;
+pub mod markdown;
+pub mod marks;
+pub mod messages;
+pub mod minimap;
+pub mod mode;
+pub mod options;
+pub mod overlay;
+pub mod paths;
+pub mod picker;
+pub mod plugins;
+pub mod profile;
+pub mod project;
+pub mod prompt;
+pub mod pty;
+pub mod quickfix;
+pub mod range;
+pub mod recent;
+pub mod reflow;
+pub mod regex;
+pub mod registers;
+pub mod remote;
+pub mod replace;
+pub mod script;
+pub mod scripts;
+pub mod search;
+pub mod sha256;
+pub mod statusline;
+pub mod style;
+pub mod substitute;
+pub mod symbols;
+pub mod tasks;
+pub mod terminal;
+pub mod tutor;
+pub mod undo;
+pub mod view;
+pub mod vt;
+pub mod wasm;
+pub mod whitespace;
+pub mod width;
+pub mod wordcount;
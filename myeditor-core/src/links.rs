@@ -0,0 +1,116 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+const SCHEMES: [&str; 4] = ["https://", "http://", "ftp://", "file://"];
+const TRAILING: &[char] = &['.', ',', ';', ':', '!', '?', '\'', '"'];
+
+pub fn url_at(line: &str, col: usize) -> Option<String> {
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let rest = chars[i..].iter().collect::<String>();
+
+        if !SCHEMES.iter().any(|scheme| rest.starts_with(scheme)) {
+            i += 1;
+            continue;
+        }
+
+        let len = chars[i..]
+            .iter()
+            .take_while(|&&c| !c.is_whitespace() && !matches!(c, '<' | '>' | '"' | '`'))
+            .count();
+        let url = trim_url(&rest.chars().take(len).collect::<String>());
+        let end = i + url.chars().count();
+
+        if (i..std::cmp::max(end, i + 1)).contains(&col) {
+            return Some(url);
+        }
+
+        i += std::cmp::max(len, 1);
+    }
+
+    None
+}
+
+pub fn path_at(line: &str, col: usize) -> Option<(String, Option<usize>)> {
+    let chars = line.chars().collect::<Vec<_>>();
+
+    if url_at(line, col).is_some() || !chars.get(col).copied().is_some_and(is_path_char) {
+        return None;
+    }
+
+    let start = chars[..col]
+        .iter()
+        .rposition(|&c| !is_path_char(c))
+        .map_or(0, |i| i + 1);
+    let end = chars[col..]
+        .iter()
+        .position(|&c| !is_path_char(c))
+        .map_or(chars.len(), |i| col + i);
+
+    let token = chars[start..end].iter().collect::<String>();
+    let token = token.trim_end_matches(TRAILING);
+    let mut parts = token.splitn(3, ':');
+    let name = parts.next()?.trim_end_matches(TRAILING);
+    let line = parts
+        .next()
+        .and_then(|line| line.parse::<usize>().ok())
+        .and_then(|line| line.checked_sub(1));
+
+    (!name.is_empty()).then(|| (name.to_string(), line))
+}
+
+pub fn find_file(name: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+    let path = Path::new(name);
+
+    if path.is_absolute() {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+
+    dirs.iter()
+        .map(|dir| dir.join(path))
+        .find(|path| path.is_file())
+}
+
+pub fn open_url(url: &str) -> Result<(), String> {
+    let opener = match std::env::var("BROWSER") {
+        Ok(browser) if !browser.is_empty() => browser,
+        _ if cfg!(target_os = "macos") => "open".to_string(),
+        _ => "xdg-open".to_string(),
+    };
+
+    let mut child = Command::new(&opener)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("{opener}: {e}"))?;
+
+    std::thread::spawn(move || child.wait());
+
+    Ok(())
+}
+
+fn trim_url(url: &str) -> String {
+    let mut url = url.trim_end_matches(TRAILING).to_string();
+
+    for (open, close) in [('(', ')'), ('[', ']')] {
+        while url.ends_with(close)
+            && url.chars().filter(|&c| c == close).count()
+                > url.chars().filter(|&c| c == open).count()
+        {
+            url.pop();
+            url = url.trim_end_matches(TRAILING).to_string();
+        }
+    }
+
+    url
+}
+
+fn is_path_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '/' | '.' | '_' | '-' | '~' | '+' | '@' | ':')
+}
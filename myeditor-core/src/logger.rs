@@ -0,0 +1,86 @@
+use crate::paths;
+use log::{Level, Log, Metadata, Record};
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::Mutex,
+    time::Instant,
+};
+
+const MAX_LOG_BYTES: u64 = 1 << 20;
+
+struct FileLogger {
+    file: Mutex<File>,
+    level: Level,
+    start: Instant,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level && metadata.target().starts_with("myeditor")
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(
+                file,
+                "[{:>9.3}s] {:<5} {}: {}",
+                self.start.elapsed().as_secs_f64(),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+pub fn log_file() -> Option<PathBuf> {
+    paths::data_file("myeditor.log")
+}
+
+pub fn init(verbosity: u8) -> std::io::Result<()> {
+    let level = match verbosity {
+        0 => return Ok(()),
+        1 => Level::Debug,
+        _ => Level::Trace,
+    };
+
+    let path = log_file().ok_or(std::io::ErrorKind::NotFound)?;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let truncate = std::fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_BYTES);
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(!truncate)
+        .write(true)
+        .truncate(truncate)
+        .open(&path)?;
+
+    let logger = FileLogger {
+        file: Mutex::new(file),
+        level,
+        start: Instant::now(),
+    };
+
+    if log::set_logger(Box::leak(Box::new(logger))).is_ok() {
+        log::set_max_level(level.to_level_filter());
+        log::info!("myeditor {} started", env!("CARGO_PKG_VERSION"));
+    }
+
+    Ok(())
+}
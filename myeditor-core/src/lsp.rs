@@ -0,0 +1,710 @@
+use crate::json::{self, Value};
+use ropey::Rope;
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    io::{BufRead, BufReader, Write},
+    ops::Range,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+pub const SIGNATURE_TIMEOUT: Duration = Duration::from_secs(1);
+
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(10);
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(500);
+const MAX_MESSAGE_LEN: usize = 64 << 20;
+const ROOT_MARKERS: [&str; 5] = [
+    ".git",
+    "Cargo.toml",
+    "go.mod",
+    "package.json",
+    "pyproject.toml",
+];
+const ACTION_KINDS: [&str; 8] = [
+    "",
+    "quickfix",
+    "refactor",
+    "refactor.extract",
+    "refactor.inline",
+    "refactor.rewrite",
+    "source",
+    "source.organizeImports",
+];
+
+#[derive(Debug)]
+pub struct Client {
+    pub command: String,
+    pub root: PathBuf,
+    child: Child,
+    stdin: ChildStdin,
+    messages: Receiver<Value>,
+    next_id: u64,
+    capabilities: Value,
+    documents: HashMap<PathBuf, (usize, Rope)>,
+    diagnostics: HashMap<String, Vec<Value>>,
+    edits: Vec<Value>,
+}
+
+impl Client {
+    pub fn start(command: &str, root: &Path) -> Result<Self, String> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("{command}: {e}"))?;
+
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(format!("{command}: no pipes"));
+        };
+
+        let (tx, messages) = channel();
+
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+
+            while let Ok(message) = read_message(&mut reader) {
+                if tx.send(message).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let mut client = Self {
+            command: command.to_string(),
+            root: root.to_path_buf(),
+            child,
+            stdin,
+            messages,
+            next_id: 1,
+            capabilities: Value::Null,
+            documents: HashMap::new(),
+            diagnostics: HashMap::new(),
+            edits: Vec::new(),
+        };
+
+        let root_uri = uri(root);
+        let name = root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let params = Value::object([
+            ("processId", (std::process::id() as usize).into()),
+            ("rootUri", root_uri.clone().into()),
+            (
+                "workspaceFolders",
+                Value::Array(vec![Value::object([
+                    ("uri", root_uri.into()),
+                    ("name", name.into()),
+                ])]),
+            ),
+            ("capabilities", capabilities()),
+            ("clientInfo", Value::object([("name", "myeditor".into())])),
+        ]);
+
+        let result = client.request("initialize", params, INITIALIZE_TIMEOUT)?;
+
+        client.capabilities = result.get("capabilities").cloned().unwrap_or(Value::Null);
+        client.notify("initialized", Value::object([]))?;
+
+        Ok(client)
+    }
+
+    pub fn is_running(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    pub fn supports(&self, capability: &str) -> bool {
+        !matches!(
+            self.capabilities.get(capability),
+            None | Some(Value::Null | Value::Bool(false))
+        )
+    }
+
+    pub fn sync(&mut self, path: &Path, text: &Rope, language: &str) -> Result<(), String> {
+        let document = uri(path);
+
+        match self.documents.get(path) {
+            Some((_, synced)) if synced == text => Ok(()),
+            Some(&(version, _)) => {
+                self.documents
+                    .insert(path.to_path_buf(), (version + 1, text.clone()));
+                self.notify(
+                    "textDocument/didChange",
+                    Value::object([
+                        (
+                            "textDocument",
+                            Value::object([
+                                ("uri", document.into()),
+                                ("version", (version + 1).into()),
+                            ]),
+                        ),
+                        (
+                            "contentChanges",
+                            Value::Array(vec![Value::object([("text", text.to_string().into())])]),
+                        ),
+                    ]),
+                )
+            }
+            None => {
+                self.documents.insert(path.to_path_buf(), (1, text.clone()));
+                self.notify(
+                    "textDocument/didOpen",
+                    Value::object([(
+                        "textDocument",
+                        Value::object([
+                            ("uri", document.into()),
+                            ("languageId", language.into()),
+                            ("version", 1.into()),
+                            ("text", text.to_string().into()),
+                        ]),
+                    )]),
+                )
+            }
+        }
+    }
+
+    pub fn diagnostics(&mut self, path: &Path) -> &[Value] {
+        self.pump();
+
+        self.diagnostics.get(&uri(path)).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn take_edits(&mut self) -> Vec<Value> {
+        std::mem::take(&mut self.edits)
+    }
+
+    pub fn notify(&mut self, method: &str, params: Value) -> Result<(), String> {
+        self.pump();
+        self.send(message(None, method, params))
+    }
+
+    pub fn request(
+        &mut self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, String> {
+        self.pump();
+
+        let id = self.next_id;
+
+        self.next_id += 1;
+        self.send(message(Some(id), method, params))?;
+
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+
+            let message = match self.messages.recv_timeout(remaining) {
+                Ok(message) => message,
+                Err(RecvTimeoutError::Timeout) => return Err(format!("{method} timed out")),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(format!("{} exited", self.command))
+                }
+            };
+
+            if message.get("method").is_some()
+                || message.get("id").and_then(Value::as_u64) != Some(id)
+            {
+                self.handle(message);
+                continue;
+            }
+
+            if let Some(error) = message.get("error") {
+                let reason = error.get("message").and_then(Value::as_str).unwrap_or("");
+
+                return Err(format!("{method}: {reason}"));
+            }
+
+            return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    fn pump(&mut self) {
+        while let Ok(message) = self.messages.try_recv() {
+            self.handle(message);
+        }
+    }
+
+    fn handle(&mut self, message: Value) {
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return;
+        };
+
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let Some(id) = message.get("id").cloned() else {
+            if method == "textDocument/publishDiagnostics" {
+                if let (Some(uri), Some(diagnostics)) = (
+                    params.get("uri").and_then(Value::as_str),
+                    params.get("diagnostics").and_then(Value::as_array),
+                ) {
+                    self.diagnostics
+                        .insert(uri.to_string(), diagnostics.to_vec());
+                }
+            }
+
+            return;
+        };
+
+        let result = match method {
+            "workspace/configuration" => Value::Array(
+                params
+                    .get("items")
+                    .and_then(Value::as_array)
+                    .map_or(Vec::new(), |items| vec![Value::Null; items.len()]),
+            ),
+            "workspace/applyEdit" => {
+                self.edits.extend(params.get("edit").cloned());
+
+                Value::object([("applied", true.into())])
+            }
+            _ => Value::Null,
+        };
+
+        let _ = self.send(Value::object([
+            ("jsonrpc", "2.0".into()),
+            ("id", id),
+            ("result", result),
+        ]));
+    }
+
+    fn send(&mut self, message: Value) -> Result<(), String> {
+        self.stdin
+            .write_all(&encode(&message))
+            .and_then(|()| self.stdin.flush())
+            .map_err(|e| format!("{}: {e}", self.command))
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        if self
+            .request("shutdown", Value::Null, SHUTDOWN_TIMEOUT)
+            .is_ok()
+        {
+            let _ = self.notify("exit", Value::Null);
+        }
+
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Debug)]
+pub struct CodeActions {
+    actions: Vec<Value>,
+    selected: usize,
+}
+
+impl CodeActions {
+    pub fn new(actions: Vec<Value>) -> Self {
+        Self {
+            actions,
+            selected: 0,
+        }
+    }
+
+    pub fn titles(&self) -> impl Iterator<Item = &str> {
+        self.actions
+            .iter()
+            .map(|action| action.get("title").and_then(Value::as_str).unwrap_or(""))
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, idx: usize) -> bool {
+        if idx < self.actions.len() {
+            self.selected = idx;
+        }
+
+        idx < self.actions.len()
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = std::cmp::min(self.selected + 1, self.actions.len().saturating_sub(1));
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn take_selected(mut self) -> Value {
+        self.actions.swap_remove(self.selected)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub line: usize,
+    pub label: String,
+    pub active: Option<Range<usize>>,
+}
+
+impl Signature {
+    pub fn from_help(help: &Value, line: usize) -> Option<Self> {
+        let signatures = help.get("signatures")?.as_array()?;
+        let idx = help
+            .get("activeSignature")
+            .and_then(Value::as_u64)
+            .unwrap_or(0) as usize;
+        let signature = signatures.get(idx).or(signatures.first())?;
+        let label = signature.get("label")?.as_str()?.to_string();
+
+        let parameter = signature
+            .get("activeParameter")
+            .or(help.get("activeParameter"))
+            .and_then(Value::as_u64)
+            .unwrap_or(0) as usize;
+        let active = signature
+            .get("parameters")
+            .and_then(Value::as_array)
+            .and_then(|parameters| parameters.get(parameter))
+            .and_then(|parameter| parameter.get("label"))
+            .and_then(|parameter| parameter_range(&label, parameter));
+
+        Some(Self {
+            line,
+            label,
+            active,
+        })
+    }
+}
+
+fn parameter_range(label: &str, parameter: &Value) -> Option<Range<usize>> {
+    match parameter {
+        Value::String(name) => {
+            let open = label.find('(').unwrap_or(0);
+            let start = open + label[open..].find(name.as_str())?;
+            let start = label[..start].chars().count();
+
+            Some(start..start + name.chars().count())
+        }
+        Value::Array(offsets) => {
+            let [start, end] = offsets.as_slice() else {
+                return None;
+            };
+
+            let char_at = |units: u64| {
+                label
+                    .chars()
+                    .scan(0, |at, c| {
+                        let start = *at;
+
+                        *at += c.len_utf16() as u64;
+                        Some(start)
+                    })
+                    .take_while(|&start| start < units)
+                    .count()
+            };
+
+            Some(char_at(start.as_u64()?)..char_at(end.as_u64()?))
+        }
+        _ => None,
+    }
+}
+
+pub fn server(filetype: &str) -> Option<&'static str> {
+    let command = match filetype {
+        "rust" => "rust-analyzer",
+        "python" => "pylsp",
+        "javascript" | "typescript" => "typescript-language-server --stdio",
+        "go" => "gopls",
+        "c" | "cpp" => "clangd",
+        _ => return None,
+    };
+
+    Some(command)
+}
+
+pub fn root(path: &Path) -> PathBuf {
+    let dir = path.parent().unwrap_or(path);
+
+    dir.ancestors()
+        .find(|dir| ROOT_MARKERS.iter().any(|marker| dir.join(marker).exists()))
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+fn capabilities() -> Value {
+    Value::object([
+        (
+            "general",
+            Value::object([("positionEncodings", Value::Array(vec!["utf-16".into()]))]),
+        ),
+        (
+            "textDocument",
+            Value::object([
+                ("publishDiagnostics", Value::object([])),
+                (
+                    "codeAction",
+                    Value::object([(
+                        "codeActionLiteralSupport",
+                        Value::object([(
+                            "codeActionKind",
+                            Value::object([(
+                                "valueSet",
+                                Value::Array(ACTION_KINDS.map(Value::from).to_vec()),
+                            )]),
+                        )]),
+                    )]),
+                ),
+                (
+                    "signatureHelp",
+                    Value::object([(
+                        "signatureInformation",
+                        Value::object([
+                            (
+                                "parameterInformation",
+                                Value::object([("labelOffsetSupport", true.into())]),
+                            ),
+                            ("activeParameterSupport", true.into()),
+                        ]),
+                    )]),
+                ),
+            ]),
+        ),
+        (
+            "workspace",
+            Value::object([
+                ("applyEdit", true.into()),
+                ("configuration", true.into()),
+                ("workspaceFolders", true.into()),
+                (
+                    "workspaceEdit",
+                    Value::object([("documentChanges", true.into())]),
+                ),
+            ]),
+        ),
+    ])
+}
+
+fn message(id: Option<u64>, method: &str, params: Value) -> Value {
+    let mut entries = vec![("jsonrpc", "2.0".into())];
+
+    if let Some(id) = id {
+        entries.push(("id", Value::Number(id.to_string())));
+    }
+
+    entries.push(("method", method.into()));
+
+    if params != Value::Null {
+        entries.push(("params", params));
+    }
+
+    Value::object(entries)
+}
+
+pub fn encode(message: &Value) -> Vec<u8> {
+    let body = message.to_string();
+
+    format!("Content-Length: {}\r\n\r\n{body}", body.len()).into_bytes()
+}
+
+pub fn read_message(reader: &mut impl BufRead) -> std::io::Result<Value> {
+    let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+
+    let mut len = None;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+
+        if reader.read_line(&mut line)? == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let header = line.trim_end();
+
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                len = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let len = len.ok_or_else(|| invalid("missing Content-Length".to_string()))?;
+
+    if len > MAX_MESSAGE_LEN {
+        return Err(invalid(format!("message of {len} bytes is too long")));
+    }
+
+    let mut body = vec![0; len];
+
+    reader.read_exact(&mut body)?;
+
+    let body = String::from_utf8(body).map_err(|e| invalid(e.to_string()))?;
+
+    json::parse(&body).map_err(invalid)
+}
+
+pub fn uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    uri
+}
+
+pub fn path_from_uri(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?.as_bytes();
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut i = 0;
+
+    while i < path.len() {
+        if path[i] == b'%' {
+            let hex = std::str::from_utf8(path.get(i + 1..i + 3)?).ok()?;
+
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            bytes.push(path[i]);
+            i += 1;
+        }
+    }
+
+    Some(PathBuf::from(OsString::from_vec(bytes)))
+}
+
+pub fn position(text: &Rope, char_idx: usize) -> Value {
+    let char_idx = std::cmp::min(char_idx, text.len_chars());
+    let line = text.char_to_line(char_idx);
+    let character = text
+        .slice(text.line_to_char(line)..char_idx)
+        .chars()
+        .map(char::len_utf16)
+        .sum::<usize>();
+
+    Value::object([("line", line.into()), ("character", character.into())])
+}
+
+pub fn range(text: &Rope, range: Range<usize>) -> Value {
+    Value::object([
+        ("start", position(text, range.start)),
+        ("end", position(text, range.end)),
+    ])
+}
+
+pub fn char_index(text: &Rope, position: &Value) -> Option<usize> {
+    let line = position.get("line")?.as_u64()? as usize;
+    let character = position.get("character")?.as_u64()? as usize;
+
+    if line >= text.len_lines() {
+        return Some(text.len_chars());
+    }
+
+    let mut idx = text.line_to_char(line);
+    let mut units = 0;
+
+    for c in text.line(line).chars() {
+        if units >= character || c == '\n' || c == '\r' {
+            break;
+        }
+
+        units += c.len_utf16();
+        idx += 1;
+    }
+
+    Some(idx)
+}
+
+pub fn char_range(text: &Rope, range: &Value) -> Option<Range<usize>> {
+    let start = char_index(text, range.get("start")?)?;
+    let end = char_index(text, range.get("end")?)?;
+
+    Some(start..std::cmp::max(start, end))
+}
+
+pub fn workspace_edit(edit: &Value) -> Result<Vec<(PathBuf, Vec<Value>)>, String> {
+    let invalid = || "Invalid workspace edit".to_string();
+    let mut files = Vec::new();
+
+    if let Some(changes) = edit.get("documentChanges").and_then(Value::as_array) {
+        for change in changes {
+            if let Some(kind) = change.get("kind").and_then(Value::as_str) {
+                return Err(format!("Can't {kind} files from a workspace edit"));
+            }
+
+            let path = change
+                .get("textDocument")
+                .and_then(|document| document.get("uri"))
+                .and_then(Value::as_str)
+                .and_then(path_from_uri)
+                .ok_or_else(invalid)?;
+            let edits = change
+                .get("edits")
+                .and_then(Value::as_array)
+                .ok_or_else(invalid)?;
+
+            files.push((path, edits.to_vec()));
+        }
+    } else if let Some(Value::Object(changes)) = edit.get("changes") {
+        for (uri, edits) in changes {
+            let path = path_from_uri(uri).ok_or_else(invalid)?;
+            let edits = edits.as_array().ok_or_else(invalid)?;
+
+            files.push((path, edits.to_vec()));
+        }
+    }
+
+    Ok(files)
+}
+
+pub fn text_edits(text: &Rope, edits: &[Value]) -> Result<Vec<(Range<usize>, String)>, String> {
+    let mut changes = edits
+        .iter()
+        .map(|edit| {
+            let range = edit
+                .get("range")
+                .and_then(|range| char_range(text, range))?;
+            let new_text = edit.get("newText").and_then(Value::as_str)?;
+
+            Some((range, new_text.to_string()))
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or("Invalid text edit")?;
+
+    changes.sort_by_key(|(range, _)| range.start);
+
+    if changes
+        .windows(2)
+        .any(|pair| pair[0].0.end > pair[1].0.start)
+    {
+        return Err("Overlapping text edits".to_string());
+    }
+
+    changes.reverse();
+
+    Ok(changes)
+}
+
+pub fn apply(text: &mut Rope, changes: &[(Range<usize>, String)]) {
+    for (range, new_text) in changes {
+        text.remove(range.clone());
+        text.insert(range.start, new_text);
+    }
+}
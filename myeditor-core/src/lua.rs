@@ -0,0 +1,3231 @@
+use std::{cell::RefCell, collections::BTreeMap, fmt, ops::Bound, rc::Rc};
+
+const MAX_DEPTH: usize = 200;
+const MAX_STACK: usize = 1 << 20;
+const STEPS: u64 = 10_000_000;
+const MAX_CAPTURES: usize = 32;
+const MAX_MATCH_DEPTH: usize = 200;
+const MAX_STRING: usize = 1 << 26;
+const CAP_UNFINISHED: isize = -1;
+const CAP_POSITION: isize = -2;
+
+type Cell = Rc<RefCell<Value>>;
+
+type Native = fn(&mut Lua, &mut dyn Host, Vec<Value>) -> Result<Vec<Value>, Error>;
+
+#[derive(Clone, Default)]
+pub enum Value {
+    #[default]
+    Nil,
+    Bool(bool),
+    Number(f64),
+    Str(Rc<str>),
+    Table(Rc<RefCell<Table>>),
+    Function(Rc<Function>),
+}
+
+pub struct Function(Callable);
+
+enum Callable {
+    Lua(Rc<FuncBody>, Rc<Scope>),
+    Native(&'static str, Native),
+    Bound(Native, Vec<Value>),
+    Host(Rc<str>),
+}
+
+pub trait Host {
+    fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Vec<Value>, String>;
+}
+
+#[derive(Default)]
+pub struct Table {
+    entries: BTreeMap<Key, (Value, Value)>,
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Key {
+    Bool(bool),
+    Int(i64),
+    Float(u64),
+    Str(Rc<str>),
+    Ref(usize),
+}
+
+struct Scope {
+    vars: RefCell<Vec<(Rc<str>, Cell)>>,
+    parent: Option<Rc<Scope>>,
+}
+
+struct FuncBody {
+    params: Vec<Rc<str>>,
+    vararg: bool,
+    block: Vec<Stat>,
+    chunk: Rc<str>,
+}
+
+pub struct Chunk(Rc<FuncBody>);
+
+pub struct Lua {
+    globals: Rc<RefCell<Table>>,
+    string: Rc<RefCell<Table>>,
+    depth: usize,
+    steps: u64,
+    stack: usize,
+}
+
+struct Env<'a> {
+    scope: Rc<Scope>,
+    varargs: &'a [Value],
+}
+
+struct Error {
+    message: String,
+    located: bool,
+}
+
+enum Flow {
+    Normal,
+    Break,
+    Return(Vec<Value>),
+}
+
+struct Stat {
+    line: usize,
+    kind: StatKind,
+}
+
+enum StatKind {
+    Local(Vec<Rc<str>>, Vec<Expr>),
+    LocalFunction(Rc<str>, Rc<FuncBody>),
+    Assign(Vec<Expr>, Vec<Expr>),
+    Call(Expr),
+    Do(Vec<Stat>),
+    While(Expr, Vec<Stat>),
+    Repeat(Vec<Stat>, Expr),
+    If(Vec<(Expr, Vec<Stat>)>, Option<Vec<Stat>>),
+    NumericFor(Rc<str>, Expr, Expr, Option<Expr>, Vec<Stat>),
+    GenericFor(Vec<Rc<str>>, Vec<Expr>, Vec<Stat>),
+    Return(Vec<Expr>),
+    Break,
+}
+
+enum Expr {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    Str(Rc<str>),
+    Vararg,
+    Function(Rc<FuncBody>),
+    Table(Vec<(Option<Expr>, Expr)>),
+    Name(Rc<str>),
+    Index(Box<Expr>, Box<Expr>),
+    Call(Box<Expr>, Vec<Expr>),
+    Method(Box<Expr>, Rc<str>, Vec<Expr>),
+    Paren(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Unary(UnOp, Box<Expr>),
+}
+
+#[derive(Clone, Copy)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    IDiv,
+    Mod,
+    Pow,
+    Concat,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Copy)]
+enum UnOp {
+    Neg,
+    Not,
+    Len,
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Name(Rc<str>),
+    Number(f64),
+    Str(Rc<str>),
+    Sym(&'static str),
+    Eof,
+}
+
+const KEYWORDS: [&str; 21] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "if", "in", "local",
+    "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+const SYMBOLS: [&str; 27] = [
+    "...", "..", "==", "~=", "<=", ">=", "//", "+", "-", "*", "/", "%", "^", "#", "<", ">", "=",
+    "(", ")", "{", "}", "[", "]", ";", ":", ",", ".",
+];
+
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Str(s) => write!(f, "{s:?}"),
+            other => f.write_str(&tostring(other)),
+        }
+    }
+}
+
+impl fmt::Debug for Lua {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lua").finish_non_exhaustive()
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Self::Number(value as f64)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::Str(value.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::Str(value.into())
+    }
+}
+
+impl From<Table> for Value {
+    fn from(table: Table) -> Self {
+        Self::Table(Rc::new(RefCell::new(table)))
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            located: false,
+        }
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+impl Value {
+    pub fn is_nil(&self) -> bool {
+        matches!(self, Self::Nil)
+    }
+
+    pub fn is_function(&self) -> bool {
+        matches!(self, Self::Function(_))
+    }
+
+    pub fn to_number(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            Self::Str(s) => parse_number(s),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> Option<String> {
+        match self {
+            Self::Str(s) => Some(s.to_string()),
+            Self::Number(n) => Some(format_number(*n)),
+            _ => None,
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        !matches!(self, Self::Nil | Self::Bool(false))
+    }
+}
+
+impl Key {
+    fn of(value: &Value) -> Result<Self, String> {
+        Ok(match value {
+            Value::Nil => return Err("table index is nil".to_string()),
+            Value::Bool(b) => Self::Bool(*b),
+            Value::Number(n) if n.is_nan() => return Err("table index is NaN".to_string()),
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 9.2e18 => Self::Int(*n as i64),
+            Value::Number(n) => Self::Float(n.to_bits()),
+            Value::Str(s) => Self::Str(s.clone()),
+            Value::Table(t) => Self::Ref(Rc::as_ptr(t) as *const () as usize),
+            Value::Function(f) => Self::Ref(Rc::as_ptr(f) as *const () as usize),
+        })
+    }
+}
+
+impl Table {
+    pub fn get(&self, key: &Value) -> Value {
+        Key::of(key)
+            .ok()
+            .and_then(|key| self.entries.get(&key))
+            .map(|(_, value)| value.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set(&mut self, key: Value, value: Value) -> Result<(), String> {
+        let k = Key::of(&key)?;
+
+        if value.is_nil() {
+            self.entries.remove(&k);
+        } else {
+            self.entries.insert(k, (key, value));
+        }
+
+        Ok(())
+    }
+
+    pub fn set_field(&mut self, name: &str, value: Value) {
+        self.entries
+            .insert(Key::Str(name.into()), (name.into(), value));
+    }
+
+    fn border(&self) -> usize {
+        let mut n = 0;
+
+        while self.entries.contains_key(&Key::Int(n as i64 + 1)) {
+            n += 1;
+        }
+
+        n
+    }
+
+    fn next(&self, key: &Value) -> Result<Value, String> {
+        let entry = match key {
+            Value::Nil => self.entries.values().next(),
+            key => self
+                .entries
+                .range((Bound::Excluded(Key::of(key)?), Bound::Unbounded))
+                .map(|(_, entry)| entry)
+                .next(),
+        };
+
+        Ok(match entry {
+            Some((key, value)) => multi(vec![key.clone(), value.clone()]),
+            None => Value::Nil,
+        })
+    }
+}
+
+fn multi(values: Vec<Value>) -> Value {
+    let mut table = Table::default();
+
+    for (i, value) in values.into_iter().enumerate() {
+        let _ = table.set((i + 1).into(), value);
+    }
+
+    table.into()
+}
+
+impl Scope {
+    fn new(parent: Option<Rc<Scope>>) -> Rc<Self> {
+        Rc::new(Self {
+            vars: RefCell::new(Vec::new()),
+            parent,
+        })
+    }
+
+    fn child(parent: &Rc<Scope>) -> Rc<Self> {
+        Self::new(Some(parent.clone()))
+    }
+
+    fn declare(&self, name: Rc<str>, value: Value) {
+        self.vars
+            .borrow_mut()
+            .push((name, Rc::new(RefCell::new(value))));
+    }
+
+    fn lookup(&self, name: &str) -> Option<Cell> {
+        let mut scope = Some(self);
+
+        while let Some(current) = scope {
+            let vars = current.vars.borrow();
+
+            if let Some((_, cell)) = vars.iter().rev().find(|(var, _)| &**var == name) {
+                return Some(cell.clone());
+            }
+
+            scope = current.parent.as_deref();
+        }
+
+        None
+    }
+}
+
+impl Default for Lua {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lua {
+    pub fn new() -> Self {
+        let mut lua = Self {
+            globals: Rc::default(),
+            string: Rc::default(),
+            depth: 0,
+            steps: STEPS,
+            stack: 0,
+        };
+
+        for (name, native) in [
+            ("assert", lib_assert as Native),
+            ("error", lib_error),
+            ("ipairs", lib_ipairs),
+            ("next", lib_next),
+            ("pairs", lib_pairs),
+            ("pcall", lib_pcall),
+            ("select", lib_select),
+            ("tonumber", lib_tonumber),
+            ("tostring", lib_tostring),
+            ("type", lib_type),
+            ("unpack", lib_unpack),
+        ] {
+            lua.set_global(name, native_function(name, native));
+        }
+
+        let string = lua.library(
+            "string",
+            &[
+                ("byte", string_byte),
+                ("char", string_char),
+                ("find", string_find),
+                ("format", string_format),
+                ("gmatch", string_gmatch),
+                ("gsub", string_gsub),
+                ("len", string_len),
+                ("lower", string_lower),
+                ("match", string_match),
+                ("rep", string_rep),
+                ("reverse", string_reverse),
+                ("sub", string_sub),
+                ("upper", string_upper),
+            ],
+        );
+
+        lua.string = string;
+        lua.library(
+            "table",
+            &[
+                ("concat", table_concat),
+                ("insert", table_insert),
+                ("remove", table_remove),
+                ("sort", table_sort),
+                ("unpack", lib_unpack),
+            ],
+        );
+
+        let math = lua.library(
+            "math",
+            &[
+                ("abs", math_abs),
+                ("ceil", math_ceil),
+                ("floor", math_floor),
+                ("fmod", math_fmod),
+                ("max", math_max),
+                ("min", math_min),
+                ("sqrt", math_sqrt),
+            ],
+        );
+
+        math.borrow_mut()
+            .set_field("huge", Value::Number(f64::INFINITY));
+        math.borrow_mut()
+            .set_field("pi", Value::Number(std::f64::consts::PI));
+
+        lua
+    }
+
+    fn library(&mut self, name: &str, functions: &[(&'static str, Native)]) -> Rc<RefCell<Table>> {
+        let mut table = Table::default();
+
+        for &(function, native) in functions {
+            table.set_field(function, native_function(function, native));
+        }
+
+        let table = Rc::new(RefCell::new(table));
+
+        self.set_global(name, Value::Table(table.clone()));
+
+        table
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.globals.borrow_mut().set_field(name, value);
+    }
+
+    pub fn global(&self, name: &str) -> Value {
+        self.globals.borrow().get(&name.into())
+    }
+
+    pub fn host_function(name: &str) -> Value {
+        Value::Function(Rc::new(Function(Callable::Host(name.into()))))
+    }
+
+    pub fn load(source: &str, chunk: &str) -> Result<Chunk, String> {
+        let tokens = lex(source, chunk)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            chunk: chunk.into(),
+            loops: 0,
+            depth: 0,
+        };
+        let block = parser.block()?;
+
+        if parser.peek() != &Token::Eof {
+            return Err(parser.error("'<eof>' expected"));
+        }
+
+        Ok(Chunk(Rc::new(FuncBody {
+            params: Vec::new(),
+            vararg: true,
+            block,
+            chunk: chunk.into(),
+        })))
+    }
+
+    pub fn run(&mut self, chunk: &Chunk, host: &mut dyn Host) -> Result<Vec<Value>, String> {
+        let function = Value::Function(Rc::new(Function(Callable::Lua(
+            chunk.0.clone(),
+            Scope::new(None),
+        ))));
+
+        self.call(&function, Vec::new(), host)
+    }
+
+    pub fn call(
+        &mut self,
+        function: &Value,
+        args: Vec<Value>,
+        host: &mut dyn Host,
+    ) -> Result<Vec<Value>, String> {
+        self.steps = STEPS;
+        self.depth = 0;
+        self.stack = stack_address();
+        self.call_value(function.clone(), args, host)
+            .map_err(|e| e.message)
+    }
+
+    fn step(&mut self) -> Result<(), Error> {
+        if self.steps == 0 {
+            return Err("script ran too long".into());
+        }
+
+        if self.stack.abs_diff(stack_address()) > MAX_STACK {
+            return Err("stack overflow".into());
+        }
+
+        self.steps -= 1;
+
+        Ok(())
+    }
+
+    fn call_value(
+        &mut self,
+        function: Value,
+        args: Vec<Value>,
+        host: &mut dyn Host,
+    ) -> Result<Vec<Value>, Error> {
+        let Value::Function(function) = function else {
+            return Err(format!("attempt to call a {} value", type_name(&function)).into());
+        };
+
+        match &function.0 {
+            Callable::Native(_, native) => Ok(native(self, host, args)?),
+            Callable::Bound(native, bound) => {
+                let args = bound.iter().cloned().chain(args).collect();
+
+                Ok(native(self, host, args)?)
+            }
+            Callable::Host(name) => Ok(host.call(name, args)?),
+            Callable::Lua(body, scope) => {
+                if self.depth >= MAX_DEPTH {
+                    return Err("stack overflow".into());
+                }
+
+                let scope = Scope::child(scope);
+                let mut args = args.into_iter();
+
+                for param in &body.params {
+                    scope.declare(param.clone(), args.next().unwrap_or_default());
+                }
+
+                let varargs: Vec<Value> = if body.vararg {
+                    args.collect()
+                } else {
+                    Vec::new()
+                };
+                let env = Env {
+                    scope,
+                    varargs: &varargs,
+                };
+
+                self.depth += 1;
+
+                let result = self.exec_block(&body.block, &env, &body.chunk, host);
+
+                self.depth -= 1;
+
+                match result? {
+                    Flow::Return(values) => Ok(values),
+                    _ => Ok(Vec::new()),
+                }
+            }
+        }
+    }
+
+    fn exec_block(
+        &mut self,
+        block: &[Stat],
+        env: &Env,
+        chunk: &str,
+        host: &mut dyn Host,
+    ) -> Result<Flow, Error> {
+        for stat in block {
+            self.step()?;
+
+            let flow = self.exec(stat, env, chunk, host).map_err(|e| match e {
+                Error {
+                    located: false,
+                    message,
+                } => Error {
+                    message: format!("{chunk}:{}: {message}", stat.line),
+                    located: true,
+                },
+                e => e,
+            })?;
+
+            if !matches!(flow, Flow::Normal) {
+                return Ok(flow);
+            }
+        }
+
+        Ok(Flow::Normal)
+    }
+
+    fn exec_scoped(
+        &mut self,
+        block: &[Stat],
+        env: &Env,
+        chunk: &str,
+        host: &mut dyn Host,
+    ) -> Result<Flow, Error> {
+        let inner = Env {
+            scope: Scope::child(&env.scope),
+            varargs: env.varargs,
+        };
+
+        self.exec_block(block, &inner, chunk, host)
+    }
+
+    fn exec(
+        &mut self,
+        stat: &Stat,
+        env: &Env,
+        chunk: &str,
+        host: &mut dyn Host,
+    ) -> Result<Flow, Error> {
+        match &stat.kind {
+            StatKind::Local(names, exprs) => {
+                let mut values = self.eval_multi(exprs, env, host)?.into_iter();
+
+                for name in names {
+                    env.scope
+                        .declare(name.clone(), values.next().unwrap_or_default());
+                }
+            }
+            StatKind::LocalFunction(name, body) => {
+                env.scope.declare(name.clone(), Value::Nil);
+
+                let function = Value::Function(Rc::new(Function(Callable::Lua(
+                    body.clone(),
+                    env.scope.clone(),
+                ))));
+
+                if let Some(cell) = env.scope.lookup(name) {
+                    *cell.borrow_mut() = function;
+                }
+            }
+            StatKind::Assign(targets, exprs) => {
+                let mut values = self.eval_multi(exprs, env, host)?.into_iter();
+
+                for target in targets {
+                    let value = values.next().unwrap_or_default();
+
+                    match target {
+                        Expr::Name(name) => match env.scope.lookup(name) {
+                            Some(cell) => *cell.borrow_mut() = value,
+                            None => self.globals.borrow_mut().set(name.as_ref().into(), value)?,
+                        },
+                        Expr::Index(object, key) => {
+                            let object = self.eval(object, env, host)?;
+                            let key = self.eval(key, env, host)?;
+
+                            match object {
+                                Value::Table(table) => table.borrow_mut().set(key, value)?,
+                                other => {
+                                    return Err(format!(
+                                        "attempt to index a {} value",
+                                        type_name(&other)
+                                    )
+                                    .into())
+                                }
+                            }
+                        }
+                        _ => return Err("cannot assign to this expression".into()),
+                    }
+                }
+            }
+            StatKind::Call(expr) => {
+                self.eval_call(expr, env, host)?;
+            }
+            StatKind::Do(block) => return self.exec_scoped(block, env, chunk, host),
+            StatKind::While(condition, block) => loop {
+                self.step()?;
+
+                if !self.eval(condition, env, host)?.truthy() {
+                    break;
+                }
+
+                match self.exec_scoped(block, env, chunk, host)? {
+                    Flow::Break => break,
+                    Flow::Return(values) => return Ok(Flow::Return(values)),
+                    Flow::Normal => {}
+                }
+            },
+            StatKind::Repeat(block, condition) => loop {
+                self.step()?;
+
+                let inner = Env {
+                    scope: Scope::child(&env.scope),
+                    varargs: env.varargs,
+                };
+
+                match self.exec_block(block, &inner, chunk, host)? {
+                    Flow::Break => break,
+                    Flow::Return(values) => return Ok(Flow::Return(values)),
+                    Flow::Normal => {}
+                }
+
+                if self.eval(condition, &inner, host)?.truthy() {
+                    break;
+                }
+            },
+            StatKind::If(clauses, otherwise) => {
+                for (condition, block) in clauses {
+                    if self.eval(condition, env, host)?.truthy() {
+                        return self.exec_scoped(block, env, chunk, host);
+                    }
+                }
+
+                if let Some(block) = otherwise {
+                    return self.exec_scoped(block, env, chunk, host);
+                }
+            }
+            StatKind::NumericFor(name, start, limit, step, block) => {
+                let number = |value: Value, what: &str| {
+                    value
+                        .to_number()
+                        .ok_or_else(|| Error::from(format!("'for' {what} must be a number")))
+                };
+
+                let mut i = number(self.eval(start, env, host)?, "initial value")?;
+                let limit = number(self.eval(limit, env, host)?, "limit")?;
+                let step = match step {
+                    Some(step) => number(self.eval(step, env, host)?, "step")?,
+                    None => 1.0,
+                };
+
+                if step == 0.0 {
+                    return Err("'for' step is zero".into());
+                }
+
+                while (step > 0.0 && i <= limit) || (step < 0.0 && i >= limit) {
+                    self.step()?;
+
+                    let inner = Env {
+                        scope: Scope::child(&env.scope),
+                        varargs: env.varargs,
+                    };
+
+                    inner.scope.declare(name.clone(), Value::Number(i));
+
+                    match self.exec_block(block, &inner, chunk, host)? {
+                        Flow::Break => break,
+                        Flow::Return(values) => return Ok(Flow::Return(values)),
+                        Flow::Normal => {}
+                    }
+
+                    i += step;
+                }
+            }
+            StatKind::GenericFor(names, exprs, block) => {
+                let mut values = self.eval_multi(exprs, env, host)?.into_iter();
+                let function = values.next().unwrap_or_default();
+                let state = values.next().unwrap_or_default();
+                let mut control = values.next().unwrap_or_default();
+
+                loop {
+                    self.step()?;
+
+                    let mut results = self
+                        .call_value(function.clone(), vec![state.clone(), control], host)?
+                        .into_iter();
+                    let first = results.next().unwrap_or_default();
+
+                    if first.is_nil() {
+                        break;
+                    }
+
+                    control = first.clone();
+
+                    let inner = Env {
+                        scope: Scope::child(&env.scope),
+                        varargs: env.varargs,
+                    };
+                    let mut results = std::iter::once(first).chain(results);
+
+                    for name in names {
+                        inner
+                            .scope
+                            .declare(name.clone(), results.next().unwrap_or_default());
+                    }
+
+                    match self.exec_block(block, &inner, chunk, host)? {
+                        Flow::Break => break,
+                        Flow::Return(values) => return Ok(Flow::Return(values)),
+                        Flow::Normal => {}
+                    }
+                }
+            }
+            StatKind::Return(exprs) => return Ok(Flow::Return(self.eval_multi(exprs, env, host)?)),
+            StatKind::Break => return Ok(Flow::Break),
+        }
+
+        Ok(Flow::Normal)
+    }
+
+    fn eval_multi(
+        &mut self,
+        exprs: &[Expr],
+        env: &Env,
+        host: &mut dyn Host,
+    ) -> Result<Vec<Value>, Error> {
+        let mut values = Vec::with_capacity(exprs.len());
+
+        for (i, expr) in exprs.iter().enumerate() {
+            match expr {
+                Expr::Call(..) | Expr::Method(..) if i == exprs.len() - 1 => {
+                    values.extend(self.eval_call(expr, env, host)?)
+                }
+                Expr::Vararg if i == exprs.len() - 1 => values.extend_from_slice(env.varargs),
+                expr => values.push(self.eval(expr, env, host)?),
+            }
+        }
+
+        Ok(values)
+    }
+
+    fn eval_call(
+        &mut self,
+        expr: &Expr,
+        env: &Env,
+        host: &mut dyn Host,
+    ) -> Result<Vec<Value>, Error> {
+        let (function, args) = match expr {
+            Expr::Call(function, args) => {
+                let value = self.eval(function, env, host)?;
+
+                (value, self.eval_multi(args, env, host)?)
+            }
+            Expr::Method(object, name, args) => {
+                let object = self.eval(object, env, host)?;
+                let function = self.index(&object, &name.as_ref().into())?;
+                let args = std::iter::once(object)
+                    .chain(self.eval_multi(args, env, host)?)
+                    .collect();
+
+                (function, args)
+            }
+            _ => return Ok(vec![self.eval(expr, env, host)?]),
+        };
+
+        if !function.is_function() {
+            let what = match expr {
+                Expr::Call(function, _) => match &**function {
+                    Expr::Name(name) if env.scope.lookup(name).is_some() => {
+                        format!(" (local '{name}')")
+                    }
+                    Expr::Name(name) => format!(" (global '{name}')"),
+                    Expr::Index(_, key) => match &**key {
+                        Expr::Str(key) => format!(" (field '{key}')"),
+                        _ => String::new(),
+                    },
+                    _ => String::new(),
+                },
+                Expr::Method(_, name, _) => format!(" (method '{name}')"),
+                _ => String::new(),
+            };
+
+            return Err(format!("attempt to call a {} value{what}", type_name(&function)).into());
+        }
+
+        self.call_value(function, args, host)
+    }
+
+    fn index(&self, object: &Value, key: &Value) -> Result<Value, Error> {
+        match object {
+            Value::Table(table) => Ok(table.borrow().get(key)),
+            Value::Str(_) => Ok(self.string.borrow().get(key)),
+            other => Err(format!("attempt to index a {} value", type_name(other)).into()),
+        }
+    }
+
+    fn eval(&mut self, expr: &Expr, env: &Env, host: &mut dyn Host) -> Result<Value, Error> {
+        if self.stack.abs_diff(stack_address()) > MAX_STACK {
+            return Err("stack overflow".into());
+        }
+
+        self.eval_expr(expr, env, host)
+    }
+
+    fn eval_expr(&mut self, expr: &Expr, env: &Env, host: &mut dyn Host) -> Result<Value, Error> {
+        Ok(match expr {
+            Expr::Nil => Value::Nil,
+            Expr::Bool(b) => Value::Bool(*b),
+            Expr::Number(n) => Value::Number(*n),
+            Expr::Str(s) => Value::Str(s.clone()),
+            Expr::Vararg => env.varargs.first().cloned().unwrap_or_default(),
+            Expr::Function(body) => Value::Function(Rc::new(Function(Callable::Lua(
+                body.clone(),
+                env.scope.clone(),
+            )))),
+            Expr::Table(items) => {
+                let mut table = Table::default();
+                let mut n = 1;
+
+                for (i, (key, value)) in items.iter().enumerate() {
+                    match key {
+                        Some(key) => {
+                            let key = self.eval(key, env, host)?;
+                            let value = self.eval(value, env, host)?;
+
+                            table.set(key, value)?;
+                        }
+                        None if i == items.len() - 1 => {
+                            for value in self.eval_multi(std::slice::from_ref(value), env, host)? {
+                                table.set(n.into(), value)?;
+                                n += 1;
+                            }
+                        }
+                        None => {
+                            let value = self.eval(value, env, host)?;
+
+                            table.set(n.into(), value)?;
+                            n += 1;
+                        }
+                    }
+                }
+
+                table.into()
+            }
+            Expr::Name(name) => match env.scope.lookup(name) {
+                Some(cell) => cell.borrow().clone(),
+                None => self.global(name),
+            },
+            Expr::Index(object, key) => {
+                let object = self.eval(object, env, host)?;
+                let key = self.eval(key, env, host)?;
+
+                self.index(&object, &key)?
+            }
+            Expr::Call(..) | Expr::Method(..) => self
+                .eval_call(expr, env, host)?
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+            Expr::Paren(expr) => self.eval(expr, env, host)?,
+            Expr::And(a, b) => {
+                let a = self.eval(a, env, host)?;
+
+                if a.truthy() {
+                    self.eval(b, env, host)?
+                } else {
+                    a
+                }
+            }
+            Expr::Or(a, b) => {
+                let a = self.eval(a, env, host)?;
+
+                if a.truthy() {
+                    a
+                } else {
+                    self.eval(b, env, host)?
+                }
+            }
+            Expr::Binary(op, a, b) => {
+                let a = self.eval(a, env, host)?;
+                let b = self.eval(b, env, host)?;
+
+                binary(*op, a, b)?
+            }
+            Expr::Unary(op, a) => {
+                let a = self.eval(a, env, host)?;
+
+                match op {
+                    UnOp::Not => Value::Bool(!a.truthy()),
+                    UnOp::Neg => Value::Number(-arith_operand(&a)?),
+                    UnOp::Len => match &a {
+                        Value::Str(s) => s.len().into(),
+                        Value::Table(t) => t.borrow().border().into(),
+                        other => {
+                            return Err(format!(
+                                "attempt to get length of a {} value",
+                                type_name(other)
+                            )
+                            .into())
+                        }
+                    },
+                }
+            }
+        })
+    }
+}
+
+fn stack_address() -> usize {
+    let marker = 0u8;
+
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+fn native_function(name: &'static str, native: Native) -> Value {
+    Value::Function(Rc::new(Function(Callable::Native(name, native))))
+}
+
+fn arith_operand(value: &Value) -> Result<f64, String> {
+    value.to_number().ok_or_else(|| {
+        format!(
+            "attempt to perform arithmetic on a {} value",
+            type_name(value)
+        )
+    })
+}
+
+fn binary(op: BinOp, a: Value, b: Value) -> Result<Value, String> {
+    let arith =
+        |f: fn(f64, f64) -> f64| Ok(Value::Number(f(arith_operand(&a)?, arith_operand(&b)?)));
+
+    match op {
+        BinOp::Add => arith(|a, b| a + b),
+        BinOp::Sub => arith(|a, b| a - b),
+        BinOp::Mul => arith(|a, b| a * b),
+        BinOp::Div => arith(|a, b| a / b),
+        BinOp::IDiv => arith(|a, b| (a / b).floor()),
+        BinOp::Mod => arith(|a, b| a - (a / b).floor() * b),
+        BinOp::Pow => arith(f64::powf),
+        BinOp::Concat => match (a.to_str(), b.to_str()) {
+            (Some(a), Some(b)) if matches!(a.len().checked_add(b.len()), Some(len) if len <= MAX_STRING) => {
+                Ok((a + &b).into())
+            }
+            (Some(_), Some(_)) => Err("string length overflow".to_string()),
+            (None, _) => Err(format!("attempt to concatenate a {} value", type_name(&a))),
+            (_, None) => Err(format!("attempt to concatenate a {} value", type_name(&b))),
+        },
+        BinOp::Eq => Ok(Value::Bool(equal(&a, &b))),
+        BinOp::Ne => Ok(Value::Bool(!equal(&a, &b))),
+        BinOp::Lt => less(&a, &b, false).map(Value::Bool),
+        BinOp::Le => less(&a, &b, true).map(Value::Bool),
+        BinOp::Gt => less(&b, &a, false).map(Value::Bool),
+        BinOp::Ge => less(&b, &a, true).map(Value::Bool),
+    }
+}
+
+fn equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Nil, Value::Nil) => true,
+        (Value::Bool(a), Value::Bool(b)) => a == b,
+        (Value::Number(a), Value::Number(b)) => a == b,
+        (Value::Str(a), Value::Str(b)) => a == b,
+        (Value::Table(a), Value::Table(b)) => Rc::ptr_eq(a, b),
+        (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+        _ => false,
+    }
+}
+
+fn less(a: &Value, b: &Value, or_equal: bool) -> Result<bool, String> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => Ok(if or_equal { a <= b } else { a < b }),
+        (Value::Str(a), Value::Str(b)) => Ok(if or_equal { a <= b } else { a < b }),
+        _ => Err(format!(
+            "attempt to compare {} with {}",
+            type_name(a),
+            type_name(b)
+        )),
+    }
+}
+
+pub fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Nil => "nil",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::Str(_) => "string",
+        Value::Table(_) => "table",
+        Value::Function(_) => "function",
+    }
+}
+
+pub fn tostring(value: &Value) -> String {
+    match value {
+        Value::Nil => "nil".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => format_number(*n),
+        Value::Str(s) => s.to_string(),
+        Value::Table(t) => format!("table: {:p}", Rc::as_ptr(t)),
+        Value::Function(f) => match &f.0 {
+            Callable::Native(name, _) => format!("builtin: {name}"),
+            _ => format!("function: {:p}", Rc::as_ptr(f)),
+        },
+    }
+}
+
+fn format_number(n: f64) -> String {
+    if n.is_nan() {
+        "nan".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else if n.fract() == 0.0 && n.abs() < 1e16 {
+        format!("{}", n as i64)
+    } else {
+        format!("{n}")
+    }
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+
+    if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        let value = u64::from_str_radix(hex, 16).ok()? as f64;
+
+        return Some(if negative { -value } else { value });
+    }
+
+    if text.is_empty()
+        || !text
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+    {
+        return None;
+    }
+
+    text.parse().ok()
+}
+
+fn lex(source: &str, chunk: &str) -> Result<Vec<(Token, usize)>, String> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    let error = |line: usize, message: String| format!("{chunk}:{line}: {message}");
+
+    if bytes.starts_with(b"#") {
+        while i < bytes.len() && bytes[i] != b'\n' {
+            i += 1;
+        }
+    }
+
+    while i < bytes.len() {
+        let c = bytes[i];
+
+        if c == b'\n' {
+            line += 1;
+            i += 1;
+
+            continue;
+        }
+
+        if c.is_ascii_whitespace() {
+            i += 1;
+
+            continue;
+        }
+
+        if bytes[i..].starts_with(b"--") {
+            i += 2;
+
+            if let Some((_, end, lines)) = long_bracket(bytes, i) {
+                i = end;
+                line += lines;
+            } else {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+
+            continue;
+        }
+
+        let start = i;
+
+        if c.is_ascii_digit() || (c == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)) {
+            if bytes[i..].starts_with(b"0x") || bytes[i..].starts_with(b"0X") {
+                i += 2;
+
+                while i < bytes.len() && bytes[i].is_ascii_hexdigit() {
+                    i += 1;
+                }
+            } else {
+                while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                    i += 1;
+                }
+
+                if i < bytes.len() && matches!(bytes[i], b'e' | b'E') {
+                    i += 1;
+
+                    if i < bytes.len() && matches!(bytes[i], b'+' | b'-') {
+                        i += 1;
+                    }
+
+                    while i < bytes.len() && bytes[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+
+            let text = &source[start..i];
+            let number = parse_number(text)
+                .ok_or_else(|| error(line, format!("malformed number near '{text}'")))?;
+
+            tokens.push((Token::Number(number), line));
+
+            continue;
+        }
+
+        if c.is_ascii_alphabetic() || c == b'_' {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+
+            let word = &source[start..i];
+            let token = match KEYWORDS.iter().find(|&&keyword| keyword == word) {
+                Some(keyword) => Token::Sym(keyword),
+                None => Token::Name(word.into()),
+            };
+
+            tokens.push((token, line));
+
+            continue;
+        }
+
+        if c == b'"' || c == b'\'' {
+            let (text, end) = short_string(bytes, i + 1, c).map_err(|e| error(line, e))?;
+
+            tokens.push((Token::Str(text.into()), line));
+            i = end;
+
+            continue;
+        }
+
+        if c == b'[' {
+            if let Some((text, end, lines)) = long_bracket(bytes, i) {
+                tokens.push((Token::Str(text.into()), line));
+                line += lines;
+                i = end;
+
+                continue;
+            }
+        }
+
+        match SYMBOLS
+            .iter()
+            .find(|symbol| bytes[i..].starts_with(symbol.as_bytes()))
+        {
+            Some(symbol) => {
+                tokens.push((Token::Sym(symbol), line));
+                i += symbol.len();
+            }
+            None => {
+                let c = source[i..].chars().next().unwrap_or_default();
+
+                return Err(error(line, format!("unexpected symbol near '{c}'")));
+            }
+        }
+    }
+
+    tokens.push((Token::Eof, line));
+
+    Ok(tokens)
+}
+
+fn long_bracket(bytes: &[u8], start: usize) -> Option<(String, usize, usize)> {
+    if bytes.get(start) != Some(&b'[') {
+        return None;
+    }
+
+    let level = bytes[start + 1..]
+        .iter()
+        .take_while(|&&b| b == b'=')
+        .count();
+
+    if bytes.get(start + 1 + level) != Some(&b'[') {
+        return None;
+    }
+
+    let mut content = start + level + 2;
+
+    if bytes[content..].starts_with(b"\r\n") {
+        content += 2;
+    } else if bytes.get(content) == Some(&b'\n') {
+        content += 1;
+    }
+
+    let close: Vec<u8> = std::iter::once(b']')
+        .chain(std::iter::repeat_n(b'=', level))
+        .chain(std::iter::once(b']'))
+        .collect();
+    let end = content
+        + bytes[content..]
+            .windows(close.len())
+            .position(|w| w == close)?;
+    let text = String::from_utf8_lossy(&bytes[content..end]).to_string();
+    let lines = bytes[start..end].iter().filter(|&&b| b == b'\n').count();
+
+    Some((text, end + close.len(), lines))
+}
+
+fn short_string(bytes: &[u8], mut i: usize, quote: u8) -> Result<(String, usize), String> {
+    let mut text = Vec::new();
+
+    loop {
+        let Some(&c) = bytes.get(i) else {
+            return Err("unfinished string".to_string());
+        };
+
+        i += 1;
+
+        match c {
+            b'\n' => return Err("unfinished string".to_string()),
+            c if c == quote => break,
+            b'\\' => {
+                let Some(&escape) = bytes.get(i) else {
+                    return Err("unfinished string".to_string());
+                };
+
+                i += 1;
+
+                match escape {
+                    b'a' => text.push(7),
+                    b'b' => text.push(8),
+                    b'f' => text.push(12),
+                    b'n' | b'\n' => text.push(b'\n'),
+                    b'r' => text.push(b'\r'),
+                    b't' => text.push(b'\t'),
+                    b'v' => text.push(11),
+                    b'\\' | b'"' | b'\'' => text.push(escape),
+                    b'z' => {
+                        while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+                            i += 1;
+                        }
+                    }
+                    b'x' => {
+                        let hex = bytes
+                            .get(i..i + 2)
+                            .and_then(|hex| std::str::from_utf8(hex).ok())
+                            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                            .ok_or("hexadecimal digit expected")?;
+
+                        text.push(hex);
+                        i += 2;
+                    }
+                    b'u' => {
+                        let end = bytes[i..]
+                            .iter()
+                            .position(|&b| b == b'}')
+                            .filter(|_| bytes.get(i) == Some(&b'{'))
+                            .ok_or("missing '{' or '}' in \\u{xxxx}")?;
+                        let c = std::str::from_utf8(&bytes[i + 1..i + end])
+                            .ok()
+                            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                            .and_then(char::from_u32)
+                            .ok_or("UTF-8 value too large")?;
+
+                        text.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                        i += end + 1;
+                    }
+                    b'0'..=b'9' => {
+                        let digits = bytes[i - 1..]
+                            .iter()
+                            .take(3)
+                            .take_while(|b| b.is_ascii_digit())
+                            .count();
+                        let value: u32 = std::str::from_utf8(&bytes[i - 1..i - 1 + digits])
+                            .unwrap_or_default()
+                            .parse()
+                            .unwrap_or(256);
+
+                        text.push(u8::try_from(value).map_err(|_| "decimal escape too large")?);
+                        i += digits - 1;
+                    }
+                    _ => return Err("invalid escape sequence".to_string()),
+                }
+            }
+            c => text.push(c),
+        }
+    }
+
+    Ok((String::from_utf8_lossy(&text).to_string(), i))
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    chunk: Rc<str>,
+    loops: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn line(&self) -> usize {
+        self.tokens[self.pos].1
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].0.clone();
+
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+
+        token
+    }
+
+    fn check(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Token::Sym(s) if *s == symbol)
+    }
+
+    fn accept(&mut self, symbol: &str) -> bool {
+        if self.check(symbol) {
+            self.advance();
+
+            return true;
+        }
+
+        false
+    }
+
+    fn error(&self, message: &str) -> String {
+        let near = match self.peek() {
+            Token::Name(name) => format!("'{name}'"),
+            Token::Number(n) => format!("'{}'", format_number(*n)),
+            Token::Str(s) => format!("'{s}'"),
+            Token::Sym(s) => format!("'{s}'"),
+            Token::Eof => "<eof>".to_string(),
+        };
+
+        format!("{}:{}: {message} near {near}", self.chunk, self.line())
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), String> {
+        if self.accept(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{symbol}' expected")))
+        }
+    }
+
+    fn name(&mut self) -> Result<Rc<str>, String> {
+        match self.peek().clone() {
+            Token::Name(name) => {
+                self.advance();
+
+                Ok(name)
+            }
+            _ => Err(self.error("<name> expected")),
+        }
+    }
+
+    fn block_end(&self) -> bool {
+        matches!(
+            self.peek(),
+            Token::Eof | Token::Sym("end" | "else" | "elseif" | "until")
+        )
+    }
+
+    fn block(&mut self) -> Result<Vec<Stat>, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("chunk has too many syntax levels"));
+        }
+
+        self.depth += 1;
+
+        let block = self.statements();
+
+        self.depth -= 1;
+
+        block
+    }
+
+    fn statements(&mut self) -> Result<Vec<Stat>, String> {
+        let mut block = Vec::new();
+
+        while !self.block_end() {
+            let line = self.line();
+
+            if self.accept("return") {
+                let exprs = if self.block_end() || self.check(";") {
+                    Vec::new()
+                } else {
+                    self.expr_list()?
+                };
+
+                self.accept(";");
+
+                if !self.block_end() {
+                    return Err(self.error("'<eof>' expected"));
+                }
+
+                block.push(Stat {
+                    line,
+                    kind: StatKind::Return(exprs),
+                });
+
+                break;
+            }
+
+            if let Some(kind) = self.statement()? {
+                block.push(Stat { line, kind });
+            }
+        }
+
+        Ok(block)
+    }
+
+    fn loop_body(&mut self) -> Result<Vec<Stat>, String> {
+        self.loops += 1;
+
+        let block = self.block();
+
+        self.loops -= 1;
+
+        block
+    }
+
+    fn statement(&mut self) -> Result<Option<StatKind>, String> {
+        let Token::Sym(symbol) = self.peek().clone() else {
+            return self.expr_statement().map(Some);
+        };
+
+        Ok(Some(match symbol {
+            ";" => {
+                self.advance();
+
+                return Ok(None);
+            }
+            "if" => {
+                self.advance();
+
+                let mut clauses = Vec::new();
+                let mut otherwise = None;
+
+                loop {
+                    let condition = self.expr()?;
+
+                    self.expect("then")?;
+                    clauses.push((condition, self.block()?));
+
+                    if self.accept("elseif") {
+                        continue;
+                    }
+
+                    if self.accept("else") {
+                        otherwise = Some(self.block()?);
+                    }
+
+                    self.expect("end")?;
+
+                    break;
+                }
+
+                StatKind::If(clauses, otherwise)
+            }
+            "while" => {
+                self.advance();
+
+                let condition = self.expr()?;
+
+                self.expect("do")?;
+
+                let block = self.loop_body()?;
+
+                self.expect("end")?;
+
+                StatKind::While(condition, block)
+            }
+            "do" => {
+                self.advance();
+
+                let block = self.block()?;
+
+                self.expect("end")?;
+
+                StatKind::Do(block)
+            }
+            "for" => {
+                self.advance();
+
+                let first = self.name()?;
+
+                if self.accept("=") {
+                    let start = self.expr()?;
+
+                    self.expect(",")?;
+
+                    let limit = self.expr()?;
+                    let step = if self.accept(",") {
+                        Some(self.expr()?)
+                    } else {
+                        None
+                    };
+
+                    self.expect("do")?;
+
+                    let block = self.loop_body()?;
+
+                    self.expect("end")?;
+
+                    StatKind::NumericFor(first, start, limit, step, block)
+                } else {
+                    let mut names = vec![first];
+
+                    while self.accept(",") {
+                        names.push(self.name()?);
+                    }
+
+                    self.expect("in")?;
+
+                    let exprs = self.expr_list()?;
+
+                    self.expect("do")?;
+
+                    let block = self.loop_body()?;
+
+                    self.expect("end")?;
+
+                    StatKind::GenericFor(names, exprs, block)
+                }
+            }
+            "repeat" => {
+                self.advance();
+
+                let block = self.loop_body()?;
+
+                self.expect("until")?;
+
+                StatKind::Repeat(block, self.expr()?)
+            }
+            "function" => {
+                self.advance();
+
+                let name = self.name()?;
+                let mut target = Expr::Name(name.clone());
+                let mut method = false;
+
+                while self.check(".") || self.check(":") {
+                    method = self.accept(":");
+
+                    if !method {
+                        self.advance();
+                    }
+
+                    let field = self.name()?;
+
+                    target = Expr::Index(Box::new(target), Box::new(Expr::Str(field)));
+
+                    if method {
+                        break;
+                    }
+                }
+
+                let body = self.function_body(method)?;
+
+                StatKind::Assign(vec![target], vec![Expr::Function(body)])
+            }
+            "local" => {
+                self.advance();
+
+                if self.accept("function") {
+                    let name = self.name()?;
+
+                    return Ok(Some(StatKind::LocalFunction(
+                        name,
+                        self.function_body(false)?,
+                    )));
+                }
+
+                let mut names = vec![self.name()?];
+
+                while self.accept(",") {
+                    names.push(self.name()?);
+                }
+
+                let exprs = if self.accept("=") {
+                    self.expr_list()?
+                } else {
+                    Vec::new()
+                };
+
+                StatKind::Local(names, exprs)
+            }
+            "break" => {
+                if self.loops == 0 {
+                    return Err(self.error("break outside a loop"));
+                }
+
+                self.advance();
+
+                StatKind::Break
+            }
+            _ => self.expr_statement()?,
+        }))
+    }
+
+    fn expr_statement(&mut self) -> Result<StatKind, String> {
+        let expr = self.suffixed()?;
+
+        if self.check("=") || self.check(",") {
+            let mut targets = vec![expr];
+
+            while self.accept(",") {
+                targets.push(self.suffixed()?);
+            }
+
+            if !targets
+                .iter()
+                .all(|target| matches!(target, Expr::Name(_) | Expr::Index(..)))
+            {
+                return Err(self.error("syntax error"));
+            }
+
+            self.expect("=")?;
+
+            return Ok(StatKind::Assign(targets, self.expr_list()?));
+        }
+
+        match expr {
+            Expr::Call(..) | Expr::Method(..) => Ok(StatKind::Call(expr)),
+            _ => Err(self.error("syntax error")),
+        }
+    }
+
+    fn function_body(&mut self, method: bool) -> Result<Rc<FuncBody>, String> {
+        self.expect("(")?;
+
+        let mut params = Vec::new();
+        let mut vararg = false;
+
+        if method {
+            params.push("self".into());
+        }
+
+        if !self.check(")") {
+            loop {
+                if self.accept("...") {
+                    vararg = true;
+
+                    break;
+                }
+
+                params.push(self.name()?);
+
+                if !self.accept(",") {
+                    break;
+                }
+            }
+        }
+
+        self.expect(")")?;
+
+        let loops = std::mem::take(&mut self.loops);
+        let block = self.block();
+
+        self.loops = loops;
+
+        let block = block?;
+
+        self.expect("end")?;
+
+        Ok(Rc::new(FuncBody {
+            params,
+            vararg,
+            block,
+            chunk: self.chunk.clone(),
+        }))
+    }
+
+    fn expr_list(&mut self) -> Result<Vec<Expr>, String> {
+        let mut exprs = vec![self.expr()?];
+
+        while self.accept(",") {
+            exprs.push(self.expr()?);
+        }
+
+        Ok(exprs)
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        self.subexpr(0)
+    }
+
+    fn subexpr(&mut self, limit: u8) -> Result<Expr, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("chunk has too many syntax levels"));
+        }
+
+        self.depth += 1;
+
+        let expr = self.operators(limit);
+
+        self.depth -= 1;
+
+        expr
+    }
+
+    fn operators(&mut self, limit: u8) -> Result<Expr, String> {
+        let unary = match self.peek() {
+            Token::Sym("not") => Some(UnOp::Not),
+            Token::Sym("-") => Some(UnOp::Neg),
+            Token::Sym("#") => Some(UnOp::Len),
+            _ => None,
+        };
+
+        let mut expr = match unary {
+            Some(op) => {
+                self.advance();
+
+                Expr::Unary(op, Box::new(self.subexpr(8)?))
+            }
+            None => self.simple()?,
+        };
+
+        while let Token::Sym(symbol) = self.peek() {
+            let (left, right) = match *symbol {
+                "or" => (1, 1),
+                "and" => (2, 2),
+                "<" | ">" | "<=" | ">=" | "~=" | "==" => (3, 3),
+                ".." => (5, 4),
+                "+" | "-" => (6, 6),
+                "*" | "/" | "//" | "%" => (7, 7),
+                "^" => (10, 9),
+                _ => break,
+            };
+
+            if left <= limit {
+                break;
+            }
+
+            let symbol = *symbol;
+
+            self.advance();
+
+            let rhs = Box::new(self.subexpr(right)?);
+            let lhs = Box::new(expr);
+
+            expr = match symbol {
+                "or" => Expr::Or(lhs, rhs),
+                "and" => Expr::And(lhs, rhs),
+                symbol => Expr::Binary(
+                    match symbol {
+                        "<" => BinOp::Lt,
+                        ">" => BinOp::Gt,
+                        "<=" => BinOp::Le,
+                        ">=" => BinOp::Ge,
+                        "~=" => BinOp::Ne,
+                        "==" => BinOp::Eq,
+                        ".." => BinOp::Concat,
+                        "+" => BinOp::Add,
+                        "-" => BinOp::Sub,
+                        "*" => BinOp::Mul,
+                        "/" => BinOp::Div,
+                        "//" => BinOp::IDiv,
+                        "%" => BinOp::Mod,
+                        _ => BinOp::Pow,
+                    },
+                    lhs,
+                    rhs,
+                ),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn simple(&mut self) -> Result<Expr, String> {
+        let expr = match self.peek().clone() {
+            Token::Number(n) => Expr::Number(n),
+            Token::Str(s) => Expr::Str(s),
+            Token::Sym("nil") => Expr::Nil,
+            Token::Sym("true") => Expr::Bool(true),
+            Token::Sym("false") => Expr::Bool(false),
+            Token::Sym("...") => Expr::Vararg,
+            Token::Sym("function") => {
+                self.advance();
+
+                return Ok(Expr::Function(self.function_body(false)?));
+            }
+            Token::Sym("{") => return self.table(),
+            _ => return self.suffixed(),
+        };
+
+        self.advance();
+
+        Ok(expr)
+    }
+
+    fn table(&mut self) -> Result<Expr, String> {
+        self.expect("{")?;
+
+        let mut items = Vec::new();
+
+        while !self.check("}") {
+            if self.accept("[") {
+                let key = self.expr()?;
+
+                self.expect("]")?;
+                self.expect("=")?;
+                items.push((Some(key), self.expr()?));
+            } else if matches!(self.peek(), Token::Name(_))
+                && matches!(self.tokens.get(self.pos + 1), Some((Token::Sym("="), _)))
+            {
+                let key = self.name()?;
+
+                self.advance();
+                items.push((Some(Expr::Str(key)), self.expr()?));
+            } else {
+                items.push((None, self.expr()?));
+            }
+
+            if !self.accept(",") && !self.accept(";") {
+                break;
+            }
+        }
+
+        self.expect("}")?;
+
+        Ok(Expr::Table(items))
+    }
+
+    fn suffixed(&mut self) -> Result<Expr, String> {
+        let mut expr = match self.peek().clone() {
+            Token::Name(name) => {
+                self.advance();
+
+                Expr::Name(name)
+            }
+            Token::Sym("(") => {
+                self.advance();
+
+                let expr = self.expr()?;
+
+                self.expect(")")?;
+
+                Expr::Paren(Box::new(expr))
+            }
+            _ => return Err(self.error("unexpected symbol")),
+        };
+
+        let mut levels = self.depth;
+
+        loop {
+            if levels >= MAX_DEPTH {
+                return Err(self.error("chunk has too many syntax levels"));
+            }
+
+            levels += 1;
+
+            expr = match self.peek() {
+                Token::Sym(".") => {
+                    self.advance();
+
+                    Expr::Index(Box::new(expr), Box::new(Expr::Str(self.name()?)))
+                }
+                Token::Sym("[") => {
+                    self.advance();
+
+                    let key = self.expr()?;
+
+                    self.expect("]")?;
+
+                    Expr::Index(Box::new(expr), Box::new(key))
+                }
+                Token::Sym(":") => {
+                    self.advance();
+
+                    let name = self.name()?;
+
+                    Expr::Method(Box::new(expr), name, self.call_args()?)
+                }
+                Token::Sym("(" | "{") | Token::Str(_) => {
+                    Expr::Call(Box::new(expr), self.call_args()?)
+                }
+                _ => return Ok(expr),
+            };
+        }
+    }
+
+    fn call_args(&mut self) -> Result<Vec<Expr>, String> {
+        match self.peek().clone() {
+            Token::Str(s) => {
+                self.advance();
+
+                Ok(vec![Expr::Str(s)])
+            }
+            Token::Sym("{") => Ok(vec![self.table()?]),
+            _ => {
+                self.expect("(")?;
+
+                if self.accept(")") {
+                    return Ok(Vec::new());
+                }
+
+                let args = self.expr_list()?;
+
+                self.expect(")")?;
+
+                Ok(args)
+            }
+        }
+    }
+}
+
+pub fn arg_string(args: &[Value], i: usize, function: &str) -> Result<String, String> {
+    args.get(i).and_then(Value::to_str).ok_or_else(|| {
+        format!(
+            "bad argument #{} to '{function}' (string expected, got {})",
+            i + 1,
+            args.get(i).map_or("no value", type_name)
+        )
+    })
+}
+
+pub fn arg_number(args: &[Value], i: usize, function: &str) -> Result<f64, String> {
+    args.get(i).and_then(Value::to_number).ok_or_else(|| {
+        format!(
+            "bad argument #{} to '{function}' (number expected, got {})",
+            i + 1,
+            args.get(i).map_or("no value", type_name)
+        )
+    })
+}
+
+fn arg_int(args: &[Value], i: usize, function: &str) -> Result<i64, String> {
+    let n = arg_number(args, i, function)?;
+
+    if n.fract() != 0.0 {
+        return Err(format!(
+            "bad argument #{} to '{function}' (number has no integer representation)",
+            i + 1
+        ));
+    }
+
+    Ok(n as i64)
+}
+
+fn opt_int(args: &[Value], i: usize, function: &str, default: i64) -> Result<i64, String> {
+    match args.get(i) {
+        None | Some(Value::Nil) => Ok(default),
+        Some(_) => arg_int(args, i, function),
+    }
+}
+
+fn arg_table(args: &[Value], i: usize, function: &str) -> Result<Rc<RefCell<Table>>, String> {
+    match args.get(i) {
+        Some(Value::Table(table)) => Ok(table.clone()),
+        other => Err(format!(
+            "bad argument #{} to '{function}' (table expected, got {})",
+            i + 1,
+            other.map_or("no value", type_name)
+        )),
+    }
+}
+
+fn start_index(i: i64, len: usize) -> usize {
+    match i {
+        i if i > 0 => i as usize,
+        0 => 1,
+        i if i.unsigned_abs() as usize > len => 1,
+        i => len - i.unsigned_abs() as usize + 1,
+    }
+}
+
+fn end_index(j: i64, len: usize) -> usize {
+    match j {
+        j if j > len as i64 => len,
+        j if j >= 0 => j as usize,
+        j if j.unsigned_abs() as usize > len => 0,
+        j => len - j.unsigned_abs() as usize + 1,
+    }
+}
+
+fn lib_assert(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    match args.first() {
+        Some(value) if value.truthy() => Ok(args),
+        _ => Err(args
+            .get(1)
+            .map_or("assertion failed!".to_string(), tostring)
+            .into()),
+    }
+}
+
+fn lib_error(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    Err(args.first().map_or("nil".to_string(), tostring).into())
+}
+
+fn lib_ipairs(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let table = arg_table(&args, 0, "ipairs")?;
+
+    Ok(vec![
+        native_function("ipairs_next", ipairs_next),
+        Value::Table(table),
+        Value::Number(0.0),
+    ])
+}
+
+fn ipairs_next(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let table = arg_table(&args, 0, "ipairs")?;
+    let i = arg_number(&args, 1, "ipairs")? + 1.0;
+    let value = table.borrow().get(&Value::Number(i));
+
+    Ok(if value.is_nil() {
+        vec![Value::Nil]
+    } else {
+        vec![Value::Number(i), value]
+    })
+}
+
+fn lib_next(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let table = arg_table(&args, 0, "next")?;
+    let entry = table.borrow().next(args.get(1).unwrap_or(&Value::Nil))?;
+
+    Ok(match entry {
+        Value::Table(pair) => {
+            let pair = pair.borrow();
+
+            vec![pair.get(&Value::Number(1.0)), pair.get(&Value::Number(2.0))]
+        }
+        _ => vec![Value::Nil],
+    })
+}
+
+fn lib_pairs(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let table = arg_table(&args, 0, "pairs")?;
+
+    Ok(vec![
+        native_function("next", lib_next),
+        Value::Table(table),
+        Value::Nil,
+    ])
+}
+
+fn lib_pcall(lua: &mut Lua, host: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let mut args = args.into_iter();
+    let function = args.next().unwrap_or_default();
+
+    match lua.call_value(function, args.collect(), host) {
+        Ok(values) => Ok(std::iter::once(Value::Bool(true)).chain(values).collect()),
+        Err(e) if e.message.ends_with("script ran too long") => Err(e),
+        Err(e) => Ok(vec![Value::Bool(false), e.message.into()]),
+    }
+}
+
+fn lib_select(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let rest = args.len().saturating_sub(1);
+
+    if matches!(args.first(), Some(Value::Str(s)) if &**s == "#") {
+        return Ok(vec![rest.into()]);
+    }
+
+    let n = arg_int(&args, 0, "select")?;
+    let start = match n {
+        n if n > 0 => n as usize,
+        n if n < 0 && n.unsigned_abs() as usize <= rest => rest - n.unsigned_abs() as usize + 1,
+        _ => return Err("bad argument #1 to 'select' (index out of range)".into()),
+    };
+
+    Ok(args.into_iter().skip(start).collect())
+}
+
+fn lib_tonumber(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let value = args.first().cloned().unwrap_or_default();
+
+    Ok(vec![match args.get(1) {
+        None | Some(Value::Nil) => value.to_number().map_or(Value::Nil, Value::Number),
+        Some(_) => {
+            let base = arg_int(&args, 1, "tonumber")?;
+            let text = arg_string(&args, 0, "tonumber")?;
+
+            if !(2..=36).contains(&base) {
+                return Err("bad argument #2 to 'tonumber' (base out of range)".into());
+            }
+
+            i64::from_str_radix(text.trim(), base as u32)
+                .map_or(Value::Nil, |n| Value::Number(n as f64))
+        }
+    }])
+}
+
+fn lib_tostring(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    Ok(vec![tostring(args.first().unwrap_or(&Value::Nil)).into()])
+}
+
+fn lib_type(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    match args.first() {
+        Some(value) => Ok(vec![type_name(value).into()]),
+        None => Err("bad argument #1 to 'type' (value expected)".into()),
+    }
+}
+
+fn lib_unpack(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let table = arg_table(&args, 0, "unpack")?;
+    let table = table.borrow();
+    let i = opt_int(&args, 1, "unpack", 1)?;
+    let j = opt_int(&args, 2, "unpack", table.border() as i64)?;
+
+    if j - i >= 1 << 20 {
+        return Err("too many results to unpack".into());
+    }
+
+    Ok((i..=j)
+        .map(|i| table.get(&Value::Number(i as f64)))
+        .collect())
+}
+
+fn string_byte(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let s = arg_string(&args, 0, "byte")?;
+    let i = start_index(opt_int(&args, 1, "byte", 1)?, s.len());
+    let j = end_index(opt_int(&args, 2, "byte", i as i64)?, s.len());
+
+    Ok(s.as_bytes()
+        .get(i - 1..j.max(i - 1))
+        .unwrap_or_default()
+        .iter()
+        .map(|&b| Value::Number(b as f64))
+        .collect())
+}
+
+fn string_char(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let bytes = (0..args.len())
+        .map(|i| {
+            let n = arg_int(&args, i, "char")?;
+
+            u8::try_from(n)
+                .map_err(|_| format!("bad argument #{} to 'char' (value out of range)", i + 1))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(vec![String::from_utf8_lossy(&bytes).to_string().into()])
+}
+
+fn string_len(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    Ok(vec![arg_string(&args, 0, "len")?.len().into()])
+}
+
+fn string_lower(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    Ok(vec![arg_string(&args, 0, "lower")?.to_lowercase().into()])
+}
+
+fn string_upper(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    Ok(vec![arg_string(&args, 0, "upper")?.to_uppercase().into()])
+}
+
+fn string_reverse(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    Ok(vec![arg_string(&args, 0, "reverse")?
+        .chars()
+        .rev()
+        .collect::<String>()
+        .into()])
+}
+
+fn string_rep(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let s = arg_string(&args, 0, "rep")?;
+    let n = arg_int(&args, 1, "rep")?.max(0) as usize;
+    let separator = match args.get(2) {
+        None | Some(Value::Nil) => String::new(),
+        Some(_) => arg_string(&args, 2, "rep")?,
+    };
+
+    if (s.len() + separator.len()).saturating_mul(n) > MAX_STRING {
+        return Err("resulting string too large".into());
+    }
+
+    Ok(vec![vec![s; n].join(&separator).into()])
+}
+
+fn string_sub(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let s = arg_string(&args, 0, "sub")?;
+    let i = start_index(opt_int(&args, 1, "sub", 1)?, s.len());
+    let j = end_index(opt_int(&args, 2, "sub", -1)?, s.len());
+    let bytes = s.as_bytes().get(i - 1..j).unwrap_or_default();
+
+    Ok(vec![String::from_utf8_lossy(bytes).to_string().into()])
+}
+
+fn string_format(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let format = arg_string(&args, 0, "format")?;
+    let mut out = String::new();
+    let mut chars = format.chars().peekable();
+    let mut next_arg = 1;
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+
+            continue;
+        }
+
+        if chars.peek() == Some(&'%') {
+            chars.next();
+            out.push('%');
+
+            continue;
+        }
+
+        let mut flags = String::new();
+
+        while let Some(&c) = chars.peek().filter(|c| "-+ #0".contains(**c)) {
+            flags.push(c);
+            chars.next();
+        }
+
+        let mut width = String::new();
+
+        while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+            width.push(c);
+            chars.next();
+        }
+
+        let precision = if chars.peek() == Some(&'.') {
+            chars.next();
+
+            let mut digits = String::new();
+
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                digits.push(c);
+                chars.next();
+            }
+
+            Some(digits.parse::<usize>().unwrap_or(0))
+        } else {
+            None
+        };
+
+        let Some(conversion) = chars.next() else {
+            return Err("invalid conversion '%' to 'format'".into());
+        };
+
+        let i = next_arg;
+
+        next_arg += 1;
+
+        let plus = |text: String, n: f64| {
+            if n >= 0.0 && flags.contains('+') {
+                format!("+{text}")
+            } else if n >= 0.0 && flags.contains(' ') {
+                format!(" {text}")
+            } else {
+                text
+            }
+        };
+
+        let text = match conversion {
+            'd' | 'i' => {
+                let n = arg_int(&args, i, "format")?;
+
+                plus(n.to_string(), n as f64)
+            }
+            'c' => {
+                let n = arg_int(&args, i, "format")?;
+
+                char::from_u32(n as u32).unwrap_or_default().to_string()
+            }
+            'x' => format!("{:x}", arg_int(&args, i, "format")?),
+            'X' => format!("{:X}", arg_int(&args, i, "format")?),
+            'o' => format!("{:o}", arg_int(&args, i, "format")?),
+            'f' | 'F' => {
+                let n = arg_number(&args, i, "format")?;
+
+                plus(format!("{n:.*}", precision.unwrap_or(6)), n)
+            }
+            'e' | 'E' => {
+                let n = arg_number(&args, i, "format")?;
+                let text = format!("{n:.*e}", precision.unwrap_or(6));
+                let (mantissa, exponent) = text.split_once('e').unwrap_or((&text, "0"));
+                let exponent: i32 = exponent.parse().unwrap_or(0);
+                let text = format!(
+                    "{mantissa}e{}{:02}",
+                    if exponent < 0 { '-' } else { '+' },
+                    exponent.abs()
+                );
+
+                plus(
+                    if conversion == 'E' {
+                        text.to_uppercase()
+                    } else {
+                        text
+                    },
+                    n,
+                )
+            }
+            'g' | 'G' => {
+                let n = arg_number(&args, i, "format")?;
+
+                plus(format_number(n), n)
+            }
+            's' => {
+                let text =
+                    tostring(args.get(i).ok_or_else(|| {
+                        format!("bad argument #{} to 'format' (no value)", i + 1)
+                    })?);
+
+                match precision {
+                    Some(precision) => text.chars().take(precision).collect(),
+                    None => text,
+                }
+            }
+            'q' => format!("{:?}", arg_string(&args, i, "format")?),
+            c => return Err(format!("invalid conversion '%{c}' to 'format'").into()),
+        };
+
+        let width: usize = width.parse().unwrap_or(0);
+        let len = text.chars().count();
+
+        if len >= width {
+            out.push_str(&text);
+        } else if flags.contains('-') {
+            out.push_str(&text);
+            out.extend(std::iter::repeat_n(' ', width - len));
+        } else if flags.contains('0') && !matches!(conversion, 's' | 'q' | 'c') {
+            let (sign, digits) = match text.strip_prefix(['-', '+', ' ']) {
+                Some(digits) => (&text[..1], digits),
+                None => ("", text.as_str()),
+            };
+
+            out.push_str(sign);
+            out.extend(std::iter::repeat_n('0', width - len));
+            out.push_str(digits);
+        } else {
+            out.extend(std::iter::repeat_n(' ', width - len));
+            out.push_str(&text);
+        }
+    }
+
+    Ok(vec![out.into()])
+}
+
+struct Matcher<'a> {
+    src: &'a [u8],
+    pat: &'a [u8],
+    level: usize,
+    captures: [(usize, isize); MAX_CAPTURES],
+    depth: usize,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(src: &'a [u8], pat: &'a [u8]) -> Self {
+        Self {
+            src,
+            pat,
+            level: 0,
+            captures: [(0, 0); MAX_CAPTURES],
+            depth: MAX_MATCH_DEPTH,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.level = 0;
+        self.depth = MAX_MATCH_DEPTH;
+    }
+
+    fn class_end(&self, mut p: usize) -> Result<usize, String> {
+        let c = self.pat[p];
+
+        p += 1;
+
+        match c {
+            b'%' => {
+                if p >= self.pat.len() {
+                    return Err("malformed pattern (ends with '%')".to_string());
+                }
+
+                Ok(p + 1)
+            }
+            b'[' => {
+                if self.pat.get(p) == Some(&b'^') {
+                    p += 1;
+                }
+
+                loop {
+                    if p >= self.pat.len() {
+                        return Err("malformed pattern (missing ']')".to_string());
+                    }
+
+                    let c = self.pat[p];
+
+                    p += 1;
+
+                    if c == b'%' && p < self.pat.len() {
+                        p += 1;
+                    }
+
+                    if self.pat.get(p) == Some(&b']') {
+                        return Ok(p + 1);
+                    }
+                }
+            }
+            _ => Ok(p),
+        }
+    }
+
+    fn match_bracket(&self, c: u8, mut p: usize, end: usize) -> bool {
+        let mut matched = true;
+
+        if self.pat.get(p + 1) == Some(&b'^') {
+            matched = false;
+            p += 1;
+        }
+
+        p += 1;
+
+        while p < end {
+            if self.pat[p] == b'%' {
+                p += 1;
+
+                if p < end && match_class(c, self.pat[p]) {
+                    return matched;
+                }
+            } else if self.pat.get(p + 1) == Some(&b'-') && p + 2 < end {
+                if self.pat[p] <= c && c <= self.pat[p + 2] {
+                    return matched;
+                }
+
+                p += 2;
+            } else if self.pat[p] == c {
+                return matched;
+            }
+
+            p += 1;
+        }
+
+        !matched
+    }
+
+    fn single_match(&self, s: usize, p: usize, ep: usize) -> bool {
+        let Some(&c) = self.src.get(s) else {
+            return false;
+        };
+
+        match self.pat[p] {
+            b'.' => true,
+            b'%' => match_class(c, self.pat[p + 1]),
+            b'[' => self.match_bracket(c, p, ep - 1),
+            pc => pc == c,
+        }
+    }
+
+    fn do_match(&mut self, mut s: usize, mut p: usize) -> Result<Option<usize>, String> {
+        if self.depth == 0 {
+            return Err("pattern too complex".to_string());
+        }
+
+        self.depth -= 1;
+
+        let result = loop {
+            let Some(&pc) = self.pat.get(p) else {
+                break Some(s);
+            };
+
+            let next = self.pat.get(p + 1).copied();
+
+            match (pc, next) {
+                (b'(', Some(b')')) => break self.start_capture(s, p + 2, CAP_POSITION)?,
+                (b'(', _) => break self.start_capture(s, p + 1, CAP_UNFINISHED)?,
+                (b')', _) => break self.end_capture(s, p + 1)?,
+                (b'$', None) => break (s == self.src.len()).then_some(s),
+                (b'%', Some(b'b')) => match self.match_balance(s, p + 2)? {
+                    Some(end) => {
+                        s = end;
+                        p += 4;
+                    }
+                    None => break None,
+                },
+                (b'%', Some(b'f')) => {
+                    p += 2;
+
+                    if self.pat.get(p) != Some(&b'[') {
+                        return Err("missing '[' after '%f' in pattern".to_string());
+                    }
+
+                    let ep = self.class_end(p)?;
+                    let previous = if s == 0 { 0 } else { self.src[s - 1] };
+                    let current = self.src.get(s).copied().unwrap_or(0);
+
+                    if self.match_bracket(previous, p, ep - 1)
+                        || !self.match_bracket(current, p, ep - 1)
+                    {
+                        break None;
+                    }
+
+                    p = ep;
+                }
+                (b'%', Some(digit)) if digit.is_ascii_digit() => {
+                    match self.match_capture(s, digit)? {
+                        Some(end) => {
+                            s = end;
+                            p += 2;
+                        }
+                        None => break None,
+                    }
+                }
+                _ => {
+                    let ep = self.class_end(p)?;
+                    let quantifier = self.pat.get(ep).copied();
+
+                    if !self.single_match(s, p, ep) {
+                        if matches!(quantifier, Some(b'*' | b'?' | b'-')) {
+                            p = ep + 1;
+
+                            continue;
+                        }
+
+                        break None;
+                    }
+
+                    match quantifier {
+                        Some(b'?') => match self.do_match(s + 1, ep + 1)? {
+                            Some(end) => break Some(end),
+                            None => p = ep + 1,
+                        },
+                        Some(b'+') => break self.max_expand(s + 1, p, ep)?,
+                        Some(b'*') => break self.max_expand(s, p, ep)?,
+                        Some(b'-') => break self.min_expand(s, p, ep)?,
+                        _ => {
+                            s += 1;
+                            p = ep;
+                        }
+                    }
+                }
+            }
+        };
+
+        self.depth += 1;
+
+        Ok(result)
+    }
+
+    fn max_expand(&mut self, s: usize, p: usize, ep: usize) -> Result<Option<usize>, String> {
+        let mut i = 0;
+
+        while self.single_match(s + i, p, ep) {
+            i += 1;
+        }
+
+        loop {
+            if let Some(end) = self.do_match(s + i, ep + 1)? {
+                return Ok(Some(end));
+            }
+
+            if i == 0 {
+                return Ok(None);
+            }
+
+            i -= 1;
+        }
+    }
+
+    fn min_expand(&mut self, mut s: usize, p: usize, ep: usize) -> Result<Option<usize>, String> {
+        loop {
+            if let Some(end) = self.do_match(s, ep + 1)? {
+                return Ok(Some(end));
+            }
+
+            if !self.single_match(s, p, ep) {
+                return Ok(None);
+            }
+
+            s += 1;
+        }
+    }
+
+    fn start_capture(&mut self, s: usize, p: usize, what: isize) -> Result<Option<usize>, String> {
+        if self.level >= MAX_CAPTURES {
+            return Err("too many captures".to_string());
+        }
+
+        self.captures[self.level] = (s, what);
+        self.level += 1;
+
+        let result = self.do_match(s, p)?;
+
+        if result.is_none() {
+            self.level -= 1;
+        }
+
+        Ok(result)
+    }
+
+    fn end_capture(&mut self, s: usize, p: usize) -> Result<Option<usize>, String> {
+        let level = (0..self.level)
+            .rev()
+            .find(|&level| self.captures[level].1 == CAP_UNFINISHED)
+            .ok_or("invalid pattern capture")?;
+
+        self.captures[level].1 = (s - self.captures[level].0) as isize;
+
+        let result = self.do_match(s, p)?;
+
+        if result.is_none() {
+            self.captures[level].1 = CAP_UNFINISHED;
+        }
+
+        Ok(result)
+    }
+
+    fn match_balance(&self, s: usize, p: usize) -> Result<Option<usize>, String> {
+        let (Some(&open), Some(&close)) = (self.pat.get(p), self.pat.get(p + 1)) else {
+            return Err("malformed pattern (missing arguments to '%b')".to_string());
+        };
+
+        if self.src.get(s) != Some(&open) {
+            return Ok(None);
+        }
+
+        let mut depth = 1;
+
+        for (i, &c) in self.src.iter().enumerate().skip(s + 1) {
+            if c == close {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Ok(Some(i + 1));
+                }
+            } else if c == open {
+                depth += 1;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn match_capture(&self, s: usize, digit: u8) -> Result<Option<usize>, String> {
+        let level = (digit as usize).wrapping_sub(b'1' as usize);
+
+        if level >= self.level || self.captures[level].1 == CAP_UNFINISHED {
+            return Err(format!("invalid capture index %{}", level.wrapping_add(1)));
+        }
+
+        let (start, len) = self.captures[level];
+        let captured = &self.src[start..start + len as usize];
+
+        Ok(self.src[s..]
+            .starts_with(captured)
+            .then_some(s + captured.len()))
+    }
+
+    fn capture(&self, i: usize, s: usize, e: usize) -> Result<Value, String> {
+        if i >= self.level {
+            if i == 0 {
+                return Ok(String::from_utf8_lossy(&self.src[s..e]).to_string().into());
+            }
+
+            return Err(format!("invalid capture index %{}", i + 1));
+        }
+
+        match self.captures[i] {
+            (start, CAP_POSITION) => Ok((start + 1).into()),
+            (_, CAP_UNFINISHED) => Err("unfinished capture".to_string()),
+            (start, len) => Ok(
+                String::from_utf8_lossy(&self.src[start..start + len as usize])
+                    .to_string()
+                    .into(),
+            ),
+        }
+    }
+
+    fn captures(&self, s: usize, e: usize, whole: bool) -> Result<Vec<Value>, String> {
+        let count = if self.level == 0 && whole {
+            1
+        } else {
+            self.level
+        };
+
+        (0..count).map(|i| self.capture(i, s, e)).collect()
+    }
+}
+
+fn match_class(c: u8, class: u8) -> bool {
+    let matched = match class.to_ascii_lowercase() {
+        b'a' => c.is_ascii_alphabetic(),
+        b'c' => c.is_ascii_control(),
+        b'd' => c.is_ascii_digit(),
+        b'g' => c.is_ascii_graphic(),
+        b'l' => c.is_ascii_lowercase(),
+        b'p' => c.is_ascii_punctuation(),
+        b's' => c == b' ' || (b'\t'..=b'\r').contains(&c),
+        b'u' => c.is_ascii_uppercase(),
+        b'w' => c.is_ascii_alphanumeric(),
+        b'x' => c.is_ascii_hexdigit(),
+        _ => return class == c,
+    };
+
+    if class.is_ascii_uppercase() {
+        !matched
+    } else {
+        matched
+    }
+}
+
+fn find(args: Vec<Value>, function: &str, find: bool) -> Result<Vec<Value>, Error> {
+    let s = arg_string(&args, 0, function)?;
+    let pattern = arg_string(&args, 1, function)?;
+    let init = start_index(opt_int(&args, 2, function, 1)?, s.len());
+
+    if init > s.len() + 1 {
+        return Ok(vec![Value::Nil]);
+    }
+
+    let plain = args.get(3).is_some_and(Value::truthy);
+
+    if find && (plain || !pattern.contains(['^', '$', '*', '+', '?', '.', '(', '[', '%', '-'])) {
+        return Ok(
+            match s.as_bytes()[init - 1..]
+                .windows(pattern.len().max(1))
+                .position(|window| window.starts_with(pattern.as_bytes()))
+                .filter(|_| !pattern.is_empty() || init <= s.len() + 1)
+            {
+                Some(start) => vec![
+                    (init + start).into(),
+                    (init + start + pattern.len() - 1).into(),
+                ],
+                None if pattern.is_empty() => vec![init.into(), (init - 1).into()],
+                None => vec![Value::Nil],
+            },
+        );
+    }
+
+    let (anchor, start) = match pattern.strip_prefix('^') {
+        Some(_) => (true, 1),
+        None => (false, 0),
+    };
+    let mut matcher = Matcher::new(s.as_bytes(), pattern.as_bytes());
+    let mut s1 = init - 1;
+
+    loop {
+        matcher.reset();
+
+        if let Some(end) = matcher.do_match(s1, start)? {
+            return if find {
+                Ok([(s1 + 1).into(), end.into()]
+                    .into_iter()
+                    .chain(matcher.captures(s1, end, false)?)
+                    .collect())
+            } else {
+                Ok(matcher.captures(s1, end, true)?)
+            };
+        }
+
+        s1 += 1;
+
+        if anchor || s1 > s.len() {
+            return Ok(vec![Value::Nil]);
+        }
+    }
+}
+
+fn string_find(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    find(args, "find", true)
+}
+
+fn string_match(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    find(args, "match", false)
+}
+
+fn string_gmatch(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let s = arg_string(&args, 0, "gmatch")?;
+    let pattern = arg_string(&args, 1, "gmatch")?;
+    let mut state = Table::default();
+
+    state.set_field("s", s.into());
+    state.set_field("pattern", pattern.into());
+    state.set_field("pos", Value::Number(0.0));
+
+    Ok(vec![Value::Function(Rc::new(Function(Callable::Bound(
+        gmatch_next,
+        vec![state.into()],
+    ))))])
+}
+
+fn gmatch_next(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let state = arg_table(&args, 0, "gmatch")?;
+    let (s, pattern, pos, last) = {
+        let state = state.borrow();
+
+        (
+            state.get(&"s".into()).to_str().unwrap_or_default(),
+            state.get(&"pattern".into()).to_str().unwrap_or_default(),
+            state.get(&"pos".into()).to_number().unwrap_or(0.0) as usize,
+            state.get(&"last".into()).to_number().map(|n| n as usize),
+        )
+    };
+    let mut matcher = Matcher::new(s.as_bytes(), pattern.as_bytes());
+
+    for s1 in pos..=s.len() {
+        matcher.reset();
+
+        if let Some(end) = matcher.do_match(s1, 0)? {
+            if Some(end) == last {
+                continue;
+            }
+
+            let mut state = state.borrow_mut();
+
+            state.set_field("pos", end.into());
+            state.set_field("last", end.into());
+
+            return Ok(matcher.captures(s1, end, true)?);
+        }
+    }
+
+    state.borrow_mut().set_field("pos", (s.len() + 1).into());
+
+    Ok(vec![Value::Nil])
+}
+
+fn string_gsub(lua: &mut Lua, host: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let s = arg_string(&args, 0, "gsub")?;
+    let pattern = arg_string(&args, 1, "gsub")?;
+    let replacement = args.get(2).cloned().unwrap_or_default();
+    let max = match args.get(3) {
+        None | Some(Value::Nil) => usize::MAX,
+        Some(_) => arg_int(&args, 3, "gsub")?.max(0) as usize,
+    };
+
+    if !matches!(
+        replacement,
+        Value::Str(_) | Value::Number(_) | Value::Table(_) | Value::Function(_)
+    ) {
+        return Err(format!(
+            "bad argument #3 to 'gsub' (string/function/table expected, got {})",
+            type_name(&replacement)
+        )
+        .into());
+    }
+
+    let (anchor, start) = match pattern.strip_prefix('^') {
+        Some(_) => (true, 1),
+        None => (false, 0),
+    };
+    let src = s.as_bytes();
+    let mut matcher = Matcher::new(src, pattern.as_bytes());
+    let mut out = Vec::new();
+    let mut s1 = 0;
+    let mut last = None;
+    let mut n = 0;
+
+    while n < max {
+        matcher.reset();
+
+        match matcher.do_match(s1, start)? {
+            Some(end) if Some(end) != last => {
+                n += 1;
+
+                let value = match &replacement {
+                    Value::Table(table) => {
+                        let key = matcher.capture(0, s1, end)?;
+
+                        table.borrow().get(&key)
+                    }
+                    Value::Function(_) => lua
+                        .call_value(replacement.clone(), matcher.captures(s1, end, true)?, host)?
+                        .into_iter()
+                        .next()
+                        .unwrap_or_default(),
+                    _ => {
+                        let template = replacement.to_str().unwrap_or_default();
+                        let mut text = Vec::new();
+                        let mut bytes = template.bytes();
+
+                        while let Some(b) = bytes.next() {
+                            if b != b'%' {
+                                text.push(b);
+
+                                continue;
+                            }
+
+                            match bytes.next() {
+                                Some(b'%') => text.push(b'%'),
+                                Some(b'0') => text.extend_from_slice(&src[s1..end]),
+                                Some(d @ b'1'..=b'9') => {
+                                    let capture = matcher.capture((d - b'1') as usize, s1, end)?;
+
+                                    text.extend_from_slice(tostring(&capture).as_bytes());
+                                }
+                                _ => return Err("invalid use of '%' in replacement string".into()),
+                            }
+                        }
+
+                        String::from_utf8_lossy(&text).to_string().into()
+                    }
+                };
+
+                match value {
+                    Value::Nil | Value::Bool(false) => out.extend_from_slice(&src[s1..end]),
+                    Value::Str(_) | Value::Number(_) => {
+                        out.extend_from_slice(tostring(&value).as_bytes())
+                    }
+                    other => {
+                        return Err(
+                            format!("invalid replacement value (a {})", type_name(&other)).into(),
+                        )
+                    }
+                }
+
+                if out.len() > MAX_STRING {
+                    return Err("resulting string too large".into());
+                }
+
+                s1 = end;
+                last = Some(end);
+            }
+            _ if s1 < src.len() => {
+                out.push(src[s1]);
+                s1 += 1;
+            }
+            _ => break,
+        }
+
+        if anchor {
+            break;
+        }
+    }
+
+    out.extend_from_slice(&src[s1.min(src.len())..]);
+
+    Ok(vec![
+        String::from_utf8_lossy(&out).to_string().into(),
+        n.into(),
+    ])
+}
+
+fn table_concat(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let table = arg_table(&args, 0, "concat")?;
+    let table = table.borrow();
+    let separator = match args.get(1) {
+        None | Some(Value::Nil) => String::new(),
+        Some(_) => arg_string(&args, 1, "concat")?,
+    };
+    let i = opt_int(&args, 2, "concat", 1)?;
+    let j = opt_int(&args, 3, "concat", table.border() as i64)?;
+    let mut parts = Vec::new();
+
+    for i in i..=j {
+        let value = table.get(&Value::Number(i as f64));
+
+        parts.push(value.to_str().ok_or_else(|| {
+            format!(
+                "invalid value (at index {i}) in table for 'concat' ({})",
+                type_name(&value)
+            )
+        })?);
+    }
+
+    Ok(vec![parts.join(&separator).into()])
+}
+
+fn table_insert(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let table = arg_table(&args, 0, "insert")?;
+    let mut table = table.borrow_mut();
+    let len = table.border();
+
+    match args.len() {
+        2 => table.set((len + 1).into(), args[1].clone())?,
+        3 => {
+            let pos = arg_int(&args, 1, "insert")?;
+
+            if pos < 1 || pos as usize > len + 1 {
+                return Err("bad argument #2 to 'insert' (position out of bounds)".into());
+            }
+
+            for i in (pos as usize..=len).rev() {
+                let value = table.get(&i.into());
+
+                table.set((i + 1).into(), value)?;
+            }
+
+            table.set(Value::Number(pos as f64), args[2].clone())?;
+        }
+        _ => return Err("wrong number of arguments to 'insert'".into()),
+    }
+
+    Ok(Vec::new())
+}
+
+fn table_remove(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let table = arg_table(&args, 0, "remove")?;
+    let mut table = table.borrow_mut();
+    let len = table.border();
+    let pos = opt_int(&args, 1, "remove", len as i64)?;
+
+    if len == 0 && (pos == 0 || pos == len as i64) {
+        return Ok(vec![table.get(&Value::Number(pos as f64))]);
+    }
+
+    if pos < 1 || pos as usize > len + 1 {
+        return Err("bad argument #2 to 'remove' (position out of bounds)".into());
+    }
+
+    let removed = table.get(&Value::Number(pos as f64));
+
+    for i in pos as usize..len {
+        let value = table.get(&(i + 1).into());
+
+        table.set(i.into(), value)?;
+    }
+
+    if pos as usize <= len {
+        table.set(len.into(), Value::Nil)?;
+    }
+
+    Ok(vec![removed])
+}
+
+fn table_sort(lua: &mut Lua, host: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let table = arg_table(&args, 0, "sort")?;
+    let comparator = args.get(1).cloned().filter(|value| !value.is_nil());
+    let len = table.borrow().border();
+    let mut items: Vec<Value> = (1..=len).map(|i| table.borrow().get(&i.into())).collect();
+
+    for i in 1..items.len() {
+        let mut j = i;
+
+        while j > 0 {
+            let before = match &comparator {
+                Some(comparator) => lua
+                    .call_value(
+                        comparator.clone(),
+                        vec![items[j].clone(), items[j - 1].clone()],
+                        host,
+                    )?
+                    .first()
+                    .is_some_and(Value::truthy),
+                None => less(&items[j], &items[j - 1], false)?,
+            };
+
+            if !before {
+                break;
+            }
+
+            items.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+
+    let mut table = table.borrow_mut();
+
+    for (i, item) in items.into_iter().enumerate() {
+        table.set((i + 1).into(), item)?;
+    }
+
+    Ok(Vec::new())
+}
+
+fn math_unary(args: &[Value], function: &str, f: fn(f64) -> f64) -> Result<Vec<Value>, Error> {
+    Ok(vec![Value::Number(f(arg_number(args, 0, function)?))])
+}
+
+fn math_abs(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    math_unary(&args, "abs", f64::abs)
+}
+
+fn math_ceil(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    math_unary(&args, "ceil", f64::ceil)
+}
+
+fn math_floor(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    math_unary(&args, "floor", f64::floor)
+}
+
+fn math_sqrt(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    math_unary(&args, "sqrt", f64::sqrt)
+}
+
+fn math_fmod(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let a = arg_number(&args, 0, "fmod")?;
+    let b = arg_number(&args, 1, "fmod")?;
+
+    Ok(vec![Value::Number(a % b)])
+}
+
+fn math_max(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let mut max = arg_number(&args, 0, "max")?;
+
+    for i in 1..args.len() {
+        max = max.max(arg_number(&args, i, "max")?);
+    }
+
+    Ok(vec![Value::Number(max)])
+}
+
+fn math_min(_: &mut Lua, _: &mut dyn Host, args: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let mut min = arg_number(&args, 0, "min")?;
+
+    for i in 1..args.len() {
+        min = min.min(arg_number(&args, i, "min")?);
+    }
+
+    Ok(vec![Value::Number(min)])
+}
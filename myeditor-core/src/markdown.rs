@@ -0,0 +1,153 @@
+use crate::style::{StyledContent, Stylize};
+use ropey::Rope;
+use std::path::Path;
+
+type Emphasis = fn(String) -> StyledContent<String>;
+
+pub fn is_markdown(path: Option<&Path>) -> bool {
+    path.and_then(Path::extension)
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext, "md" | "markdown" | "mkd"))
+}
+
+pub fn render_line(text: &Rope, line_idx: usize) -> Vec<StyledContent<String>> {
+    let Some(line) = text.get_line(line_idx) else {
+        return Vec::new();
+    };
+
+    let line = line
+        .to_string()
+        .trim_end_matches(['\n', '\r'])
+        .replace('\t', "    ");
+    let in_fence = (0..line_idx)
+        .filter(|&idx| is_fence(&text.line(idx).to_string()))
+        .count()
+        % 2
+        == 1;
+
+    if is_fence(&line) {
+        let lang = line.trim_start().trim_start_matches(['`', '~']).trim();
+
+        return vec![format!("─── {lang} ").dark_grey()];
+    }
+
+    if in_fence {
+        return vec![format!("  {line}").green()];
+    }
+
+    let trimmed = line.trim_start();
+    let indent = " ".repeat(line.len() - trimmed.len());
+
+    if let Some((level, heading)) = heading(trimmed) {
+        let heading = heading.to_string();
+
+        return vec![match level {
+            1 => heading.to_uppercase().bold().underlined().magenta(),
+            2 => heading.bold().magenta(),
+            _ => heading.bold(),
+        }];
+    }
+
+    if let Some(quote) = trimmed.strip_prefix('>') {
+        let mut spans = vec![format!("{indent}│ ").dark_grey()];
+        spans.extend(
+            inline(quote.trim_start())
+                .into_iter()
+                .map(|span| span.italic()),
+        );
+
+        return spans;
+    }
+
+    if matches!(trimmed, "---" | "***" | "___") {
+        return vec!["─".repeat(40).dark_grey()];
+    }
+
+    if let Some(item) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| trimmed.strip_prefix(bullet))
+    {
+        let mut spans = vec![format!("{indent}• ").cyan()];
+        spans.extend(inline(item));
+
+        return spans;
+    }
+
+    if let Some((number, item)) = trimmed.split_once(". ") {
+        if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) {
+            let mut spans = vec![format!("{indent}{number}. ").cyan()];
+            spans.extend(inline(item));
+
+            return spans;
+        }
+    }
+
+    let mut spans = vec![indent.reset()];
+    spans.extend(inline(trimmed));
+
+    spans
+}
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+
+    if (1..=6).contains(&level) {
+        line[level..]
+            .strip_prefix(' ')
+            .map(|rest| (level, rest.trim()))
+    } else {
+        None
+    }
+}
+
+fn inline(text: &str) -> Vec<StyledContent<String>> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (marker, style): (&[char], Emphasis) = match chars[i] {
+            '`' => (&['`'], |s| s.yellow()),
+            '*' if chars.get(i + 1) == Some(&'*') => (&['*', '*'], |s| s.bold()),
+            '_' if chars.get(i + 1) == Some(&'_') => (&['_', '_'], |s| s.bold()),
+            '*' => (&['*'], |s| s.italic()),
+            '_' => (&['_'], |s| s.italic()),
+            c => {
+                plain.push(c);
+                i += 1;
+                continue;
+            }
+        };
+
+        let start = i + marker.len();
+        let end = (start..chars.len()).find(|&j| chars[j..].starts_with(marker) && j > start);
+
+        match end {
+            Some(end) => {
+                if !plain.is_empty() {
+                    spans.push(std::mem::take(&mut plain).reset());
+                }
+
+                spans.push(style(chars[start..end].iter().collect()));
+                i = end + marker.len();
+            }
+            None => {
+                plain.extend(marker);
+                i = start;
+            }
+        }
+    }
+
+    if !plain.is_empty() {
+        spans.push(plain.reset());
+    }
+
+    spans
+}
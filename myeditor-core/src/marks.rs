@@ -0,0 +1,80 @@
+use crate::paths;
+use std::{collections::BTreeMap, path::PathBuf};
+
+const FILE_NAME: &str = "marks";
+
+#[derive(Debug, Default)]
+pub struct GlobalMarks {
+    marks: BTreeMap<char, GlobalMark>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GlobalMark {
+    pub path: PathBuf,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl GlobalMarks {
+    pub fn load() -> Self {
+        let Some(text) = paths::data_file(FILE_NAME).and_then(|p| std::fs::read_to_string(p).ok())
+        else {
+            return Self::default();
+        };
+
+        let marks = text
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(4, '\t');
+
+                let name = parts.next()?.chars().next()?;
+                let mark = GlobalMark {
+                    line: parts.next()?.parse().ok()?,
+                    col: parts.next()?.parse().ok()?,
+                    path: PathBuf::from(parts.next()?),
+                };
+
+                Some((name, mark))
+            })
+            .collect();
+
+        Self { marks }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = paths::data_file(FILE_NAME) else {
+            return Ok(());
+        };
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let text = self
+            .marks
+            .iter()
+            .map(|(name, mark)| {
+                format!(
+                    "{name}\t{}\t{}\t{}\n",
+                    mark.line,
+                    mark.col,
+                    mark.path.display()
+                )
+            })
+            .collect::<String>();
+
+        std::fs::write(path, text)
+    }
+
+    pub fn get(&self, name: char) -> Option<&GlobalMark> {
+        self.marks.get(&name)
+    }
+
+    pub fn set(&mut self, name: char, mark: GlobalMark) {
+        self.marks.insert(name, mark);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (char, &GlobalMark)> {
+        self.marks.iter().map(|(name, mark)| (*name, mark))
+    }
+}
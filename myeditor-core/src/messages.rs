@@ -0,0 +1,102 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+const MAX_HISTORY: usize = 200;
+const INFO_TIMEOUT: Duration = Duration::from_secs(4);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub severity: Severity,
+    pub text: String,
+    pub time: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct Messages {
+    history: VecDeque<Message>,
+    current: Option<Message>,
+}
+
+impl Messages {
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(Severity::Info, text.into());
+    }
+
+    pub fn warn(&mut self, text: impl Into<String>) {
+        self.push(Severity::Warning, text.into());
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(Severity::Error, text.into());
+    }
+
+    pub fn show(&mut self, text: impl Into<String>) {
+        self.current = Some(Message {
+            severity: Severity::Info,
+            text: text.into(),
+            time: Instant::now(),
+        });
+    }
+
+    fn push(&mut self, severity: Severity, text: String) {
+        let message = Message {
+            severity,
+            text,
+            time: Instant::now(),
+        };
+
+        self.history.push_back(message.clone());
+
+        if self.history.len() > MAX_HISTORY {
+            self.history.pop_front();
+        }
+
+        self.current = Some(message);
+    }
+
+    pub fn current(&self) -> Option<&Message> {
+        self.current.as_ref()
+    }
+
+    pub fn dismiss(&mut self) {
+        self.current = None;
+    }
+
+    pub fn history(&self) -> impl Iterator<Item = &Message> {
+        self.history.iter()
+    }
+
+    pub fn time_left(&self) -> Option<Duration> {
+        let message = self
+            .current
+            .as_ref()
+            .filter(|message| message.severity == Severity::Info && !message.is_multiline())?;
+
+        Some(INFO_TIMEOUT.saturating_sub(message.time.elapsed()))
+    }
+
+    pub fn expire(&mut self) -> bool {
+        if self.time_left().is_some_and(|left| left.is_zero()) {
+            self.current = None;
+
+            return true;
+        }
+
+        false
+    }
+}
+
+impl Message {
+    pub fn is_multiline(&self) -> bool {
+        self.text.contains('\n')
+    }
+}
@@ -0,0 +1,31 @@
+pub const WIDTH: usize = 12;
+pub const CHARS_PER_CELL: usize = 4;
+
+pub fn first_line(lines: usize, height: usize, top_line: usize) -> usize {
+    let shown = height * 2;
+
+    if lines <= shown {
+        return 0;
+    }
+
+    let scrollable = std::cmp::max(lines.saturating_sub(height), 1);
+    let start = std::cmp::min(top_line * (lines - shown) / scrollable, lines - shown);
+
+    start - start % 2
+}
+
+pub fn cell(top: &[char], bottom: &[char], col: usize) -> char {
+    let filled = |line: &[char]| {
+        line.iter()
+            .skip(col * CHARS_PER_CELL)
+            .take(CHARS_PER_CELL)
+            .any(|c| !c.is_whitespace())
+    };
+
+    match (filled(top), filled(bottom)) {
+        (true, true) => '█',
+        (true, false) => '▀',
+        (false, true) => '▄',
+        (false, false) => ' ',
+    }
+}
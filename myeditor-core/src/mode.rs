@@ -0,0 +1,22 @@
+#[derive(Debug)]
+pub enum Mode {
+    Normal,
+    Insert,
+    Visual,
+    Command,
+    Search,
+    Terminal,
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mode::Normal => write!(f, "NRM"),
+            Mode::Insert => write!(f, "INS"),
+            Mode::Visual => write!(f, "VIS"),
+            Mode::Command => write!(f, "CMD"),
+            Mode::Search => write!(f, "SRC"),
+            Mode::Terminal => write!(f, "TRM"),
+        }
+    }
+}
@@ -0,0 +1,449 @@
+use crate::statusline;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberStyle {
+    Absolute,
+    Relative,
+    None,
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub autosave: bool,
+    pub backups: usize,
+    pub cargocheck: bool,
+    pub cursorline: bool,
+    pub expandtab: bool,
+    pub colorcolumn: Vec<usize>,
+    pub ignorecase: bool,
+    pub indentguides: bool,
+    pub largefile: usize,
+    pub lintwhitespace: bool,
+    pub makeprg: String,
+    pub maxfps: u32,
+    pub minimap: bool,
+    pub moveindent: bool,
+    pub number: NumberStyle,
+    pub osc52: bool,
+    pub path: Vec<String>,
+    pub rainbow: bool,
+    pub scrollbar: bool,
+    pub scrolloff: usize,
+    pub shiftwidth: usize,
+    pub smartcase: bool,
+    pub smarthome: bool,
+    pub statusline: String,
+    pub textwidth: usize,
+    pub timeoutlen: u64,
+    pub whichkey: bool,
+    pub writeprg: String,
+    pub zenwidth: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            autosave: false,
+            backups: 10,
+            cargocheck: false,
+            cursorline: false,
+            expandtab: true,
+            colorcolumn: Vec::new(),
+            ignorecase: false,
+            indentguides: false,
+            largefile: 100,
+            lintwhitespace: true,
+            makeprg: "make".to_string(),
+            maxfps: 60,
+            minimap: false,
+            moveindent: false,
+            number: NumberStyle::Absolute,
+            osc52: false,
+            path: Vec::new(),
+            rainbow: false,
+            scrollbar: true,
+            scrolloff: 0,
+            shiftwidth: 4,
+            smartcase: false,
+            smarthome: false,
+            statusline: statusline::DEFAULT.to_string(),
+            textwidth: 0,
+            timeoutlen: 1000,
+            whichkey: true,
+            writeprg: "sudo tee % >/dev/null".to_string(),
+            zenwidth: 80,
+        }
+    }
+}
+
+impl Options {
+    pub fn set(&mut self, arg: &str) -> Result<Option<String>, String> {
+        if let Some((name, value)) = arg.split_once('=') {
+            return self.assign(name, value).map(|()| None);
+        }
+
+        if let Some(name) = arg.strip_suffix('?') {
+            return self.show(name).map(Some);
+        }
+
+        if let Some(name) = arg.strip_suffix('!') {
+            let value = self.flag(name)?;
+            *value = !*value;
+
+            return Ok(None);
+        }
+
+        if let Some(name) = arg.strip_prefix("no") {
+            if let Ok(value) = self.flag(name) {
+                *value = false;
+
+                return Ok(None);
+            }
+        }
+
+        match self.flag(arg) {
+            Ok(value) => {
+                *value = true;
+
+                Ok(None)
+            }
+            Err(e) => self.show(arg).map(Some).map_err(|_| e),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        [
+            "autosave",
+            "backups",
+            "cargocheck",
+            "cursorline",
+            "colorcolumn",
+            "expandtab",
+            "ignorecase",
+            "indentguides",
+            "largefile",
+            "lintwhitespace",
+            "makeprg",
+            "maxfps",
+            "minimap",
+            "moveindent",
+            "number",
+            "osc52",
+            "path",
+            "rainbow",
+            "scrollbar",
+            "scrolloff",
+            "shiftwidth",
+            "smartcase",
+            "smarthome",
+            "statusline",
+            "textwidth",
+            "timeoutlen",
+            "whichkey",
+            "writeprg",
+            "zenwidth",
+        ]
+        .into_iter()
+        .filter_map(|name| self.show(name).ok())
+        .collect::<Vec<_>>()
+        .join("  ")
+    }
+
+    fn flag(&mut self, name: &str) -> Result<&mut bool, String> {
+        match name {
+            "autosave" => Ok(&mut self.autosave),
+            "cargocheck" | "ck" => Ok(&mut self.cargocheck),
+            "cursorline" | "cul" => Ok(&mut self.cursorline),
+            "expandtab" | "et" => Ok(&mut self.expandtab),
+            "ignorecase" | "ic" => Ok(&mut self.ignorecase),
+            "indentguides" | "ig" => Ok(&mut self.indentguides),
+            "lintwhitespace" | "lw" => Ok(&mut self.lintwhitespace),
+            "minimap" => Ok(&mut self.minimap),
+            "moveindent" | "mi" => Ok(&mut self.moveindent),
+            "osc52" => Ok(&mut self.osc52),
+            "rainbow" => Ok(&mut self.rainbow),
+            "scrollbar" | "sb" => Ok(&mut self.scrollbar),
+            "smartcase" | "scs" => Ok(&mut self.smartcase),
+            "smarthome" | "sh" => Ok(&mut self.smarthome),
+            "whichkey" | "wk" => Ok(&mut self.whichkey),
+            _ => Err(unknown(name)),
+        }
+    }
+
+    fn assign(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "backups" | "bk" => {
+                self.backups = value
+                    .parse()
+                    .map_err(|_| format!("Invalid number of backups: {value}"))?;
+
+                Ok(())
+            }
+            "colorcolumn" | "cc" => {
+                self.colorcolumn = parse_columns(value)?;
+
+                Ok(())
+            }
+            "largefile" | "lf" => {
+                self.largefile = value
+                    .parse()
+                    .map_err(|_| format!("Invalid file size: {value}"))?;
+
+                Ok(())
+            }
+            "makeprg" | "mp" => {
+                if value.is_empty() {
+                    return Err("makeprg can't be empty".to_string());
+                }
+
+                self.makeprg = value.to_string();
+
+                Ok(())
+            }
+            "maxfps" | "mfps" => {
+                self.maxfps = value
+                    .parse()
+                    .map_err(|_| format!("Invalid frame rate: {value}"))?;
+
+                Ok(())
+            }
+            "number" | "nu" => {
+                self.number = match value {
+                    "absolute" => NumberStyle::Absolute,
+                    "relative" => NumberStyle::Relative,
+                    "none" => NumberStyle::None,
+                    _ => return Err(format!("Invalid number style: {value}")),
+                };
+
+                Ok(())
+            }
+            "path" | "pa" => {
+                self.path = value
+                    .split(',')
+                    .filter(|dir| !dir.is_empty())
+                    .map(str::to_string)
+                    .collect();
+
+                Ok(())
+            }
+            "scrolloff" | "so" => {
+                self.scrolloff = value
+                    .parse()
+                    .map_err(|_| format!("Invalid scroll offset: {value}"))?;
+
+                Ok(())
+            }
+            "shiftwidth" | "sw" => {
+                self.shiftwidth = value
+                    .parse()
+                    .ok()
+                    .filter(|width| *width > 0)
+                    .ok_or(format!("Invalid shift width: {value}"))?;
+
+                Ok(())
+            }
+            "statusline" | "stl" => {
+                statusline::parse(value)?;
+                self.statusline = value.to_string();
+
+                Ok(())
+            }
+            "textwidth" | "tw" => {
+                self.textwidth = value
+                    .parse()
+                    .map_err(|_| format!("Invalid text width: {value}"))?;
+
+                Ok(())
+            }
+            "timeoutlen" | "tm" => {
+                self.timeoutlen = value
+                    .parse()
+                    .map_err(|_| format!("Invalid timeout: {value}"))?;
+
+                Ok(())
+            }
+            "writeprg" | "wp" => {
+                if value.is_empty() {
+                    return Err("writeprg can't be empty".to_string());
+                }
+
+                self.writeprg = value.to_string();
+
+                Ok(())
+            }
+            "zenwidth" | "zw" => {
+                self.zenwidth = value
+                    .parse()
+                    .ok()
+                    .filter(|width| *width > 0)
+                    .ok_or(format!("Invalid zen width: {value}"))?;
+
+                Ok(())
+            }
+            _ if self.flag(name).is_ok() => Err(format!("Option {name} takes no value")),
+            _ => Err(unknown(name)),
+        }
+    }
+
+    fn show(&self, name: &str) -> Result<String, String> {
+        match name {
+            "autosave" => Ok(if self.autosave {
+                "autosave".to_string()
+            } else {
+                "noautosave".to_string()
+            }),
+            "backups" | "bk" => Ok(format!("backups={}", self.backups)),
+            "cargocheck" | "ck" => Ok(if self.cargocheck {
+                "cargocheck".to_string()
+            } else {
+                "nocargocheck".to_string()
+            }),
+            "cursorline" | "cul" => Ok(if self.cursorline {
+                "cursorline".to_string()
+            } else {
+                "nocursorline".to_string()
+            }),
+            "colorcolumn" | "cc" => Ok(format!(
+                "colorcolumn={}",
+                self.colorcolumn
+                    .iter()
+                    .map(usize::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )),
+            "expandtab" | "et" => Ok(if self.expandtab {
+                "expandtab".to_string()
+            } else {
+                "noexpandtab".to_string()
+            }),
+            "ignorecase" | "ic" => Ok(if self.ignorecase {
+                "ignorecase".to_string()
+            } else {
+                "noignorecase".to_string()
+            }),
+            "indentguides" | "ig" => Ok(if self.indentguides {
+                "indentguides".to_string()
+            } else {
+                "noindentguides".to_string()
+            }),
+            "lintwhitespace" | "lw" => Ok(if self.lintwhitespace {
+                "lintwhitespace".to_string()
+            } else {
+                "nolintwhitespace".to_string()
+            }),
+            "largefile" | "lf" => Ok(format!("largefile={}", self.largefile)),
+            "makeprg" | "mp" => Ok(format!(
+                "makeprg={}",
+                self.makeprg.replace('\\', "\\\\").replace(' ', "\\ ")
+            )),
+            "maxfps" | "mfps" => Ok(format!("maxfps={}", self.maxfps)),
+            "minimap" => Ok(if self.minimap {
+                "minimap".to_string()
+            } else {
+                "nominimap".to_string()
+            }),
+            "moveindent" | "mi" => Ok(if self.moveindent {
+                "moveindent".to_string()
+            } else {
+                "nomoveindent".to_string()
+            }),
+            "number" | "nu" => Ok(format!(
+                "number={}",
+                match self.number {
+                    NumberStyle::Absolute => "absolute",
+                    NumberStyle::Relative => "relative",
+                    NumberStyle::None => "none",
+                }
+            )),
+            "osc52" => Ok(if self.osc52 {
+                "osc52".to_string()
+            } else {
+                "noosc52".to_string()
+            }),
+            "path" | "pa" => Ok(format!("path={}", self.path.join(","))),
+            "rainbow" => Ok(if self.rainbow {
+                "rainbow".to_string()
+            } else {
+                "norainbow".to_string()
+            }),
+            "scrollbar" | "sb" => Ok(if self.scrollbar {
+                "scrollbar".to_string()
+            } else {
+                "noscrollbar".to_string()
+            }),
+            "scrolloff" | "so" => Ok(format!("scrolloff={}", self.scrolloff)),
+            "shiftwidth" | "sw" => Ok(format!("shiftwidth={}", self.shiftwidth)),
+            "smartcase" | "scs" => Ok(if self.smartcase {
+                "smartcase".to_string()
+            } else {
+                "nosmartcase".to_string()
+            }),
+            "smarthome" | "sh" => Ok(if self.smarthome {
+                "smarthome".to_string()
+            } else {
+                "nosmarthome".to_string()
+            }),
+            "statusline" | "stl" => Ok(format!(
+                "statusline={}",
+                self.statusline.replace('\\', "\\\\").replace(' ', "\\ ")
+            )),
+            "textwidth" | "tw" => Ok(format!("textwidth={}", self.textwidth)),
+            "timeoutlen" | "tm" => Ok(format!("timeoutlen={}", self.timeoutlen)),
+            "whichkey" | "wk" => Ok(if self.whichkey {
+                "whichkey".to_string()
+            } else {
+                "nowhichkey".to_string()
+            }),
+            "writeprg" | "wp" => Ok(format!(
+                "writeprg={}",
+                self.writeprg.replace('\\', "\\\\").replace(' ', "\\ ")
+            )),
+            "zenwidth" | "zw" => Ok(format!("zenwidth={}", self.zenwidth)),
+            _ => Err(unknown(name)),
+        }
+    }
+}
+
+pub fn split_args(args: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut chars = args.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => part.extend(chars.next()),
+            c if c.is_whitespace() => {
+                if !part.is_empty() {
+                    parts.push(std::mem::take(&mut part));
+                }
+            }
+            c => part.push(c),
+        }
+    }
+
+    if !part.is_empty() {
+        parts.push(part);
+    }
+
+    parts
+}
+
+fn parse_columns(value: &str) -> Result<Vec<usize>, String> {
+    let mut columns = value
+        .split(',')
+        .filter(|column| !column.is_empty())
+        .map(|column| match column.parse::<usize>() {
+            Ok(column) if column > 0 => Ok(column),
+            _ => Err(format!("Invalid column: {column}")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    columns.sort_unstable();
+    columns.dedup();
+
+    Ok(columns)
+}
+
+fn unknown(name: &str) -> String {
+    format!("Unknown option: {name}")
+}
@@ -0,0 +1,175 @@
+use crate::style::{StyledContent, Stylize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    pub fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn rows(&self) -> std::ops::Range<u16> {
+        self.y..self.y + self.height
+    }
+
+    pub fn covers(&self, other: &Rect) -> bool {
+        self.x <= other.x
+            && self.y <= other.y
+            && self.x + self.width >= other.x + other.width
+            && self.y + self.height >= other.y + other.height
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Popup {
+    pub rect: Rect,
+    pub z: u8,
+    pub border: bool,
+    pub title: Option<String>,
+    pub header: Option<String>,
+    pub lines: Vec<StyledContent<String>>,
+}
+
+impl Popup {
+    pub fn new(rect: Rect, z: u8) -> Self {
+        Self {
+            rect,
+            z,
+            border: true,
+            title: None,
+            header: None,
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn header(mut self, header: impl Into<String>) -> Self {
+        self.header = Some(header.into());
+        self
+    }
+
+    pub fn borderless(mut self) -> Self {
+        self.border = false;
+        self
+    }
+
+    pub fn lines(mut self, lines: Vec<StyledContent<String>>) -> Self {
+        self.lines = lines;
+        self
+    }
+
+    pub fn content_height(&self) -> usize {
+        let chrome = match (self.border, self.header.is_some()) {
+            (true, true) => 4,
+            (true, false) => 2,
+            (false, true) => 1,
+            (false, false) => 0,
+        };
+
+        (self.rect.height as usize).saturating_sub(chrome)
+    }
+
+    pub fn render(&self) -> Vec<StyledContent<String>> {
+        let height = self.rect.height as usize;
+        let inner = if self.border {
+            self.rect.width.saturating_sub(2) as usize
+        } else {
+            self.rect.width as usize
+        };
+
+        let wrap = |content: StyledContent<String>| {
+            let text: String = content.content().chars().take(inner).collect();
+            let padded = StyledContent::new(*content.style(), format!("{:<1$}", text, inner));
+
+            if self.border {
+                format!("│{padded}│").reset()
+            } else {
+                format!("{padded}").reset()
+            }
+        };
+
+        let mut rows = Vec::new();
+
+        if self.border {
+            let title = self
+                .title
+                .as_ref()
+                .map(|title| format!(" {title} "))
+                .unwrap_or_default();
+            let title: String = title.chars().take(inner).collect();
+
+            rows.push(format!("┌{:─<1$}┐", title, inner).reset());
+        }
+
+        if let Some(header) = &self.header {
+            rows.push(wrap(header.clone().reset()));
+
+            if self.border {
+                rows.push(format!("├{:─<1$}┤", "", inner).reset());
+            }
+        }
+
+        let bottom = usize::from(self.border);
+
+        for line in self.lines.iter().take(self.content_height()) {
+            rows.push(wrap(line.clone()));
+        }
+
+        while rows.len() + bottom < height {
+            rows.push(wrap(String::new().reset()));
+        }
+
+        if self.border {
+            rows.push(format!("└{:─<1$}┘", "", inner).reset());
+        }
+
+        rows
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Overlays {
+    popups: Vec<Popup>,
+    previous: Vec<Rect>,
+}
+
+impl Overlays {
+    pub fn set(&mut self, mut popups: Vec<Popup>) {
+        popups.sort_by_key(|popup| popup.z);
+
+        self.previous = self.popups.iter().map(|popup| popup.rect).collect();
+        self.popups = popups;
+    }
+
+    pub fn popups(&self) -> &[Popup] {
+        &self.popups
+    }
+
+    pub fn exposed_rows(&self) -> Vec<u16> {
+        let mut rows = self
+            .previous
+            .iter()
+            .filter(|old| !self.popups.iter().any(|popup| popup.rect.covers(old)))
+            .flat_map(Rect::rows)
+            .collect::<Vec<_>>();
+
+        rows.sort_unstable();
+        rows.dedup();
+
+        rows
+    }
+}
@@ -0,0 +1,49 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+static DATA_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+pub fn set_data_dir(dir: PathBuf) {
+    if let Ok(mut data_dir) = DATA_DIR.lock() {
+        *data_dir = Some(dir);
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(base.join("myeditor"))
+}
+
+pub fn config_file(name: &str) -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(name))
+}
+
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = DATA_DIR.lock().ok().and_then(|dir| dir.clone()) {
+        return Some(dir);
+    }
+
+    let base = match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/share"),
+    };
+
+    Some(base.join("myeditor"))
+}
+
+pub fn data_file(name: &str) -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(name))
+}
+
+pub fn project_root(dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(dir)
+        .to_path_buf()
+}
@@ -0,0 +1,172 @@
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub struct Picker {
+    title: String,
+    items: Vec<PickerItem>,
+    query: String,
+    matches: Vec<usize>,
+    selected: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct PickerItem {
+    pub label: String,
+    pub detail: String,
+    pub target: Target,
+}
+
+#[derive(Debug, Clone)]
+pub enum Target {
+    Position {
+        line: usize,
+        col: usize,
+    },
+    File {
+        path: PathBuf,
+        line: usize,
+        col: usize,
+    },
+    Diff {
+        path: PathBuf,
+    },
+    Command {
+        command: String,
+        prefill: bool,
+    },
+    Keys {
+        keys: String,
+    },
+}
+
+impl Picker {
+    pub fn new(title: impl Into<String>, items: Vec<PickerItem>) -> Self {
+        let mut picker = Self {
+            title: title.into(),
+            items,
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+        };
+
+        picker.refilter();
+
+        picker
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        self.title = title.into();
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    pub fn item_count(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn extend(&mut self, items: Vec<PickerItem>) {
+        let selected = self.selected;
+
+        self.items.extend(items);
+        self.refilter();
+
+        self.selected = std::cmp::min(selected, self.matches.len().saturating_sub(1));
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.query.push(c);
+        self.refilter();
+    }
+
+    pub fn pop_char(&mut self) {
+        self.query.pop();
+        self.refilter();
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.matches.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn selected_item(&self) -> Option<&PickerItem> {
+        self.matches.get(self.selected).map(|&idx| &self.items[idx])
+    }
+
+    pub fn visible(&self, height: usize) -> impl Iterator<Item = (&PickerItem, bool)> {
+        let start = self.selected.saturating_sub(height.saturating_sub(1));
+
+        self.matches
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(height)
+            .map(|(i, &idx)| (&self.items[idx], i == self.selected))
+    }
+
+    fn refilter(&mut self) {
+        self.selected = 0;
+
+        if self.query.is_empty() {
+            self.matches = (0..self.items.len()).collect();
+            return;
+        }
+
+        let mut scored = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, item)| fuzzy_score(&self.query, &item.label).map(|s| (s, idx)))
+            .collect::<Vec<_>>();
+
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        self.matches = scored.into_iter().map(|(_, idx)| idx).collect();
+    }
+}
+
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut candidate_chars = candidate.chars().enumerate();
+    let mut last_match: Option<usize> = None;
+    let mut prev_char = None;
+
+    for q in query.chars().flat_map(char::to_lowercase) {
+        loop {
+            let (i, c) = candidate_chars.next()?;
+            let is_word_start = prev_char.is_none_or(|p: char| !p.is_alphanumeric());
+            prev_char = Some(c);
+
+            if c.to_lowercase().eq(std::iter::once(q)) {
+                score += 1;
+
+                if last_match.is_some_and(|last| last + 1 == i) {
+                    score += 5;
+                }
+
+                if is_word_start {
+                    score += 3;
+                }
+
+                last_match = Some(i);
+                break;
+            }
+        }
+    }
+
+    Some(score - candidate.len() as i64 / 16)
+}
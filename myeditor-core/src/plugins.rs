@@ -0,0 +1,397 @@
+use crate::{
+    buffer::{Buffer, LINE_BREAKS},
+    messages::Messages,
+    wasm::{Host, Import, Instance, ValType, Value},
+};
+use std::{fmt, fs, path::Path};
+
+const IMPORTS: [(&str, usize, bool, Option<Capability>); 10] = [
+    ("message", 2, false, None),
+    ("register_command", 2, false, None),
+    ("line_count", 0, true, Some(Capability::Read)),
+    ("line", 3, true, Some(Capability::Read)),
+    ("cursor_line", 0, true, Some(Capability::Read)),
+    ("cursor_col", 0, true, Some(Capability::Read)),
+    ("set_cursor", 2, false, Some(Capability::Read)),
+    ("set_line", 3, true, Some(Capability::Edit)),
+    ("insert", 2, false, Some(Capability::Edit)),
+    ("set_status", 2, false, Some(Capability::Status)),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    Read,
+    Edit,
+    Status,
+}
+
+#[derive(Debug, Default)]
+pub struct Plugins {
+    plugins: Vec<Plugin>,
+}
+
+#[derive(Debug)]
+struct Plugin {
+    name: String,
+    instance: Instance,
+    capabilities: Vec<Capability>,
+    commands: Vec<String>,
+    status: String,
+    failed: bool,
+}
+
+struct Context<'a> {
+    buffer: &'a mut Buffer,
+    messages: &'a mut Messages,
+    commands: &'a mut Vec<String>,
+    status: &'a mut String,
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Read => "read",
+            Self::Edit => "edit",
+            Self::Status => "status",
+        })
+    }
+}
+
+impl Capability {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "read" => Some(Self::Read),
+            "edit" => Some(Self::Edit),
+            "status" => Some(Self::Status),
+            _ => None,
+        }
+    }
+}
+
+impl Plugins {
+    pub fn load(dir: &Path, buffer: &mut Buffer, messages: &mut Messages) -> Self {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Self::default();
+        };
+
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "wasm"))
+            .collect();
+
+        paths.sort();
+
+        let mut plugins = Vec::new();
+
+        for path in paths {
+            let name = path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+
+            match Plugin::load(&name, &path, buffer, messages) {
+                Ok(plugin) => plugins.push(plugin),
+                Err(e) => messages.error(format!("Plugin {name}: {e}")),
+            }
+        }
+
+        Self { plugins }
+    }
+
+    pub fn on_key(&mut self, key: &str, buffer: &mut Buffer, messages: &mut Messages) -> bool {
+        self.plugins.iter_mut().any(|plugin| {
+            plugin
+                .call("on_key", &[key], buffer, messages)
+                .is_some_and(|result| result.first() != Some(&Value::I32(0)))
+        })
+    }
+
+    pub fn on_save(&mut self, path: &str, buffer: &mut Buffer, messages: &mut Messages) {
+        for plugin in &mut self.plugins {
+            plugin.call("on_save", &[path], buffer, messages);
+        }
+    }
+
+    pub fn run_command(
+        &mut self,
+        name: &str,
+        args: &str,
+        buffer: &mut Buffer,
+        messages: &mut Messages,
+    ) -> bool {
+        let Some(plugin) = self
+            .plugins
+            .iter_mut()
+            .find(|plugin| !plugin.failed && plugin.commands.iter().any(|c| c == name))
+        else {
+            return false;
+        };
+
+        plugin.call("on_command", &[name, args], buffer, messages);
+
+        true
+    }
+
+    pub fn status(&self) -> impl Iterator<Item = &str> {
+        self.plugins
+            .iter()
+            .filter(|plugin| !plugin.failed && !plugin.status.is_empty())
+            .map(|plugin| plugin.status.as_str())
+    }
+
+    pub fn describe(&self) -> Vec<String> {
+        self.plugins
+            .iter()
+            .map(|plugin| {
+                let capabilities: Vec<_> =
+                    plugin.capabilities.iter().map(|c| c.to_string()).collect();
+                let commands: Vec<_> = plugin.commands.iter().map(|c| format!(":{c}")).collect();
+
+                format!(
+                    "{}{}  [{}]  {}",
+                    plugin.name,
+                    if plugin.failed { " (failed)" } else { "" },
+                    capabilities.join(" "),
+                    commands.join(" ")
+                )
+                .trim_end()
+                .to_string()
+            })
+            .collect()
+    }
+}
+
+impl Plugin {
+    fn load(
+        name: &str,
+        path: &Path,
+        buffer: &mut Buffer,
+        messages: &mut Messages,
+    ) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        let capabilities = match fs::read_to_string(path.with_extension("caps")) {
+            Ok(text) => text
+                .split_whitespace()
+                .map(|word| {
+                    Capability::parse(word).ok_or_else(|| format!("Unknown capability {word}"))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Err(_) => Vec::new(),
+        };
+
+        let instance = Instance::new(&bytes)?;
+
+        for import in instance.imports() {
+            check_import(import, &capabilities)?;
+        }
+
+        let mut plugin = Self {
+            name: name.to_string(),
+            instance,
+            capabilities,
+            commands: Vec::new(),
+            status: String::new(),
+            failed: false,
+        };
+
+        plugin.instance.start(&mut Context {
+            buffer,
+            messages,
+            commands: &mut plugin.commands,
+            status: &mut plugin.status,
+        })?;
+        plugin.call("init", &[], buffer, messages);
+
+        Ok(plugin)
+    }
+
+    fn call(
+        &mut self,
+        export: &str,
+        strings: &[&str],
+        buffer: &mut Buffer,
+        messages: &mut Messages,
+    ) -> Option<Vec<Value>> {
+        if self.failed || !self.instance.has_function(export) {
+            return None;
+        }
+
+        let mut context = Context {
+            buffer,
+            messages,
+            commands: &mut self.commands,
+            status: &mut self.status,
+        };
+
+        match invoke(&mut self.instance, export, strings, &mut context) {
+            Ok(result) => Some(result),
+            Err(e) => {
+                self.failed = true;
+                self.status.clear();
+                messages.error(format!("Plugin {} failed in {export}: {e}", self.name));
+
+                None
+            }
+        }
+    }
+}
+
+impl Host for Context<'_> {
+    fn call(
+        &mut self,
+        import: &Import,
+        args: &[Value],
+        memory: &mut [u8],
+    ) -> Result<Option<Value>, String> {
+        let int = |i: usize| match args.get(i) {
+            Some(Value::I32(value)) => *value,
+            _ => 0,
+        };
+        let text = &self.buffer.text;
+
+        Ok(match import.name.as_str() {
+            "message" => {
+                self.messages.info(string(memory, int(0), int(1))?);
+
+                None
+            }
+            "register_command" => {
+                let name = string(memory, int(0), int(1))?;
+
+                if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    return Err(format!("Invalid command name {name:?}"));
+                }
+
+                self.commands.push(name);
+
+                None
+            }
+            "line_count" => Some(Value::I32(text.len_lines() as i32)),
+            "line" => {
+                let Some(line) = usize::try_from(int(0))
+                    .ok()
+                    .filter(|&line| line < text.len_lines())
+                else {
+                    return Ok(Some(Value::I32(-1)));
+                };
+
+                let line = text.line(line).to_string();
+                let content = line.trim_end_matches(LINE_BREAKS).as_bytes();
+                let len = content.len().min(int(2).max(0) as usize);
+
+                slice(memory, int(1), len as i32)?.copy_from_slice(&content[..len]);
+
+                Some(Value::I32(content.len() as i32))
+            }
+            "cursor_line" => Some(Value::I32(self.buffer.cursor.1 as i32)),
+            "cursor_col" => Some(Value::I32(self.buffer.cursor.0 as i32)),
+            "set_cursor" => {
+                let line = (int(0).max(0) as usize).min(text.len_lines() - 1);
+                let content = text.line(line).to_string();
+                let len = content.trim_end_matches(LINE_BREAKS).chars().count();
+
+                self.buffer.cursor = ((int(1).max(0) as usize).min(len), line);
+
+                None
+            }
+            "set_line" => {
+                let replacement = string(memory, int(1), int(2))?;
+                let Some(line) = usize::try_from(int(0))
+                    .ok()
+                    .filter(|&line| line < text.len_lines() && !self.buffer.read_only)
+                else {
+                    return Ok(Some(Value::I32(-1)));
+                };
+
+                if replacement.contains(LINE_BREAKS) {
+                    return Ok(Some(Value::I32(-1)));
+                }
+
+                let start = text.line_to_char(line);
+                let content = text.line(line).to_string();
+                let end = start + content.trim_end_matches(LINE_BREAKS).chars().count();
+
+                self.buffer.remove(start..end)?;
+                self.buffer.insert(start, &replacement)?;
+
+                Some(Value::I32(0))
+            }
+            "insert" => {
+                let insertion = string(memory, int(0), int(1))?;
+
+                if !self.buffer.read_only {
+                    let (col, line) = self.buffer.cursor;
+                    let idx = (text.line_to_char(line) + col).min(text.len_chars());
+
+                    self.buffer.insert(idx, &insertion)?;
+                }
+
+                None
+            }
+            "set_status" => {
+                *self.status = string(memory, int(0), int(1))?.replace(LINE_BREAKS, " ");
+
+                None
+            }
+            name => return Err(format!("Unknown import {}.{name}", import.module)),
+        })
+    }
+}
+
+fn check_import(import: &Import, capabilities: &[Capability]) -> Result<(), String> {
+    let known = IMPORTS
+        .iter()
+        .find(|(name, ..)| import.module == "myeditor" && *name == import.name);
+
+    let Some(&(_, params, returns, capability)) = known else {
+        return Err(format!("Unknown import {}.{}", import.module, import.name));
+    };
+
+    if import.ty.params != vec![ValType::I32; params]
+        || import.ty.results != vec![ValType::I32; returns as usize]
+    {
+        return Err(format!("Wrong type for import {}", import.name));
+    }
+
+    match capability {
+        Some(capability) if !capabilities.contains(&capability) => {
+            Err(format!("{} needs the {capability} capability", import.name))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn invoke(
+    instance: &mut Instance,
+    export: &str,
+    strings: &[&str],
+    context: &mut Context,
+) -> Result<Vec<Value>, String> {
+    let mut args = Vec::new();
+
+    for s in strings {
+        let len = i32::try_from(s.len()).map_err(|_| "String too long")?;
+        let ptr = match instance.call("alloc", &[Value::I32(len)], context)?[..] {
+            [Value::I32(ptr)] => ptr,
+            _ => return Err("alloc must return an i32".to_string()),
+        };
+
+        slice(instance.memory_mut(), ptr, len)?.copy_from_slice(s.as_bytes());
+        args.extend([Value::I32(ptr), Value::I32(len)]);
+    }
+
+    instance.call(export, &args, context)
+}
+
+fn slice(memory: &mut [u8], ptr: i32, len: i32) -> Result<&mut [u8], String> {
+    let start = ptr as u32 as usize;
+
+    memory
+        .get_mut(start..start + len.max(0) as usize)
+        .ok_or_else(|| "Pointer out of bounds".to_string())
+}
+
+fn string(memory: &mut [u8], ptr: i32, len: i32) -> Result<String, String> {
+    Ok(String::from_utf8_lossy(slice(memory, ptr, len)?).to_string())
+}
@@ -0,0 +1,55 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+static PROFILE: Mutex<Option<Profile>> = Mutex::new(None);
+
+#[derive(Debug)]
+struct Profile {
+    start: Instant,
+    last: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+pub fn start(start: Instant) {
+    if let Ok(mut profile) = PROFILE.lock() {
+        *profile = Some(Profile {
+            start,
+            last: start,
+            phases: Vec::new(),
+        });
+    }
+}
+
+pub fn mark(phase: &'static str) {
+    let Ok(mut profile) = PROFILE.lock() else {
+        return;
+    };
+
+    if let Some(profile) = profile.as_mut() {
+        let now = Instant::now();
+
+        profile.phases.push((phase, now - profile.last));
+        profile.last = now;
+    }
+}
+
+pub fn report() -> Option<String> {
+    let profile = PROFILE.lock().ok()?.take()?;
+    let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+
+    let mut lines = profile
+        .phases
+        .iter()
+        .map(|&(phase, duration)| format!("{phase:<16}{:>10.3} ms", ms(duration)))
+        .collect::<Vec<_>>();
+
+    lines.push(format!(
+        "{:<16}{:>10.3} ms",
+        "total",
+        ms(profile.last - profile.start)
+    ));
+
+    Some(lines.join("\n"))
+}
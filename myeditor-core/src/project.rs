@@ -0,0 +1,262 @@
+use crate::{indent::Indent, paths, sha256};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+pub const FILE_NAME: &str = ".myeditor.toml";
+
+const TRUST_FILE: &str = "trusted";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectConfig {
+    pub path: PathBuf,
+    pub shiftwidth: Option<usize>,
+    pub expandtab: Option<bool>,
+    pub makeprg: Option<String>,
+    pub format: BTreeMap<String, String>,
+    pub lsp: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    String(String),
+    Integer(usize),
+    Bool(bool),
+}
+
+impl ProjectConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        Self::from_text(path, &Self::read(path)?)
+    }
+
+    pub fn read(path: &Path) -> Result<String, String> {
+        std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    pub fn from_text(path: &Path, text: &str) -> Result<Self, String> {
+        parse(text)
+            .map(|config| Self {
+                path: path.to_path_buf(),
+                ..config
+            })
+            .map_err(|e| format!("{}:{e}", path.display()))
+    }
+
+    pub fn root(&self) -> &Path {
+        self.path.parent().unwrap_or(&self.path)
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        path.starts_with(self.root())
+    }
+
+    pub fn indent(&self, current: Indent) -> Option<Indent> {
+        if self.shiftwidth.is_none() && self.expandtab.is_none() {
+            return None;
+        }
+
+        Some(Indent {
+            tabs: self.expandtab.map_or(current.tabs, |expandtab| !expandtab),
+            width: self.shiftwidth.unwrap_or(current.width),
+        })
+    }
+}
+
+pub fn find(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+pub fn parse(text: &str) -> Result<ProjectConfig, String> {
+    let mut config = ProjectConfig::default();
+    let mut section = String::new();
+
+    for (idx, line) in text.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        let error = |message: String| format!("{}: {message}", idx + 1);
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .ok_or_else(|| error("Missing ] after the table name".to_string()))?
+                .trim();
+
+            if !matches!(name, "format" | "lsp") {
+                return Err(error(format!("Unknown table: {name}")));
+            }
+
+            section = name.to_string();
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error(format!("Expected key = value: {line}")))?;
+        let key = unquote(key.trim());
+        let value = parse_value(value.trim()).map_err(error)?;
+
+        match (section.as_str(), key.as_str(), value) {
+            ("", "shiftwidth" | "sw", Value::Integer(width)) if width > 0 => {
+                config.shiftwidth = Some(width)
+            }
+            ("", "expandtab" | "et", Value::Bool(expandtab)) => config.expandtab = Some(expandtab),
+            ("", "makeprg" | "mp", Value::String(command)) => config.makeprg = Some(command),
+            ("", "shiftwidth" | "sw" | "expandtab" | "et" | "makeprg" | "mp", _) => {
+                return Err(error(format!("Invalid value for {key}")))
+            }
+            ("format", _, Value::String(command)) => {
+                config.format.insert(key, command);
+            }
+            ("lsp", _, Value::String(command)) => {
+                config.lsp.insert(key, command);
+            }
+            ("format" | "lsp", _, _) => {
+                return Err(error(format!("Expected a command string for {key}")))
+            }
+            _ => return Err(error(format!("Unknown setting: {key}"))),
+        }
+    }
+
+    Ok(config)
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (idx, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => return &line[..idx],
+            _ => {}
+        }
+
+        escaped = false;
+    }
+
+    line
+}
+
+fn unquote(key: &str) -> String {
+    match parse_value(key) {
+        Ok(Value::String(key)) => key,
+        _ => key.to_string(),
+    }
+}
+
+fn parse_value(value: &str) -> Result<Value, String> {
+    if let Some(text) = value.strip_prefix('\'') {
+        return text
+            .strip_suffix('\'')
+            .map(|text| Value::String(text.to_string()))
+            .ok_or_else(|| format!("Unterminated string: {value}"));
+    }
+
+    if let Some(text) = value.strip_prefix('"') {
+        let text = text
+            .strip_suffix('"')
+            .ok_or_else(|| format!("Unterminated string: {value}"))?;
+        let mut out = String::new();
+        let mut chars = text.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+
+            out.push(match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some(c @ ('"' | '\\')) => c,
+                _ => return Err(format!("Invalid escape in {value}")),
+            });
+        }
+
+        return Ok(Value::String(out));
+    }
+
+    match value {
+        "true" => Ok(Value::Bool(true)),
+        "false" => Ok(Value::Bool(false)),
+        _ => value
+            .replace('_', "")
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| format!("Invalid value: {value}")),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trusted {
+    Yes,
+    No,
+    Changed,
+}
+
+#[derive(Debug, Default)]
+pub struct Trust {
+    entries: Vec<(String, PathBuf)>,
+}
+
+impl Trust {
+    pub fn load() -> Self {
+        let entries = paths::data_file(TRUST_FILE)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|text| {
+                text.lines()
+                    .filter_map(|line| {
+                        let (hash, path) = line.split_once(' ')?;
+
+                        Some((hash.to_string(), PathBuf::from(path)))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { entries }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = paths::data_file(TRUST_FILE) else {
+            return Ok(());
+        };
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let text = self
+            .entries
+            .iter()
+            .map(|(hash, path)| format!("{hash} {}\n", path.display()))
+            .collect::<String>();
+
+        std::fs::write(path, text)
+    }
+
+    pub fn check(&self, path: &Path, text: &str) -> Trusted {
+        match self.entries.iter().find(|(_, trusted)| trusted == path) {
+            Some((hash, _)) if *hash == sha256::hex_digest(text.as_bytes()) => Trusted::Yes,
+            Some(_) => Trusted::Changed,
+            None => Trusted::No,
+        }
+    }
+
+    pub fn trust(&mut self, path: PathBuf, text: &str) {
+        self.entries.retain(|(_, trusted)| *trusted != path);
+        self.entries
+            .push((sha256::hex_digest(text.as_bytes()), path));
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub struct Prompt {
+    pub message: String,
+    pub action: PromptAction,
+    cancellable: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum PromptAction {
+    SaveAndQuit,
+    Overwrite {
+        then_quit: bool,
+    },
+    WriteAs {
+        path: PathBuf,
+        rebind: bool,
+        then_quit: bool,
+    },
+    Reload,
+    TrustProject {
+        path: PathBuf,
+        text: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Yes,
+    No,
+    Cancel,
+}
+
+impl Prompt {
+    pub fn yes_no(message: impl Into<String>, action: PromptAction) -> Self {
+        Self {
+            message: message.into(),
+            action,
+            cancellable: false,
+        }
+    }
+
+    pub fn yes_no_cancel(message: impl Into<String>, action: PromptAction) -> Self {
+        Self {
+            message: message.into(),
+            action,
+            cancellable: true,
+        }
+    }
+
+    pub fn choices(&self) -> &'static str {
+        if self.cancellable {
+            "[y]es, [n]o, [c]ancel"
+        } else {
+            "[y]es, [n]o"
+        }
+    }
+
+    pub fn answer(&self, key: char) -> Option<Answer> {
+        match key.to_ascii_lowercase() {
+            'y' => Some(Answer::Yes),
+            'n' => Some(Answer::No),
+            'c' if self.cancellable => Some(Answer::Cancel),
+            _ => None,
+        }
+    }
+
+    pub fn dismiss(&self) -> Answer {
+        if self.cancellable {
+            Answer::Cancel
+        } else {
+            Answer::No
+        }
+    }
+}
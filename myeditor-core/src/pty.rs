@@ -0,0 +1,140 @@
+use crate::events::AppEvent;
+use std::{
+    fs::File,
+    io::{Read, Write},
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::process::CommandExt,
+    },
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::mpsc::Sender,
+    thread,
+};
+
+#[derive(Debug)]
+pub struct Pty {
+    master: File,
+    child: Child,
+}
+
+impl Pty {
+    pub fn spawn(
+        command: Option<&str>,
+        (cols, rows): (u16, u16),
+        id: usize,
+        tx: Sender<AppEvent>,
+    ) -> std::io::Result<Self> {
+        let (mut master, mut slave) = (0, 0);
+        let size = window_size(cols, rows);
+
+        if unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                std::ptr::null_mut(),
+                std::ptr::null(),
+                &size,
+            )
+        } != 0
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let master = unsafe { File::from_raw_fd(master) };
+        let slave = unsafe { OwnedFd::from_raw_fd(slave) };
+
+        for fd in [master.as_raw_fd(), slave.as_raw_fd()] {
+            if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+        let mut process = Command::new(&shell);
+
+        if let Some(command) = command {
+            process.arg("-c").arg(command);
+        }
+
+        process
+            .env("TERM", "dumb")
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+
+        unsafe {
+            process.pre_exec(|| {
+                if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY, 0) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+
+                Ok(())
+            });
+        }
+
+        let child = process.spawn()?;
+
+        log::info!("spawned {shell} in a pty, pid {}", child.id());
+
+        let mut reader = master.try_clone()?;
+
+        thread::spawn(move || {
+            let mut buf = [0; 4096];
+
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) | Err(_) => {
+                        let _ = tx.send(AppEvent::TerminalExited(id));
+
+                        return;
+                    }
+                    Ok(n) => {
+                        if tx.send(AppEvent::Terminal(id, buf[..n].to_vec())).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(Self { master, child })
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.master.write_all(bytes)
+    }
+
+    pub fn resize(&self, cols: u16, rows: u16) {
+        let size = window_size(cols, rows);
+
+        unsafe {
+            libc::ioctl(
+                std::os::fd::AsRawFd::as_raw_fd(&self.master),
+                libc::TIOCSWINSZ,
+                &size,
+            );
+        }
+    }
+
+    pub fn wait(&mut self) -> Option<ExitStatus> {
+        self.child.wait().ok()
+    }
+}
+
+impl Drop for Pty {
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+fn window_size(cols: u16, rows: u16) -> libc::winsize {
+    libc::winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    }
+}
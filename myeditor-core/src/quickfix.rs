@@ -0,0 +1,222 @@
+use crate::jobs::Progress;
+use std::{
+    io::{self, Read},
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    thread,
+    time::Duration,
+};
+
+const POLL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub line: usize,
+    pub col: usize,
+    pub error: bool,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub struct Finished {
+    pub command: String,
+    pub dir: PathBuf,
+    pub status: io::Result<ExitStatus>,
+    pub output: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Quickfix {
+    entries: Vec<Entry>,
+    current: usize,
+}
+
+impl Quickfix {
+    pub fn new(entries: Vec<Entry>) -> Self {
+        let current = entries.iter().position(|entry| entry.error).unwrap_or(0);
+
+        Self { entries, current }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn current(&self) -> Option<(usize, &Entry)> {
+        self.entries
+            .get(self.current)
+            .map(|entry| (self.current, entry))
+    }
+
+    pub fn select_next(&mut self) -> Option<(usize, &Entry)> {
+        if self.current + 1 >= self.entries.len() {
+            return None;
+        }
+
+        self.current += 1;
+        self.current()
+    }
+
+    pub fn select_prev(&mut self) -> Option<(usize, &Entry)> {
+        self.current = self.current.checked_sub(1)?;
+        self.current()
+    }
+}
+
+pub fn run(
+    command: String,
+    dir: PathBuf,
+    progress: Progress,
+    done: impl FnOnce(Finished) + Send + 'static,
+) {
+    thread::spawn(move || {
+        let (status, output) = match wait(&command, &dir, &progress) {
+            Ok((status, output)) => (Ok(status), output),
+            Err(e) => (Err(e), String::new()),
+        };
+
+        if !progress.is_cancelled() {
+            done(Finished {
+                command,
+                dir,
+                status,
+                output,
+            });
+        }
+    });
+}
+
+fn wait(command: &str, dir: &Path, progress: &Progress) -> io::Result<(ExitStatus, String)> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(format!("({command}) 2>&1"))
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .process_group(0)
+        .spawn()?;
+
+    let mut stdout = child.stdout.take();
+    let reader = thread::spawn(move || {
+        let mut output = Vec::new();
+
+        if let Some(stdout) = &mut stdout {
+            let _ = stdout.read_to_end(&mut output);
+        }
+
+        output
+    });
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            let output = reader.join().unwrap_or_default();
+
+            return Ok((status, String::from_utf8_lossy(&output).into_owned()));
+        }
+
+        if progress.is_cancelled() {
+            let _ = Command::new("kill")
+                .args(["-s", "TERM", "--", &format!("-{}", child.id())])
+                .stderr(Stdio::null())
+                .status();
+            let _ = child.wait();
+
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+
+        thread::sleep(POLL);
+    }
+}
+
+pub fn parse(output: &str, dir: &Path) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut header = None;
+
+    for line in output.lines() {
+        let line = line.trim_end();
+
+        if line.starts_with("error") || line.starts_with("warning") {
+            if let Some(kind) = line.split(':').next().filter(|kind| !kind.contains(' ')) {
+                header = Some((kind.starts_with("error"), line.to_string()));
+            }
+        }
+
+        if let Some(location) = line.trim_start().strip_prefix("--> ") {
+            if let (Some((error, message)), Some((path, line, col))) =
+                (header.take(), split_location(location))
+            {
+                entries.push(Entry {
+                    path: dir.join(path),
+                    line,
+                    col,
+                    error,
+                    message,
+                });
+            }
+
+            continue;
+        }
+
+        if let Some(entry) = parse_tsc(line, dir).or_else(|| parse_gcc(line, dir)) {
+            entries.push(entry);
+        }
+    }
+
+    entries
+}
+
+fn split_location(location: &str) -> Option<(&str, usize, usize)> {
+    let mut parts = location.rsplitn(3, ':');
+    let col = parts.next()?.parse::<usize>().ok()?;
+    let line = parts.next()?.parse::<usize>().ok()?;
+
+    Some((parts.next()?, line.checked_sub(1)?, col.saturating_sub(1)))
+}
+
+fn parse_tsc(line: &str, dir: &Path) -> Option<Entry> {
+    let (path, rest) = line.split_once('(')?;
+    let (position, message) = rest.split_once("): ")?;
+    let (line, col) = position.split_once(',')?;
+
+    entry(dir, path, line.parse().ok()?, col.parse().ok()?, message)
+}
+
+fn parse_gcc(line: &str, dir: &Path) -> Option<Entry> {
+    let mut parts = line.splitn(3, ':');
+    let path = parts.next()?;
+    let number = parts.next()?.parse().ok()?;
+    let rest = parts.next()?;
+
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (col, message) = match (rest[..digits].parse(), &rest[digits..]) {
+        (Ok(col), message) if message.starts_with(':') => (col, &message[1..]),
+        (Ok(col), message) if message.starts_with(" - ") => (col, &message[3..]),
+        _ => (1, rest),
+    };
+
+    entry(dir, path, number, col, message)
+}
+
+fn entry(dir: &Path, path: &str, line: usize, col: usize, message: &str) -> Option<Entry> {
+    let message = message.trim();
+
+    if path.is_empty() || path.starts_with(char::is_whitespace) || message.starts_with("note") {
+        return None;
+    }
+
+    let path = dir.join(path);
+
+    if !path.is_file() {
+        return None;
+    }
+
+    Some(Entry {
+        path,
+        line: line.checked_sub(1)?,
+        col: col.saturating_sub(1),
+        error: !message.starts_with("warning"),
+        message: message.to_string(),
+    })
+}
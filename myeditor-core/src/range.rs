@@ -0,0 +1,116 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl LineRange {
+    pub fn lines(&self) -> std::ops::Range<usize> {
+        self.start..self.end + 1
+    }
+
+    pub fn count(&self) -> usize {
+        self.end + 1 - self.start
+    }
+}
+
+pub fn parse(
+    command: &str,
+    current: usize,
+    last: usize,
+    mark: impl Fn(char) -> Option<usize>,
+) -> Result<(Option<LineRange>, &str), String> {
+    if let Some(rest) = command.strip_prefix('%') {
+        return Ok((
+            Some(LineRange {
+                start: 0,
+                end: last,
+            }),
+            rest,
+        ));
+    }
+
+    let (Some(start), rest) = address(command, current, last, &mark)? else {
+        return Ok((None, command));
+    };
+
+    let (end, rest) = match rest.strip_prefix([',', ';']) {
+        Some(after) => {
+            let base = if rest.starts_with(';') {
+                start
+            } else {
+                current
+            };
+
+            match address(after, base, last, &mark)? {
+                (Some(end), rest) => (end, rest),
+                (None, _) => (current, after),
+            }
+        }
+        None => (start, rest),
+    };
+
+    let (start, end) = (start.min(end), start.max(end));
+
+    Ok((Some(LineRange { start, end }), rest))
+}
+
+fn address<'a>(
+    text: &'a str,
+    current: usize,
+    last: usize,
+    mark: &impl Fn(char) -> Option<usize>,
+) -> Result<(Option<usize>, &'a str), String> {
+    let (mut line, mut rest) = match text.chars().next() {
+        Some('.') => (Some(current as isize), &text[1..]),
+        Some('$') => (Some(last as isize), &text[1..]),
+        Some('\'') => {
+            let name = text[1..].chars().next().ok_or("Missing mark name")?;
+            let line = mark(name).ok_or(format!("Mark not set: {name}"))?;
+
+            (Some(line as isize), &text[1 + name.len_utf8()..])
+        }
+        Some(c) if c.is_ascii_digit() => {
+            let (digits, rest) = split_number(text);
+            let number = digits
+                .parse::<isize>()
+                .map_err(|_| format!("Invalid line number: {digits}"))?;
+
+            (Some((number - 1).max(0)), rest)
+        }
+        _ => (None, text),
+    };
+
+    while let Some(sign) = rest.chars().next().filter(|c| matches!(c, '+' | '-')) {
+        let (digits, after) = split_number(&rest[1..]);
+        let offset = match digits {
+            "" => 1,
+            digits => digits
+                .parse::<isize>()
+                .map_err(|_| format!("Invalid offset: {digits}"))?,
+        };
+
+        let base = line.unwrap_or(current as isize);
+
+        line = Some(if sign == '+' {
+            base.saturating_add(offset)
+        } else {
+            base.saturating_sub(offset)
+        });
+        rest = after;
+    }
+
+    match line {
+        Some(line) if line < 0 => Err("Invalid range: before the first line".to_string()),
+        Some(line) => Ok((Some(line as usize), rest)),
+        None => Ok((None, rest)),
+    }
+}
+
+fn split_number(text: &str) -> (&str, &str) {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+
+    text.split_at(end)
+}
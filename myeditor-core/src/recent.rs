@@ -0,0 +1,80 @@
+use crate::paths;
+use std::path::{Path, PathBuf};
+
+const MAX_ENTRIES: usize = 100;
+const FILE_NAME: &str = "oldfiles";
+
+#[derive(Debug, Default)]
+pub struct RecentFiles {
+    entries: Vec<RecentFile>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecentFile {
+    pub path: PathBuf,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl RecentFiles {
+    pub fn load() -> Self {
+        let Some(text) = paths::data_file(FILE_NAME).and_then(|p| std::fs::read_to_string(p).ok())
+        else {
+            return Self::default();
+        };
+
+        let entries = text
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, '\t');
+
+                Some(RecentFile {
+                    line: parts.next()?.parse().ok()?,
+                    col: parts.next()?.parse().ok()?,
+                    path: PathBuf::from(parts.next()?),
+                })
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = paths::data_file(FILE_NAME) else {
+            return Ok(());
+        };
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let text = self
+            .entries
+            .iter()
+            .map(|entry| format!("{}\t{}\t{}\n", entry.line, entry.col, entry.path.display()))
+            .collect::<String>();
+
+        std::fs::write(path, text)
+    }
+
+    pub fn entries(&self) -> &[RecentFile] {
+        &self.entries
+    }
+
+    pub fn position(&self, path: &Path) -> Option<(usize, usize)> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        self.entries
+            .iter()
+            .find(|entry| entry.path == path)
+            .map(|entry| (entry.line, entry.col))
+    }
+
+    pub fn record(&mut self, path: &Path, line: usize, col: usize) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        self.entries.retain(|entry| entry.path != path);
+        self.entries.insert(0, RecentFile { path, line, col });
+        self.entries.truncate(MAX_ENTRIES);
+    }
+}
@@ -0,0 +1,133 @@
+const LEADERS: [&str; 8] = ["///", "//!", "//", "#", "--", ";;", ";", ">"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leader {
+    pub first: String,
+    pub rest: String,
+}
+
+pub fn leader(line: &str) -> Leader {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let after = &line[indent.len()..];
+
+    for leader in LEADERS {
+        if let Some(text) = after.strip_prefix(leader) {
+            if text.is_empty() || text.starts_with(char::is_whitespace) {
+                let spaces = text.len() - text.trim_start().len();
+                let prefix = format!("{indent}{leader}{}", &text[..spaces]);
+
+                return Leader {
+                    first: prefix.clone(),
+                    rest: prefix,
+                };
+            }
+        }
+    }
+
+    let digits = after.chars().take_while(char::is_ascii_digit).count();
+    let marker = match after[digits..].chars().next() {
+        Some('.' | ')') if digits > 0 => digits + 1,
+        Some('-' | '*' | '+') if digits == 0 => 1,
+        _ => 0,
+    };
+
+    let text = &after[marker..];
+
+    if marker > 0 && text.starts_with(' ') {
+        let spaces = text.len() - text.trim_start().len();
+        let width = marker + spaces;
+
+        return Leader {
+            first: format!("{indent}{}", &after[..width]),
+            rest: format!("{indent}{}", " ".repeat(width)),
+        };
+    }
+
+    Leader {
+        first: indent.to_string(),
+        rest: indent.to_string(),
+    }
+}
+
+pub fn starts_item(line: &str) -> bool {
+    let leader = leader(line);
+
+    leader.first != leader.rest
+}
+
+pub fn is_blank(line: &str) -> bool {
+    let leader = leader(line);
+
+    line.len() == leader.first.len() || line[leader.first.len()..].trim().is_empty()
+}
+
+pub fn reflow(lines: &[&str], width: usize) -> Vec<String> {
+    let Some(first) = lines.first() else {
+        return Vec::new();
+    };
+
+    let leader = leader(first);
+    let words = lines
+        .iter()
+        .enumerate()
+        .flat_map(|(i, line)| {
+            let prefix = if i == 0 { &leader.first } else { &leader.rest };
+
+            let text = match line.strip_prefix(prefix.as_str()) {
+                Some(text) => text,
+                None => {
+                    let own = self::leader(line);
+
+                    &line[own.rest.len().min(own.first.len())..]
+                }
+            };
+
+            text.split_whitespace()
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = Vec::new();
+    let mut current = leader.first.clone();
+    let mut empty = true;
+
+    for word in words {
+        let len = current.chars().count() + usize::from(!empty) + word.chars().count();
+
+        if !empty && len > width {
+            out.push(current);
+            current = leader.rest.clone();
+            empty = true;
+        }
+
+        if !empty {
+            current.push(' ');
+        }
+
+        current.push_str(word);
+        empty = false;
+    }
+
+    out.push(current.trim_end().to_string());
+
+    out
+}
+
+pub fn soft_wrap(line: &str, width: usize) -> Vec<usize> {
+    let chars = line.chars().collect::<Vec<_>>();
+    let width = std::cmp::max(width, 1);
+    let mut starts = vec![0];
+    let mut start = 0;
+
+    while chars.len() - start > width {
+        let limit = start + width;
+        let end = (start + 1..=limit)
+            .rev()
+            .find(|&i| chars[i] == ' ' && chars[i - 1] != ' ')
+            .map_or(limit, |i| i + 1);
+
+        starts.push(end);
+        start = end;
+    }
+
+    starts
+}
@@ -0,0 +1,761 @@
+const MAX_REPEAT: usize = 1000;
+const MAX_PROGRAM: usize = 10_000;
+const MAX_NESTING: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct Regex {
+    program: Vec<Inst>,
+    groups: usize,
+    case_insensitive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub start: usize,
+    pub end: usize,
+    groups: Vec<Option<(usize, usize)>>,
+}
+
+impl Match {
+    pub fn group(&self, idx: usize) -> Option<(usize, usize)> {
+        if idx == 0 {
+            Some((self.start, self.end))
+        } else {
+            self.groups.get(idx - 1).copied().flatten()
+        }
+    }
+
+    pub fn expand(&self, text: &[char], replacement: &str) -> String {
+        let mut out = String::new();
+        let mut chars = replacement.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '$' {
+                out.push(c);
+                continue;
+            }
+
+            match chars.peek().and_then(|c| c.to_digit(10)) {
+                Some(idx) => {
+                    chars.next();
+
+                    if let Some((start, end)) = self.group(idx as usize) {
+                        out.extend(&text[start..end]);
+                    }
+                }
+                None => {
+                    if chars.peek() == Some(&'$') {
+                        chars.next();
+                    }
+
+                    out.push('$');
+                }
+            }
+        }
+
+        out
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(Class),
+    Assert(Assertion),
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Class {
+    negated: bool,
+    items: Vec<ClassItem>,
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Assertion {
+    LineStart,
+    LineEnd,
+    WordBoundary,
+    NotWordBoundary,
+}
+
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Assert(Assertion),
+    Split(usize, usize),
+    Jmp(usize),
+    Save(usize),
+    Match,
+}
+
+impl Regex {
+    pub fn new(pattern: &str, case_insensitive: bool) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            groups: 0,
+            depth: 0,
+        };
+
+        let node = parser.parse_alt()?;
+
+        if parser.pos < parser.chars.len() {
+            return Err(format!("Unmatched ')' in pattern: {pattern}"));
+        }
+
+        let mut program = Vec::new();
+        compile(&node, &mut program, case_insensitive)?;
+        program.push(Inst::Match);
+
+        Ok(Self {
+            program,
+            groups: parser.groups,
+            case_insensitive,
+        })
+    }
+
+    pub fn replace_all(&self, text: &str, replacement: &str) -> Option<String> {
+        self.replace(text, replacement, true)
+    }
+
+    pub fn replace_first(&self, text: &str, replacement: &str) -> Option<String> {
+        self.replace(text, replacement, false)
+    }
+
+    fn replace(&self, text: &str, replacement: &str, all: bool) -> Option<String> {
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut out = String::new();
+        let mut last = 0;
+        let mut pos = 0;
+        let mut replaced = false;
+
+        while let Some(m) = self.find_at(&chars, pos) {
+            out.extend(&chars[last..m.start]);
+            out.push_str(&m.expand(&chars, replacement));
+            replaced = true;
+
+            if !all {
+                last = m.end;
+                break;
+            }
+
+            if m.end > m.start {
+                last = m.end;
+                pos = m.end;
+            } else if m.start < chars.len() {
+                out.push(chars[m.start]);
+                last = m.start + 1;
+                pos = m.start + 1;
+            } else {
+                last = m.start;
+                break;
+            }
+        }
+
+        if !replaced {
+            return None;
+        }
+
+        out.extend(&chars[last..]);
+
+        Some(out)
+    }
+
+    pub fn prefix(&self) -> String {
+        if self.case_insensitive {
+            return String::new();
+        }
+
+        self.program
+            .iter()
+            .map_while(|inst| match inst {
+                Inst::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn find_at(&self, text: &[char], start: usize) -> Option<Match> {
+        let slots = self.groups * 2 + 2;
+
+        let mut current = Threads::new(self.program.len(), slots);
+        let mut next = Threads::new(self.program.len(), slots);
+        let mut saves = vec![None; slots];
+        let mut stack = Vec::new();
+        let mut found = None;
+
+        for pos in start..=text.len() {
+            if found.is_none() {
+                saves.fill(None);
+                saves[0] = Some(pos);
+
+                self.add(&mut current, 0, text, pos, &mut saves, &mut stack);
+            }
+
+            for (i, &pc) in current.pcs.iter().enumerate() {
+                let c = text.get(pos).copied();
+
+                let step = match &self.program[pc] {
+                    Inst::Char(expected) => {
+                        c.is_some_and(|c| fold(c, self.case_insensitive) == *expected)
+                    }
+                    Inst::Any => c.is_some_and(|c| c != '\n'),
+                    Inst::Class(class) => {
+                        c.is_some_and(|c| class.matches(c, self.case_insensitive))
+                    }
+                    Inst::Match => {
+                        let mut caps = current.saves(i).to_vec();
+                        caps[1] = Some(pos);
+                        found = Some(caps);
+
+                        break;
+                    }
+                    _ => false,
+                };
+
+                if step {
+                    saves.copy_from_slice(current.saves(i));
+
+                    self.add(&mut next, pc + 1, text, pos + 1, &mut saves, &mut stack);
+                }
+            }
+
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+
+            if found.is_some() && current.pcs.is_empty() {
+                break;
+            }
+        }
+
+        let saves = found?;
+        let groups = (1..=self.groups)
+            .map(|g| match (saves[g * 2], saves[g * 2 + 1]) {
+                (Some(s), Some(e)) => Some((s, e)),
+                _ => None,
+            })
+            .collect();
+
+        Some(Match {
+            start: saves[0]?,
+            end: saves[1]?,
+            groups,
+        })
+    }
+
+    fn add(
+        &self,
+        threads: &mut Threads,
+        pc: usize,
+        text: &[char],
+        pos: usize,
+        saves: &mut [Option<usize>],
+        stack: &mut Vec<Job>,
+    ) {
+        stack.push(Job::Run(pc));
+
+        while let Some(job) = stack.pop() {
+            let mut pc = match job {
+                Job::Run(pc) => pc,
+                Job::Restore(slot, value) => {
+                    saves[slot] = value;
+                    continue;
+                }
+            };
+
+            while threads.visit(pc) {
+                match &self.program[pc] {
+                    Inst::Split(first, second) => {
+                        stack.push(Job::Run(*second));
+                        pc = *first;
+                    }
+                    Inst::Jmp(target) => pc = *target,
+                    Inst::Save(slot) => {
+                        stack.push(Job::Restore(*slot, saves[*slot]));
+                        saves[*slot] = Some(pos);
+                        pc += 1;
+                    }
+                    Inst::Assert(assertion) => {
+                        if !assertion.holds(text, pos) {
+                            break;
+                        }
+
+                        pc += 1;
+                    }
+                    _ => {
+                        threads.push(pc, saves);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+enum Job {
+    Run(usize),
+    Restore(usize, Option<usize>),
+}
+
+struct Threads {
+    pcs: Vec<usize>,
+    saves: Vec<Option<usize>>,
+    seen: Vec<usize>,
+    generation: usize,
+    slots: usize,
+}
+
+impl Threads {
+    fn new(len: usize, slots: usize) -> Self {
+        Self {
+            pcs: Vec::new(),
+            saves: Vec::new(),
+            seen: vec![0; len],
+            generation: 1,
+            slots,
+        }
+    }
+
+    fn visit(&mut self, pc: usize) -> bool {
+        let fresh = self.seen[pc] != self.generation;
+
+        self.seen[pc] = self.generation;
+
+        fresh
+    }
+
+    fn push(&mut self, pc: usize, saves: &[Option<usize>]) {
+        self.pcs.push(pc);
+        self.saves.extend_from_slice(saves);
+    }
+
+    fn saves(&self, i: usize) -> &[Option<usize>] {
+        &self.saves[i * self.slots..(i + 1) * self.slots]
+    }
+
+    fn clear(&mut self) {
+        self.pcs.clear();
+        self.saves.clear();
+        self.generation += 1;
+    }
+}
+
+fn fold(c: char, case_insensitive: bool) -> char {
+    if case_insensitive {
+        c.to_lowercase().next().unwrap_or(c)
+    } else {
+        c
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Assertion {
+    fn holds(self, text: &[char], pos: usize) -> bool {
+        match self {
+            Assertion::LineStart => pos == 0 || text[pos - 1] == '\n',
+            Assertion::LineEnd => pos == text.len() || text[pos] == '\n',
+            Assertion::WordBoundary | Assertion::NotWordBoundary => {
+                let before = pos > 0 && is_word(text[pos - 1]);
+                let after = pos < text.len() && is_word(text[pos]);
+
+                (before != after) == matches!(self, Assertion::WordBoundary)
+            }
+        }
+    }
+}
+
+impl Class {
+    fn matches(&self, c: char, case_insensitive: bool) -> bool {
+        let hit = |c: char| {
+            self.items.iter().any(|item| match *item {
+                ClassItem::Range(lo, hi) => (lo..=hi).contains(&c),
+                ClassItem::Digit(yes) => c.is_ascii_digit() == yes,
+                ClassItem::Word(yes) => is_word(c) == yes,
+                ClassItem::Space(yes) => c.is_whitespace() == yes,
+            })
+        };
+
+        let found = if case_insensitive {
+            hit(c) || c.to_lowercase().any(hit) || c.to_uppercase().any(hit)
+        } else {
+            hit(c)
+        };
+
+        found != self.negated
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    groups: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_alt(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.parse_concat()?];
+
+        while self.eat('|') {
+            branches.push(self.parse_concat()?);
+        }
+
+        Ok(if branches.len() == 1 {
+            branches.pop().unwrap()
+        } else {
+            Node::Alt(branches)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifier(atom)?);
+        }
+
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn parse_quantifier(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('{') => match self.parse_braces()? {
+                Some(bounds) => bounds,
+                None => return Ok(atom),
+            },
+            Some(c @ ('*' | '+' | '?')) => {
+                self.pos += 1;
+
+                match c {
+                    '*' => (0, None),
+                    '+' => (1, None),
+                    _ => (0, Some(1)),
+                }
+            }
+            _ => return Ok(atom),
+        };
+
+        if matches!(atom, Node::Assert(_) | Node::Empty) {
+            return Err("Nothing to repeat".to_string());
+        }
+
+        let greedy = !self.eat('?');
+
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    fn parse_braces(&mut self) -> Result<Option<(usize, Option<usize>)>, String> {
+        let rest = self.chars[self.pos + 1..].iter().collect::<String>();
+        let Some(close) = rest.find('}') else {
+            return Ok(None);
+        };
+        let inner = &rest[..close];
+
+        let count = |s: &str| {
+            (!s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()))
+                .then(|| s.parse().unwrap_or(usize::MAX))
+        };
+
+        let bounds = match inner.split_once(',') {
+            None => match count(inner) {
+                Some(n) => (n, Some(n)),
+                None => return Ok(None),
+            },
+            Some((lo, hi)) => {
+                let lo = match count(lo) {
+                    Some(lo) => lo,
+                    None if lo.is_empty() => 0,
+                    None => return Ok(None),
+                };
+                let hi = match count(hi) {
+                    Some(hi) => Some(hi),
+                    None if hi.is_empty() => None,
+                    None => return Ok(None),
+                };
+                (lo, hi)
+            }
+        };
+
+        if bounds.0 > MAX_REPEAT || bounds.1.is_some_and(|hi| hi > MAX_REPEAT) {
+            return Err(format!("Repeat count over {MAX_REPEAT}: {{{inner}}}"));
+        }
+
+        self.pos += close + 2;
+
+        Ok(Some(bounds))
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some('.') => Ok(Node::Any),
+            Some('^') => Ok(Node::Assert(Assertion::LineStart)),
+            Some('$') => Ok(Node::Assert(Assertion::LineEnd)),
+            Some('(') => {
+                let index = if self.eat('?') {
+                    if !self.eat(':') {
+                        return Err("Unsupported group syntax".to_string());
+                    }
+                    None
+                } else {
+                    self.groups += 1;
+                    Some(self.groups)
+                };
+
+                if self.depth == MAX_NESTING {
+                    return Err(format!("Groups nested deeper than {MAX_NESTING}"));
+                }
+
+                self.depth += 1;
+
+                let inner = self.parse_alt()?;
+
+                self.depth -= 1;
+
+                if !self.eat(')') {
+                    return Err("Unmatched '('".to_string());
+                }
+
+                Ok(Node::Group(Box::new(inner), index))
+            }
+            Some('[') => self.parse_class(),
+            Some('\\') => self.parse_escape(),
+            Some(c @ ('*' | '+' | '?')) => Err(format!("Nothing to repeat before '{c}'")),
+            Some(c) => Ok(Node::Char(c)),
+            None => Ok(Node::Empty),
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<Node, String> {
+        let c = self.next().ok_or("Trailing backslash in pattern")?;
+
+        Ok(match c {
+            'd' | 'D' | 'w' | 'W' | 's' | 'S' => Node::Class(Class {
+                negated: false,
+                items: vec![escape_class(c).unwrap()],
+            }),
+            'b' => Node::Assert(Assertion::WordBoundary),
+            'B' => Node::Assert(Assertion::NotWordBoundary),
+            'n' => Node::Char('\n'),
+            't' => Node::Char('\t'),
+            c => Node::Char(c),
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<Node, String> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        let mut first = true;
+
+        loop {
+            let c = self.next().ok_or("Unmatched '['")?;
+
+            if c == ']' && !first {
+                break;
+            }
+
+            first = false;
+
+            let lo = if c == '\\' {
+                let e = self.next().ok_or("Unmatched '['")?;
+
+                if let Some(item) = escape_class(e) {
+                    items.push(item);
+                    continue;
+                }
+
+                match e {
+                    'n' => '\n',
+                    't' => '\t',
+                    e => e,
+                }
+            } else {
+                c
+            };
+
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']') {
+                self.pos += 1;
+                let hi = match self.next() {
+                    Some('\\') => self.next().ok_or("Unmatched '['")?,
+                    Some(hi) => hi,
+                    None => return Err("Unmatched '['".to_string()),
+                };
+
+                if hi < lo {
+                    return Err(format!("Invalid range {lo}-{hi}"));
+                }
+
+                items.push(ClassItem::Range(lo, hi));
+            } else {
+                items.push(ClassItem::Range(lo, lo));
+            }
+        }
+
+        Ok(Node::Class(Class { negated, items }))
+    }
+}
+
+fn escape_class(c: char) -> Option<ClassItem> {
+    match c {
+        'd' => Some(ClassItem::Digit(true)),
+        'D' => Some(ClassItem::Digit(false)),
+        'w' => Some(ClassItem::Word(true)),
+        'W' => Some(ClassItem::Word(false)),
+        's' => Some(ClassItem::Space(true)),
+        'S' => Some(ClassItem::Space(false)),
+        _ => None,
+    }
+}
+
+fn compile(node: &Node, program: &mut Vec<Inst>, case_insensitive: bool) -> Result<(), String> {
+    if program.len() > MAX_PROGRAM {
+        return Err("Pattern is too large".to_string());
+    }
+
+    match node {
+        Node::Empty => {}
+        Node::Char(c) => program.push(Inst::Char(fold(*c, case_insensitive))),
+        Node::Any => program.push(Inst::Any),
+        Node::Class(class) => program.push(Inst::Class(class.clone())),
+        Node::Assert(assertion) => program.push(Inst::Assert(*assertion)),
+        Node::Group(inner, index) => {
+            if let Some(index) = index {
+                program.push(Inst::Save(index * 2));
+                compile(inner, program, case_insensitive)?;
+                program.push(Inst::Save(index * 2 + 1));
+            } else {
+                compile(inner, program, case_insensitive)?;
+            }
+        }
+        Node::Concat(nodes) => {
+            for node in nodes {
+                compile(node, program, case_insensitive)?;
+            }
+        }
+        Node::Alt(branches) => {
+            let mut jumps = Vec::new();
+
+            for (i, branch) in branches.iter().enumerate() {
+                if i + 1 < branches.len() {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(branch, program, case_insensitive)?;
+                    jumps.push(program.len());
+                    program.push(Inst::Jmp(0));
+                    let next = program.len();
+                    program[split] = Inst::Split(split + 1, next);
+                } else {
+                    compile(branch, program, case_insensitive)?;
+                }
+            }
+
+            let end = program.len();
+
+            for jump in jumps {
+                program[jump] = Inst::Jmp(end);
+            }
+        }
+        Node::Repeat {
+            node,
+            min,
+            max,
+            greedy,
+        } => {
+            for _ in 0..*min {
+                compile(node, program, case_insensitive)?;
+            }
+
+            match max {
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(0, 0));
+                    compile(node, program, case_insensitive)?;
+                    program.push(Inst::Jmp(split));
+                    let end = program.len();
+                    program[split] = branch(split + 1, end, *greedy);
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(0, 0));
+                        compile(node, program, case_insensitive)?;
+                    }
+
+                    let end = program.len();
+
+                    for split in splits {
+                        program[split] = branch(split + 1, end, *greedy);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn branch(body: usize, skip: usize, greedy: bool) -> Inst {
+    if greedy {
+        Inst::Split(body, skip)
+    } else {
+        Inst::Split(skip, body)
+    }
+}
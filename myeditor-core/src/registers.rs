@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Register {
+    pub text: String,
+    pub linewise: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Registers {
+    unnamed: Option<Register>,
+    registers: BTreeMap<char, Register>,
+}
+
+impl Registers {
+    pub fn is_valid(name: char) -> bool {
+        name.is_ascii_alphanumeric() || matches!(name, '"' | '-' | '_' | '+' | '*')
+    }
+
+    pub fn is_clipboard(name: char) -> bool {
+        matches!(name, '+' | '*')
+    }
+
+    pub fn get(&self, name: char) -> Option<&Register> {
+        match name {
+            '"' => self.unnamed.as_ref(),
+            name => self.registers.get(&name.to_ascii_lowercase()),
+        }
+    }
+
+    pub fn yank(&mut self, name: Option<char>, register: Register) {
+        match name {
+            None | Some('"') => {
+                self.registers.insert('0', register.clone());
+                self.unnamed = Some(register);
+            }
+            Some(name) => self.store(name, register),
+        }
+    }
+
+    pub fn delete(&mut self, name: Option<char>, register: Register) {
+        match name {
+            None | Some('"') if register.linewise || register.text.contains('\n') => {
+                for n in (b'1'..b'9').rev() {
+                    if let Some(older) = self.registers.remove(&(n as char)) {
+                        self.registers.insert((n + 1) as char, older);
+                    }
+                }
+
+                self.registers.insert('1', register.clone());
+                self.unnamed = Some(register);
+            }
+            None | Some('"') => {
+                self.registers.insert('-', register.clone());
+                self.unnamed = Some(register);
+            }
+            Some(name) => self.store(name, register),
+        }
+    }
+
+    fn store(&mut self, name: char, register: Register) {
+        if name == '_' {
+            return;
+        }
+
+        let register = match self.registers.get(&name.to_ascii_lowercase()) {
+            Some(old) if name.is_ascii_uppercase() => {
+                let mut text = old.text.clone();
+
+                if register.linewise && !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+
+                text.push_str(&register.text);
+
+                Register {
+                    text,
+                    linewise: old.linewise || register.linewise,
+                }
+            }
+            _ => register,
+        };
+
+        self.registers
+            .insert(name.to_ascii_lowercase(), register.clone());
+        self.unnamed = Some(register);
+    }
+
+    pub fn summary(&self) -> String {
+        std::iter::once(('"', self.unnamed.as_ref()))
+            .chain(self.registers.iter().map(|(name, reg)| (*name, Some(reg))))
+            .filter_map(|(name, reg)| Some((name, reg?)))
+            .map(|(name, reg)| {
+                let kind = if reg.linewise { 'l' } else { 'c' };
+                let text = reg
+                    .text
+                    .chars()
+                    .take(60)
+                    .map(|c| match c {
+                        '\n' => "^J".to_string(),
+                        '\t' => "^I".to_string(),
+                        c => c.to_string(),
+                    })
+                    .collect::<String>();
+
+                format!("{kind}  \"{name}   {text}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
@@ -0,0 +1,223 @@
+use crate::{
+    events::AppEvent,
+    json::{self, Value},
+    paths,
+};
+use std::{
+    fs::{self, DirBuilder, Permissions},
+    io::{BufRead, BufReader, Read, Write},
+    os::unix::{
+        fs::{DirBuilderExt, FileTypeExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Sender},
+    thread,
+};
+
+const SOCKET_NAME: &str = "myeditor.sock";
+const MAX_REQUEST: usize = 16 * 1024 * 1024;
+const METHODS: [&str; 6] = ["open", "command", "keys", "paste", "resize", "buffer"];
+
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const SERVER_ERROR: i32 = -32000;
+
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub params: Value,
+    pub reply: Sender<Result<Value, String>>,
+}
+
+#[derive(Debug)]
+pub struct Server {
+    path: PathBuf,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+pub fn default_address() -> Option<PathBuf> {
+    paths::data_file(SOCKET_NAME)
+}
+
+pub fn listen(path: PathBuf, tx: Sender<AppEvent>) -> std::io::Result<Server> {
+    if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+        if !metadata.file_type().is_socket() || UnixStream::connect(&path).is_ok() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!("{} is already in use", path.display()),
+            ));
+        }
+
+        fs::remove_file(&path)?;
+    }
+
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    fs::create_dir_all(dir)?;
+
+    let private = dir.join(format!(".myeditor-{}", std::process::id()));
+
+    DirBuilder::new().mode(0o700).create(&private)?;
+
+    let staged = private.join(SOCKET_NAME);
+    let listener = UnixListener::bind(&staged).and_then(|listener| {
+        fs::set_permissions(&staged, Permissions::from_mode(0o600))?;
+        fs::hard_link(&staged, &path)?;
+
+        Ok(listener)
+    });
+
+    let _ = fs::remove_dir_all(&private);
+    let listener = listener?;
+
+    log::info!("listening on {}", path.display());
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+
+            let tx = tx.clone();
+
+            thread::spawn(move || serve(stream, tx));
+        }
+    });
+
+    Ok(Server { path })
+}
+
+fn serve(stream: UnixStream, tx: Sender<AppEvent>) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+
+    let mut reader = BufReader::new(stream);
+
+    loop {
+        let mut line = Vec::new();
+
+        match (&mut reader)
+            .take(MAX_REQUEST as u64 + 1)
+            .read_until(b'\n', &mut line)
+        {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+
+        if line.len() > MAX_REQUEST && !line.ends_with(b"\n") {
+            let message = format!("Request longer than {MAX_REQUEST} bytes");
+            let _ = writeln!(writer, "{}", error(Value::Null, INVALID_REQUEST, message));
+
+            return;
+        }
+
+        let Ok(line) = String::from_utf8(line) else {
+            return;
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let Some(response) = respond(&line, &tx) else {
+            return;
+        };
+
+        if writeln!(writer, "{response}").is_err() {
+            return;
+        }
+    }
+}
+
+fn respond(line: &str, tx: &Sender<AppEvent>) -> Option<Value> {
+    let request = match json::parse(line) {
+        Ok(request) => request,
+        Err(e) => return Some(error(Value::Null, PARSE_ERROR, e)),
+    };
+
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        return Some(error(id, INVALID_REQUEST, "Missing method".to_string()));
+    };
+
+    if !METHODS.contains(&method) {
+        return Some(error(
+            id,
+            METHOD_NOT_FOUND,
+            format!("Unknown method: {method}"),
+        ));
+    }
+
+    log::debug!("remote {method}");
+
+    let (reply, result) = channel();
+
+    tx.send(AppEvent::Remote(Request {
+        method: method.to_string(),
+        params: request.get("params").cloned().unwrap_or(Value::Null),
+        reply,
+    }))
+    .ok()?;
+
+    Some(match result.recv() {
+        Ok(Ok(result)) => {
+            Value::object([("jsonrpc", "2.0".into()), ("id", id), ("result", result)])
+        }
+        Ok(Err(e)) => error(id, SERVER_ERROR, e),
+        Err(_) => error(id, SERVER_ERROR, "Editor closed".to_string()),
+    })
+}
+
+fn error(id: Value, code: i32, message: String) -> Value {
+    Value::object([
+        ("jsonrpc", "2.0".into()),
+        ("id", id),
+        (
+            "error",
+            Value::object([
+                ("code", Value::Number(code.to_string())),
+                ("message", message.into()),
+            ]),
+        ),
+    ])
+}
+
+pub fn call(path: &Path, method: &str, params: Value) -> std::io::Result<Result<Value, String>> {
+    let mut stream = UnixStream::connect(path)?;
+
+    let request = Value::object([
+        ("jsonrpc", "2.0".into()),
+        ("id", 1.into()),
+        ("method", method.into()),
+        ("params", params),
+    ]);
+
+    writeln!(stream, "{request}")?;
+
+    let mut line = String::new();
+
+    BufReader::new(stream).read_line(&mut line)?;
+
+    let response =
+        json::parse(&line).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+    if let Some(error) = response.get("error") {
+        let message = error.get("message").and_then(Value::as_str).unwrap_or("");
+
+        return Ok(Err(message.to_string()));
+    }
+
+    Ok(Ok(response.get("result").cloned().unwrap_or(Value::Null)))
+}
@@ -0,0 +1,157 @@
+use crate::{grep::GrepMatch, regex::Regex};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub struct ReplacePanel {
+    regex: Regex,
+    replacement: String,
+    hunks: Vec<Hunk>,
+    selected: usize,
+    searching: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub path: PathBuf,
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+    pub accepted: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Row {
+    File(usize),
+    Before(usize),
+    After(usize),
+}
+
+impl ReplacePanel {
+    pub fn new(regex: Regex, replacement: String) -> Self {
+        Self {
+            regex,
+            replacement,
+            hunks: Vec::new(),
+            selected: 0,
+            searching: true,
+        }
+    }
+
+    pub fn hunks(&self) -> &[Hunk] {
+        &self.hunks
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.searching
+    }
+
+    pub fn finish_search(&mut self) {
+        self.searching = false;
+    }
+
+    pub fn push(&mut self, m: GrepMatch) {
+        if let Some(after) = self.regex.replace_all(&m.text, &self.replacement) {
+            self.hunks.push(Hunk {
+                path: m.path,
+                line: m.line,
+                before: m.text,
+                after,
+                accepted: true,
+            });
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.hunks.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn toggle(&mut self) {
+        if let Some(hunk) = self.hunks.get_mut(self.selected) {
+            hunk.accepted = !hunk.accepted;
+        }
+    }
+
+    pub fn toggle_file(&mut self) {
+        let Some(path) = self.hunks.get(self.selected).map(|h| h.path.clone()) else {
+            return;
+        };
+
+        let file_hunks = self.hunks.iter_mut().filter(|h| h.path == path);
+        let hunks = file_hunks.collect::<Vec<_>>();
+        let accept = !hunks.iter().all(|h| h.accepted);
+
+        for hunk in hunks {
+            hunk.accepted = accept;
+        }
+    }
+
+    pub fn accepted_count(&self) -> usize {
+        self.hunks.iter().filter(|h| h.accepted).count()
+    }
+
+    pub fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        let mut last_path: Option<&Path> = None;
+
+        for (idx, hunk) in self.hunks.iter().enumerate() {
+            if last_path != Some(&hunk.path) {
+                rows.push(Row::File(idx));
+                last_path = Some(&hunk.path);
+            }
+
+            rows.push(Row::Before(idx));
+            rows.push(Row::After(idx));
+        }
+
+        rows
+    }
+
+    pub fn accepted_by_file(&self) -> Vec<(PathBuf, Vec<&Hunk>)> {
+        let mut files: Vec<(PathBuf, Vec<&Hunk>)> = Vec::new();
+
+        for hunk in self.hunks.iter().filter(|h| h.accepted) {
+            match files.last_mut() {
+                Some((path, hunks)) if *path == hunk.path => hunks.push(hunk),
+                _ => files.push((hunk.path.clone(), vec![hunk])),
+            }
+        }
+
+        files
+    }
+}
+
+pub fn apply_to_file(path: &Path, hunks: &[&Hunk]) -> std::io::Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    let mut out = String::with_capacity(text.len());
+    let mut applied = 0;
+
+    for (line_idx, line) in text.split_inclusive('\n').enumerate() {
+        let content = line.trim_end_matches(['\n', '\r']);
+        let ending = &line[content.len()..];
+
+        match hunks.iter().find(|h| h.line == line_idx) {
+            Some(hunk) if hunk.before == content => {
+                out.push_str(&hunk.after);
+                out.push_str(ending);
+                applied += 1;
+            }
+            _ => out.push_str(line),
+        }
+    }
+
+    if applied > 0 {
+        std::fs::write(path, out)?;
+    }
+
+    Ok(applied)
+}
@@ -0,0 +1,91 @@
+use crate::input::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+pub fn parse(text: &str) -> Result<Vec<Event>, String> {
+    let mut events = Vec::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            if let Some(end) = rest.find('>') {
+                let name = &rest[1..end];
+
+                if !name.is_empty() && !name.contains(char::is_whitespace) {
+                    events.push(named_event(name)?);
+                    rest = &rest[end + 1..];
+
+                    continue;
+                }
+            }
+        }
+
+        if c != '\n' && c != '\r' {
+            events.push(Event::Key(KeyEvent::new(
+                KeyCode::Char(c),
+                KeyModifiers::NONE,
+            )));
+        }
+
+        rest = &rest[c.len_utf8()..];
+    }
+
+    Ok(events)
+}
+
+fn named_event(name: &str) -> Result<Event, String> {
+    let Some(size) = name
+        .to_ascii_lowercase()
+        .strip_prefix("resize:")
+        .map(String::from)
+    else {
+        return named_key(name).map(Event::Key);
+    };
+
+    size.split_once('x')
+        .and_then(|(width, height)| Some(Event::Resize(width.parse().ok()?, height.parse().ok()?)))
+        .ok_or_else(|| format!("Expected <Resize:{{width}}x{{height}}>, found <{name}>"))
+}
+
+fn named_key(name: &str) -> Result<KeyEvent, String> {
+    let lower = name.to_ascii_lowercase();
+
+    if let Some(key) = lower.strip_prefix("c-") {
+        let mut chars = key.chars();
+
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL));
+        }
+    }
+
+    if let Some(key) = name
+        .strip_prefix(['a', 'A', 'm', 'M'])
+        .and_then(|key| key.strip_prefix('-'))
+    {
+        let mut chars = key.chars();
+
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT));
+        }
+    }
+
+    let code = match lower.as_str() {
+        "esc" => KeyCode::Esc,
+        "cr" | "enter" | "return" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "s-tab" => KeyCode::BackTab,
+        "bs" | "backspace" => KeyCode::Backspace,
+        "del" => KeyCode::Delete,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "space" => KeyCode::Char(' '),
+        "lt" => KeyCode::Char('<'),
+        _ => return Err(format!("Unknown key: <{name}>")),
+    };
+
+    Ok(KeyEvent::new(code, KeyModifiers::NONE))
+}
@@ -0,0 +1,337 @@
+use crate::{
+    buffer::{Buffer, LINE_BREAKS},
+    lua::{arg_number, arg_string, tostring, Chunk, Host, Lua, Table, Value},
+    messages::Messages,
+    options::Options,
+};
+use std::{collections::HashMap, fs, io, path::Path};
+
+const API: [(&str, &[&str]); 3] = [
+    (
+        "buffer",
+        &["get_line", "insert", "line_count", "name", "set_line"],
+    ),
+    ("cursor", &["get", "set"]),
+    ("", &["command", "map", "message", "print", "set"]),
+];
+
+#[derive(Debug)]
+pub struct Scripts {
+    lua: Lua,
+    callbacks: Vec<Value>,
+    bindings: Vec<Binding>,
+    commands: HashMap<String, Value>,
+}
+
+#[derive(Debug)]
+pub struct Binding {
+    pub keys: String,
+    pub id: usize,
+    pub description: String,
+}
+
+struct Context<'a> {
+    buffer: &'a mut Buffer,
+    messages: &'a mut Messages,
+    options: &'a mut Options,
+    callbacks: &'a mut Vec<Value>,
+    bindings: &'a mut Vec<Binding>,
+    commands: &'a mut HashMap<String, Value>,
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        let mut lua = Lua::new();
+
+        for (table, functions) in API {
+            if table.is_empty() {
+                for function in functions {
+                    lua.set_global(function, Lua::host_function(function));
+                }
+
+                continue;
+            }
+
+            let mut fields = Table::default();
+
+            for function in functions {
+                fields.set_field(function, Lua::host_function(&format!("{table}.{function}")));
+            }
+
+            lua.set_global(table, fields.into());
+        }
+
+        Self {
+            lua,
+            callbacks: Vec::new(),
+            bindings: Vec::new(),
+            commands: HashMap::new(),
+        }
+    }
+}
+
+impl Scripts {
+    pub fn load(
+        &mut self,
+        path: &Path,
+        buffer: &mut Buffer,
+        messages: &mut Messages,
+        options: &mut Options,
+    ) -> Result<(), String> {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(format!("{}: {e}", path.display())),
+        };
+
+        let chunk = Lua::load(&source, "init.lua")?;
+
+        self.run_chunk(&chunk, buffer, messages, options)
+            .map(|_| ())
+    }
+
+    pub fn run(
+        &mut self,
+        code: &str,
+        buffer: &mut Buffer,
+        messages: &mut Messages,
+        options: &mut Options,
+    ) -> Result<(), String> {
+        let chunk = match Lua::load(&format!("return {code}"), "lua") {
+            Ok(chunk) => chunk,
+            Err(_) => Lua::load(code, "lua")?,
+        };
+
+        let results = self.run_chunk(&chunk, buffer, messages, options)?;
+
+        if !results.is_empty() {
+            let results: Vec<_> = results.iter().map(tostring).collect();
+
+            messages.info(results.join(", "));
+        }
+
+        Ok(())
+    }
+
+    pub fn run_mapping(
+        &mut self,
+        id: usize,
+        buffer: &mut Buffer,
+        messages: &mut Messages,
+        options: &mut Options,
+    ) -> Result<(), String> {
+        let callback = self.callbacks.get(id).cloned().unwrap_or_default();
+
+        self.call_function(&callback, Vec::new(), buffer, messages, options)
+    }
+
+    pub fn run_command(
+        &mut self,
+        name: &str,
+        args: &str,
+        buffer: &mut Buffer,
+        messages: &mut Messages,
+        options: &mut Options,
+    ) -> Option<Result<(), String>> {
+        let callback = self.commands.get(name)?.clone();
+
+        Some(self.call_function(&callback, vec![args.into()], buffer, messages, options))
+    }
+
+    pub fn take_bindings(&mut self) -> Vec<Binding> {
+        std::mem::take(&mut self.bindings)
+    }
+
+    fn call_function(
+        &mut self,
+        function: &Value,
+        args: Vec<Value>,
+        buffer: &mut Buffer,
+        messages: &mut Messages,
+        options: &mut Options,
+    ) -> Result<(), String> {
+        let mut context = Context {
+            buffer,
+            messages,
+            options,
+            callbacks: &mut self.callbacks,
+            bindings: &mut self.bindings,
+            commands: &mut self.commands,
+        };
+
+        self.lua.call(function, args, &mut context).map(|_| ())
+    }
+
+    fn run_chunk(
+        &mut self,
+        chunk: &Chunk,
+        buffer: &mut Buffer,
+        messages: &mut Messages,
+        options: &mut Options,
+    ) -> Result<Vec<Value>, String> {
+        let mut context = Context {
+            buffer,
+            messages,
+            options,
+            callbacks: &mut self.callbacks,
+            bindings: &mut self.bindings,
+            commands: &mut self.commands,
+        };
+
+        self.lua.run(chunk, &mut context)
+    }
+}
+
+impl Host for Context<'_> {
+    fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Vec<Value>, String> {
+        let text = &self.buffer.text;
+
+        Ok(match name {
+            "buffer.line_count" => vec![text.len_lines().into()],
+            "buffer.get_line" => {
+                let line = arg_number(&args, 0, "get_line")?;
+
+                match line_index(line, text.len_lines()) {
+                    Some(line) => vec![line_content(self.buffer, line).into()],
+                    None => vec![Value::Nil],
+                }
+            }
+            "buffer.set_line" => {
+                let line = arg_number(&args, 0, "set_line")?;
+                let replacement = arg_string(&args, 1, "set_line")?;
+                let line = line_index(line, text.len_lines())
+                    .ok_or_else(|| format!("set_line: line {line} out of range"))?;
+
+                if replacement.contains(LINE_BREAKS) {
+                    return Err("set_line: text contains a line break".to_string());
+                }
+
+                self.writable()?;
+
+                let start = self.buffer.text.line_to_char(line);
+                let end = start + line_content(self.buffer, line).chars().count();
+
+                self.buffer.remove(start..end)?;
+                self.buffer.insert(start, &replacement)?;
+
+                if self.buffer.cursor.1 == line {
+                    self.buffer.cursor.0 = self.buffer.cursor.0.min(replacement.chars().count());
+                }
+
+                Vec::new()
+            }
+            "buffer.insert" => {
+                let insertion = arg_string(&args, 0, "insert")?;
+
+                self.writable()?;
+
+                let (col, line) = self.buffer.cursor;
+                let idx = (text.line_to_char(line) + col).min(text.len_chars());
+
+                self.buffer.insert(idx, &insertion)?;
+
+                Vec::new()
+            }
+            "buffer.name" => match &self.buffer.path {
+                Some(path) => vec![path.display().to_string().into()],
+                None => vec![Value::Nil],
+            },
+            "cursor.get" => {
+                let (col, line) = self.buffer.cursor;
+
+                vec![(line + 1).into(), (col + 1).into()]
+            }
+            "cursor.set" => {
+                let line = arg_number(&args, 0, "set")?;
+                let col = arg_number(&args, 1, "set")?;
+                let line = (line.max(1.0) as usize - 1).min(text.len_lines() - 1);
+                let len = line_content(self.buffer, line).chars().count();
+
+                self.buffer.cursor = ((col.max(1.0) as usize - 1).min(len), line);
+
+                Vec::new()
+            }
+            "map" => {
+                let keys = arg_string(&args, 0, "map")?;
+                let callback = args.get(1).cloned().unwrap_or_default();
+
+                if keys.is_empty() {
+                    return Err("map: empty key sequence".to_string());
+                }
+
+                if !callback.is_function() {
+                    return Err("bad argument #2 to 'map' (function expected)".to_string());
+                }
+
+                let description = match args.get(2) {
+                    Some(value) if !value.is_nil() => arg_string(&args, 2, "map")?,
+                    _ => "lua".to_string(),
+                };
+
+                self.bindings.push(Binding {
+                    keys,
+                    id: self.callbacks.len(),
+                    description,
+                });
+                self.callbacks.push(callback);
+
+                Vec::new()
+            }
+            "command" => {
+                let command = arg_string(&args, 0, "command")?;
+                let callback = args.get(1).cloned().unwrap_or_default();
+
+                if command.is_empty() || !command.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    return Err(format!("command: invalid command name {command:?}"));
+                }
+
+                if !callback.is_function() {
+                    return Err("bad argument #2 to 'command' (function expected)".to_string());
+                }
+
+                self.commands.insert(command, callback);
+
+                Vec::new()
+            }
+            "set" => {
+                let option = arg_string(&args, 0, "set")?;
+
+                match self.options.set(&option)? {
+                    Some(value) => vec![value.into()],
+                    None => Vec::new(),
+                }
+            }
+            "message" | "print" => {
+                let text: Vec<_> = args.iter().map(tostring).collect();
+
+                self.messages.info(text.join(" ").replace(LINE_BREAKS, " "));
+
+                Vec::new()
+            }
+            name => return Err(format!("Unknown function {name}")),
+        })
+    }
+}
+
+impl Context<'_> {
+    fn writable(&self) -> Result<(), String> {
+        if self.buffer.read_only {
+            return Err("Buffer is read-only".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+fn line_index(line: f64, len_lines: usize) -> Option<usize> {
+    (line >= 1.0 && line <= len_lines as f64).then(|| line as usize - 1)
+}
+
+fn line_content(buffer: &Buffer, line: usize) -> String {
+    buffer
+        .text
+        .line(line)
+        .to_string()
+        .trim_end_matches(LINE_BREAKS)
+        .to_string()
+}
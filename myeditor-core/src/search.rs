@@ -0,0 +1,198 @@
+use crate::regex::Regex;
+use ropey::Rope;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Found {
+    pub line: usize,
+    pub col: usize,
+    pub end: usize,
+    pub wrapped: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Count {
+    pub current: usize,
+    pub total: usize,
+    pub capped: bool,
+}
+
+impl std::fmt::Display for Count {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.capped && self.current == self.total {
+            write!(f, "[match ?/>{}]", self.total)
+        } else if self.capped {
+            write!(f, "[match {}/>{}]", self.current, self.total)
+        } else {
+            write!(f, "[match {}/{}]", self.current, self.total)
+        }
+    }
+}
+
+pub fn ignore_case(pattern: &str, ignorecase: bool, smartcase: bool) -> bool {
+    ignorecase && !(smartcase && has_uppercase(pattern))
+}
+
+fn has_uppercase(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+
+    false
+}
+
+pub fn find(
+    text: &Rope,
+    regex: &Regex,
+    (line, col): (usize, usize),
+    forward: bool,
+) -> Option<Found> {
+    let lines = text.len_lines();
+
+    for step in 0..=lines {
+        let idx = if forward {
+            (line + step) % lines
+        } else {
+            (line + lines - step % lines) % lines
+        };
+
+        let wrapped = if forward { idx < line } else { idx > line } || step == lines;
+        let chars = line_chars(text, idx);
+
+        let found = if forward {
+            let start = if step == 0 { col + 1 } else { 0 };
+
+            (start <= chars.len())
+                .then(|| regex.find_at(&chars, start))
+                .flatten()
+                .map(|m| (m.start, m.end))
+        } else {
+            let limit = if step == 0 { col } else { usize::MAX };
+            let mut last = None;
+            let mut pos = 0;
+
+            while let Some(m) = regex.find_at(&chars, pos) {
+                if m.start >= limit {
+                    break;
+                }
+
+                last = Some((m.start, m.end));
+                pos = m.start + 1;
+
+                if pos > chars.len() {
+                    break;
+                }
+            }
+
+            last
+        };
+
+        if let Some((col, end)) = found {
+            return Some(Found {
+                line: idx,
+                col,
+                end,
+                wrapped,
+            });
+        }
+    }
+
+    None
+}
+
+pub fn find_covering(
+    text: &Rope,
+    regex: &Regex,
+    (line, col): (usize, usize),
+    forward: bool,
+) -> Option<Found> {
+    let chars = line_chars(text, line);
+    let mut pos = 0;
+
+    while let Some(m) = regex.find_at(&chars, pos) {
+        if m.start > col {
+            break;
+        }
+
+        if m.end > col {
+            return Some(Found {
+                line,
+                col: m.start,
+                end: m.end,
+                wrapped: false,
+            });
+        }
+
+        pos = m.start + 1;
+
+        if pos > chars.len() {
+            break;
+        }
+    }
+
+    find(text, regex, (line, col), forward)
+}
+
+pub fn count(text: &Rope, regex: &Regex, (line, col): (usize, usize), cap: usize) -> Count {
+    let mut count = Count {
+        current: 0,
+        total: 0,
+        capped: false,
+    };
+
+    for (idx, slice) in text.lines().enumerate() {
+        let chars = trim_newline(slice.chars().collect());
+        let mut pos = 0;
+
+        while let Some(m) = regex.find_at(&chars, pos) {
+            if count.total == cap {
+                count.capped = true;
+
+                return count;
+            }
+
+            count.total += 1;
+
+            if (idx, m.start) <= (line, col) {
+                count.current = count.total;
+            }
+
+            pos = m.start + 1;
+
+            if pos > chars.len() {
+                break;
+            }
+        }
+    }
+
+    count
+}
+
+pub fn matching_lines(text: &Rope, regex: &Regex) -> Vec<usize> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, slice)| {
+            regex
+                .find_at(&trim_newline(slice.chars().collect()), 0)
+                .is_some()
+        })
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+fn line_chars(text: &Rope, idx: usize) -> Vec<char> {
+    trim_newline(text.line(idx).chars().collect())
+}
+
+fn trim_newline(mut chars: Vec<char>) -> Vec<char> {
+    while matches!(chars.last(), Some('\n' | '\r')) {
+        chars.pop();
+    }
+
+    chars
+}
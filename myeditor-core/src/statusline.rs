@@ -0,0 +1,111 @@
+use std::path::Path;
+
+pub const DEFAULT: &str = "%M | %f%m%r%h | %L lines | %B bytes%=%j%J%I  %R ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Text(String),
+    Field(Field),
+    Split,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Mode,
+    File,
+    Modified,
+    ReadOnly,
+    Hex,
+    FileType,
+    Encoding,
+    Indent,
+    Percent,
+    Viewport,
+    Ruler,
+    Line,
+    Column,
+    Lines,
+    Bytes,
+    Jobs,
+    JsonPath,
+}
+
+pub fn parse(format: &str) -> Result<Vec<Item>, String> {
+    let mut items = Vec::new();
+    let mut text = String::new();
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+
+        let field = match chars.next() {
+            Some('%') => {
+                text.push('%');
+                continue;
+            }
+            Some('=') => None,
+            Some('M') => Some(Field::Mode),
+            Some('f') => Some(Field::File),
+            Some('m') => Some(Field::Modified),
+            Some('r') => Some(Field::ReadOnly),
+            Some('h') => Some(Field::Hex),
+            Some('y') => Some(Field::FileType),
+            Some('e') => Some(Field::Encoding),
+            Some('I') => Some(Field::Indent),
+            Some('p') => Some(Field::Percent),
+            Some('P') => Some(Field::Viewport),
+            Some('R') => Some(Field::Ruler),
+            Some('l') => Some(Field::Line),
+            Some('c') => Some(Field::Column),
+            Some('L') => Some(Field::Lines),
+            Some('B') => Some(Field::Bytes),
+            Some('j') => Some(Field::Jobs),
+            Some('J') => Some(Field::JsonPath),
+            Some(c) => return Err(format!("Unknown statusline item: %{c}")),
+            None => return Err("Statusline ends with %".to_string()),
+        };
+
+        if !text.is_empty() {
+            items.push(Item::Text(std::mem::take(&mut text)));
+        }
+
+        items.push(field.map_or(Item::Split, Item::Field));
+    }
+
+    if !text.is_empty() {
+        items.push(Item::Text(text));
+    }
+
+    if items.iter().filter(|item| **item == Item::Split).count() > 1 {
+        return Err("Statusline can only have one %=".to_string());
+    }
+
+    Ok(items)
+}
+
+pub fn filetype(path: &Path) -> Option<&'static str> {
+    let name = match path.extension()?.to_str()? {
+        "rs" => "rust",
+        "md" | "markdown" | "mkd" => "markdown",
+        "py" => "python",
+        "js" | "jsx" | "mjs" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "go" => "go",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "hpp" => "cpp",
+        "toml" => "toml",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "sh" | "bash" => "sh",
+        "txt" => "text",
+        "todo" => "todo",
+        "html" | "htm" => "html",
+        "css" => "css",
+        _ => return None,
+    };
+
+    Some(name)
+}
@@ -0,0 +1,300 @@
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    Reset,
+    Black,
+    DarkGrey,
+    Red,
+    DarkRed,
+    Green,
+    DarkGreen,
+    Yellow,
+    DarkYellow,
+    Blue,
+    DarkBlue,
+    Magenta,
+    DarkMagenta,
+    Cyan,
+    DarkCyan,
+    White,
+    Grey,
+    Rgb { r: u8, g: u8, b: u8 },
+    AnsiValue(u8),
+}
+
+impl Color {
+    fn sgr(self, base: u8) -> String {
+        let named = |index: u8| format!("{base};5;{index}");
+
+        match self {
+            Self::Reset => (base + 1).to_string(),
+            Self::Black => named(0),
+            Self::DarkRed => named(1),
+            Self::DarkGreen => named(2),
+            Self::DarkYellow => named(3),
+            Self::DarkBlue => named(4),
+            Self::DarkMagenta => named(5),
+            Self::DarkCyan => named(6),
+            Self::Grey => named(7),
+            Self::DarkGrey => named(8),
+            Self::Red => named(9),
+            Self::Green => named(10),
+            Self::Yellow => named(11),
+            Self::Blue => named(12),
+            Self::Magenta => named(13),
+            Self::Cyan => named(14),
+            Self::White => named(15),
+            Self::AnsiValue(index) => named(index),
+            Self::Rgb { r, g, b } => format!("{base};2;{r};{g};{b}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Attribute {
+    Bold,
+    Dim,
+    Italic,
+    Underlined,
+    SlowBlink,
+    RapidBlink,
+    Reverse,
+    Hidden,
+    CrossedOut,
+}
+
+impl Attribute {
+    pub const ALL: [Self; 9] = [
+        Self::Bold,
+        Self::Dim,
+        Self::Italic,
+        Self::Underlined,
+        Self::SlowBlink,
+        Self::RapidBlink,
+        Self::Reverse,
+        Self::Hidden,
+        Self::CrossedOut,
+    ];
+
+    pub fn sgr(self) -> u8 {
+        self as u8 + 1
+    }
+
+    fn bit(self) -> u16 {
+        1 << self as u16
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Attributes(u16);
+
+impl Attributes {
+    pub fn set(&mut self, attribute: Attribute) {
+        self.0 |= attribute.bit();
+    }
+
+    pub fn unset(&mut self, attribute: Attribute) {
+        self.0 &= !attribute.bit();
+    }
+
+    pub fn has(self, attribute: Attribute) -> bool {
+        self.0 & attribute.bit() != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ContentStyle {
+    pub foreground_color: Option<Color>,
+    pub background_color: Option<Color>,
+    pub underline_color: Option<Color>,
+    pub attributes: Attributes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StyledContent<D: Display> {
+    style: ContentStyle,
+    content: D,
+}
+
+impl<D: Display> StyledContent<D> {
+    pub fn new(style: ContentStyle, content: D) -> Self {
+        Self { style, content }
+    }
+
+    pub fn content(&self) -> &D {
+        &self.content
+    }
+
+    pub fn style(&self) -> &ContentStyle {
+        &self.style
+    }
+
+    pub fn style_mut(&mut self) -> &mut ContentStyle {
+        &mut self.style
+    }
+}
+
+impl<D: Display> Display for StyledContent<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let style = &self.style;
+        let colors = [
+            (style.background_color, 48),
+            (style.foreground_color, 38),
+            (style.underline_color, 58),
+        ];
+
+        for (color, base) in colors {
+            if let Some(color) = color {
+                write!(f, "\x1b[{}m", color.sgr(base))?;
+            }
+        }
+
+        for attribute in Attribute::ALL {
+            if style.attributes.has(attribute) {
+                write!(f, "\x1b[{}m", attribute.sgr())?;
+            }
+        }
+
+        write!(f, "{}", self.content)?;
+
+        if !style.attributes.is_empty() {
+            write!(f, "\x1b[0m")?;
+        } else {
+            if style.background_color.is_some() {
+                write!(f, "\x1b[49m")?;
+            }
+
+            if style.foreground_color.is_some() || style.underline_color.is_some() {
+                write!(f, "\x1b[39m")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorStyle {
+    SteadyBlock,
+    SteadyBar,
+}
+
+pub trait Stylize: Sized {
+    type Content: Display;
+
+    fn stylize(self) -> StyledContent<Self::Content>;
+
+    fn with(self, color: Color) -> StyledContent<Self::Content> {
+        let mut styled = self.stylize();
+
+        styled.style.foreground_color = Some(color);
+        styled
+    }
+
+    fn on(self, color: Color) -> StyledContent<Self::Content> {
+        let mut styled = self.stylize();
+
+        styled.style.background_color = Some(color);
+        styled
+    }
+
+    fn attribute(self, attribute: Attribute) -> StyledContent<Self::Content> {
+        let mut styled = self.stylize();
+
+        styled.style.attributes.set(attribute);
+        styled
+    }
+
+    fn reset(self) -> StyledContent<Self::Content> {
+        self.stylize()
+    }
+
+    fn bold(self) -> StyledContent<Self::Content> {
+        self.attribute(Attribute::Bold)
+    }
+
+    fn italic(self) -> StyledContent<Self::Content> {
+        self.attribute(Attribute::Italic)
+    }
+
+    fn underlined(self) -> StyledContent<Self::Content> {
+        self.attribute(Attribute::Underlined)
+    }
+
+    fn black(self) -> StyledContent<Self::Content> {
+        self.with(Color::Black)
+    }
+
+    fn red(self) -> StyledContent<Self::Content> {
+        self.with(Color::Red)
+    }
+
+    fn green(self) -> StyledContent<Self::Content> {
+        self.with(Color::Green)
+    }
+
+    fn yellow(self) -> StyledContent<Self::Content> {
+        self.with(Color::Yellow)
+    }
+
+    fn blue(self) -> StyledContent<Self::Content> {
+        self.with(Color::Blue)
+    }
+
+    fn magenta(self) -> StyledContent<Self::Content> {
+        self.with(Color::Magenta)
+    }
+
+    fn cyan(self) -> StyledContent<Self::Content> {
+        self.with(Color::Cyan)
+    }
+
+    fn dark_grey(self) -> StyledContent<Self::Content> {
+        self.with(Color::DarkGrey)
+    }
+
+    fn on_dark_grey(self) -> StyledContent<Self::Content> {
+        self.on(Color::DarkGrey)
+    }
+
+    fn on_white(self) -> StyledContent<Self::Content> {
+        self.on(Color::White)
+    }
+}
+
+impl<D: Display> Stylize for StyledContent<D> {
+    type Content = D;
+
+    fn stylize(self) -> Self {
+        self
+    }
+}
+
+impl Stylize for String {
+    type Content = Self;
+
+    fn stylize(self) -> StyledContent<Self> {
+        StyledContent::new(ContentStyle::default(), self)
+    }
+}
+
+impl Stylize for &str {
+    type Content = Self;
+
+    fn stylize(self) -> StyledContent<Self> {
+        StyledContent::new(ContentStyle::default(), self)
+    }
+}
+
+impl Stylize for char {
+    type Content = Self;
+
+    fn stylize(self) -> StyledContent<Self> {
+        StyledContent::new(ContentStyle::default(), self)
+    }
+}
@@ -0,0 +1,102 @@
+use crate::regex::Regex;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitute {
+    pub pattern: String,
+    pub replacement: String,
+    pub flags: Flags,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Flags {
+    pub global: bool,
+    pub ignore_case: Option<bool>,
+}
+
+impl Flags {
+    pub fn parse(flags: &str) -> Result<Self, String> {
+        let mut parsed = Self::default();
+
+        for flag in flags.chars() {
+            match flag {
+                'g' => parsed.global = !parsed.global,
+                'i' => parsed.ignore_case = Some(true),
+                'I' => parsed.ignore_case = Some(false),
+                flag => return Err(format!("Invalid substitute flag: {flag}")),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            global: self.global ^ other.global,
+            ignore_case: other.ignore_case.or(self.ignore_case),
+        }
+    }
+}
+
+impl Substitute {
+    pub fn parse(args: &str) -> Result<Self, String> {
+        let mut chars = args.chars();
+        let delimiter = chars.next().ok_or("Usage: s/pattern/replacement/flags")?;
+
+        if delimiter.is_alphanumeric() || delimiter == '\\' || delimiter == '"' {
+            return Err(format!("Invalid delimiter: {delimiter}"));
+        }
+
+        let (pattern, rest) = split_part(chars.as_str(), delimiter);
+        let (replacement, rest) = split_part(rest.unwrap_or(""), delimiter);
+
+        if pattern.is_empty() {
+            return Err("No search pattern".to_string());
+        }
+
+        Ok(Self {
+            pattern,
+            replacement,
+            flags: Flags::parse(rest.unwrap_or("").trim())?,
+        })
+    }
+
+    pub fn regex(&self, flags: Flags, ignore_case: bool) -> Result<Regex, String> {
+        Regex::new(&self.pattern, flags.ignore_case.unwrap_or(ignore_case))
+    }
+
+    pub fn apply(&self, regex: &Regex, flags: Flags, line: &str) -> Option<String> {
+        if flags.global {
+            regex.replace_all(line, &self.replacement)
+        } else {
+            regex.replace_first(line, &self.replacement)
+        }
+    }
+}
+
+fn split_part(text: &str, delimiter: char) -> (String, Option<&str>) {
+    let mut part = String::new();
+    let mut chars = text.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        if c == delimiter {
+            return (part, Some(&text[i + c.len_utf8()..]));
+        }
+
+        if c == '\\' {
+            match chars.next() {
+                Some((_, next)) if next == delimiter => part.push(next),
+                Some((_, next)) => {
+                    part.push(c);
+                    part.push(next);
+                }
+                None => part.push(c),
+            }
+
+            continue;
+        }
+
+        part.push(c);
+    }
+
+    (part, None)
+}
@@ -0,0 +1,198 @@
+use ropey::Rope;
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub line: usize,
+    pub col: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Type,
+    Trait,
+    Impl,
+    Module,
+    Constant,
+    Macro,
+    Heading(usize),
+}
+
+impl std::fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymbolKind::Function => write!(f, "fn"),
+            SymbolKind::Type => write!(f, "type"),
+            SymbolKind::Trait => write!(f, "trait"),
+            SymbolKind::Impl => write!(f, "impl"),
+            SymbolKind::Module => write!(f, "mod"),
+            SymbolKind::Constant => write!(f, "const"),
+            SymbolKind::Macro => write!(f, "macro"),
+            SymbolKind::Heading(level) => write!(f, "h{level}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Language {
+    Rust,
+    Markdown,
+    Python,
+    JavaScript,
+    Go,
+}
+
+impl Language {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Language::Rust),
+            "md" | "markdown" => Some(Language::Markdown),
+            "py" => Some(Language::Python),
+            "js" | "jsx" | "ts" | "tsx" | "mjs" => Some(Language::JavaScript),
+            "go" => Some(Language::Go),
+            _ => None,
+        }
+    }
+
+    fn keywords(self) -> &'static [(&'static str, SymbolKind)] {
+        match self {
+            Language::Rust => &[
+                ("fn ", SymbolKind::Function),
+                ("struct ", SymbolKind::Type),
+                ("enum ", SymbolKind::Type),
+                ("union ", SymbolKind::Type),
+                ("type ", SymbolKind::Type),
+                ("trait ", SymbolKind::Trait),
+                ("impl", SymbolKind::Impl),
+                ("mod ", SymbolKind::Module),
+                ("const ", SymbolKind::Constant),
+                ("static ", SymbolKind::Constant),
+                ("macro_rules! ", SymbolKind::Macro),
+            ],
+            Language::Python => &[("def ", SymbolKind::Function), ("class ", SymbolKind::Type)],
+            Language::JavaScript => &[
+                ("function ", SymbolKind::Function),
+                ("class ", SymbolKind::Type),
+                ("interface ", SymbolKind::Type),
+                ("type ", SymbolKind::Type),
+            ],
+            Language::Go => &[("func ", SymbolKind::Function), ("type ", SymbolKind::Type)],
+            Language::Markdown => &[],
+        }
+    }
+
+    fn modifiers(self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &[
+                "pub(crate) ",
+                "pub(super) ",
+                "pub(self) ",
+                "pub ",
+                "async ",
+                "unsafe ",
+                "extern \"C\" ",
+                "default ",
+            ],
+            Language::Python => &["async "],
+            Language::JavaScript => &["export ", "default ", "async ", "abstract ", "declare "],
+            Language::Go | Language::Markdown => &[],
+        }
+    }
+}
+
+pub fn document_symbols(text: &Rope, path: Option<&Path>) -> Vec<Symbol> {
+    let Some(language) = path.and_then(Language::from_path) else {
+        return Vec::new();
+    };
+
+    match language {
+        Language::Markdown => markdown_symbols(text),
+        _ => keyword_symbols(text, language),
+    }
+}
+
+fn keyword_symbols(text: &Rope, language: Language) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+
+    for (line_idx, line) in text.lines().enumerate() {
+        let line = line.to_string();
+        let trimmed = line.trim_start();
+        let col = line.chars().count() - trimmed.chars().count();
+
+        let mut rest = trimmed;
+
+        while let Some(stripped) = language
+            .modifiers()
+            .iter()
+            .find_map(|modifier| rest.strip_prefix(modifier))
+        {
+            rest = stripped;
+        }
+
+        for (keyword, kind) in language.keywords() {
+            let Some(after) = rest.strip_prefix(keyword) else {
+                continue;
+            };
+
+            let name = if *kind == SymbolKind::Impl {
+                if !after.starts_with([' ', '<']) {
+                    continue;
+                }
+
+                after.split('{').next().unwrap_or(after).trim().to_string()
+            } else {
+                after
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+                    .collect()
+            };
+
+            if !name.is_empty() {
+                symbols.push(Symbol {
+                    name,
+                    kind: *kind,
+                    line: line_idx,
+                    col,
+                });
+            }
+
+            break;
+        }
+    }
+
+    symbols
+}
+
+fn markdown_symbols(text: &Rope) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let mut in_fence = false;
+
+    for (line_idx, line) in text.lines().enumerate() {
+        let line = line.to_string();
+
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+
+        if in_fence {
+            continue;
+        }
+
+        let level = line.chars().take_while(|c| *c == '#').count();
+
+        if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+            symbols.push(Symbol {
+                name: line[level..].trim().to_string(),
+                kind: SymbolKind::Heading(level),
+                line: line_idx,
+                col: 0,
+            });
+        }
+    }
+
+    symbols
+}
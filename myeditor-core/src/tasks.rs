@@ -0,0 +1,245 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules"];
+const BULLETS: [&str; 3] = ["- ", "* ", "+ "];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i64,
+    pub month: i64,
+    pub day: i64,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs()) as libc::time_t;
+        let mut tm = unsafe { std::mem::zeroed::<libc::tm>() };
+
+        if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+            return Self::from_days(secs as i64 / 86400);
+        }
+
+        Self {
+            year: tm.tm_year as i64 + 1900,
+            month: tm.tm_mon as i64 + 1,
+            day: tm.tm_mday as i64,
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.splitn(3, '-');
+        let mut number = |len: usize| {
+            parts
+                .next()
+                .filter(|part| part.len() == len && part.chars().all(|c| c.is_ascii_digit()))?
+                .parse()
+                .ok()
+        };
+
+        let date = Self {
+            year: number(4)?,
+            month: number(2)?,
+            day: number(2)?,
+        };
+
+        (Self::from_days(date.days()) == date).then_some(date)
+    }
+
+    pub fn from_days(days: i64) -> Self {
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+
+        Self {
+            year: yoe + era * 400 + i64::from(month <= 2),
+            month,
+            day: doy - (153 * mp + 2) / 5 + 1,
+        }
+    }
+
+    pub fn days(self) -> i64 {
+        let year = self.year - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let doy = (153 * ((self.month + 9) % 12) + 2) / 5 + self.day - 1;
+
+        era * 146097 + yoe * 365 + yoe / 4 - yoe / 100 + doy - 719468
+    }
+
+    pub fn add_days(self, days: i64) -> Self {
+        Self::from_days(self.days() + days)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    pub path: PathBuf,
+    pub line: usize,
+    pub text: String,
+    pub due: Option<Date>,
+}
+
+pub fn is_task_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext, "todo" | "md" | "markdown" | "mkd"))
+}
+
+pub fn state(line: &str) -> Option<(bool, &str)> {
+    let rest = strip_bullet(line.trim_start());
+
+    if let Some(text) = rest.strip_prefix("TODO ") {
+        return Some((false, text));
+    }
+
+    if let Some(text) = rest.strip_prefix("DONE ") {
+        return Some((true, text));
+    }
+
+    if rest.len() == line.trim_start().len() {
+        return None;
+    }
+
+    match rest.get(..4)? {
+        "[ ] " => Some((false, &rest[4..])),
+        "[x] " | "[X] " => Some((true, &rest[4..])),
+        _ => None,
+    }
+}
+
+pub fn toggle(line: &str) -> String {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let rest = strip_bullet(&line[indent.len()..]);
+    let prefix = &line[..line.len() - rest.len()];
+
+    if let Some(text) = rest.strip_prefix("TODO ") {
+        format!("{prefix}DONE {text}")
+    } else if let Some(text) = rest.strip_prefix("DONE ") {
+        format!("{prefix}TODO {text}")
+    } else {
+        format!("{prefix}TODO {rest}")
+    }
+}
+
+pub fn due(line: &str) -> Option<Date> {
+    line.split_whitespace()
+        .find_map(|word| word.strip_prefix("due:"))
+        .and_then(Date::parse)
+}
+
+pub fn set_due(line: &str, due: Option<Date>) -> String {
+    let mut line = line
+        .split(' ')
+        .filter(|word| !word.starts_with("due:"))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end()
+        .to_string();
+
+    if let Some(due) = due {
+        line.push_str(&format!(" due:{due}"));
+    }
+
+    line
+}
+
+pub fn parse_due(arg: &str, today: Date) -> Option<Date> {
+    match arg {
+        "today" => Some(today),
+        "tomorrow" => Some(today.add_days(1)),
+        _ => match arg.strip_prefix('+') {
+            Some(days) => days.parse().ok().map(|days| today.add_days(days)),
+            None => Date::parse(arg),
+        },
+    }
+}
+
+pub fn collect(root: &Path) -> Vec<Task> {
+    let mut tasks = Vec::new();
+
+    collect_dir(root, &mut tasks);
+
+    tasks.sort_by(|a, b| match (a.due, b.due) {
+        (Some(x), Some(y)) => x.cmp(&y),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+
+    tasks
+}
+
+fn collect_dir(dir: &Path, tasks: &mut Vec<Task>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut entries = entries.filter_map(Result::ok).collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let path = entry.path();
+
+        if name.starts_with('.') {
+            continue;
+        }
+
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() && !SKIPPED_DIRS.contains(&name.as_ref()) => {
+                collect_dir(&path, tasks)
+            }
+            Ok(file_type) if file_type.is_file() && is_task_file(&path) => {
+                collect_file(path, tasks)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_file(path: PathBuf, tasks: &mut Vec<Task>) {
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return;
+    };
+
+    for (line, text) in text.lines().enumerate() {
+        if let Some((false, task)) = state(text) {
+            tasks.push(Task {
+                path: path.clone(),
+                line,
+                text: set_due(task, None),
+                due: due(task),
+            });
+        }
+    }
+}
+
+fn strip_bullet(line: &str) -> &str {
+    let stars = line.len() - line.trim_start_matches('*').len();
+
+    if stars > 0 && line[stars..].starts_with(' ') {
+        return &line[stars + 1..];
+    }
+
+    BULLETS
+        .iter()
+        .find_map(|bullet| line.strip_prefix(bullet))
+        .unwrap_or(line)
+}
@@ -0,0 +1,375 @@
+use crate::{
+    backend::{Backend, Cell},
+    colors::ColorDepth,
+    display::WIDE_TAIL,
+    error::{EditorError, Result},
+    style::{Attribute, Color, ContentStyle, CursorStyle, StyledContent},
+    width,
+};
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub struct Terminal {
+    backend: Box<dyn Backend>,
+    size: Size,
+    screen: Screen,
+    drawn: Option<Vec<Vec<Cell>>>,
+    cursor_visible: bool,
+    started: bool,
+    title: Option<String>,
+}
+
+#[derive(Debug)]
+struct Screen {
+    cells: Vec<Vec<Cell>>,
+    cursor: (u16, u16),
+}
+
+impl Terminal {
+    pub fn new(backend: Box<dyn Backend>) -> Result<Self> {
+        let size = backend.size().map_err(EditorError::Terminal)?;
+
+        Ok(Self {
+            backend,
+            size,
+            screen: Screen::new(size),
+            drawn: None,
+            cursor_visible: true,
+            started: false,
+            title: None,
+        })
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.backend.is_headless()
+    }
+
+    pub fn snapshot(&mut self) -> Option<String> {
+        if self.is_headless() {
+            self.present().ok()?;
+        }
+
+        self.backend.snapshot()
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    pub fn color_depth(&self) -> ColorDepth {
+        self.backend.color_depth()
+    }
+
+    pub fn enhanced_keys(&self) -> bool {
+        self.backend.enhanced_keys()
+    }
+
+    pub fn start(&mut self) -> Result<()> {
+        self.backend.start().map_err(EditorError::Terminal)?;
+
+        self.started = true;
+        self.change_cursor_style(CursorStyle::SteadyBlock)
+    }
+
+    pub fn finish(&mut self) -> Result<()> {
+        self.started = false;
+        self.title = None;
+
+        self.backend.finish().map_err(EditorError::Terminal)?;
+        self.change_cursor_style(CursorStyle::SteadyBlock)
+    }
+
+    pub fn suspend(&mut self) -> Result<()> {
+        self.finish()?;
+        self.flush().map_err(EditorError::Terminal)
+    }
+
+    pub fn resume(&mut self) -> Result<()> {
+        self.start()?;
+        self.clear()?;
+        self.flush().map_err(EditorError::Terminal)
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        self.screen = Screen::new(self.size);
+        self.drawn = None;
+
+        Ok(())
+    }
+
+    pub fn resize(&mut self, size: Size) -> Result<()> {
+        self.size = size;
+        self.backend.resize(size).map_err(EditorError::Terminal)?;
+
+        self.clear()
+    }
+
+    pub fn clear_line(&mut self, y: u16) -> Result<()> {
+        if let Some(row) = self.screen.cells.get_mut(y as usize) {
+            row.fill(Cell::default());
+        }
+
+        self.goto(0, y)
+    }
+
+    pub fn goto(&mut self, x: u16, y: u16) -> Result<()> {
+        self.screen.cursor = (x, y);
+
+        Ok(())
+    }
+
+    pub fn print(&mut self, text: impl Display) -> Result<()> {
+        self.screen
+            .print(&text.to_string(), ContentStyle::default());
+
+        Ok(())
+    }
+
+    pub fn print_at(&mut self, coords: (u16, u16), text: impl Display) -> Result<()> {
+        self.goto(coords.0, coords.1)?;
+        self.print(text)
+    }
+
+    pub fn print_styled(&mut self, text: StyledContent<impl Display>) -> Result<()> {
+        self.screen
+            .print(&text.content().to_string(), *text.style());
+
+        Ok(())
+    }
+
+    pub fn print_styled_at(
+        &mut self,
+        coords: (u16, u16),
+        text: StyledContent<impl Display>,
+    ) -> Result<()> {
+        self.goto(coords.0, coords.1)?;
+        self.print_styled(text)
+    }
+
+    pub fn move_cursor(&mut self, x: u16, y: u16) -> Result<()> {
+        self.goto(x, y)
+    }
+
+    pub fn show_cursor(&mut self) -> Result<()> {
+        self.cursor_visible = true;
+
+        Ok(())
+    }
+
+    pub fn hide_cursor(&mut self) -> Result<()> {
+        self.cursor_visible = false;
+
+        Ok(())
+    }
+
+    pub fn set_title(&mut self, title: &str) -> Result<()> {
+        if self.title.as_deref() == Some(title) {
+            return Ok(());
+        }
+
+        self.title = Some(title.to_string());
+
+        self.backend.set_title(title).map_err(EditorError::Terminal)
+    }
+
+    pub fn write_raw(&mut self, text: &str) -> Result<()> {
+        self.backend.write_raw(text).map_err(EditorError::Terminal)
+    }
+
+    pub fn change_cursor_style(&mut self, style: CursorStyle) -> Result<()> {
+        self.backend
+            .set_cursor_style(style)
+            .map_err(EditorError::Terminal)
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        if self.started {
+            self.present()?;
+        }
+
+        self.backend.flush()
+    }
+
+    fn present(&mut self) -> std::io::Result<()> {
+        self.backend.hide_cursor()?;
+
+        for (y, row) in self.screen.cells.iter().enumerate() {
+            let drawn = self.drawn.as_ref().map(|drawn| &drawn[y]);
+            let changed = |x: usize| drawn.is_none_or(|drawn| drawn[x] != row[x]);
+
+            let mut x = 0;
+
+            while x < row.len() {
+                if !changed(x) {
+                    x += 1;
+                    continue;
+                }
+
+                let start = if x > 0 && row[x].c == WIDE_TAIL {
+                    x - 1
+                } else {
+                    x
+                };
+
+                while x < row.len() && changed(x) {
+                    x += 1;
+                }
+
+                self.backend.draw(start as u16, y as u16, &row[start..x])?;
+            }
+        }
+
+        self.drawn = Some(self.screen.cells.clone());
+
+        let (x, y) = self.screen.cursor;
+
+        self.backend.move_cursor(x, y)?;
+
+        if self.cursor_visible {
+            self.backend.show_cursor()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Screen {
+    fn new(size: Size) -> Self {
+        Self {
+            cells: vec![vec![Cell::default(); size.width as usize]; size.height as usize],
+            cursor: (0, 0),
+        }
+    }
+
+    fn print(&mut self, text: &str, base: ContentStyle) {
+        let (x, y) = self.cursor;
+
+        let Some(row) = self.cells.get_mut(y as usize) else {
+            return;
+        };
+
+        let mut x = x as usize;
+        let mut style = base;
+        let mut chars = text.chars();
+
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                if chars.next() == Some('[') {
+                    let mut params = String::new();
+
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            if c == 'm' {
+                                apply_sgr(&mut style, &params);
+                            }
+
+                            break;
+                        }
+
+                        params.push(c);
+                    }
+                }
+
+                continue;
+            }
+
+            if c.is_control() {
+                continue;
+            }
+
+            let columns = width::char_width(c).max(1);
+
+            if x < row.len() {
+                let end = std::cmp::min(x + columns, row.len());
+
+                if x > 0 && row[x].c == WIDE_TAIL {
+                    row[x - 1].c = ' ';
+                }
+
+                if row.get(end).is_some_and(|cell| cell.c == WIDE_TAIL) {
+                    row[end].c = ' ';
+                }
+
+                row[x] = Cell {
+                    c: if x + columns > end { ' ' } else { c },
+                    style,
+                };
+
+                for cell in &mut row[x + 1..end] {
+                    *cell = Cell {
+                        c: WIDE_TAIL,
+                        style,
+                    };
+                }
+            }
+
+            x += columns;
+        }
+
+        self.cursor.0 = x.min(u16::MAX as usize) as u16;
+    }
+}
+
+fn apply_sgr(style: &mut ContentStyle, params: &str) {
+    let mut codes = params
+        .split(';')
+        .map(|code| code.parse::<u8>().unwrap_or(0));
+
+    while let Some(code) = codes.next() {
+        match code {
+            0 => *style = ContentStyle::default(),
+            22 => {
+                style.attributes.unset(Attribute::Bold);
+                style.attributes.unset(Attribute::Dim);
+            }
+            23 => style.attributes.unset(Attribute::Italic),
+            24 => style.attributes.unset(Attribute::Underlined),
+            25 => style.attributes.unset(Attribute::SlowBlink),
+            27 => style.attributes.unset(Attribute::Reverse),
+            28 => style.attributes.unset(Attribute::Hidden),
+            29 => style.attributes.unset(Attribute::CrossedOut),
+            30..=37 => style.foreground_color = Some(Color::AnsiValue(code - 30)),
+            90..=97 => style.foreground_color = Some(Color::AnsiValue(code - 82)),
+            40..=47 => style.background_color = Some(Color::AnsiValue(code - 40)),
+            100..=107 => style.background_color = Some(Color::AnsiValue(code - 92)),
+            38 => style.foreground_color = extended_color(&mut codes),
+            48 => style.background_color = extended_color(&mut codes),
+            58 => style.underline_color = extended_color(&mut codes),
+            39 => style.foreground_color = None,
+            49 => style.background_color = None,
+            59 => style.underline_color = None,
+            code => {
+                if let Some(attribute) = Attribute::ALL
+                    .into_iter()
+                    .find(|attribute| attribute.sgr() == code)
+                {
+                    style.attributes.set(attribute);
+                }
+            }
+        }
+    }
+}
+
+fn extended_color(codes: &mut impl Iterator<Item = u8>) -> Option<Color> {
+    match codes.next()? {
+        5 => codes.next().map(Color::AnsiValue),
+        2 => Some(Color::Rgb {
+            r: codes.next()?,
+            g: codes.next()?,
+            b: codes.next()?,
+        }),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Size {
+    pub width: u16,
+    pub height: u16,
+}
+
+impl From<(u16, u16)> for Size {
+    fn from((width, height): (u16, u16)) -> Self {
+        Self { width, height }
+    }
+}
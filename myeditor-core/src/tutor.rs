@@ -0,0 +1,54 @@
+use ropey::Rope;
+
+pub const TEXT: &str = include_str!("../../doc/tutor.txt");
+
+const SOLUTIONS: [&[&str]; 8] = [
+    &["--> The cow jumped over the moon."],
+    &["--> This line is missing its end."],
+    &["--> The cat sat on the mat."],
+    &["--> keep this line", "--> keep this line too"],
+    &["--> 1. first", "--> 2. second"],
+    &["--> one cat two cat red cat blue cat"],
+    &["    --> indent me"],
+    &["--> qux bar qux baz qux"],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exercise {
+    pub line: usize,
+    pub done: bool,
+}
+
+pub fn exercises(text: &Rope) -> Vec<Exercise> {
+    let lines = text
+        .lines()
+        .map(|line| line.to_string().trim_end().to_string())
+        .collect::<Vec<_>>();
+
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, line)| Some((idx, number(line)?)))
+        .filter_map(|(idx, exercise)| {
+            let solution = SOLUTIONS.get(exercise.checked_sub(1)?)?;
+            let body = lines[idx + 1..]
+                .iter()
+                .take_while(|line| number(line).is_none() && !line.starts_with("==="))
+                .filter(|line| line.trim_start().starts_with("-->"))
+                .map(String::as_str);
+
+            Some(Exercise {
+                line: idx,
+                done: body.eq(solution.iter().copied()),
+            })
+        })
+        .collect()
+}
+
+fn number(line: &str) -> Option<usize> {
+    line.strip_prefix("Exercise ")?
+        .split_once(':')?
+        .0
+        .parse()
+        .ok()
+}
@@ -0,0 +1,216 @@
+use ropey::Rope;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone)]
+pub struct State {
+    pub text: Rope,
+    pub cursor: (usize, usize),
+    pub parent: Option<usize>,
+    pub time: SystemTime,
+    redo: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Row {
+    pub state: usize,
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    states: Vec<State>,
+    current: usize,
+    saved: Option<usize>,
+    pending: Option<(usize, usize)>,
+}
+
+impl History {
+    pub fn record(&mut self, text: &Rope, cursor: (usize, usize)) {
+        if self.states.is_empty() {
+            self.states.push(State {
+                text: text.clone(),
+                cursor: (0, 0),
+                parent: None,
+                time: SystemTime::now(),
+                redo: None,
+            });
+            self.saved = self.saved.or(Some(0));
+        }
+
+        self.pending.get_or_insert(cursor);
+    }
+
+    pub fn commit(&mut self, text: &Rope) {
+        let Some(cursor) = self.pending.take() else {
+            return;
+        };
+
+        let idx = self.states.len();
+
+        self.states.push(State {
+            text: text.clone(),
+            cursor,
+            parent: Some(self.current),
+            time: SystemTime::now(),
+            redo: None,
+        });
+        self.states[self.current].redo = Some(idx);
+        self.current = idx;
+    }
+
+    pub fn undo(&mut self) -> Option<(&State, usize)> {
+        let undone = self.current;
+        let parent = self.states.get(undone)?.parent?;
+
+        self.states[parent].redo = Some(undone);
+        self.current = parent;
+
+        Some((&self.states[parent], undone))
+    }
+
+    pub fn redo(&mut self) -> Option<(&State, usize)> {
+        let child = self.states.get(self.current)?.redo?;
+
+        self.current = child;
+
+        Some((&self.states[child], child))
+    }
+
+    pub fn goto(&mut self, idx: usize) -> Option<&State> {
+        self.states.get(idx)?;
+
+        let mut child = idx;
+
+        while let Some(parent) = self.states[child].parent {
+            self.states[parent].redo = Some(child);
+            child = parent;
+        }
+
+        self.current = idx;
+
+        Some(&self.states[idx])
+    }
+
+    pub fn states(&self) -> &[State] {
+        &self.states
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn saved(&self) -> Option<usize> {
+        self.saved
+    }
+
+    pub fn is_saved(&self) -> bool {
+        self.saved == Some(self.current) || self.states.is_empty()
+    }
+
+    pub fn mark_saved(&mut self, text: &Rope) {
+        self.commit(text);
+
+        self.saved = Some(self.current);
+    }
+
+    pub fn rows(&self) -> Vec<Row> {
+        let mut children = vec![Vec::new(); self.states.len()];
+
+        for (idx, state) in self.states.iter().enumerate() {
+            if let Some(parent) = state.parent {
+                children[parent].push(idx);
+            }
+        }
+
+        let mut rows = Vec::with_capacity(self.states.len());
+        let mut stack = Vec::new();
+
+        if !self.states.is_empty() {
+            stack.push(Row { state: 0, depth: 0 });
+        }
+
+        while let Some(row) = stack.pop() {
+            rows.push(row);
+
+            if let Some((&newest, older)) = children[row.state].split_last() {
+                stack.push(Row {
+                    state: newest,
+                    depth: row.depth,
+                });
+
+                for &state in older.iter().rev() {
+                    stack.push(Row {
+                        state,
+                        depth: row.depth + 1,
+                    });
+                }
+            }
+        }
+
+        rows
+    }
+}
+
+#[derive(Debug)]
+pub struct UndoTree {
+    rows: Vec<Row>,
+    selected: usize,
+    pub cursor: (usize, usize),
+}
+
+impl UndoTree {
+    pub fn new(history: &History, cursor: (usize, usize)) -> Self {
+        let rows = history.rows();
+        let selected = rows
+            .iter()
+            .position(|row| row.state == history.current())
+            .unwrap_or(0);
+
+        Self {
+            rows,
+            selected,
+            cursor,
+        }
+    }
+
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_state(&self) -> usize {
+        self.rows[self.selected].state
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.rows.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_first(&mut self) {
+        self.selected = 0;
+    }
+
+    pub fn select_last(&mut self) {
+        self.selected = self.rows.len().saturating_sub(1);
+    }
+}
+
+pub fn age(time: SystemTime, now: SystemTime) -> String {
+    let secs = now.duration_since(time).unwrap_or(Duration::ZERO).as_secs();
+
+    match secs {
+        0..=59 => format!("{secs}s ago"),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
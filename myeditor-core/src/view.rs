@@ -0,0 +1,117 @@
+use crate::display;
+use ropey::RopeSlice;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Viewport {
+    pub top_line: usize,
+    pub left_col: usize,
+}
+
+impl Viewport {
+    pub fn screen_line(&self, row: usize, folded: &[Range<usize>]) -> usize {
+        (0..row).fold(self.top_line, |line, _| next_visible(folded, line))
+    }
+
+    pub fn screen_row(&self, line: usize, folded: &[Range<usize>]) -> Option<usize> {
+        rows_between(self.top_line, line, folded, usize::MAX)
+    }
+
+    pub fn screen_col(&self, col: usize) -> Option<usize> {
+        col.checked_sub(self.left_col)
+    }
+
+    pub fn buffer_col(&self, line: RopeSlice, x: usize) -> usize {
+        index(line, self.left_col + x)
+    }
+
+    pub fn scroll_to(
+        &mut self,
+        line: usize,
+        cols: Range<usize>,
+        (width, height): (usize, usize),
+        scrolloff: usize,
+        folded: &[Range<usize>],
+    ) -> bool {
+        let before = *self;
+        let scrolloff = std::cmp::min(scrolloff, height.saturating_sub(1) / 2);
+
+        if let Some(hidden) = folded.iter().find(|hidden| hidden.contains(&self.top_line)) {
+            self.top_line = hidden.start - 1;
+        }
+
+        let above = (0..scrolloff).fold(line, |top, _| prev_visible(folded, top));
+
+        if above < self.top_line {
+            self.top_line = above;
+        }
+
+        let below = height.saturating_sub(scrolloff + 1);
+
+        if rows_between(self.top_line, line, folded, below + 1).is_none_or(|row| row > below) {
+            self.top_line = (0..below).fold(line, |top, _| prev_visible(folded, top));
+        }
+
+        if cols.start < self.left_col {
+            self.left_col = cols.start;
+        } else if cols.end > self.left_col + width {
+            self.left_col = std::cmp::min(cols.end.saturating_sub(width), cols.start);
+        }
+
+        *self != before
+    }
+
+    pub fn center_on(&mut self, line: usize, height: usize) -> bool {
+        if (self.top_line..self.top_line + height).contains(&line) {
+            return false;
+        }
+
+        self.top_line = line.saturating_sub(height / 2);
+
+        true
+    }
+}
+
+pub fn column(line: RopeSlice, idx: usize) -> usize {
+    display::columns(line.slice(..std::cmp::min(idx, line.len_chars())))
+}
+
+pub fn index(line: RopeSlice, column: usize) -> usize {
+    let line = display::trim_newline(line);
+    let mut width = 0;
+
+    line.chars()
+        .take_while(|&c| {
+            width += display::char_columns(c);
+            width <= column
+        })
+        .count()
+}
+
+pub fn next_visible(folded: &[Range<usize>], line: usize) -> usize {
+    folded
+        .iter()
+        .find(|hidden| hidden.contains(&(line + 1)))
+        .map_or(line + 1, |hidden| hidden.end)
+}
+
+pub fn prev_visible(folded: &[Range<usize>], line: usize) -> usize {
+    let prev = line.saturating_sub(1);
+
+    folded
+        .iter()
+        .find(|hidden| hidden.contains(&prev))
+        .map_or(prev, |hidden| hidden.start - 1)
+}
+
+fn rows_between(top: usize, line: usize, folded: &[Range<usize>], limit: usize) -> Option<usize> {
+    let mut visible = top;
+    let mut row = 0;
+
+    while visible < line && row < limit {
+        visible = next_visible(folded, visible);
+        row += 1;
+    }
+
+    (visible == line).then_some(row)
+}
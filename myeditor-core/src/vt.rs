@@ -0,0 +1,261 @@
+#[derive(Debug)]
+pub struct Vt {
+    lines: Vec<Vec<char>>,
+    cols: usize,
+    cursor: (usize, usize),
+    saved: (usize, usize),
+    state: State,
+    pending: Vec<u8>,
+}
+
+#[derive(Debug)]
+enum State {
+    Ground,
+    Escape,
+    Csi(String),
+    Osc(bool),
+}
+
+impl Vt {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        let cols = cols.max(1);
+
+        Self {
+            lines: vec![vec![' '; cols]; rows.max(1)],
+            cols,
+            cursor: (0, 0),
+            saved: (0, 0),
+            state: State::Ground,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.cols, self.lines.len())
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.cursor.0.min(self.cols - 1), self.cursor.1)
+    }
+
+    pub fn line(&self, row: usize) -> Option<String> {
+        self.lines
+            .get(row)
+            .map(|line| line.iter().collect::<String>().trim_end().to_string())
+    }
+
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+
+        if self.lines.len() > rows {
+            let extra = self.lines.len() - rows;
+            let removed = std::cmp::min(extra, self.cursor.1);
+
+            self.lines.drain(..removed);
+            self.lines.truncate(rows);
+            self.cursor.1 -= removed;
+        }
+
+        self.lines.resize(rows, vec![' '; cols]);
+
+        for line in &mut self.lines {
+            line.resize(cols, ' ');
+        }
+
+        self.cols = cols;
+        self.cursor.0 = self.cursor.0.min(cols);
+        self.cursor.1 = self.cursor.1.min(rows - 1);
+        self.saved = (self.saved.0.min(cols), self.saved.1.min(rows - 1));
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+
+        let pending = std::mem::take(&mut self.pending);
+        let mut rest = pending.as_slice();
+
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(text) => {
+                    self.feed_str(text);
+                    break;
+                }
+                Err(e) => {
+                    let (valid, after) = rest.split_at(e.valid_up_to());
+
+                    self.feed_str(std::str::from_utf8(valid).unwrap_or_default());
+
+                    match e.error_len() {
+                        Some(len) => {
+                            self.feed_str("\u{fffd}");
+                            rest = &after[len..];
+                        }
+                        None => {
+                            self.pending = after.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn feed_str(&mut self, text: &str) {
+        for c in text.chars() {
+            self.feed_char(c);
+        }
+    }
+
+    fn feed_char(&mut self, c: char) {
+        match std::mem::replace(&mut self.state, State::Ground) {
+            State::Ground => self.ground(c),
+            State::Escape => match c {
+                '[' => self.state = State::Csi(String::new()),
+                ']' => self.state = State::Osc(false),
+                '7' => self.saved = self.cursor,
+                '8' => self.cursor = self.saved,
+                'D' => self.line_feed(),
+                'M' => self.reverse_line_feed(),
+                'c' => *self = Self::new(self.cols, self.lines.len()),
+                _ => {}
+            },
+            State::Csi(mut params) => {
+                if ('@'..='~').contains(&c) {
+                    self.csi(&params, c);
+                } else {
+                    params.push(c);
+                    self.state = State::Csi(params);
+                }
+            }
+            State::Osc(escaped) => match c {
+                '\u{7}' => {}
+                '\\' if escaped => {}
+                c => self.state = State::Osc(c == '\u{1b}'),
+            },
+        }
+    }
+
+    fn ground(&mut self, c: char) {
+        match c {
+            '\u{1b}' => self.state = State::Escape,
+            '\r' => self.cursor.0 = 0,
+            '\n' | '\u{b}' | '\u{c}' => self.line_feed(),
+            '\u{8}' => self.cursor.0 = self.cursor.0.min(self.cols).saturating_sub(1),
+            '\t' => self.cursor.0 = std::cmp::min((self.cursor.0 / 8 + 1) * 8, self.cols - 1),
+            c if c.is_control() => {}
+            c => {
+                if self.cursor.0 >= self.cols {
+                    self.cursor.0 = 0;
+                    self.line_feed();
+                }
+
+                self.lines[self.cursor.1][self.cursor.0] = c;
+                self.cursor.0 += 1;
+            }
+        }
+    }
+
+    fn line_feed(&mut self) {
+        if self.cursor.1 + 1 < self.lines.len() {
+            self.cursor.1 += 1;
+        } else {
+            self.lines.remove(0);
+            self.lines.push(vec![' '; self.cols]);
+        }
+    }
+
+    fn reverse_line_feed(&mut self) {
+        if self.cursor.1 > 0 {
+            self.cursor.1 -= 1;
+        } else {
+            self.lines.pop();
+            self.lines.insert(0, vec![' '; self.cols]);
+        }
+    }
+
+    fn csi(&mut self, params: &str, action: char) {
+        if params.starts_with(['?', '>', '=']) {
+            return;
+        }
+
+        let args = params
+            .split(';')
+            .map(|arg| arg.parse::<usize>().unwrap_or(0))
+            .collect::<Vec<_>>();
+
+        let arg = |i: usize| args.get(i).copied().unwrap_or(0);
+        let count = arg(0).max(1);
+        let rows = self.lines.len();
+        let (col, row) = (self.cursor.0.min(self.cols - 1), self.cursor.1);
+
+        match action {
+            'A' => self.cursor.1 = row.saturating_sub(count),
+            'B' => self.cursor.1 = std::cmp::min(row + count, rows - 1),
+            'C' => self.cursor.0 = std::cmp::min(col + count, self.cols - 1),
+            'D' => self.cursor.0 = col.saturating_sub(count),
+            'E' => self.cursor = (0, std::cmp::min(row + count, rows - 1)),
+            'F' => self.cursor = (0, row.saturating_sub(count)),
+            'G' | '`' => self.cursor.0 = std::cmp::min(count - 1, self.cols - 1),
+            'd' => self.cursor.1 = std::cmp::min(count - 1, rows - 1),
+            'H' | 'f' => {
+                self.cursor = (
+                    std::cmp::min(arg(1).max(1) - 1, self.cols - 1),
+                    std::cmp::min(count - 1, rows - 1),
+                );
+            }
+            'J' => match arg(0) {
+                0 => {
+                    self.lines[row][col..].fill(' ');
+                    self.lines[row + 1..].iter_mut().for_each(|l| l.fill(' '));
+                }
+                1 => {
+                    self.lines[..row].iter_mut().for_each(|l| l.fill(' '));
+                    self.lines[row][..=col].fill(' ');
+                }
+                _ => self.lines.iter_mut().for_each(|l| l.fill(' ')),
+            },
+            'K' => match arg(0) {
+                0 => self.lines[row][col..].fill(' '),
+                1 => self.lines[row][..=col].fill(' '),
+                _ => self.lines[row].fill(' '),
+            },
+            'X' => {
+                let end = std::cmp::min(col + count, self.cols);
+
+                self.lines[row][col..end].fill(' ');
+            }
+            'P' => {
+                let line = &mut self.lines[row];
+                let count = std::cmp::min(count, self.cols - col);
+
+                line.drain(col..col + count);
+                line.resize(self.cols, ' ');
+            }
+            '@' => {
+                let line = &mut self.lines[row];
+
+                for _ in 0..std::cmp::min(count, self.cols - col) {
+                    line.insert(col, ' ');
+                }
+
+                line.truncate(self.cols);
+            }
+            'L' => {
+                for _ in 0..std::cmp::min(count, rows - row) {
+                    self.lines.pop();
+                    self.lines.insert(row, vec![' '; self.cols]);
+                }
+            }
+            'M' => {
+                for _ in 0..std::cmp::min(count, rows - row) {
+                    self.lines.remove(row);
+                    self.lines.push(vec![' '; self.cols]);
+                }
+            }
+            's' => self.saved = self.cursor,
+            'u' => self.cursor = self.saved,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,1398 @@
+use std::{collections::HashMap, rc::Rc};
+
+const PAGE_SIZE: usize = 65536;
+const MAX_PAGES: u32 = 1024;
+const MAX_DEPTH: usize = 256;
+const MAX_LOCALS: usize = 50000;
+const FUEL: u64 = 10_000_000;
+
+const OUT_OF_BOUNDS: &str = "Out of bounds memory access";
+const STACK_UNDERFLOW: &str = "Value stack underflow";
+const INTEGER_OVERFLOW: &str = "Integer overflow";
+const DIVIDE_BY_ZERO: &str = "Integer divide by zero";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuncType {
+    pub params: Vec<ValType>,
+    pub results: Vec<ValType>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub module: String,
+    pub name: String,
+    pub ty: FuncType,
+}
+
+pub trait Host {
+    fn call(
+        &mut self,
+        import: &Import,
+        args: &[Value],
+        memory: &mut [u8],
+    ) -> Result<Option<Value>, String>;
+}
+
+#[derive(Debug)]
+pub struct Instance {
+    types: Vec<FuncType>,
+    imports: Vec<Import>,
+    functions: Vec<Rc<Function>>,
+    exports: HashMap<String, (u8, u32)>,
+    table: Vec<Option<u32>>,
+    globals: Vec<Value>,
+    memory: Vec<u8>,
+    max_pages: u32,
+    start: Option<u32>,
+    fuel: u64,
+}
+
+#[derive(Debug)]
+struct Function {
+    ty: u32,
+    locals: Vec<ValType>,
+    code: Vec<Op>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Label {
+    pc: usize,
+    height: usize,
+    arity: usize,
+    is_loop: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    Unreachable,
+    Nop,
+    Block {
+        params: usize,
+        results: usize,
+        end: usize,
+    },
+    Loop {
+        params: usize,
+    },
+    If {
+        params: usize,
+        results: usize,
+        otherwise: Option<usize>,
+        end: usize,
+    },
+    Else {
+        end: usize,
+    },
+    End,
+    Br(u32),
+    BrIf(u32),
+    BrTable(Box<[u32]>, u32),
+    Return,
+    Call(u32),
+    CallIndirect(u32),
+    Drop,
+    Select,
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+    Load(u8, u32),
+    Store(u8, u32),
+    MemorySize,
+    MemoryGrow,
+    MemoryCopy,
+    MemoryFill,
+    Const(Value),
+    Numeric(u8),
+    Saturate(u8),
+}
+
+impl Value {
+    pub fn ty(&self) -> ValType {
+        match self {
+            Self::I32(_) => ValType::I32,
+            Self::I64(_) => ValType::I64,
+            Self::F32(_) => ValType::F32,
+            Self::F64(_) => ValType::F64,
+        }
+    }
+
+    fn bits(&self) -> u64 {
+        match *self {
+            Self::I32(v) => v as u32 as u64,
+            Self::I64(v) => v as u64,
+            Self::F32(v) => v.to_bits() as u64,
+            Self::F64(v) => v.to_bits(),
+        }
+    }
+}
+
+impl ValType {
+    fn zero(&self) -> Value {
+        match self {
+            Self::I32 => Value::I32(0),
+            Self::I64 => Value::I64(0),
+            Self::F32 => Value::F32(0.0),
+            Self::F64 => Value::F64(0.0),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::I32(value as i32)
+    }
+}
+
+macro_rules! value_conversions {
+    ($($ty:ty => $variant:ident),*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(value: $ty) -> Self {
+                    Self::$variant(value)
+                }
+            }
+
+            impl TryFrom<Value> for $ty {
+                type Error = String;
+
+                fn try_from(value: Value) -> Result<Self, String> {
+                    match value {
+                        Value::$variant(v) => Ok(v),
+                        other => Err(format!(
+                            "Type mismatch: expected {:?}, found {:?}",
+                            ValType::$variant,
+                            other.ty()
+                        )),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+value_conversions!(i32 => I32, i64 => I64, f32 => F32, f64 => F64);
+
+impl Instance {
+    pub fn new(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new(bytes);
+
+        if reader.bytes(4)? != b"\0asm" {
+            return Err("Not a WebAssembly module".to_string());
+        }
+
+        if reader.bytes(4)? != [1, 0, 0, 0] {
+            return Err("Unsupported WebAssembly version".to_string());
+        }
+
+        let mut instance = Self {
+            types: Vec::new(),
+            imports: Vec::new(),
+            functions: Vec::new(),
+            exports: HashMap::new(),
+            table: Vec::new(),
+            globals: Vec::new(),
+            memory: Vec::new(),
+            max_pages: 0,
+            start: None,
+            fuel: FUEL,
+        };
+        let mut declared = Vec::new();
+
+        while !reader.is_empty() {
+            let id = reader.byte()?;
+            let size = reader.u32()? as usize;
+            let mut section = Reader::new(reader.bytes(size)?);
+
+            match id {
+                0 | 12 => {}
+                1 => {
+                    for _ in 0..section.u32()? {
+                        if section.byte()? != 0x60 {
+                            return Err("Invalid function type".to_string());
+                        }
+
+                        let params = section.val_types()?;
+                        let results = section.val_types()?;
+
+                        instance.types.push(FuncType { params, results });
+                    }
+                }
+                2 => {
+                    for _ in 0..section.u32()? {
+                        let module = section.name()?;
+                        let name = section.name()?;
+
+                        if section.byte()? != 0 {
+                            return Err(format!(
+                                "Unsupported import {module}.{name}: only functions can be imported"
+                            ));
+                        }
+
+                        let ty = instance.func_type(section.u32()?)?.clone();
+
+                        instance.imports.push(Import { module, name, ty });
+                    }
+                }
+                3 => {
+                    for _ in 0..section.u32()? {
+                        declared.push(section.u32()?);
+                    }
+                }
+                4 => {
+                    for _ in 0..section.u32()? {
+                        if section.byte()? != 0x70 {
+                            return Err("Unsupported table type".to_string());
+                        }
+
+                        let (min, _) = section.limits()?;
+
+                        instance.table.resize(min as usize, None);
+                    }
+                }
+                5 => {
+                    for _ in 0..section.u32()? {
+                        let (min, max) = section.limits()?;
+
+                        if min > MAX_PAGES {
+                            return Err("Memory too large".to_string());
+                        }
+
+                        instance.memory = vec![0; min as usize * PAGE_SIZE];
+                        instance.max_pages = max.unwrap_or(MAX_PAGES).min(MAX_PAGES);
+                    }
+                }
+                6 => {
+                    for _ in 0..section.u32()? {
+                        section.val_type()?;
+                        section.byte()?;
+
+                        let value = section.const_expr(&instance.globals)?;
+
+                        instance.globals.push(value);
+                    }
+                }
+                7 => {
+                    for _ in 0..section.u32()? {
+                        let name = section.name()?;
+                        let kind = section.byte()?;
+                        let index = section.u32()?;
+
+                        instance.exports.insert(name, (kind, index));
+                    }
+                }
+                8 => instance.start = Some(section.u32()?),
+                9 => {
+                    for _ in 0..section.u32()? {
+                        let flags = section.u32()?;
+
+                        if flags == 2 {
+                            section.u32()?;
+                        }
+
+                        if flags != 0 && flags != 2 {
+                            return Err(format!("Unsupported element segment {flags}"));
+                        }
+
+                        let offset = section.const_offset(&instance.globals)?;
+
+                        if flags == 2 && section.byte()? != 0 {
+                            return Err("Unsupported element kind".to_string());
+                        }
+
+                        for i in 0..section.u32()? {
+                            let function = section.u32()?;
+                            let slot = instance
+                                .table
+                                .get_mut(offset + i as usize)
+                                .ok_or("Element segment out of bounds")?;
+
+                            *slot = Some(function);
+                        }
+                    }
+                }
+                10 => {
+                    let count = section.u32()? as usize;
+
+                    if count != declared.len() {
+                        return Err("Function and code section sizes differ".to_string());
+                    }
+
+                    for ty in declared.iter().copied() {
+                        let size = section.u32()? as usize;
+                        let mut body = Reader::new(section.bytes(size)?);
+                        let mut locals = Vec::new();
+
+                        for _ in 0..body.u32()? {
+                            let count = body.u32()? as usize;
+                            let ty = body.val_type()?;
+
+                            if locals.len() + count > MAX_LOCALS {
+                                return Err("Too many locals".to_string());
+                            }
+
+                            locals.extend(std::iter::repeat_n(ty, count));
+                        }
+
+                        let code = body.code(&instance.types)?;
+
+                        instance
+                            .functions
+                            .push(Rc::new(Function { ty, locals, code }));
+                    }
+                }
+                11 => {
+                    for _ in 0..section.u32()? {
+                        match section.u32()? {
+                            0 => {}
+                            1 => {
+                                let len = section.u32()? as usize;
+
+                                section.bytes(len)?;
+
+                                continue;
+                            }
+                            2 => {
+                                section.u32()?;
+                            }
+                            mode => return Err(format!("Unsupported data segment {mode}")),
+                        }
+
+                        let offset = section.const_offset(&instance.globals)?;
+                        let len = section.u32()? as usize;
+                        let data = section.bytes(len)?;
+                        let target = offset
+                            .checked_add(len)
+                            .and_then(|end| instance.memory.get_mut(offset..end))
+                            .ok_or("Data segment out of bounds")?;
+
+                        target.copy_from_slice(data);
+                    }
+                }
+                id => return Err(format!("Unknown section {id}")),
+            }
+        }
+
+        if instance.functions.len() != declared.len() {
+            return Err("Missing code section".to_string());
+        }
+
+        for function in &instance.functions {
+            instance.func_type(function.ty)?;
+        }
+
+        Ok(instance)
+    }
+
+    pub fn start(&mut self, host: &mut dyn Host) -> Result<(), String> {
+        match self.start.take() {
+            Some(index) => {
+                self.fuel = FUEL;
+                self.invoke(index, Vec::new(), host, 0).map(|_| ())
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub fn imports(&self) -> &[Import] {
+        &self.imports
+    }
+
+    pub fn has_function(&self, name: &str) -> bool {
+        matches!(self.exports.get(name), Some((0, _)))
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+
+    pub fn call(
+        &mut self,
+        name: &str,
+        args: &[Value],
+        host: &mut dyn Host,
+    ) -> Result<Vec<Value>, String> {
+        let Some(&(0, index)) = self.exports.get(name) else {
+            return Err(format!("No exported function {name}"));
+        };
+
+        let ty = self.function_type(index)?;
+
+        if ty.params.len() != args.len()
+            || ty.params.iter().zip(args).any(|(ty, arg)| *ty != arg.ty())
+        {
+            return Err(format!("Wrong arguments for {name}"));
+        }
+
+        self.fuel = FUEL;
+        self.invoke(index, args.to_vec(), host, 0)
+    }
+
+    fn func_type(&self, index: u32) -> Result<&FuncType, String> {
+        self.types
+            .get(index as usize)
+            .ok_or_else(|| format!("Invalid type index {index}"))
+    }
+
+    fn function_type(&self, index: u32) -> Result<&FuncType, String> {
+        let imports = self.imports.len();
+
+        match (index as usize).checked_sub(imports) {
+            None => Ok(&self.imports[index as usize].ty),
+            Some(defined) => match self.functions.get(defined) {
+                Some(function) => self.func_type(function.ty),
+                None => Err(format!("Invalid function index {index}")),
+            },
+        }
+    }
+
+    fn invoke(
+        &mut self,
+        index: u32,
+        args: Vec<Value>,
+        host: &mut dyn Host,
+        depth: usize,
+    ) -> Result<Vec<Value>, String> {
+        let imports = self.imports.len();
+
+        if let Some(import) = self.imports.get(index as usize) {
+            let result = host.call(import, &args, &mut self.memory)?;
+
+            return match (result, import.ty.results.as_slice()) {
+                (None, []) => Ok(Vec::new()),
+                (Some(value), [ty]) if value.ty() == *ty => Ok(vec![value]),
+                _ => Err(format!(
+                    "{}.{} returned the wrong type",
+                    import.module, import.name
+                )),
+            };
+        }
+
+        if depth >= MAX_DEPTH {
+            return Err("Call stack exhausted".to_string());
+        }
+
+        let function = Rc::clone(&self.functions[index as usize - imports]);
+        let results = self.func_type(function.ty)?.results.len();
+        let mut locals = args;
+        let mut stack = Vec::new();
+        let mut labels: Vec<Label> = Vec::new();
+        let mut pc = 0;
+
+        locals.extend(function.locals.iter().map(ValType::zero));
+
+        loop {
+            if self.fuel == 0 {
+                return Err("Ran out of fuel".to_string());
+            }
+
+            self.fuel -= 1;
+
+            let op = function
+                .code
+                .get(pc)
+                .ok_or("Fell off the end of a function")?;
+
+            pc += 1;
+
+            match op {
+                Op::Unreachable => return Err("Unreachable executed".to_string()),
+                Op::Nop => {}
+                &Op::Block {
+                    params,
+                    results,
+                    end,
+                } => labels.push(Label {
+                    pc: end + 1,
+                    height: stack.len().checked_sub(params).ok_or(STACK_UNDERFLOW)?,
+                    arity: results,
+                    is_loop: false,
+                }),
+                &Op::Loop { params } => labels.push(Label {
+                    pc,
+                    height: stack.len().checked_sub(params).ok_or(STACK_UNDERFLOW)?,
+                    arity: params,
+                    is_loop: true,
+                }),
+                &Op::If {
+                    params,
+                    results,
+                    otherwise,
+                    end,
+                } => {
+                    let condition = pop::<i32>(&mut stack)?;
+                    let label = Label {
+                        pc: end + 1,
+                        height: stack.len().checked_sub(params).ok_or(STACK_UNDERFLOW)?,
+                        arity: results,
+                        is_loop: false,
+                    };
+
+                    match (condition != 0, otherwise) {
+                        (true, _) => labels.push(label),
+                        (false, Some(otherwise)) => {
+                            labels.push(label);
+                            pc = otherwise + 1;
+                        }
+                        (false, None) => pc = end + 1,
+                    }
+                }
+                &Op::Else { end } => {
+                    labels.pop();
+                    pc = end + 1;
+                }
+                Op::End => {
+                    if labels.pop().is_none() {
+                        return split_top(&mut stack, results);
+                    }
+                }
+                &Op::Br(depth) => match branch(&mut stack, &mut labels, depth)? {
+                    Some(target) => pc = target,
+                    None => return split_top(&mut stack, results),
+                },
+                &Op::BrIf(depth) => {
+                    if pop::<i32>(&mut stack)? != 0 {
+                        match branch(&mut stack, &mut labels, depth)? {
+                            Some(target) => pc = target,
+                            None => return split_top(&mut stack, results),
+                        }
+                    }
+                }
+                Op::BrTable(targets, default) => {
+                    let index = pop::<i32>(&mut stack)? as u32 as usize;
+                    let depth = targets.get(index).copied().unwrap_or(*default);
+
+                    match branch(&mut stack, &mut labels, depth)? {
+                        Some(target) => pc = target,
+                        None => return split_top(&mut stack, results),
+                    }
+                }
+                Op::Return => return split_top(&mut stack, results),
+                &Op::Call(callee) => {
+                    let params = self.function_type(callee)?.params.len();
+                    let args = split_top(&mut stack, params)?;
+                    let values = self.invoke(callee, args, host, depth + 1)?;
+
+                    stack.extend(values);
+                }
+                &Op::CallIndirect(ty) => {
+                    let slot = pop::<i32>(&mut stack)? as u32 as usize;
+                    let callee = self
+                        .table
+                        .get(slot)
+                        .copied()
+                        .flatten()
+                        .ok_or("Undefined table element")?;
+
+                    if self.function_type(callee)? != self.func_type(ty)? {
+                        return Err("Indirect call type mismatch".to_string());
+                    }
+
+                    let params = self.func_type(ty)?.params.len();
+                    let args = split_top(&mut stack, params)?;
+                    let values = self.invoke(callee, args, host, depth + 1)?;
+
+                    stack.extend(values);
+                }
+                Op::Drop => {
+                    stack.pop().ok_or(STACK_UNDERFLOW)?;
+                }
+                Op::Select => {
+                    let condition = pop::<i32>(&mut stack)?;
+                    let b = stack.pop().ok_or(STACK_UNDERFLOW)?;
+                    let a = stack.pop().ok_or(STACK_UNDERFLOW)?;
+
+                    stack.push(if condition != 0 { a } else { b });
+                }
+                &Op::LocalGet(index) => {
+                    stack.push(*locals.get(index as usize).ok_or("Invalid local index")?)
+                }
+                &Op::LocalSet(index) => {
+                    let value = stack.pop().ok_or(STACK_UNDERFLOW)?;
+
+                    *locals
+                        .get_mut(index as usize)
+                        .ok_or("Invalid local index")? = value;
+                }
+                &Op::LocalTee(index) => {
+                    let value = *stack.last().ok_or(STACK_UNDERFLOW)?;
+
+                    *locals
+                        .get_mut(index as usize)
+                        .ok_or("Invalid local index")? = value;
+                }
+                &Op::GlobalGet(index) => stack.push(
+                    *self
+                        .globals
+                        .get(index as usize)
+                        .ok_or("Invalid global index")?,
+                ),
+                &Op::GlobalSet(index) => {
+                    let value = stack.pop().ok_or(STACK_UNDERFLOW)?;
+
+                    *self
+                        .globals
+                        .get_mut(index as usize)
+                        .ok_or("Invalid global index")? = value;
+                }
+                &Op::Load(opcode, offset) => {
+                    let address = address(pop::<i32>(&mut stack)?, offset)?;
+
+                    stack.push(self.load(opcode, address)?);
+                }
+                &Op::Store(opcode, offset) => {
+                    let value = stack.pop().ok_or(STACK_UNDERFLOW)?;
+                    let address = address(pop::<i32>(&mut stack)?, offset)?;
+
+                    self.store(opcode, address, value)?;
+                }
+                Op::MemorySize => stack.push(Value::I32((self.memory.len() / PAGE_SIZE) as i32)),
+                Op::MemoryGrow => {
+                    let delta = pop::<i32>(&mut stack)? as u32;
+                    let pages = (self.memory.len() / PAGE_SIZE) as u32;
+
+                    match pages.checked_add(delta) {
+                        Some(total) if total <= self.max_pages => {
+                            self.memory.resize(total as usize * PAGE_SIZE, 0);
+                            stack.push(Value::I32(pages as i32));
+                        }
+                        _ => stack.push(Value::I32(-1)),
+                    }
+                }
+                Op::MemoryCopy => {
+                    let len = pop::<i32>(&mut stack)? as u32 as usize;
+                    let source = pop::<i32>(&mut stack)? as u32 as usize;
+                    let target = pop::<i32>(&mut stack)? as u32 as usize;
+
+                    if source + len > self.memory.len() || target + len > self.memory.len() {
+                        return Err(OUT_OF_BOUNDS.to_string());
+                    }
+
+                    self.memory.copy_within(source..source + len, target);
+                }
+                Op::MemoryFill => {
+                    let len = pop::<i32>(&mut stack)? as u32 as usize;
+                    let value = pop::<i32>(&mut stack)? as u8;
+                    let target = pop::<i32>(&mut stack)? as u32 as usize;
+
+                    self.memory
+                        .get_mut(target..target + len)
+                        .ok_or(OUT_OF_BOUNDS)?
+                        .fill(value);
+                }
+                Op::Const(value) => stack.push(*value),
+                &Op::Numeric(opcode) => numeric(opcode, &mut stack)?,
+                &Op::Saturate(opcode) => saturate(opcode, &mut stack)?,
+            }
+        }
+    }
+
+    fn load(&self, opcode: u8, address: usize) -> Result<Value, String> {
+        let size = match opcode {
+            0x29 | 0x2B => 8,
+            0x28 | 0x2A | 0x34 | 0x35 => 4,
+            0x2E | 0x2F | 0x32 | 0x33 => 2,
+            _ => 1,
+        };
+        let bytes = address
+            .checked_add(size)
+            .and_then(|end| self.memory.get(address..end))
+            .ok_or(OUT_OF_BOUNDS)?;
+        let mut buffer = [0; 8];
+
+        buffer[..size].copy_from_slice(bytes);
+
+        let raw = u64::from_le_bytes(buffer);
+
+        Ok(match opcode {
+            0x28 => Value::I32(raw as i32),
+            0x29 => Value::I64(raw as i64),
+            0x2A => Value::F32(f32::from_bits(raw as u32)),
+            0x2B => Value::F64(f64::from_bits(raw)),
+            0x2C => Value::I32(raw as i8 as i32),
+            0x2D => Value::I32(raw as u8 as i32),
+            0x2E => Value::I32(raw as i16 as i32),
+            0x2F => Value::I32(raw as u16 as i32),
+            0x30 => Value::I64(raw as i8 as i64),
+            0x31 => Value::I64(raw as u8 as i64),
+            0x32 => Value::I64(raw as i16 as i64),
+            0x33 => Value::I64(raw as u16 as i64),
+            0x34 => Value::I64(raw as i32 as i64),
+            _ => Value::I64(raw as u32 as i64),
+        })
+    }
+
+    fn store(&mut self, opcode: u8, address: usize, value: Value) -> Result<(), String> {
+        let (size, ty) = match opcode {
+            0x36 => (4, ValType::I32),
+            0x37 => (8, ValType::I64),
+            0x38 => (4, ValType::F32),
+            0x39 => (8, ValType::F64),
+            0x3A => (1, ValType::I32),
+            0x3B => (2, ValType::I32),
+            0x3C => (1, ValType::I64),
+            0x3D => (2, ValType::I64),
+            _ => (4, ValType::I64),
+        };
+
+        if value.ty() != ty {
+            return Err("Type mismatch in store".to_string());
+        }
+
+        address
+            .checked_add(size)
+            .and_then(|end| self.memory.get_mut(address..end))
+            .ok_or(OUT_OF_BOUNDS)?
+            .copy_from_slice(&value.bits().to_le_bytes()[..size]);
+
+        Ok(())
+    }
+}
+
+fn address(base: i32, offset: u32) -> Result<usize, String> {
+    (base as u32)
+        .checked_add(offset)
+        .map(|address| address as usize)
+        .ok_or_else(|| OUT_OF_BOUNDS.to_string())
+}
+
+fn branch(
+    stack: &mut Vec<Value>,
+    labels: &mut Vec<Label>,
+    depth: u32,
+) -> Result<Option<usize>, String> {
+    let Some(index) = labels.len().checked_sub(depth as usize + 1) else {
+        return Ok(None);
+    };
+
+    let label = labels[index];
+    let kept = split_top(stack, label.arity)?;
+
+    stack.truncate(label.height);
+    stack.extend(kept);
+    labels.truncate(if label.is_loop { index + 1 } else { index });
+
+    Ok(Some(label.pc))
+}
+
+fn split_top(stack: &mut Vec<Value>, count: usize) -> Result<Vec<Value>, String> {
+    let at = stack.len().checked_sub(count).ok_or(STACK_UNDERFLOW)?;
+
+    Ok(stack.split_off(at))
+}
+
+fn pop<T: TryFrom<Value, Error = String>>(stack: &mut Vec<Value>) -> Result<T, String> {
+    stack.pop().ok_or(STACK_UNDERFLOW)?.try_into()
+}
+
+fn unary<A, R>(stack: &mut Vec<Value>, f: impl FnOnce(A) -> R) -> Result<(), String>
+where
+    A: TryFrom<Value, Error = String>,
+    R: Into<Value>,
+{
+    let a = pop(stack)?;
+
+    stack.push(f(a).into());
+
+    Ok(())
+}
+
+fn try_unary<A, R>(
+    stack: &mut Vec<Value>,
+    f: impl FnOnce(A) -> Result<R, String>,
+) -> Result<(), String>
+where
+    A: TryFrom<Value, Error = String>,
+    R: Into<Value>,
+{
+    let a = pop(stack)?;
+
+    stack.push(f(a)?.into());
+
+    Ok(())
+}
+
+fn binary<A, R>(stack: &mut Vec<Value>, f: impl FnOnce(A, A) -> R) -> Result<(), String>
+where
+    A: TryFrom<Value, Error = String>,
+    R: Into<Value>,
+{
+    try_binary(stack, |a, b| Ok(f(a, b)))
+}
+
+fn try_binary<A, R>(
+    stack: &mut Vec<Value>,
+    f: impl FnOnce(A, A) -> Result<R, String>,
+) -> Result<(), String>
+where
+    A: TryFrom<Value, Error = String>,
+    R: Into<Value>,
+{
+    let b = pop(stack)?;
+    let a = pop(stack)?;
+
+    stack.push(f(a, b)?.into());
+
+    Ok(())
+}
+
+fn truncate(value: f64, min: f64, max: f64) -> Result<f64, String> {
+    if value.is_nan() {
+        return Err("Invalid conversion to integer".to_string());
+    }
+
+    let value = value.trunc();
+
+    if value < min || value >= max {
+        return Err(INTEGER_OVERFLOW.to_string());
+    }
+
+    Ok(value)
+}
+
+macro_rules! float_min_max {
+    ($name:ident, $ty:ty, $pick:ident, $negative_wins:expr) => {
+        fn $name(a: $ty, b: $ty) -> $ty {
+            if a.is_nan() || b.is_nan() {
+                <$ty>::NAN
+            } else if a == b {
+                if a.is_sign_negative() == $negative_wins {
+                    a
+                } else {
+                    b
+                }
+            } else {
+                a.$pick(b)
+            }
+        }
+    };
+}
+
+float_min_max!(min_f32, f32, min, true);
+float_min_max!(max_f32, f32, max, false);
+float_min_max!(min_f64, f64, min, true);
+float_min_max!(max_f64, f64, max, false);
+
+fn numeric(opcode: u8, stack: &mut Vec<Value>) -> Result<(), String> {
+    match opcode {
+        0x45 => unary(stack, |a: i32| a == 0),
+        0x46 => binary(stack, |a: i32, b| a == b),
+        0x47 => binary(stack, |a: i32, b| a != b),
+        0x48 => binary(stack, |a: i32, b| a < b),
+        0x49 => binary(stack, |a: i32, b| (a as u32) < (b as u32)),
+        0x4A => binary(stack, |a: i32, b| a > b),
+        0x4B => binary(stack, |a: i32, b| (a as u32) > (b as u32)),
+        0x4C => binary(stack, |a: i32, b| a <= b),
+        0x4D => binary(stack, |a: i32, b| (a as u32) <= (b as u32)),
+        0x4E => binary(stack, |a: i32, b| a >= b),
+        0x4F => binary(stack, |a: i32, b| (a as u32) >= (b as u32)),
+        0x50 => unary(stack, |a: i64| a == 0),
+        0x51 => binary(stack, |a: i64, b| a == b),
+        0x52 => binary(stack, |a: i64, b| a != b),
+        0x53 => binary(stack, |a: i64, b| a < b),
+        0x54 => binary(stack, |a: i64, b| (a as u64) < (b as u64)),
+        0x55 => binary(stack, |a: i64, b| a > b),
+        0x56 => binary(stack, |a: i64, b| (a as u64) > (b as u64)),
+        0x57 => binary(stack, |a: i64, b| a <= b),
+        0x58 => binary(stack, |a: i64, b| (a as u64) <= (b as u64)),
+        0x59 => binary(stack, |a: i64, b| a >= b),
+        0x5A => binary(stack, |a: i64, b| (a as u64) >= (b as u64)),
+        0x5B => binary(stack, |a: f32, b| a == b),
+        0x5C => binary(stack, |a: f32, b| a != b),
+        0x5D => binary(stack, |a: f32, b| a < b),
+        0x5E => binary(stack, |a: f32, b| a > b),
+        0x5F => binary(stack, |a: f32, b| a <= b),
+        0x60 => binary(stack, |a: f32, b| a >= b),
+        0x61 => binary(stack, |a: f64, b| a == b),
+        0x62 => binary(stack, |a: f64, b| a != b),
+        0x63 => binary(stack, |a: f64, b| a < b),
+        0x64 => binary(stack, |a: f64, b| a > b),
+        0x65 => binary(stack, |a: f64, b| a <= b),
+        0x66 => binary(stack, |a: f64, b| a >= b),
+        0x67 => unary(stack, |a: i32| a.leading_zeros() as i32),
+        0x68 => unary(stack, |a: i32| a.trailing_zeros() as i32),
+        0x69 => unary(stack, |a: i32| a.count_ones() as i32),
+        0x6A => binary(stack, |a: i32, b| a.wrapping_add(b)),
+        0x6B => binary(stack, |a: i32, b| a.wrapping_sub(b)),
+        0x6C => binary(stack, |a: i32, b| a.wrapping_mul(b)),
+        0x6D => try_binary(stack, |a: i32, b| match b {
+            0 => Err(DIVIDE_BY_ZERO.to_string()),
+            -1 if a == i32::MIN => Err(INTEGER_OVERFLOW.to_string()),
+            _ => Ok(a / b),
+        }),
+        0x6E => try_binary(stack, |a: i32, b| match b {
+            0 => Err(DIVIDE_BY_ZERO.to_string()),
+            _ => Ok(((a as u32) / (b as u32)) as i32),
+        }),
+        0x6F => try_binary(stack, |a: i32, b| match b {
+            0 => Err(DIVIDE_BY_ZERO.to_string()),
+            _ => Ok(a.wrapping_rem(b)),
+        }),
+        0x70 => try_binary(stack, |a: i32, b| match b {
+            0 => Err(DIVIDE_BY_ZERO.to_string()),
+            _ => Ok(((a as u32) % (b as u32)) as i32),
+        }),
+        0x71 => binary(stack, |a: i32, b| a & b),
+        0x72 => binary(stack, |a: i32, b| a | b),
+        0x73 => binary(stack, |a: i32, b| a ^ b),
+        0x74 => binary(stack, |a: i32, b| a.wrapping_shl(b as u32)),
+        0x75 => binary(stack, |a: i32, b| a.wrapping_shr(b as u32)),
+        0x76 => binary(stack, |a: i32, b| (a as u32).wrapping_shr(b as u32) as i32),
+        0x77 => binary(stack, |a: i32, b| a.rotate_left(b as u32)),
+        0x78 => binary(stack, |a: i32, b| a.rotate_right(b as u32)),
+        0x79 => unary(stack, |a: i64| a.leading_zeros() as i64),
+        0x7A => unary(stack, |a: i64| a.trailing_zeros() as i64),
+        0x7B => unary(stack, |a: i64| a.count_ones() as i64),
+        0x7C => binary(stack, |a: i64, b| a.wrapping_add(b)),
+        0x7D => binary(stack, |a: i64, b| a.wrapping_sub(b)),
+        0x7E => binary(stack, |a: i64, b| a.wrapping_mul(b)),
+        0x7F => try_binary(stack, |a: i64, b| match b {
+            0 => Err(DIVIDE_BY_ZERO.to_string()),
+            -1 if a == i64::MIN => Err(INTEGER_OVERFLOW.to_string()),
+            _ => Ok(a / b),
+        }),
+        0x80 => try_binary(stack, |a: i64, b| match b {
+            0 => Err(DIVIDE_BY_ZERO.to_string()),
+            _ => Ok(((a as u64) / (b as u64)) as i64),
+        }),
+        0x81 => try_binary(stack, |a: i64, b| match b {
+            0 => Err(DIVIDE_BY_ZERO.to_string()),
+            _ => Ok(a.wrapping_rem(b)),
+        }),
+        0x82 => try_binary(stack, |a: i64, b| match b {
+            0 => Err(DIVIDE_BY_ZERO.to_string()),
+            _ => Ok(((a as u64) % (b as u64)) as i64),
+        }),
+        0x83 => binary(stack, |a: i64, b| a & b),
+        0x84 => binary(stack, |a: i64, b| a | b),
+        0x85 => binary(stack, |a: i64, b| a ^ b),
+        0x86 => binary(stack, |a: i64, b| a.wrapping_shl(b as u32)),
+        0x87 => binary(stack, |a: i64, b| a.wrapping_shr(b as u32)),
+        0x88 => binary(stack, |a: i64, b| (a as u64).wrapping_shr(b as u32) as i64),
+        0x89 => binary(stack, |a: i64, b| a.rotate_left(b as u32)),
+        0x8A => binary(stack, |a: i64, b| a.rotate_right(b as u32)),
+        0x8B => unary(stack, f32::abs),
+        0x8C => unary(stack, |a: f32| -a),
+        0x8D => unary(stack, f32::ceil),
+        0x8E => unary(stack, f32::floor),
+        0x8F => unary(stack, f32::trunc),
+        0x90 => unary(stack, f32::round_ties_even),
+        0x91 => unary(stack, f32::sqrt),
+        0x92 => binary(stack, |a: f32, b| a + b),
+        0x93 => binary(stack, |a: f32, b| a - b),
+        0x94 => binary(stack, |a: f32, b| a * b),
+        0x95 => binary(stack, |a: f32, b| a / b),
+        0x96 => binary(stack, min_f32),
+        0x97 => binary(stack, max_f32),
+        0x98 => binary(stack, f32::copysign),
+        0x99 => unary(stack, f64::abs),
+        0x9A => unary(stack, |a: f64| -a),
+        0x9B => unary(stack, f64::ceil),
+        0x9C => unary(stack, f64::floor),
+        0x9D => unary(stack, f64::trunc),
+        0x9E => unary(stack, f64::round_ties_even),
+        0x9F => unary(stack, f64::sqrt),
+        0xA0 => binary(stack, |a: f64, b| a + b),
+        0xA1 => binary(stack, |a: f64, b| a - b),
+        0xA2 => binary(stack, |a: f64, b| a * b),
+        0xA3 => binary(stack, |a: f64, b| a / b),
+        0xA4 => binary(stack, min_f64),
+        0xA5 => binary(stack, max_f64),
+        0xA6 => binary(stack, f64::copysign),
+        0xA7 => unary(stack, |a: i64| a as i32),
+        0xA8 => try_unary(stack, |a: f32| {
+            truncate(a.into(), -2147483648.0, 2147483648.0).map(|v| v as i32)
+        }),
+        0xA9 => try_unary(stack, |a: f32| {
+            truncate(a.into(), 0.0, 4294967296.0).map(|v| v as u32 as i32)
+        }),
+        0xAA => try_unary(stack, |a: f64| {
+            truncate(a, -2147483648.0, 2147483648.0).map(|v| v as i32)
+        }),
+        0xAB => try_unary(stack, |a: f64| {
+            truncate(a, 0.0, 4294967296.0).map(|v| v as u32 as i32)
+        }),
+        0xAC => unary(stack, |a: i32| a as i64),
+        0xAD => unary(stack, |a: i32| a as u32 as i64),
+        0xAE => try_unary(stack, |a: f32| {
+            truncate(a.into(), -9223372036854775808.0, 9223372036854775808.0).map(|v| v as i64)
+        }),
+        0xAF => try_unary(stack, |a: f32| {
+            truncate(a.into(), 0.0, 18446744073709551616.0).map(|v| v as u64 as i64)
+        }),
+        0xB0 => try_unary(stack, |a: f64| {
+            truncate(a, -9223372036854775808.0, 9223372036854775808.0).map(|v| v as i64)
+        }),
+        0xB1 => try_unary(stack, |a: f64| {
+            truncate(a, 0.0, 18446744073709551616.0).map(|v| v as u64 as i64)
+        }),
+        0xB2 => unary(stack, |a: i32| a as f32),
+        0xB3 => unary(stack, |a: i32| a as u32 as f32),
+        0xB4 => unary(stack, |a: i64| a as f32),
+        0xB5 => unary(stack, |a: i64| a as u64 as f32),
+        0xB6 => unary(stack, |a: f64| a as f32),
+        0xB7 => unary(stack, |a: i32| a as f64),
+        0xB8 => unary(stack, |a: i32| a as u32 as f64),
+        0xB9 => unary(stack, |a: i64| a as f64),
+        0xBA => unary(stack, |a: i64| a as u64 as f64),
+        0xBB => unary(stack, |a: f32| a as f64),
+        0xBC => unary(stack, |a: f32| a.to_bits() as i32),
+        0xBD => unary(stack, |a: f64| a.to_bits() as i64),
+        0xBE => unary(stack, |a: i32| f32::from_bits(a as u32)),
+        0xBF => unary(stack, |a: i64| f64::from_bits(a as u64)),
+        0xC0 => unary(stack, |a: i32| a as i8 as i32),
+        0xC1 => unary(stack, |a: i32| a as i16 as i32),
+        0xC2 => unary(stack, |a: i64| a as i8 as i64),
+        0xC3 => unary(stack, |a: i64| a as i16 as i64),
+        0xC4 => unary(stack, |a: i64| a as i32 as i64),
+        _ => Err(format!("Unsupported instruction 0x{opcode:02x}")),
+    }
+}
+
+fn saturate(opcode: u8, stack: &mut Vec<Value>) -> Result<(), String> {
+    match opcode {
+        0 => unary(stack, |a: f32| a as i32),
+        1 => unary(stack, |a: f32| a as u32 as i32),
+        2 => unary(stack, |a: f64| a as i32),
+        3 => unary(stack, |a: f64| a as u32 as i32),
+        4 => unary(stack, |a: f32| a as i64),
+        5 => unary(stack, |a: f32| a as u64 as i64),
+        6 => unary(stack, |a: f64| a as i64),
+        _ => unary(stack, |a: f64| a as u64 as i64),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let byte = *self.bytes.get(self.pos).ok_or("Unexpected end of module")?;
+
+        self.pos += 1;
+
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(self.pos..end))
+            .ok_or("Unexpected end of module")?;
+
+        self.pos += len;
+
+        Ok(bytes)
+    }
+
+    fn leb(&mut self, bits: u32, signed: bool) -> Result<u64, String> {
+        let mut result = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.byte()?;
+
+            result |= u64::from(byte & 0x7F) << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                if signed && shift < 64 && byte & 0x40 != 0 {
+                    result |= !0 << shift;
+                }
+
+                return Ok(result);
+            }
+
+            if shift >= bits {
+                return Err("Invalid LEB128 integer".to_string());
+            }
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(self.leb(32, false)? as u32)
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(self.leb(32, true)? as i32)
+    }
+
+    fn i64(&mut self) -> Result<i64, String> {
+        Ok(self.leb(64, true)? as i64)
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        let bytes = self.bytes(4)?.try_into().map_err(|_| "Invalid f32")?;
+
+        Ok(f32::from_le_bytes(bytes))
+    }
+
+    fn f64(&mut self) -> Result<f64, String> {
+        let bytes = self.bytes(8)?.try_into().map_err(|_| "Invalid f64")?;
+
+        Ok(f64::from_le_bytes(bytes))
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let len = self.u32()? as usize;
+
+        String::from_utf8(self.bytes(len)?.to_vec()).map_err(|_| "Invalid UTF-8 name".to_string())
+    }
+
+    fn val_type(&mut self) -> Result<ValType, String> {
+        match self.byte()? {
+            0x7F => Ok(ValType::I32),
+            0x7E => Ok(ValType::I64),
+            0x7D => Ok(ValType::F32),
+            0x7C => Ok(ValType::F64),
+            byte => Err(format!("Unsupported value type 0x{byte:02x}")),
+        }
+    }
+
+    fn val_types(&mut self) -> Result<Vec<ValType>, String> {
+        (0..self.u32()?).map(|_| self.val_type()).collect()
+    }
+
+    fn limits(&mut self) -> Result<(u32, Option<u32>), String> {
+        match self.byte()? {
+            0 => Ok((self.u32()?, None)),
+            1 => Ok((self.u32()?, Some(self.u32()?))),
+            flags => Err(format!("Unsupported limits {flags}")),
+        }
+    }
+
+    fn const_expr(&mut self, globals: &[Value]) -> Result<Value, String> {
+        let value = match self.byte()? {
+            0x41 => Value::I32(self.i32()?),
+            0x42 => Value::I64(self.i64()?),
+            0x43 => Value::F32(self.f32()?),
+            0x44 => Value::F64(self.f64()?),
+            0x23 => *globals
+                .get(self.u32()? as usize)
+                .ok_or("Invalid global index")?,
+            byte => return Err(format!("Unsupported constant expression 0x{byte:02x}")),
+        };
+
+        if self.byte()? != 0x0B {
+            return Err("Unterminated constant expression".to_string());
+        }
+
+        Ok(value)
+    }
+
+    fn const_offset(&mut self, globals: &[Value]) -> Result<usize, String> {
+        match self.const_expr(globals)? {
+            Value::I32(offset) => Ok(offset as u32 as usize),
+            _ => Err("Offset must be an i32".to_string()),
+        }
+    }
+
+    fn block_type(&mut self, types: &[FuncType]) -> Result<(usize, usize), String> {
+        match self.bytes.get(self.pos) {
+            Some(0x40) => {
+                self.pos += 1;
+
+                Ok((0, 0))
+            }
+            Some(0x7C..=0x7F) => {
+                self.val_type()?;
+
+                Ok((0, 1))
+            }
+            _ => {
+                let index = self.leb(33, true)? as usize;
+                let ty = types.get(index).ok_or("Invalid block type")?;
+
+                Ok((ty.params.len(), ty.results.len()))
+            }
+        }
+    }
+
+    fn memarg(&mut self) -> Result<u32, String> {
+        self.u32()?;
+        self.u32()
+    }
+
+    fn code(&mut self, types: &[FuncType]) -> Result<Vec<Op>, String> {
+        let mut code = Vec::new();
+        let mut open: Vec<usize> = Vec::new();
+
+        loop {
+            let opcode = self.byte()?;
+            let op = match opcode {
+                0x00 => Op::Unreachable,
+                0x01 => Op::Nop,
+                0x02 => {
+                    let (params, results) = self.block_type(types)?;
+
+                    open.push(code.len());
+
+                    Op::Block {
+                        params,
+                        results,
+                        end: 0,
+                    }
+                }
+                0x03 => {
+                    let (params, _) = self.block_type(types)?;
+
+                    open.push(code.len());
+
+                    Op::Loop { params }
+                }
+                0x04 => {
+                    let (params, results) = self.block_type(types)?;
+
+                    open.push(code.len());
+
+                    Op::If {
+                        params,
+                        results,
+                        otherwise: None,
+                        end: 0,
+                    }
+                }
+                0x05 => {
+                    let index = code.len();
+
+                    match open.last().map(|&start| &mut code[start]) {
+                        Some(Op::If { otherwise, .. }) if otherwise.is_none() => {
+                            *otherwise = Some(index)
+                        }
+                        _ => return Err("Else without if".to_string()),
+                    }
+
+                    Op::Else { end: 0 }
+                }
+                0x0B => {
+                    let index = code.len();
+
+                    match open.pop() {
+                        None => {
+                            code.push(Op::End);
+
+                            if !self.is_empty() {
+                                return Err("Code after the end of a function".to_string());
+                            }
+
+                            return Ok(code);
+                        }
+                        Some(start) => {
+                            if let Op::If {
+                                otherwise: Some(otherwise),
+                                ..
+                            } = code[start]
+                            {
+                                code[otherwise] = Op::Else { end: index };
+                            }
+
+                            if let Op::Block { end, .. } | Op::If { end, .. } = &mut code[start] {
+                                *end = index;
+                            }
+                        }
+                    }
+
+                    Op::End
+                }
+                0x0C => Op::Br(self.u32()?),
+                0x0D => Op::BrIf(self.u32()?),
+                0x0E => {
+                    let targets = (0..self.u32()?)
+                        .map(|_| self.u32())
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    Op::BrTable(targets.into(), self.u32()?)
+                }
+                0x0F => Op::Return,
+                0x10 => Op::Call(self.u32()?),
+                0x11 => {
+                    let ty = self.u32()?;
+
+                    if self.byte()? != 0 {
+                        return Err("Unsupported table index".to_string());
+                    }
+
+                    Op::CallIndirect(ty)
+                }
+                0x1A => Op::Drop,
+                0x1B => Op::Select,
+                0x1C => {
+                    self.val_types()?;
+
+                    Op::Select
+                }
+                0x20 => Op::LocalGet(self.u32()?),
+                0x21 => Op::LocalSet(self.u32()?),
+                0x22 => Op::LocalTee(self.u32()?),
+                0x23 => Op::GlobalGet(self.u32()?),
+                0x24 => Op::GlobalSet(self.u32()?),
+                0x28..=0x35 => Op::Load(opcode, self.memarg()?),
+                0x36..=0x3E => Op::Store(opcode, self.memarg()?),
+                0x3F => {
+                    self.byte()?;
+
+                    Op::MemorySize
+                }
+                0x40 => {
+                    self.byte()?;
+
+                    Op::MemoryGrow
+                }
+                0x41 => Op::Const(Value::I32(self.i32()?)),
+                0x42 => Op::Const(Value::I64(self.i64()?)),
+                0x43 => Op::Const(Value::F32(self.f32()?)),
+                0x44 => Op::Const(Value::F64(self.f64()?)),
+                0x45..=0xC4 => Op::Numeric(opcode),
+                0xFC => match self.u32()? {
+                    sub @ 0..=7 => Op::Saturate(sub as u8),
+                    10 => {
+                        self.bytes(2)?;
+
+                        Op::MemoryCopy
+                    }
+                    11 => {
+                        self.byte()?;
+
+                        Op::MemoryFill
+                    }
+                    sub => return Err(format!("Unsupported instruction 0xfc {sub}")),
+                },
+                _ => return Err(format!("Unsupported instruction 0x{opcode:02x}")),
+            };
+
+            code.push(op);
+        }
+    }
+}
@@ -0,0 +1,39 @@
+pub fn indent(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+pub fn is_mixed(line: &str) -> bool {
+    let indent = indent(line);
+
+    indent.contains(' ') && indent.contains('\t') && !line.trim().is_empty()
+}
+
+pub fn trailing(line: &str) -> &str {
+    &line[line.trim_end_matches([' ', '\t']).len()..]
+}
+
+pub fn fix(line: &str, tabs: bool, width: usize) -> String {
+    let line = line.trim_end_matches([' ', '\t']);
+
+    if !is_mixed(line) {
+        return line.to_string();
+    }
+
+    let indent = indent(line);
+    let columns = indent
+        .chars()
+        .map(|c| if c == '\t' { width } else { 1 })
+        .sum::<usize>();
+
+    let indent = if tabs && width > 0 {
+        format!(
+            "{}{}",
+            "\t".repeat(columns / width),
+            " ".repeat(columns % width)
+        )
+    } else {
+        " ".repeat(columns)
+    };
+
+    format!("{indent}{}", line.trim_start_matches([' ', '\t']))
+}
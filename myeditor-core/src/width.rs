@@ -0,0 +1,87 @@
+use crate::charinfo;
+
+const ELLIPSIS: char = '…';
+
+pub fn char_width(c: char) -> usize {
+    if c.is_control() || charinfo::is_combining(c) {
+        return 0;
+    }
+
+    let wide = matches!(
+        c,
+        '\u{1100}'..='\u{115f}'
+            | '\u{2e80}'..='\u{303e}'
+            | '\u{3041}'..='\u{33ff}'
+            | '\u{3400}'..='\u{4dbf}'
+            | '\u{4e00}'..='\u{9fff}'
+            | '\u{a000}'..='\u{a4cf}'
+            | '\u{ac00}'..='\u{d7a3}'
+            | '\u{f900}'..='\u{faff}'
+            | '\u{fe30}'..='\u{fe4f}'
+            | '\u{ff00}'..='\u{ff60}'
+            | '\u{ffe0}'..='\u{ffe6}'
+            | '\u{1f300}'..='\u{1f64f}'
+            | '\u{1f900}'..='\u{1f9ff}'
+            | '\u{20000}'..='\u{3fffd}'
+    );
+
+    if wide {
+        2
+    } else {
+        1
+    }
+}
+
+pub fn str_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+pub fn truncate_end(text: &str, width: usize) -> String {
+    if str_width(text) <= width {
+        return text.to_string();
+    }
+
+    let mut out = take_width(text.chars(), width.saturating_sub(1));
+
+    if width > 0 {
+        out.push(ELLIPSIS);
+    }
+
+    out
+}
+
+pub fn truncate_middle(text: &str, width: usize) -> String {
+    if str_width(text) <= width {
+        return text.to_string();
+    }
+
+    if width < 3 {
+        return truncate_end(text, width);
+    }
+
+    let tail_width = (width - 1).div_ceil(2);
+    let head = take_width(text.chars(), width - 1 - tail_width);
+    let tail = take_width(text.chars().rev(), tail_width)
+        .chars()
+        .rev()
+        .collect::<String>();
+
+    format!("{head}{ELLIPSIS}{tail}")
+}
+
+fn take_width(chars: impl Iterator<Item = char>, width: usize) -> String {
+    let mut out = String::new();
+    let mut used = 0;
+
+    for c in chars {
+        used += char_width(c);
+
+        if used > width {
+            break;
+        }
+
+        out.push(c);
+    }
+
+    out
+}
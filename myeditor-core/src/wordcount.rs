@@ -0,0 +1,68 @@
+use ropey::RopeSlice;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub lines: usize,
+    pub words: usize,
+    pub chars: usize,
+    pub bytes: usize,
+}
+
+impl Counts {
+    pub fn of(text: RopeSlice) -> Self {
+        let mut counts = Self {
+            chars: text.len_chars(),
+            bytes: text.len_bytes(),
+            ..Self::default()
+        };
+
+        let mut in_word = false;
+        let mut last = None;
+
+        for c in text.chars() {
+            if c.is_whitespace() {
+                in_word = false;
+            } else if !in_word {
+                in_word = true;
+                counts.words += 1;
+            }
+
+            if c == '\n' {
+                counts.lines += 1;
+            }
+
+            last = Some(c);
+        }
+
+        if last.is_some_and(|c| c != '\n') {
+            counts.lines += 1;
+        }
+
+        counts
+    }
+}
+
+impl std::fmt::Display for Counts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}, {} {}, {} {}, {} {}",
+            self.lines,
+            plural(self.lines, "line"),
+            self.words,
+            plural(self.words, "word"),
+            self.chars,
+            plural(self.chars, "char"),
+            self.bytes,
+            plural(self.bytes, "byte"),
+        )
+    }
+}
+
+fn plural(n: usize, word: &str) -> String {
+    if n == 1 {
+        word.to_string()
+    } else {
+        format!("{word}s")
+    }
+}
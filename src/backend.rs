@@ -0,0 +1,397 @@
+use crossterm::{
+    cursor::{Hide, MoveTo, SetCursorStyle, Show},
+    event::{
+        self, DisableFocusChange, EnableFocusChange, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    queue,
+    style::{self as term, Print, PrintStyledContent, StyledContent},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, is_raw_mode_enabled, size,
+        supports_keyboard_enhancement, EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
+    },
+};
+use myeditor_core::{
+    backend::{Backend, Cell},
+    colors::{self, ColorDepth},
+    display::WIDE_TAIL,
+    events::EventSource,
+    input::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    style::{Attribute, Color, ContentStyle, CursorStyle},
+    terminal::Size,
+};
+use std::{
+    io::{self, BufWriter, Stdout, Write},
+    time::Duration,
+};
+
+const PUSH_TITLE: &str = "\x1b[22;0t";
+const POP_TITLE: &str = "\x1b[23;0t";
+
+const ANSI16_NAMES: [Color; 16] = [
+    Color::Black,
+    Color::DarkRed,
+    Color::DarkGreen,
+    Color::DarkYellow,
+    Color::DarkBlue,
+    Color::DarkMagenta,
+    Color::DarkCyan,
+    Color::Grey,
+    Color::DarkGrey,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+];
+
+pub fn restore() {
+    if !is_raw_mode_enabled().unwrap_or(false) {
+        return;
+    }
+
+    let mut stdout = std::io::stdout();
+
+    let _ = disable_raw_mode();
+    let _ = queue!(
+        stdout,
+        PopKeyboardEnhancementFlags,
+        DisableFocusChange,
+        LeaveAlternateScreen,
+        Show,
+        Print(POP_TITLE)
+    );
+    let _ = stdout.flush();
+}
+
+#[derive(Debug)]
+pub struct CrosstermBackend {
+    stdout: BufWriter<Stdout>,
+    started: bool,
+    enhanced_keys: bool,
+    depth: ColorDepth,
+}
+
+impl CrosstermBackend {
+    pub fn new(stdout: Stdout) -> Self {
+        Self {
+            stdout: BufWriter::new(stdout),
+            started: false,
+            enhanced_keys: false,
+            depth: colors::detect(
+                std::env::var("COLORTERM").ok().as_deref(),
+                std::env::var("TERM").ok().as_deref(),
+            ),
+        }
+    }
+}
+
+impl Backend for CrosstermBackend {
+    fn size(&self) -> io::Result<Size> {
+        size().map(Size::from)
+    }
+
+    fn color_depth(&self) -> ColorDepth {
+        self.depth
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        queue!(self.stdout, EnterAlternateScreen, EnableFocusChange)?;
+        write!(self.stdout, "{PUSH_TITLE}")?;
+        enable_raw_mode()?;
+
+        if supports_keyboard_enhancement().unwrap_or(false) {
+            queue!(
+                self.stdout,
+                PushKeyboardEnhancementFlags(
+                    KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                        | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                )
+            )?;
+
+            self.enhanced_keys = true;
+        }
+
+        log::debug!("keyboard enhancement: {}", self.enhanced_keys);
+
+        self.started = true;
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        disable_raw_mode()?;
+
+        if std::mem::replace(&mut self.started, false) {
+            write!(self.stdout, "{POP_TITLE}")?;
+        }
+
+        if std::mem::take(&mut self.enhanced_keys) {
+            queue!(self.stdout, PopKeyboardEnhancementFlags)?;
+        }
+
+        queue!(self.stdout, DisableFocusChange, LeaveAlternateScreen)
+    }
+
+    fn draw(&mut self, x: u16, y: u16, cells: &[Cell]) -> io::Result<()> {
+        let mut x = x;
+
+        for run in cells.chunk_by(|a, b| a.style == b.style) {
+            let (colors, style) = split_ansi16(downsample(run[0].style, self.depth), self.depth);
+            let text = run
+                .iter()
+                .map(|cell| cell.c)
+                .filter(|&c| c != WIDE_TAIL)
+                .collect::<String>();
+
+            queue!(
+                self.stdout,
+                MoveTo(x, y),
+                Print(colors),
+                PrintStyledContent(StyledContent::new(term_style(style), text))
+            )?;
+
+            x += run.len() as u16;
+        }
+
+        Ok(())
+    }
+
+    fn move_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+        queue!(self.stdout, MoveTo(x, y))
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        queue!(self.stdout, Show)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        queue!(self.stdout, Hide)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.started && self.depth == ColorDepth::Ansi16 {
+            queue!(self.stdout, Print("\x1b[39;49m"))?;
+        }
+
+        self.stdout.flush()
+    }
+
+    fn events(&mut self) -> Box<dyn EventSource> {
+        Box::new(CrosstermEvents)
+    }
+
+    fn set_cursor_style(&mut self, style: CursorStyle) -> io::Result<()> {
+        match style {
+            CursorStyle::SteadyBlock => queue!(self.stdout, SetCursorStyle::SteadyBlock),
+            CursorStyle::SteadyBar => queue!(self.stdout, SetCursorStyle::SteadyBar),
+        }
+    }
+
+    fn set_title(&mut self, title: &str) -> io::Result<()> {
+        queue!(self.stdout, SetTitle(title))
+    }
+
+    fn write_raw(&mut self, text: &str) -> io::Result<()> {
+        write!(self.stdout, "{text}")
+    }
+
+    fn enhanced_keys(&self) -> bool {
+        self.enhanced_keys
+    }
+}
+
+#[derive(Debug)]
+struct CrosstermEvents;
+
+impl EventSource for CrosstermEvents {
+    fn read(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        if event::poll(timeout)? {
+            event::read().map(convert_event)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+fn convert_event(event: event::Event) -> Option<Event> {
+    Some(match event {
+        event::Event::FocusGained => Event::FocusGained,
+        event::Event::FocusLost => Event::FocusLost,
+        event::Event::Key(key) => Event::Key(KeyEvent::new_with_kind(
+            convert_key_code(key.code)?,
+            convert_modifiers(key.modifiers),
+            match key.kind {
+                event::KeyEventKind::Press => KeyEventKind::Press,
+                event::KeyEventKind::Repeat => KeyEventKind::Repeat,
+                event::KeyEventKind::Release => KeyEventKind::Release,
+            },
+        )),
+        event::Event::Paste(text) => Event::Paste(text),
+        event::Event::Resize(width, height) => Event::Resize(width, height),
+        event::Event::Mouse(_) => return None,
+    })
+}
+
+fn convert_key_code(code: event::KeyCode) -> Option<KeyCode> {
+    Some(match code {
+        event::KeyCode::Backspace => KeyCode::Backspace,
+        event::KeyCode::Enter => KeyCode::Enter,
+        event::KeyCode::Left => KeyCode::Left,
+        event::KeyCode::Right => KeyCode::Right,
+        event::KeyCode::Up => KeyCode::Up,
+        event::KeyCode::Down => KeyCode::Down,
+        event::KeyCode::Home => KeyCode::Home,
+        event::KeyCode::End => KeyCode::End,
+        event::KeyCode::PageUp => KeyCode::PageUp,
+        event::KeyCode::PageDown => KeyCode::PageDown,
+        event::KeyCode::Tab => KeyCode::Tab,
+        event::KeyCode::BackTab => KeyCode::BackTab,
+        event::KeyCode::Delete => KeyCode::Delete,
+        event::KeyCode::Insert => KeyCode::Insert,
+        event::KeyCode::F(n) => KeyCode::F(n),
+        event::KeyCode::Char(c) => KeyCode::Char(c),
+        event::KeyCode::Null => KeyCode::Null,
+        event::KeyCode::Esc => KeyCode::Esc,
+        event::KeyCode::CapsLock => KeyCode::CapsLock,
+        event::KeyCode::ScrollLock => KeyCode::ScrollLock,
+        event::KeyCode::NumLock => KeyCode::NumLock,
+        event::KeyCode::PrintScreen => KeyCode::PrintScreen,
+        event::KeyCode::Pause => KeyCode::Pause,
+        event::KeyCode::Menu => KeyCode::Menu,
+        event::KeyCode::KeypadBegin => KeyCode::KeypadBegin,
+        event::KeyCode::Media(_) | event::KeyCode::Modifier(_) => return None,
+    })
+}
+
+fn convert_modifiers(modifiers: event::KeyModifiers) -> KeyModifiers {
+    [
+        (event::KeyModifiers::SHIFT, KeyModifiers::SHIFT),
+        (event::KeyModifiers::CONTROL, KeyModifiers::CONTROL),
+        (event::KeyModifiers::ALT, KeyModifiers::ALT),
+        (event::KeyModifiers::SUPER, KeyModifiers::SUPER),
+        (event::KeyModifiers::HYPER, KeyModifiers::HYPER),
+        (event::KeyModifiers::META, KeyModifiers::META),
+    ]
+    .into_iter()
+    .filter(|&(modifier, _)| modifiers.contains(modifier))
+    .fold(KeyModifiers::NONE, |all, (_, modifier)| all | modifier)
+}
+
+fn term_style(style: ContentStyle) -> term::ContentStyle {
+    let mut converted = term::ContentStyle {
+        foreground_color: style.foreground_color.map(term_color),
+        background_color: style.background_color.map(term_color),
+        underline_color: style.underline_color.map(term_color),
+        ..term::ContentStyle::default()
+    };
+
+    for attribute in Attribute::ALL {
+        if style.attributes.has(attribute) {
+            converted.attributes.set(term_attribute(attribute));
+        }
+    }
+
+    converted
+}
+
+fn term_attribute(attribute: Attribute) -> term::Attribute {
+    match attribute {
+        Attribute::Bold => term::Attribute::Bold,
+        Attribute::Dim => term::Attribute::Dim,
+        Attribute::Italic => term::Attribute::Italic,
+        Attribute::Underlined => term::Attribute::Underlined,
+        Attribute::SlowBlink => term::Attribute::SlowBlink,
+        Attribute::RapidBlink => term::Attribute::RapidBlink,
+        Attribute::Reverse => term::Attribute::Reverse,
+        Attribute::Hidden => term::Attribute::Hidden,
+        Attribute::CrossedOut => term::Attribute::CrossedOut,
+    }
+}
+
+fn term_color(color: Color) -> term::Color {
+    match color {
+        Color::Reset => term::Color::Reset,
+        Color::Black => term::Color::Black,
+        Color::DarkGrey => term::Color::DarkGrey,
+        Color::Red => term::Color::Red,
+        Color::DarkRed => term::Color::DarkRed,
+        Color::Green => term::Color::Green,
+        Color::DarkGreen => term::Color::DarkGreen,
+        Color::Yellow => term::Color::Yellow,
+        Color::DarkYellow => term::Color::DarkYellow,
+        Color::Blue => term::Color::Blue,
+        Color::DarkBlue => term::Color::DarkBlue,
+        Color::Magenta => term::Color::Magenta,
+        Color::DarkMagenta => term::Color::DarkMagenta,
+        Color::Cyan => term::Color::Cyan,
+        Color::DarkCyan => term::Color::DarkCyan,
+        Color::White => term::Color::White,
+        Color::Grey => term::Color::Grey,
+        Color::Rgb { r, g, b } => term::Color::Rgb { r, g, b },
+        Color::AnsiValue(index) => term::Color::AnsiValue(index),
+    }
+}
+
+fn downsample(mut style: ContentStyle, depth: ColorDepth) -> ContentStyle {
+    let convert = |color: Option<Color>| color.map(|color| downsample_color(color, depth));
+
+    style.foreground_color = convert(style.foreground_color);
+    style.background_color = convert(style.background_color);
+    style.underline_color = convert(style.underline_color);
+
+    style
+}
+
+fn downsample_color(color: Color, depth: ColorDepth) -> Color {
+    match (depth, color) {
+        (ColorDepth::TrueColor, _) => color,
+        (ColorDepth::Ansi256, Color::Rgb { r, g, b }) => {
+            Color::AnsiValue(colors::rgb_to_ansi256((r, g, b)))
+        }
+        (ColorDepth::Ansi16, Color::Rgb { r, g, b }) => {
+            Color::AnsiValue(colors::nearest_ansi16((r, g, b)))
+        }
+        (ColorDepth::Ansi16, Color::AnsiValue(index)) => {
+            Color::AnsiValue(colors::nearest_ansi16(colors::ansi256_to_rgb(index)))
+        }
+        _ => color,
+    }
+}
+
+fn split_ansi16(mut style: ContentStyle, depth: ColorDepth) -> (String, ContentStyle) {
+    if depth != ColorDepth::Ansi16 {
+        return (String::new(), style);
+    }
+
+    let code = |color: Option<Color>, base: u8| {
+        let index = match color? {
+            Color::AnsiValue(index) => index,
+            Color::Reset => return None,
+            color => ANSI16_NAMES.iter().position(|named| *named == color)? as u8,
+        };
+
+        Some(if index < 8 {
+            base + index
+        } else {
+            base + 60 + index % 8
+        })
+    };
+
+    let codes = [
+        Some(39),
+        Some(49),
+        code(style.foreground_color.take(), 30),
+        code(style.background_color.take(), 40),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|code| code.to_string())
+    .collect::<Vec<_>>();
+
+    style.underline_color = None;
+
+    (format!("\x1b[{}m", codes.join(";")), style)
+}
@@ -1,54 +1,278 @@
+use backend::CrosstermBackend;
 use clap::Parser;
-use editor::Editor;
-use std::{fs::read_to_string, io::Write, path::PathBuf};
-use terminal::Terminal;
+use myeditor_core::{
+    backend::{Backend, MemoryBackend},
+    crash,
+    editor::{self, Editor},
+    error::{EditorError, Result},
+    events::Events,
+    json::Value,
+    logger, paths, profile, remote, script,
+    terminal::{Size, Terminal},
+};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
-mod editor;
-mod terminal;
+mod backend;
 
 #[derive(Parser)]
 struct Args {
     filename: Option<PathBuf>,
+
+    #[arg(value_name = "+LINE", value_parser = parse_line)]
+    line: Option<usize>,
+
+    #[arg(short = 'b', long = "binary")]
+    binary: bool,
+
+    #[arg(short = 'F', long = "follow", requires = "filename")]
+    follow: bool,
+
+    #[arg(short = 'd', long = "diff", num_args = 2, value_names = ["A", "B"], conflicts_with = "filename")]
+    diff: Option<Vec<PathBuf>>,
+
+    #[arg(long = "tutor", conflicts_with_all = ["filename", "diff"])]
+    tutor: bool,
+
+    #[arg(long = "script", value_name = "FILE")]
+    script: Option<PathBuf>,
+
+    #[arg(long = "print-buffer", requires = "script")]
+    print_buffer: bool,
+
+    #[arg(long = "startup-profile")]
+    startup_profile: bool,
+
+    #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    #[arg(long = "listen", value_name = "SOCKET", num_args = 0..=1, require_equals = true)]
+    listen: Option<Option<PathBuf>>,
+
+    #[arg(long = "remote", requires = "filename", conflicts_with_all = ["diff", "script"])]
+    remote: bool,
+
+    #[arg(long = "server", value_name = "SOCKET")]
+    server: Option<PathBuf>,
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+const HEADLESS_SIZE: Size = Size {
+    width: 80,
+    height: 24,
+};
+
+fn main() -> Result<()> {
+    let started = Instant::now();
     let args = Args::parse();
 
-    let stdout = std::io::stdout();
-    let terminal = Terminal::new(stdout)?;
+    if args.startup_profile {
+        profile::start(started);
+    }
+
+    logger::init(args.verbose)?;
+    profile::mark("logger");
+
+    std::panic::set_hook(Box::new(|info| {
+        backend::restore();
+        report_crash(&info.to_string());
+    }));
+
+    if args.remote && open_remote(&args)? {
+        return Ok(());
+    }
+
+    let scratch = args
+        .script
+        .as_ref()
+        .map(|_| std::env::temp_dir().join(format!("myeditor-script-{}", std::process::id())));
+
+    if let Some(dir) = &scratch {
+        paths::set_data_dir(dir.clone());
+    }
+
+    let (terminal, events) = match &args.script {
+        Some(path) => {
+            let keys = std::fs::read_to_string(path)?;
+            let mut backend = MemoryBackend::new(HEADLESS_SIZE, script::parse(&keys)?);
+            let events = Events::script(backend.events());
+
+            (Terminal::new(Box::new(backend))?, events)
+        }
+        None => {
+            let mut backend = CrosstermBackend::new(std::io::stdout());
+            let events = Events::terminal(backend.events());
+
+            (Terminal::new(Box::new(backend))?, events)
+        }
+    };
+
+    profile::mark("terminal");
+
+    let _server = match &args.listen {
+        Some(address) => {
+            let address = address
+                .clone()
+                .or_else(remote::default_address)
+                .ok_or("No data directory for the server socket")?;
+
+            Some(remote::listen(address, events.sender())?)
+        }
+        None => None,
+    };
 
-    let mut editor = match args.filename {
-        Some(filename) => {
-            let text = read_to_string(filename)?;
+    if _server.is_some() {
+        profile::mark("server");
+    }
 
-            Editor::new_with_text(terminal, text)
+    let mut editor = match (args.diff, args.filename) {
+        (Some(files), _) => {
+            let [a, b]: [PathBuf; 2] = files.try_into().map_err(|_| "-d takes two files")?;
+
+            Editor::new_diff(terminal, events, a, b)
         }
-        None => Editor::new(terminal),
+        (None, Some(filename)) => Editor::new_with_file(terminal, events, filename, args.binary),
+        (None, None) => Editor::new(terminal, events),
     };
 
+    if let Some(line) = args.line {
+        editor.goto_line(line);
+    }
+
+    if args.follow {
+        editor.follow()?;
+    }
+
+    if args.tutor {
+        editor.open_tutor();
+    }
+
     let result = run(&mut editor);
 
     editor.finish()?;
 
+    if let Some(report) = profile::report() {
+        eprintln!("{report}");
+    }
+
+    if args.script.is_some() {
+        editor.draw()?;
+
+        let output = if args.print_buffer {
+            editor.contents()
+        } else {
+            editor.snapshot().unwrap_or_default() + "\n"
+        };
+
+        print!("{output}");
+    }
+
+    if let Err(e) = &result {
+        report_crash(&format!("{e:?}"));
+    } else if let Some(dir) = scratch {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
     result
 }
 
-fn run(editor: &mut Editor) -> Result<(), Box<dyn std::error::Error>> {
+fn report_crash(error: &str) {
+    let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+
+    match crash::write_report(env!("CARGO_PKG_VERSION"), error, &backtrace) {
+        Ok(path) => eprintln!(
+            "myeditor crashed: {error}\nA crash report was written to {}",
+            path.display()
+        ),
+        Err(e) => eprintln!("myeditor crashed: {error}\nCan't write a crash report: {e}"),
+    }
+}
+
+fn parse_line(arg: &str) -> std::result::Result<usize, String> {
+    arg.strip_prefix('+')
+        .and_then(|line| line.parse().ok())
+        .ok_or_else(|| format!("expected +LINE, found {arg:?}"))
+}
+
+fn open_remote(args: &Args) -> Result<bool> {
+    let address = args
+        .server
+        .clone()
+        .or_else(remote::default_address)
+        .ok_or("No data directory for the server socket")?;
+
+    let Some(filename) = &args.filename else {
+        return Ok(false);
+    };
+
+    let mut params = vec![(
+        "path",
+        std::path::absolute(filename)?.display().to_string().into(),
+    )];
+
+    if let Some(line) = args.line {
+        params.push(("line", line.into()));
+    }
+
+    match remote::call(&address, "open", Value::object(params)) {
+        Ok(Ok(_)) => Ok(true),
+        Ok(Err(e)) => Err(EditorError::Command(e)),
+        Err(e) => {
+            log::info!("no server at {}: {e}", address.display());
+
+            Ok(false)
+        }
+    }
+}
+
+fn run(editor: &mut Editor) -> Result<()> {
     editor.start()?;
     editor.clear()?;
     editor.draw()?;
-    editor.flush()?;
+    editor.flush().map_err(EditorError::Terminal)?;
+
+    profile::mark("first draw");
+
+    let mut last_draw = Instant::now();
+
+    'events: loop {
+        if handle_event(editor)? {
+            break;
+        }
 
-    loop {
-        if let Some(result) = editor.handle_event()? {
-            match result {
-                editor::EventResult::Quit => break,
+        while editor.wait_for_input(Duration::ZERO) {
+            if handle_event(editor)? {
+                break 'events;
+            }
+        }
+
+        if let Some(interval) = editor.frame_interval() {
+            let elapsed = last_draw.elapsed();
+
+            if elapsed < interval && editor.wait_for_input(interval - elapsed) {
+                continue;
             }
         }
 
         editor.draw()?;
-        editor.flush()?;
+        editor.flush().map_err(EditorError::Terminal)?;
+
+        last_draw = Instant::now();
     }
 
-    Ok(editor.finish()?)
+    editor.finish()
+}
+
+fn handle_event(editor: &mut Editor) -> Result<bool> {
+    match editor.handle_event() {
+        Ok(Some(editor::EventResult::Quit)) => Ok(true),
+        Ok(None) => Ok(false),
+        Err(e) if e.is_fatal() => Err(e),
+        Err(e) => {
+            editor.report(e);
+
+            Ok(false)
+        }
+    }
 }
@@ -0,0 +1,61 @@
+mod common;
+
+use common::scratch_dir;
+use std::{fs, process::Command};
+
+fn run(name: &str, source: &str, keys: &str, print: &[&str]) -> String {
+    let dir = scratch_dir(name);
+    let server = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/lsp/server.sh");
+
+    fs::write(
+        dir.join(".myeditor.toml"),
+        format!("[lsp]\nrust = \"sh {server}\"\n"),
+    )
+    .unwrap();
+    fs::write(dir.join("main.rs"), source).unwrap();
+    fs::write(dir.join("keys"), keys).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_myeditor"))
+        .current_dir(&dir)
+        .env("XDG_CONFIG_HOME", &dir)
+        .args(["main.rs", "--script", "keys"])
+        .args(print)
+        .output()
+        .unwrap();
+
+    let _ = fs::remove_dir_all(&dir);
+
+    assert!(output.status.success(), "{output:?}");
+
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn code_actions_apply_edits_and_commands() {
+    assert_eq!(
+        run(
+            "code-actions",
+            "fn main() {}\n",
+            "ygra1gra<Down><CR>",
+            &["--print-buffer"]
+        ),
+        "use std::fmt;\nfn main() {}\n// end\n"
+    );
+}
+
+#[test]
+fn signature_help_follows_the_arguments() {
+    let source = "fn main() {\n    let x = 1;\n}\n";
+    let screen = |keys: &str| run("signature-help", source, keys, &[]);
+
+    let first = screen("yjA add(<Resize:40x8>");
+    let second = screen("yjA add(1,<Resize:40x8>");
+    let closed = screen("yjA add(1, 2)<Resize:40x8>");
+
+    assert!(first.contains("│fn add(a: i32, b: i32) -> i32│"), "{first}");
+    assert!(
+        second.contains("│fn add(a: i32, b: i32) -> i32│"),
+        "{second}"
+    );
+    assert!(!closed.contains("fn add"), "{closed}");
+}
@@ -0,0 +1,60 @@
+#!/bin/sh
+
+cr=$(printf '\r')
+
+send() {
+    printf 'Content-Length: %d\r\n\r\n%s' "${#1}" "$1"
+}
+
+reply() {
+    send "{\"jsonrpc\":\"2.0\",\"id\":$1,\"result\":$2}"
+}
+
+edit() {
+    printf '%s' "{\"changes\":{\"$uri\":[{\"range\":{\"start\":{\"line\":$1,\"character\":0},\"end\":{\"line\":$1,\"character\":0}},\"newText\":\"$2\"}]}}"
+}
+
+while IFS= read -r header; do
+    case $header in
+    Content-Length:*)
+        len=$(printf '%s' "$header" | tr -dc 0-9)
+        continue
+        ;;
+    "$cr" | "") ;;
+    *) continue ;;
+    esac
+
+    body=$(head -c "$len")
+    id=$(printf '%s' "$body" | sed -n 's/^{"jsonrpc":"2.0","id":\([0-9]*\),.*/\1/p')
+
+    case $body in
+    *'"uri":"file://'*) uri=$(printf '%s' "$body" | sed 's/.*"uri":"\(file:[^"]*\)".*/\1/') ;;
+    esac
+
+    case $body in
+    *'"method":"initialize"'*)
+        reply "$id" '{"capabilities":{"codeActionProvider":true,"signatureHelpProvider":{"triggerCharacters":["(",","]}}}'
+        ;;
+    *'"method":"textDocument/signatureHelp"'*)
+        case $body in
+        *'"triggerCharacter":","'*) active=1 ;;
+        *) active=0 ;;
+        esac
+
+        reply "$id" "{\"signatures\":[{\"label\":\"fn add(a: i32, b: i32) -> i32\",\"parameters\":[{\"label\":\"a: i32\"},{\"label\":[15,21]}]}],\"activeParameter\":$active}"
+        ;;
+    *'"method":"textDocument/codeAction"'*)
+        reply "$id" "[{\"title\":\"Import fmt\",\"edit\":$(edit 0 'use std::fmt;\n')},{\"title\":\"Add a footer\",\"command\":{\"title\":\"Add a footer\",\"command\":\"footer\"}}]"
+        ;;
+    *'"method":"workspace/executeCommand"'*)
+        send "{\"jsonrpc\":\"2.0\",\"id\":\"edit\",\"method\":\"workspace/applyEdit\",\"params\":{\"edit\":$(edit 99 '// end\n')}}"
+        reply "$id" null
+        ;;
+    *'"method":"shutdown"'*)
+        reply "$id" null
+        ;;
+    *'"method":"exit"'*)
+        exit 0
+        ;;
+    esac
+done
@@ -0,0 +1,108 @@
+mod common;
+
+use common::scratch_dir;
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::{fs::PermissionsExt, net::UnixStream},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn listen_keeps_other_files() {
+    let dir = scratch_dir("listen-file");
+
+    fs::write(dir.join("notes.txt"), "notes\n").unwrap();
+    fs::write(dir.join("keys"), "").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_myeditor"))
+        .current_dir(&dir)
+        .args(["--listen=notes.txt", "--script", "keys"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.join("notes.txt")).unwrap(),
+        "notes\n"
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn listen_socket_is_private_and_limits_nesting() {
+    let dir = scratch_dir("listen-socket");
+    let socket = dir.join("myeditor.sock");
+
+    fs::write(dir.join("keys"), ":w !sleep 3<CR>").unwrap();
+
+    let mut editor = Command::new(env!("CARGO_BIN_EXE_myeditor"))
+        .current_dir(&dir)
+        .args(["--listen=myeditor.sock", "--script", "keys"])
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let started = Instant::now();
+
+    while !socket.exists() && started.elapsed() < Duration::from_secs(2) {
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    let mode = fs::metadata(&socket).unwrap().permissions().mode();
+
+    let mut stream = UnixStream::connect(&socket).unwrap();
+
+    writeln!(stream, "{}", "[".repeat(100_000)).unwrap();
+
+    let mut response = String::new();
+
+    BufReader::new(stream).read_line(&mut response).unwrap();
+
+    assert!(editor.wait().unwrap().success());
+    assert_eq!(mode & 0o777, 0o600);
+    assert!(response.contains("\"code\":-32700"), "{response}");
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn listen_refuses_long_requests() {
+    let dir = scratch_dir("listen-long");
+    let socket = dir.join("myeditor.sock");
+
+    fs::write(dir.join("keys"), ":w !sleep 3<CR>").unwrap();
+
+    let mut editor = Command::new(env!("CARGO_BIN_EXE_myeditor"))
+        .current_dir(&dir)
+        .args(["--listen=myeditor.sock", "--script", "keys"])
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let started = Instant::now();
+
+    while !socket.exists() && started.elapsed() < Duration::from_secs(2) {
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    let mut stream = UnixStream::connect(&socket).unwrap();
+    let _ = stream.write_all(" ".repeat(17 * 1024 * 1024).as_bytes());
+
+    let mut response = String::new();
+
+    BufReader::new(stream).read_line(&mut response).unwrap();
+
+    assert!(editor.wait().unwrap().success());
+    assert!(response.contains("\"code\":-32600"), "{response}");
+    assert!(fs::read_dir(&dir).unwrap().all(|entry| !entry
+        .unwrap()
+        .file_name()
+        .to_string_lossy()
+        .starts_with(".myeditor-")));
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,116 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const EXPECTED: [&str; 2] = ["buffer", "screen"];
+
+#[test]
+fn scripts() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/scripts");
+
+    let mut cases = fs::read_dir(&root)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+
+    cases.sort();
+
+    let failures = cases
+        .iter()
+        .filter_map(|case| run(case).err())
+        .collect::<Vec<_>>();
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} scripts failed:\n\n{}",
+        failures.len(),
+        cases.len(),
+        failures.join("\n\n")
+    );
+}
+
+fn run(case: &Path) -> Result<(), String> {
+    let name = case.file_name().unwrap().to_string_lossy().to_string();
+    let fail = |message: String| format!("{name}: {message}");
+
+    let (kind, expected) = EXPECTED
+        .iter()
+        .find_map(|kind| Some((*kind, fs::read_to_string(case.join(kind)).ok()?)))
+        .ok_or_else(|| fail("no buffer or screen file".to_string()))?;
+
+    let input = fs::read_dir(case)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            let file = path.file_name().unwrap().to_string_lossy();
+
+            file != "keys" && !EXPECTED.contains(&file.as_ref())
+        });
+
+    let first = output(case, input.as_deref(), kind).map_err(fail)?;
+    let second = output(case, input.as_deref(), kind).map_err(fail)?;
+
+    if first != second {
+        return Err(fail(format!(
+            "a second run gave a different {kind}:\n{first}\n---\n{second}"
+        )));
+    }
+
+    if first != expected {
+        return Err(fail(format!(
+            "expected {kind}:\n{expected}\n---\nfound:\n{first}"
+        )));
+    }
+
+    Ok(())
+}
+
+fn output(case: &Path, input: Option<&Path>, kind: &str) -> Result<String, String> {
+    let dir = scratch_dir(case);
+
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_myeditor"));
+
+    command
+        .current_dir(&dir)
+        .env("XDG_CONFIG_HOME", &dir)
+        .arg("--script")
+        .arg(case.join("keys"));
+
+    if let Some(input) = input {
+        let file = input.file_name().unwrap();
+
+        fs::copy(input, dir.join(file)).map_err(|e| e.to_string())?;
+        command.arg(file);
+    }
+
+    if kind == "buffer" {
+        command.arg("--print-buffer");
+    }
+
+    let output = command.output().map_err(|e| e.to_string())?;
+    let _ = fs::remove_dir_all(&dir);
+
+    if !output.status.success() {
+        return Err(format!(
+            "exited with {}:\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn scratch_dir(case: &Path) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "myeditor-test-{}-{}",
+        std::process::id(),
+        case.file_name().unwrap().to_string_lossy()
+    ))
+}